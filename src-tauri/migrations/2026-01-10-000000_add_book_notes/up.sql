@@ -0,0 +1,3 @@
+-- Freeform per-book note. Stored on the book row so it syncs with the
+-- book's own last-write-wins timestamp (one note per book, no separate merge).
+ALTER TABLE books ADD COLUMN notes TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE sync_state DROP COLUMN last_errors;
+ALTER TABLE sync_state DROP COLUMN last_attempt_at;
@@ -0,0 +1,4 @@
+-- Outcome of the most recent sync attempt, so the UI can show failures later.
+-- last_errors is a JSON array of messages; NULL when the last attempt succeeded.
+ALTER TABLE sync_state ADD COLUMN last_attempt_at TIMESTAMP;
+ALTER TABLE sync_state ADD COLUMN last_errors TEXT;
@@ -0,0 +1,3 @@
+-- Archive format detected at import ("zip" or "rar"), so reads can skip sniffing
+-- magic bytes. NULL for older rows and after a relink; detected again on first read.
+ALTER TABLE books ADD COLUMN archive_type TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE books DROP COLUMN completed_count;
+ALTER TABLE books DROP COLUMN open_count;
@@ -0,0 +1,4 @@
+-- Engagement counters ("read 3 times"). They only ever grow, so sync merges
+-- them by taking the larger value instead of last-write-wins.
+ALTER TABLE books ADD COLUMN open_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE books ADD COLUMN completed_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,13 @@
+-- Global "up next" queue: a single ordered list of books
+CREATE TABLE reading_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    book_id INTEGER NOT NULL REFERENCES books(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    uuid TEXT UNIQUE,
+    deleted_at TIMESTAMP
+);
+
+CREATE INDEX idx_reading_queue_book ON reading_queue(book_id);
+CREATE INDEX idx_reading_queue_position ON reading_queue(position);
@@ -0,0 +1,2 @@
+DROP INDEX idx_books_sort_key;
+ALTER TABLE books DROP COLUMN sort_key;
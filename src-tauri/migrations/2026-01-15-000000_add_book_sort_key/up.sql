@@ -0,0 +1,5 @@
+-- Normalized title used for a library order that is the same on every device.
+-- Existing rows are backfilled on startup, since the normalization lives in Rust.
+ALTER TABLE books ADD COLUMN sort_key TEXT;
+
+CREATE INDEX idx_books_sort_key ON books(sort_key);
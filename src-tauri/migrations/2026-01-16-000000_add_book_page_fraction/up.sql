@@ -0,0 +1,3 @@
+-- Fraction (0.0-1.0) of the current page scrolled past, for resuming mid-page in
+-- vertical/continuous mode. NULL means the top of the page.
+ALTER TABLE books ADD COLUMN page_fraction REAL;
@@ -0,0 +1,3 @@
+-- Pages left after dropping those ComicInfo.xml marks as ads, deleted or covers,
+-- for progress that ignores padding. NULL when the archive has no page metadata.
+ALTER TABLE books ADD COLUMN story_page_count INTEGER;
@@ -0,0 +1,3 @@
+-- Which hash file_hash holds: 'content' (SHA-256 of the images, the default) or
+-- 'file' (SHA-256 of the raw archive, from fast import until the content hash is backfilled)
+ALTER TABLE books ADD COLUMN hash_scheme TEXT NOT NULL DEFAULT 'content';
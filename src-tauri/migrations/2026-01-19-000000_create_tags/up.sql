@@ -0,0 +1,24 @@
+-- Lightweight labels for books; unlike collections they carry no description
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    uuid TEXT UNIQUE,
+    deleted_at TIMESTAMP
+);
+
+-- Junction table for many-to-many book-tag relationships
+CREATE TABLE book_tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    book_id INTEGER NOT NULL REFERENCES books(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    uuid TEXT UNIQUE,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP,
+    UNIQUE(book_id, tag_id)
+);
+
+CREATE INDEX idx_book_tags_book ON book_tags(book_id);
+CREATE INDEX idx_book_tags_tag ON book_tags(tag_id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_books_series;
+ALTER TABLE books DROP COLUMN volume_number;
+ALTER TABLE books DROP COLUMN series_name;
@@ -0,0 +1,6 @@
+-- Series membership: volumes share a series name and are ordered by volume number,
+-- which is fractional so side stories (e.g. 1.5) can sit between volumes
+ALTER TABLE books ADD COLUMN series_name TEXT;
+ALTER TABLE books ADD COLUMN volume_number DOUBLE;
+
+CREATE INDEX idx_books_series ON books(series_name);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_collections_parent;
+ALTER TABLE collections DROP COLUMN parent_id;
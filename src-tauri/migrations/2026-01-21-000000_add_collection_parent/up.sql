@@ -0,0 +1,5 @@
+-- Collections can be nested under another collection ("Manga > Shonen > Naruto").
+-- NULL means a top-level collection.
+ALTER TABLE collections ADD COLUMN parent_id INTEGER REFERENCES collections(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_collections_parent ON collections(parent_id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_reading_history_started;
+DROP INDEX IF EXISTS idx_reading_history_book;
+DROP TABLE IF EXISTS reading_history;
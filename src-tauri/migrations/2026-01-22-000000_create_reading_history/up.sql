@@ -0,0 +1,12 @@
+-- One row per reader session: the pages the reader was on when it opened and closed
+CREATE TABLE reading_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    book_id INTEGER NOT NULL REFERENCES books(id) ON DELETE CASCADE,
+    page_from INTEGER NOT NULL,
+    page_to INTEGER NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_reading_history_book ON reading_history(book_id);
+CREATE INDEX idx_reading_history_started ON reading_history(started_at);
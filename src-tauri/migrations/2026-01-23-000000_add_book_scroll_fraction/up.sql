@@ -0,0 +1,3 @@
+-- Fraction (0.0-1.0) of the whole strip scrolled past in vertical/webtoon mode, so
+-- resuming restores the exact scroll offset. NULL means no position was recorded.
+ALTER TABLE books ADD COLUMN scroll_fraction REAL;
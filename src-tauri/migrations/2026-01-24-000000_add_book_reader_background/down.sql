@@ -0,0 +1 @@
+ALTER TABLE book_settings DROP COLUMN reader_background;
@@ -0,0 +1,2 @@
+-- Per-book reader background override; NULL uses the reading.background setting
+ALTER TABLE book_settings ADD COLUMN reader_background TEXT CHECK(reader_background IN ('white', 'black', 'gray', 'sepia'));
@@ -0,0 +1 @@
+ALTER TABLE sync_state DROP COLUMN last_changes;
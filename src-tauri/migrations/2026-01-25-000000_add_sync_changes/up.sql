@@ -0,0 +1,3 @@
+-- What the last successful sync changed, for the recent changes feed.
+-- last_changes is a JSON array of change entries; NULL before the first sync.
+ALTER TABLE sync_state ADD COLUMN last_changes TEXT;
@@ -0,0 +1,3 @@
+-- Book whose content a fast-imported book was found to duplicate by the hash backfill,
+-- so the backfill doesn't hash it again while that book is still in the library
+ALTER TABLE books ADD COLUMN content_duplicate_of INTEGER;
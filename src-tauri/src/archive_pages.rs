@@ -0,0 +1,135 @@
+//! Which archive entries are pages, and in what order
+//!
+//! Import (page counts) and the comic:// protocol both use these, so the page count
+//! stored for a book matches the pages the reader shows.
+//!
+//! Content hashes are stored, synced and name the book files on Drive, so the
+//! entries they cover and their order are frozen at what hashing has always used
+//! (`is_hashed_entry`, `hash_order`) instead of following the page rules. ZIP, RAR
+//! and 7z go through the same ones, so the same pages hash the same whatever the
+//! container.
+
+use std::cmp::Ordering;
+
+/// Extensions of archive entries served as pages, lowercase
+const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".gif", ".webp", ".avif"];
+
+/// Extensions content hashes cover when an archive has pages of these types
+const HASHED_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png"];
+
+/// Folder the macOS archiver puts resource forks in
+const MACOS_METADATA_DIR: &str = "__MACOSX";
+
+/// Whether an archive entry is an image, judged by its extension
+pub fn is_image_file(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    IMAGE_EXTENSIONS.iter().any(|extension| lower.ends_with(extension))
+}
+
+/// Whether an archive entry is hidden or macOS metadata rather than content
+///
+/// Covers dot files and anything inside dot folders (including AppleDouble `._`
+/// files) and the `__MACOSX` resource fork folder added by the macOS archiver.
+/// Some archivers write `\` separators, so both are recognized.
+pub fn is_hidden_entry(name: &str) -> bool {
+    name.split(['/', '\\'])
+        .any(|component| component.starts_with('.') || component == MACOS_METADATA_DIR)
+}
+
+/// Whether an archive entry is a page: an image that isn't hidden or metadata
+pub fn is_page_entry(name: &str) -> bool {
+    is_image_file(name) && !is_hidden_entry(name)
+}
+
+/// Whether an archive entry is covered by the book's content hash
+///
+/// Hidden entries are judged by the rule hashing has always used, not `is_hidden_entry`.
+pub fn is_hashed_entry(name: &str) -> bool {
+    is_image_file(name) && !name.starts_with('.') && !name.contains("/.")
+}
+
+/// Pick the hashed entries, keyed by entry name, and put them in hash order
+///
+/// Byte order of the names, not reading order: "page10" hashes before "page2".
+/// Hashes covered JPEG and PNG only before GIF, WebP and AVIF pages were read, so
+/// those are left out whenever the archive has a JPEG or PNG; archives with none
+/// (which couldn't be imported before) hash all their images.
+pub fn hash_order<T>(mut entries: Vec<(String, T)>) -> Vec<(String, T)> {
+    let has_hashed_extension = |name: &str| {
+        let lower = name.to_lowercase();
+        HASHED_EXTENSIONS.iter().any(|extension| lower.ends_with(extension))
+    };
+    if entries.iter().any(|(name, _)| has_hashed_extension(name)) {
+        entries.retain(|(name, _)| has_hashed_extension(name));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+/// Reading order of two page names
+///
+/// Natural order, so "page2" comes before "page10".
+pub fn compare_pages(a: &str, b: &str) -> Ordering {
+    natord::compare(a, b)
+}
+
+/// Put pages, keyed by entry name, in reading order
+pub fn sort_pages<T>(pages: &mut [(String, T)]) {
+    pages.sort_by(|a, b| compare_pages(&a.0, &b.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_files() {
+        for name in ["001.jpg", "001.JPEG", "a/001.png", "001.gif", "001.webp", "001.AVIF"] {
+            assert!(is_image_file(name), "{}", name);
+        }
+        assert!(!is_image_file("ComicInfo.xml"));
+        assert!(!is_image_file("001.jpg.txt"));
+    }
+
+    #[test]
+    fn test_hidden_entries() {
+        assert!(is_hidden_entry(".cover.jpg"));
+        assert!(is_hidden_entry("chapter1/.thumb.jpg"));
+        assert!(is_hidden_entry("chapter1/._001.jpg"));
+        assert!(is_hidden_entry("__MACOSX/chapter1/001.jpg"));
+        assert!(is_hidden_entry("chapter1\\._001.jpg"));
+        assert!(!is_hidden_entry("chapter1/001.jpg"));
+        assert!(!is_hidden_entry("scans__MACOSX_edition/001.jpg"));
+        assert!(!is_hidden_entry("001.jpg"));
+    }
+
+    #[test]
+    fn test_page_entries() {
+        assert!(is_page_entry("chapter1/001.jpg"));
+        assert!(!is_page_entry("__MACOSX/chapter1/001.jpg"));
+        assert!(!is_page_entry("chapter1/ComicInfo.xml"));
+    }
+
+    #[test]
+    fn test_hash_order_is_frozen() {
+        assert!(is_hashed_entry("__MACOSX/chapter1/001.jpg"));
+        assert!(!is_hashed_entry("__MACOSX/chapter1/._001.jpg"));
+        assert!(!is_hashed_entry(".cover.jpg"));
+
+        let entries = vec![("page2.jpg".to_string(), ()), ("page10.jpg".to_string(), ()), ("page1.jpg".to_string(), ())];
+        let names: Vec<String> = hash_order(entries).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["page1.jpg", "page10.jpg", "page2.jpg"]);
+
+        let mixed = vec![("002.webp".to_string(), ()), ("001.PNG".to_string(), ())];
+        assert_eq!(hash_order(mixed).len(), 1);
+        let webp_only = vec![("002.webp".to_string(), ()), ("001.gif".to_string(), ())];
+        assert_eq!(hash_order(webp_only).len(), 2);
+    }
+
+    #[test]
+    fn test_compare_pages() {
+        let mut pages = vec!["page10.jpg", "page2.jpg", "page1.jpg"];
+        pages.sort_by(|a, b| compare_pages(a, b));
+        assert_eq!(pages, vec!["page1.jpg", "page2.jpg", "page10.jpg"]);
+    }
+}
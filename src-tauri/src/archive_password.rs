@@ -0,0 +1,120 @@
+//! Passwords of encrypted ZIP/CBZ archives
+//!
+//! Entry names of an encrypted ZIP are readable without the password, so page
+//! lists and counts work as usual; only reading an entry's content needs it.
+//! Passwords are held in memory for the session, keyed by book ID, and never
+//! written to disk. The frontend asks for one when a page read fails with
+//! `ArchiveEncrypted` and hands it over with `set_archive_password`.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::sync::RwLock;
+
+use zip::read::ZipFile;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::error::{AppError, ErrorCode};
+
+/// Message of the error for an encrypted entry read without a password
+pub const PASSWORD_REQUIRED: &str = "This archive is password protected";
+
+/// Message of the error for an encrypted entry read with the wrong password
+pub const WRONG_PASSWORD: &str = "Wrong password for this archive";
+
+/// Session passwords by book ID
+static PASSWORDS: RwLock<Option<HashMap<i32, String>>> = RwLock::new(None);
+
+/// Remember a book's password for this session; `None` or an empty one forgets it
+pub fn set(book_id: i32, password: Option<String>) {
+    let mut passwords = PASSWORDS.write().unwrap_or_else(|e| e.into_inner());
+    match password.filter(|p| !p.is_empty()) {
+        Some(password) => {
+            passwords.get_or_insert_with(HashMap::new).insert(book_id, password);
+        }
+        None => {
+            if let Some(passwords) = passwords.as_mut() {
+                passwords.remove(&book_id);
+            }
+        }
+    }
+}
+
+/// A book's password, if one was entered this session
+pub fn get(book_id: i32) -> Option<String> {
+    let passwords = PASSWORDS.read().unwrap_or_else(|e| e.into_inner());
+    passwords.as_ref()?.get(&book_id).cloned()
+}
+
+/// Whether an error message comes from a missing or wrong password
+pub fn is_password_error(message: &str) -> bool {
+    message == PASSWORD_REQUIRED || message == WRONG_PASSWORD
+}
+
+/// Index of the first encrypted entry of a ZIP archive, if it has any
+pub fn first_encrypted_entry<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<usize> {
+    (0..archive.len()).find(|&i| archive.by_index_raw(i).is_ok_and(|entry| entry.encrypted()))
+}
+
+/// Open the ZIP entry at `index` for reading, decrypting it if it is encrypted
+///
+/// Fails with `ArchiveEncrypted` if the entry is encrypted and `password` is
+/// missing or wrong.
+pub fn zip_entry<'a, R: Read + Seek>(
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> Result<ZipFile<'a>, AppError> {
+    let encrypted = archive
+        .by_index_raw(index)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to read archive entry: {}", e)))?
+        .encrypted();
+
+    let entry = match (encrypted, password) {
+        (false, _) => archive.by_index(index),
+        (true, None) => return Err(AppError::archive_encrypted(PASSWORD_REQUIRED)),
+        (true, Some(password)) => archive.by_index_decrypt(index, password.as_bytes()),
+    };
+    entry.map_err(|e| match e {
+        ZipError::InvalidPassword => AppError::archive_encrypted(WRONG_PASSWORD),
+        e => AppError::new(ErrorCode::IoError, format!("Failed to read archive entry: {}", e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_zip_entry_needs_the_password() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let locked = zip::write::SimpleFileOptions::default()
+            .with_aes_encryption(zip::AesMode::Aes256, "open sesame");
+        zip.start_file("001.jpg", locked).unwrap();
+        zip.write_all(b"secret page").unwrap();
+        let mut archive = ZipArchive::new(zip.finish().unwrap()).unwrap();
+
+        assert_eq!(first_encrypted_entry(&mut archive), Some(0));
+        let missing = zip_entry(&mut archive, 0, None).err().unwrap();
+        assert!(matches!(missing.code, ErrorCode::ArchiveEncrypted));
+        assert!(is_password_error(&missing.message));
+        let wrong = zip_entry(&mut archive, 0, Some("guess")).err().unwrap();
+        assert_eq!(wrong.message, WRONG_PASSWORD);
+
+        let mut page = Vec::new();
+        zip_entry(&mut archive, 0, Some("open sesame"))
+            .unwrap()
+            .read_to_end(&mut page)
+            .unwrap();
+        assert_eq!(page, b"secret page");
+    }
+
+    #[test]
+    fn test_session_passwords() {
+        set(-10, Some("hunter2".to_string()));
+        assert_eq!(get(-10).as_deref(), Some("hunter2"));
+        set(-10, Some(String::new()));
+        assert_eq!(get(-10), None);
+    }
+}
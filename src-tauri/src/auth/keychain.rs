@@ -0,0 +1,60 @@
+//! Vault keys in the OS credential store
+//!
+//! Keys set by `rotate_vault_key` are kept in the platform's credential store
+//! (Keychain, Credential Manager, Secret Service) instead of a file next to the
+//! vault, so a copy of the app data folder doesn't carry the key to its vault.
+//! Android has no such store here: nothing is ever found and keys can't be saved.
+
+use crate::error::AppError;
+
+/// Whether this platform has a credential store to keep keys in
+pub const AVAILABLE: bool = cfg!(not(target_os = "android"));
+
+/// Service the entries are stored under (the app identifier)
+#[cfg(not(target_os = "android"))]
+const SERVICE: &str = "com.yomiyougu.app";
+
+#[cfg(not(target_os = "android"))]
+fn entry(name: &str) -> Result<keyring::Entry, AppError> {
+    keyring::Entry::new(SERVICE, name).map_err(AppError::config_read_failed)
+}
+
+/// Key stored under `name`, if there is one
+#[cfg(not(target_os = "android"))]
+pub fn get(name: &str) -> Result<Option<String>, AppError> {
+    match entry(name)?.get_password() {
+        Ok(key) => Ok(Some(key).filter(|key| !key.is_empty())),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::config_read_failed(e)),
+    }
+}
+
+/// Store `key` under `name`, replacing any previous one
+#[cfg(not(target_os = "android"))]
+pub fn set(name: &str, key: &str) -> Result<(), AppError> {
+    entry(name)?.set_password(key).map_err(AppError::config_write_failed)
+}
+
+/// Remove the key stored under `name`; a missing one is not an error
+#[cfg(not(target_os = "android"))]
+pub fn delete(name: &str) -> Result<(), AppError> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AppError::config_write_failed(e)),
+    }
+}
+
+#[cfg(target_os = "android")]
+pub fn get(_name: &str) -> Result<Option<String>, AppError> {
+    Ok(None)
+}
+
+#[cfg(target_os = "android")]
+pub fn set(_name: &str, _key: &str) -> Result<(), AppError> {
+    Err(AppError::invalid_input("credential keys can't be stored on this device"))
+}
+
+#[cfg(target_os = "android")]
+pub fn delete(_name: &str) -> Result<(), AppError> {
+    Ok(())
+}
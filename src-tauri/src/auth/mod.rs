@@ -3,6 +3,7 @@
 //! Handles storage and retrieval of OAuth tokens for Google Drive sync.
 //! Uses secure file storage in the app's config directory.
 
+mod keychain;
 mod storage;
 mod types;
 
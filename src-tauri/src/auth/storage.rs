@@ -2,18 +2,43 @@
 //!
 //! Uses IOTA Stronghold for credential storage.
 //! Tokens are stored in an encrypted vault with argon2 key derivation.
+//!
+//! Each Google account's token has its own vault entry, keyed by email, and the
+//! account in use is recorded in a small pointer file. Tokens stored by the older
+//! single-account layout are moved into this layout on first access.
+//!
+//! The vault key is the one `rotate_vault_key` saved to the OS keychain (see
+//! [`keychain`]), or else `STRONGHOLD_VAULT_PASSWORD` or the built-in default. Keys
+//! older versions saved to a file next to the vault are moved into the keychain. A vault that can't be
+//! decrypted with the current key (e.g. after the key changed) is deleted instead
+//! of failing every call, and the user is asked to sign in again. Other errors
+//! opening the vault (I/O, a locked file) leave it alone.
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 use tauri::Manager;
 use tauri_plugin_stronghold::stronghold::Stronghold;
 
-use super::types::{AuthStatus, AuthToken};
-use crate::error::AppError;
+use super::keychain;
+use super::types::{AccountSummary, AuthStatus, AuthToken};
+use crate::error::{AppError, ErrorCode};
 
 const VAULT_FILENAME: &str = "credentials.hold";
 const SALT_FILENAME: &str = "salt.txt";
+/// Keychain entry holding the key set by `rotate_vault_key`
+const KEY_ENTRY: &str = "vault_key";
+/// Keychain entry a new key is staged in while the vault is replaced
+const PENDING_KEY_ENTRY: &str = "vault_key_pending";
+/// File older versions kept the rotated key in
+const LEGACY_KEY_FILENAME: &str = "vault_key.txt";
+const CLIENT_NAME: &[u8] = b"auth_client";
+
+/// Pointer file naming the active account
+const ACTIVE_ACCOUNT_FILENAME: &str = "active_account.txt";
+
+/// Account used for tokens without an email
+const DEFAULT_ACCOUNT: &str = "default";
 
 static TOKEN_KEY: LazyLock<String> = LazyLock::new(|| {
     std::env::var("STRONGHOLD_TOKEN_KEY").unwrap_or_else(|_| "google_oauth_token".to_string())
@@ -22,6 +47,63 @@ static VAULT_PASSWORD: LazyLock<String> = LazyLock::new(|| {
     std::env::var("STRONGHOLD_VAULT_PASSWORD").unwrap_or_else(|_| "yomiyougu_secure_vault_2025".to_string())
 });
 
+/// Start of the Stronghold error for a snapshot the key doesn't decrypt
+const DECRYPT_FAILURE: &str = "failed to decode/decrypt age content";
+
+/// Current vault key: the rotated key if there is one, else `VAULT_PASSWORD`
+///
+/// A keychain that can't be read is an error rather than a fallback to the
+/// default, which would get a rotated vault deleted as undecryptable.
+fn vault_password(app: &tauri::AppHandle) -> Result<String, AppError> {
+    migrate_key_files(app)?;
+    Ok(keychain::get(KEY_ENTRY)?.unwrap_or_else(|| VAULT_PASSWORD.clone()))
+}
+
+fn get_legacy_key_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_local_data_dir()
+        .map(|path| path.join(LEGACY_KEY_FILENAME))
+        .map_err(AppError::config_read_failed)
+}
+
+/// Move keys older versions wrote next to the vault into the keychain
+///
+/// Without a keychain the file is only removed; the vault then can't be decrypted
+/// and is reset, asking the user to sign in again.
+fn migrate_key_files(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let key_path = get_legacy_key_path(app)?;
+    for (path, entry) in [(pending_key_path(&key_path), PENDING_KEY_ENTRY), (key_path, KEY_ENTRY)] {
+        if !path.exists() {
+            continue;
+        }
+        match read_key_file(&path) {
+            Some(key) if keychain::AVAILABLE => {
+                keychain::set(entry, &key)?;
+                log::info!("Moved the credential key in {:?} to the keychain", path);
+            }
+            _ => log::warn!("Dropping the credential key in {:?}", path),
+        }
+        fs::remove_file(&path).map_err(AppError::config_write_failed)?;
+    }
+    Ok(())
+}
+
+/// Key of a key file, if it exists and isn't blank
+fn read_key_file(path: &Path) -> Option<String> {
+    let key = fs::read_to_string(path).ok()?;
+    Some(key.trim_end_matches('\n').to_string()).filter(|key| !key.is_empty())
+}
+
+/// Path older versions staged a key file at before it replaced the current one
+fn pending_key_path(key_path: &Path) -> PathBuf {
+    key_path.with_extension("txt.new")
+}
+
+/// Whether a Stronghold error means the vault was opened with the wrong key
+fn is_decrypt_failure(error: &impl std::fmt::Display) -> bool {
+    error.to_string().contains(DECRYPT_FAILURE)
+}
+
 fn get_vault_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
     app.path()
         .app_local_data_dir()
@@ -70,17 +152,295 @@ fn hash_password(password: &str, salt_path: &PathBuf) -> Result<Vec<u8>, AppErro
     Ok(hash_bytes.as_bytes().to_vec())
 }
 
-/// Open or create the Stronghold vault
+/// Open or create the Stronghold vault with the current key
+///
+/// A rotation interrupted after the vault was replaced leaves the new key staged;
+/// if the current key doesn't open the vault, the staged one is tried and kept.
 fn open_vault(app: &tauri::AppHandle) -> Result<Stronghold, AppError> {
     let vault_path = get_vault_path(app)?;
     let salt_path = get_salt_path(app)?;
-    let password = hash_password(&VAULT_PASSWORD, &salt_path)?;
+
+    match open_vault_at(&vault_path, &salt_path, &vault_password(app)?) {
+        Err(e) if matches!(e.code, ErrorCode::CredentialsUnreadable) => {
+            let Some(pending_key) = keychain::get(PENDING_KEY_ENTRY)? else {
+                return Err(e);
+            };
+            let stronghold = open_vault_at(&vault_path, &salt_path, &pending_key)?;
+            keychain::set(KEY_ENTRY, &pending_key)?;
+            keychain::delete(PENDING_KEY_ENTRY)?;
+            log::info!("Finished an interrupted credential key rotation");
+            Ok(stronghold)
+        }
+        result => result,
+    }
+}
+
+/// Open or create a Stronghold vault file with the given key
+///
+/// Fails with `CredentialsUnreadable` only if the file exists and the key doesn't
+/// decrypt it.
+fn open_vault_at(vault_path: &PathBuf, salt_path: &PathBuf, key: &str) -> Result<Stronghold, AppError> {
+    let password = hash_password(key, salt_path)?;
 
     if let Some(parent) = vault_path.parent() {
         fs::create_dir_all(parent).map_err(AppError::config_write_failed)?;
     }
 
-    Stronghold::new(&vault_path, password).map_err(|e| AppError::config_read_failed(e.to_string()))
+    Stronghold::new(vault_path, password).map_err(|e| {
+        if vault_path.exists() && is_decrypt_failure(&e) {
+            AppError::credentials_unreadable(e)
+        } else {
+            AppError::config_read_failed(e.to_string())
+        }
+    })
+}
+
+/// Open the vault, deleting it first if it can't be decrypted
+///
+/// Used where losing the stored token is acceptable (saving a new one, logout).
+fn open_or_reset_vault(app: &tauri::AppHandle) -> Result<Stronghold, AppError> {
+    match open_vault(app) {
+        Err(e) if matches!(e.code, ErrorCode::CredentialsUnreadable) => {
+            reset_vault(app)?;
+            open_vault(app)
+        }
+        result => result,
+    }
+}
+
+/// Delete an unreadable vault so a new one is created on next use
+fn reset_vault(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let vault_path = get_vault_path(app)?;
+    log::warn!("Credential vault {:?} can't be decrypted, clearing it", vault_path);
+    if vault_path.exists() {
+        fs::remove_file(&vault_path).map_err(AppError::config_write_failed)?;
+    }
+    Ok(())
+}
+
+/// Store key of one account's token
+fn account_token_key(account: &str) -> Vec<u8> {
+    format!("{}:{}", *TOKEN_KEY, account).into_bytes()
+}
+
+/// Store key of the account list
+fn account_index_key() -> Vec<u8> {
+    format!("{}#accounts", *TOKEN_KEY).into_bytes()
+}
+
+/// Account a token is stored under: its email, or `DEFAULT_ACCOUNT` when unknown
+fn account_id(token: &AuthToken) -> String {
+    token
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|email| !email.is_empty())
+        .unwrap_or(DEFAULT_ACCOUNT)
+        .to_string()
+}
+
+/// The active account: the recorded one if it's still known, otherwise the first
+fn choose_active_account(accounts: &[String], recorded: Option<&str>) -> Option<String> {
+    recorded
+        .filter(|recorded| accounts.iter().any(|account| account == recorded))
+        .or_else(|| accounts.first().map(String::as_str))
+        .map(str::to_string)
+}
+
+fn get_active_account_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_local_data_dir()
+        .map(|path| path.join(ACTIVE_ACCOUNT_FILENAME))
+        .map_err(AppError::config_read_failed)
+}
+
+/// Account recorded as active in the pointer file
+fn read_active_account(app: &tauri::AppHandle) -> Option<String> {
+    let path = get_active_account_path(app).ok()?;
+    let account = fs::read_to_string(path).ok()?;
+    Some(account.trim().to_string()).filter(|account| !account.is_empty())
+}
+
+/// Record the active account, or remove the pointer file when there is none
+fn write_active_account(app: &tauri::AppHandle, account: Option<&str>) -> Result<(), AppError> {
+    let path = get_active_account_path(app)?;
+    match account {
+        Some(account) => fs::write(&path, account).map_err(AppError::config_write_failed),
+        None if path.exists() => fs::remove_file(&path).map_err(AppError::config_write_failed),
+        None => Ok(()),
+    }
+}
+
+/// Read one entry from an open vault
+fn read_entry(stronghold: &Stronghold, key: &[u8]) -> Result<Option<Vec<u8>>, AppError> {
+    let client = stronghold.load_client(CLIENT_NAME)
+        .or_else(|_| stronghold.create_client(CLIENT_NAME))
+        .map_err(|e| AppError::config_read_failed(e.to_string()))?;
+
+    client
+        .store()
+        .get(key)
+        .map_err(|e| AppError::config_read_failed(e.to_string()))
+}
+
+/// Insert and delete entries in an open vault, then save it to disk
+fn write_entries(
+    stronghold: &Stronghold,
+    inserts: Vec<(Vec<u8>, Vec<u8>)>,
+    deletes: Vec<Vec<u8>>,
+) -> Result<(), AppError> {
+    let client = stronghold.load_client(CLIENT_NAME)
+        .or_else(|_| stronghold.create_client(CLIENT_NAME))
+        .map_err(|e| AppError::config_read_failed(e.to_string()))?;
+
+    let store = client.store();
+    for (key, value) in inserts {
+        store
+            .insert(key, value, None)
+            .map_err(|e| AppError::config_write_failed(e.to_string()))?;
+    }
+    for key in deletes {
+        let _ = store.delete(&key);
+    }
+
+    stronghold.write_client(CLIENT_NAME)
+        .map_err(|e| AppError::config_write_failed(e.to_string()))?;
+
+    stronghold
+        .save()
+        .map_err(|e| AppError::config_write_failed(e.to_string()))
+}
+
+fn parse_token(bytes: Vec<u8>) -> Result<AuthToken, AppError> {
+    let json = String::from_utf8(bytes).map_err(|e| AppError::config_parse_failed(e.to_string()))?;
+    serde_json::from_str(&json).map_err(AppError::config_parse_failed)
+}
+
+/// Accounts with a stored token, in sign-in order
+fn read_accounts(stronghold: &Stronghold) -> Result<Vec<String>, AppError> {
+    let Some(bytes) = read_entry(stronghold, &account_index_key())? else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_slice(&bytes).map_err(AppError::config_parse_failed)
+}
+
+fn account_index_entry(accounts: &[String]) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    let json = serde_json::to_vec(accounts).map_err(AppError::serialization_failed)?;
+    Ok((account_index_key(), json))
+}
+
+fn read_account_token(stronghold: &Stronghold, account: &str) -> Result<Option<AuthToken>, AppError> {
+    read_entry(stronghold, &account_token_key(account))?
+        .map(parse_token)
+        .transpose()
+}
+
+/// Move a token stored by the single-account layout into its account's entry
+///
+/// The migrated account becomes the active one unless another is already recorded.
+fn migrate_legacy_token(app: &tauri::AppHandle, stronghold: &Stronghold) -> Result<(), AppError> {
+    let Some(bytes) = read_entry(stronghold, TOKEN_KEY.as_bytes())? else {
+        return Ok(());
+    };
+
+    let token = match parse_token(bytes.clone()) {
+        Ok(token) => token,
+        Err(e) => {
+            log::warn!("Dropping unreadable single-account token: {}", e);
+            return write_entries(stronghold, Vec::new(), vec![TOKEN_KEY.as_bytes().to_vec()]);
+        }
+    };
+
+    let account = account_id(&token);
+    let mut accounts = read_accounts(stronghold)?;
+    if !accounts.contains(&account) {
+        accounts.push(account.clone());
+    }
+    write_entries(
+        stronghold,
+        vec![(account_token_key(&account), bytes), account_index_entry(&accounts)?],
+        vec![TOKEN_KEY.as_bytes().to_vec()],
+    )?;
+    if read_active_account(app).is_none() {
+        write_active_account(app, Some(&account))?;
+    }
+
+    log::info!("Migrated stored token to account '{}'", account);
+    Ok(())
+}
+
+/// Open the vault for reading accounts, migrating the single-account layout
+///
+/// An unreadable vault is cleared and reported as `CredentialsUnreadable`.
+fn open_accounts(app: &tauri::AppHandle) -> Result<Stronghold, AppError> {
+    let stronghold = match open_vault(app) {
+        Err(e) if matches!(e.code, ErrorCode::CredentialsUnreadable) => {
+            reset_vault(app)?;
+            return Err(e);
+        }
+        result => result?,
+    };
+    migrate_legacy_token(app, &stronghold)?;
+    Ok(stronghold)
+}
+
+/// Re-encrypt the stored credentials under a new key
+///
+/// The vault is read with `old_key` and written to a new file under `new_key`,
+/// which then replaces the old vault, so a failure part-way leaves the old vault
+/// intact. The new key is saved to the OS keychain and used from then on, taking
+/// precedence over `STRONGHOLD_VAULT_PASSWORD`. It is staged in its own keychain
+/// entry before the vault is replaced, so `open_vault` can finish an interrupted
+/// rotation. Fails on devices without a keychain (Android).
+pub fn rotate_vault_key(app: &tauri::AppHandle, old_key: &str, new_key: &str) -> Result<(), AppError> {
+    if new_key.is_empty() {
+        return Err(AppError::invalid_input("new credential key can't be empty"));
+    }
+    if old_key == new_key {
+        return Err(AppError::invalid_input("new credential key must differ from the old one"));
+    }
+
+    let vault_path = get_vault_path(app)?;
+    let salt_path = get_salt_path(app)?;
+    let rotated_path = vault_path.with_extension("hold.new");
+
+    // Every entry: the account list, each account's token and a not yet migrated token
+    let old_vault = open_vault_at(&vault_path, &salt_path, old_key)?;
+    let mut keys = vec![TOKEN_KEY.as_bytes().to_vec(), account_index_key()];
+    keys.extend(read_accounts(&old_vault)?.iter().map(|account| account_token_key(account)));
+    let mut entries = Vec::new();
+    for key in keys {
+        if let Some(value) = read_entry(&old_vault, &key)? {
+            entries.push((key, value));
+        }
+    }
+    drop(old_vault);
+
+    if rotated_path.exists() {
+        fs::remove_file(&rotated_path).map_err(AppError::config_write_failed)?;
+    }
+    let new_vault = open_vault_at(&rotated_path, &salt_path, new_key)?;
+    let written = write_entries(&new_vault, entries, Vec::new());
+    drop(new_vault);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&rotated_path);
+        return Err(e);
+    }
+
+    if let Err(e) = keychain::set(PENDING_KEY_ENTRY, new_key) {
+        let _ = fs::remove_file(&rotated_path);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&rotated_path, &vault_path) {
+        let _ = fs::remove_file(&rotated_path);
+        let _ = keychain::delete(PENDING_KEY_ENTRY);
+        return Err(AppError::config_write_failed(e));
+    }
+    keychain::set(KEY_ENTRY, new_key)?;
+    keychain::delete(PENDING_KEY_ENTRY)?;
+
+    log::info!("Credential vault re-encrypted with a new key");
+    Ok(())
 }
 
 /// Check if user is authenticated (has valid token or can refresh)
@@ -95,6 +455,10 @@ pub fn get_auth_status(app: &tauri::AppHandle) -> Result<AuthStatus, AppError> {
             );
             Ok(AuthStatus::from_token(&token))
         }
+        Err(e) if matches!(e.code, ErrorCode::CredentialsUnreadable) => Ok(AuthStatus {
+            credentials_reset: true,
+            ..AuthStatus::not_authenticated()
+        }),
         Err(e) => {
             log::debug!("Failed to load token: {:?}", e);
             Ok(AuthStatus::not_authenticated())
@@ -102,84 +466,166 @@ pub fn get_auth_status(app: &tauri::AppHandle) -> Result<AuthStatus, AppError> {
     }
 }
 
-/// Load OAuth token from secure storage
+/// Load the active account's OAuth token from secure storage
+///
+/// An unreadable vault is cleared and reported as `CredentialsUnreadable`, so the
+/// caller can ask the user to sign in again instead of failing on every call.
 pub fn load_token(app: &tauri::AppHandle) -> Result<AuthToken, AppError> {
     log::debug!("Loading token from Stronghold vault...");
-    let stronghold = open_vault(app)?;
-    
-    let client_name = b"auth_client";
-    let client = stronghold.load_client(client_name)
-        .or_else(|_| stronghold.create_client(client_name))
-        .map_err(|e| AppError::config_read_failed(e.to_string()))?;
-    
-    let store = client.store();
-    let data = store
-        .get(TOKEN_KEY.as_bytes())
-        .map_err(|e| AppError::config_read_failed(e.to_string()))?;
+    let stronghold = open_accounts(app)?;
+    let accounts = read_accounts(&stronghold)?;
 
-    log::debug!("Token data from store: {:?}", data.as_ref().map(|d| d.len()));
+    let Some(account) = choose_active_account(&accounts, read_active_account(app).as_deref()) else {
+        log::debug!("No token found in store");
+        return Err(AppError::not_authenticated());
+    };
 
-    match data {
-        Some(bytes) => {
-            let json =
-                String::from_utf8(bytes).map_err(|e| AppError::config_parse_failed(e.to_string()))?;
-            log::debug!("Token JSON loaded: {} bytes", json.len());
-            let token: AuthToken =
-                serde_json::from_str(&json).map_err(AppError::config_parse_failed)?;
-            Ok(token)
-        }
+    match read_account_token(&stronghold, &account)? {
+        Some(token) => Ok(token),
         None => {
-            log::debug!("No token found in store");
+            log::debug!("No token found for account '{}'", account);
             Err(AppError::not_authenticated())
         }
     }
 }
 
+/// Store a token under its account and make that account the active one
 pub fn save_token(app: &tauri::AppHandle, token: &AuthToken) -> Result<(), AppError> {
-    let stronghold = open_vault(app)?;
-    
-    let client_name = b"auth_client";
-    let client = stronghold.load_client(client_name)
-        .or_else(|_| stronghold.create_client(client_name))
-        .map_err(|e| AppError::config_read_failed(e.to_string()))?;
-    
-    let store = client.store();
+    let stronghold = open_or_reset_vault(app)?;
+    migrate_legacy_token(app, &stronghold)?;
 
-    let json = serde_json::to_string(token).map_err(AppError::serialization_failed)?;
-
-    store
-        .insert(TOKEN_KEY.as_bytes().to_vec(), json.into_bytes(), None)
-        .map_err(|e| AppError::config_write_failed(e.to_string()))?;
-
-    stronghold.write_client(client_name)
-        .map_err(|e| AppError::config_write_failed(e.to_string()))?;
+    let account = account_id(token);
+    let mut accounts = read_accounts(&stronghold)?;
+    if !accounts.contains(&account) {
+        accounts.push(account.clone());
+    }
 
-    stronghold
-        .save()
-        .map_err(|e| AppError::config_write_failed(e.to_string()))?;
+    let json = serde_json::to_string(token).map_err(AppError::serialization_failed)?;
+    write_entries(
+        &stronghold,
+        vec![(account_token_key(&account), json.into_bytes()), account_index_entry(&accounts)?],
+        Vec::new(),
+    )?;
+    write_active_account(app, Some(&account))?;
 
     log::info!("OAuth token securely stored in Stronghold vault");
     Ok(())
 }
 
-/// Clear stored OAuth token (logout)
+/// Clear the active account's OAuth token (logout)
+///
+/// The next remaining account, if any, becomes active.
 pub fn clear_token(app: &tauri::AppHandle) -> Result<(), AppError> {
-    let stronghold = open_vault(app)?;
-    
-    let client_name = b"auth_client";
-    let client = stronghold.load_client(client_name)
-        .or_else(|_| stronghold.create_client(client_name))
-        .map_err(|e| AppError::config_read_failed(e.to_string()))?;
-    
-    let store = client.store();
+    let stronghold = open_or_reset_vault(app)?;
+    migrate_legacy_token(app, &stronghold)?;
 
-    let _ = store.delete(TOKEN_KEY.as_bytes());
+    let mut accounts = read_accounts(&stronghold)?;
+    let Some(account) = choose_active_account(&accounts, read_active_account(app).as_deref()) else {
+        write_active_account(app, None)?;
+        return Ok(());
+    };
 
-    let _ = stronghold.write_client(client_name);
-    stronghold
-        .save()
-        .map_err(|e| AppError::config_write_failed(e.to_string()))?;
+    accounts.retain(|known| *known != account);
+    write_entries(
+        &stronghold,
+        vec![account_index_entry(&accounts)?],
+        vec![account_token_key(&account)],
+    )?;
+    write_active_account(app, accounts.first().map(String::as_str))?;
 
     log::info!("OAuth token cleared from Stronghold vault");
     Ok(())
 }
+
+/// Accounts with a stored token, in sign-in order
+pub fn list_accounts(app: &tauri::AppHandle) -> Result<Vec<AccountSummary>, AppError> {
+    let stronghold = match open_accounts(app) {
+        Err(e) if matches!(e.code, ErrorCode::CredentialsUnreadable) => return Ok(Vec::new()),
+        result => result?,
+    };
+    let accounts = read_accounts(&stronghold)?;
+    let active = choose_active_account(&accounts, read_active_account(app).as_deref());
+
+    let mut summaries = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        let display_name = read_account_token(&stronghold, &account)?.and_then(|token| token.display_name);
+        summaries.push(AccountSummary {
+            is_active: active.as_deref() == Some(account.as_str()),
+            email: account,
+            display_name,
+        });
+    }
+    Ok(summaries)
+}
+
+/// Make another signed-in account the active one
+pub fn switch_account(app: &tauri::AppHandle, email: &str) -> Result<AuthStatus, AppError> {
+    let stronghold = open_accounts(app)?;
+    let accounts = read_accounts(&stronghold)?;
+    if !accounts.iter().any(|account| account == email) {
+        return Err(AppError::invalid_input(format!("no signed-in account '{}'", email)));
+    }
+
+    let token = read_account_token(&stronghold, email)?.ok_or_else(AppError::not_authenticated)?;
+    write_active_account(app, Some(email))?;
+
+    log::info!("Switched active account to '{}'", email);
+    Ok(AuthStatus::from_token(&token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_active_account() {
+        let accounts = vec!["a@example.com".to_string(), "b@example.com".to_string()];
+        assert_eq!(
+            choose_active_account(&accounts, Some("b@example.com")).as_deref(),
+            Some("b@example.com")
+        );
+        // A pointer to a removed account falls back to the first one
+        assert_eq!(
+            choose_active_account(&accounts, Some("gone@example.com")).as_deref(),
+            Some("a@example.com")
+        );
+        assert_eq!(choose_active_account(&accounts, None).as_deref(), Some("a@example.com"));
+        assert_eq!(choose_active_account(&[], Some("a@example.com")), None);
+    }
+
+    #[test]
+    fn test_legacy_key_file() {
+        let dir = std::env::temp_dir().join(format!("yomiyougu_vault_key_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let key_path = dir.join(LEGACY_KEY_FILENAME);
+
+        assert_eq!(read_key_file(&key_path), None);
+        fs::write(&key_path, "").unwrap();
+        assert_eq!(read_key_file(&key_path), None);
+        fs::write(&key_path, "rotated key\n").unwrap();
+        assert_eq!(read_key_file(&key_path).as_deref(), Some("rotated key"));
+        assert_eq!(pending_key_path(&key_path), dir.join("vault_key.txt.new"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_only_decrypt_failures_reset_the_vault() {
+        assert!(is_decrypt_failure(
+            &"inner error occurred(invalid file failed to decode/decrypt age content NoMatchingKeys)"
+        ));
+        assert!(!is_decrypt_failure(&"inner error occurred(I/O error: Permission denied (os error 13))"));
+    }
+
+    #[test]
+    fn test_account_id() {
+        let mut token = AuthToken::new("token".to_string());
+        assert_eq!(account_id(&token), DEFAULT_ACCOUNT);
+
+        token.email = Some("  ".to_string());
+        assert_eq!(account_id(&token), DEFAULT_ACCOUNT);
+
+        token.email = Some("reader@example.com".to_string());
+        assert_eq!(account_id(&token), "reader@example.com");
+    }
+}
@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Lifetime assumed for an access token when Google doesn't report `expires_in`
+pub const DEFAULT_TOKEN_LIFETIME_SECS: i64 = 3600;
+
 /// Stored OAuth token information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -31,6 +34,18 @@ impl AuthToken {
         }
     }
 
+    /// Expiry timestamp for a token issued now that lives `expires_in` seconds
+    ///
+    /// Falls back to `DEFAULT_TOKEN_LIFETIME_SECS` when the lifetime is unknown, so
+    /// the token is still refreshed instead of being treated as valid forever.
+    pub fn expires_at_from_now(expires_in: Option<i64>) -> i64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        now + expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS)
+    }
+
     /// Check if the access token is expired
     pub fn is_expired(&self) -> bool {
         match self.expires_at {
@@ -55,6 +70,20 @@ impl AuthToken {
     pub fn is_authenticated(&self) -> bool {
         !self.is_expired() || self.can_refresh()
     }
+
+    /// OAuth client ID and secret to refresh with, if the token can be refreshed
+    pub fn refresh_client(&self) -> Option<(String, String)> {
+        if !self.can_refresh() {
+            return None;
+        }
+        Some((self.client_id.clone()?, self.client_secret.clone()?))
+    }
+
+    /// Update the account details from a userinfo response, keeping values it leaves out
+    pub fn update_profile(&mut self, email: Option<String>, display_name: Option<String>) {
+        self.email = email.or(self.email.take());
+        self.display_name = display_name.or(self.display_name.take());
+    }
 }
 
 /// Authentication status for the frontend
@@ -66,6 +95,9 @@ pub struct AuthStatus {
     pub needs_refresh: bool,
     pub email: Option<String>,
     pub display_name: Option<String>,
+    /// Whether stored credentials were unreadable and had to be cleared
+    #[serde(default)]
+    pub credentials_reset: bool,
 }
 
 impl AuthStatus {
@@ -75,6 +107,7 @@ impl AuthStatus {
             needs_refresh: false,
             email: None,
             display_name: None,
+            credentials_reset: false,
         }
     }
 
@@ -85,10 +118,33 @@ impl AuthStatus {
             needs_refresh: token.is_expired() && token.can_refresh(),
             email: token.email.clone(),
             display_name: token.display_name.clone(),
+            credentials_reset: false,
         }
     }
 }
 
+/// A signed-in Google account, for the account switcher
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSummary {
+    /// Account email, or "default" for a token stored without one
+    pub email: String,
+    pub display_name: Option<String>,
+    /// Whether this account is used for sync
+    pub is_active: bool,
+}
+
+/// Result of checking the stored token against Google
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenValidation {
+    /// Whether Google accepted the access token
+    pub valid: bool,
+    /// Whether the token was refreshed during validation
+    pub refreshed: bool,
+    pub status: AuthStatus,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,6 +158,38 @@ mod tests {
         assert!(token.client_id.is_none());
     }
 
+    #[test]
+    fn test_refresh_client_needs_refresh_token_and_client() {
+        let mut token = AuthToken::new("test".to_string());
+        token.client_id = Some("client".to_string());
+        token.client_secret = Some("secret".to_string());
+        assert_eq!(token.refresh_client(), None);
+
+        token.refresh_token = Some(String::new());
+        assert_eq!(token.refresh_client(), None);
+
+        token.refresh_token = Some("refresh".to_string());
+        assert_eq!(token.refresh_client(), Some(("client".to_string(), "secret".to_string())));
+
+        token.client_secret = None;
+        assert_eq!(token.refresh_client(), None);
+    }
+
+    #[test]
+    fn test_update_profile_keeps_missing_fields() {
+        let mut token = AuthToken::new("test".to_string());
+        token.email = Some("old@example.com".to_string());
+        token.display_name = Some("Old Name".to_string());
+
+        token.update_profile(Some("new@example.com".to_string()), None);
+        assert_eq!(token.email.as_deref(), Some("new@example.com"));
+        assert_eq!(token.display_name.as_deref(), Some("Old Name"));
+
+        token.update_profile(None, Some("New Name".to_string()));
+        assert_eq!(token.email.as_deref(), Some("new@example.com"));
+        assert_eq!(token.display_name.as_deref(), Some("New Name"));
+    }
+
     #[test]
     fn test_auth_token_not_expired_when_no_expiry() {
         let token = AuthToken::new("test".to_string());
@@ -136,6 +224,24 @@ mod tests {
         assert!(!token.is_expired());
     }
 
+    #[test]
+    fn test_expires_at_defaults_when_lifetime_missing() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let reported = AuthToken::expires_at_from_now(Some(120));
+        assert!((now + 120..=now + 121).contains(&reported));
+
+        let defaulted = AuthToken::expires_at_from_now(None);
+        assert!((now + DEFAULT_TOKEN_LIFETIME_SECS..=now + DEFAULT_TOKEN_LIFETIME_SECS + 1).contains(&defaulted));
+
+        let mut token = AuthToken::new("test".to_string());
+        token.expires_at = Some(defaulted);
+        assert!(!token.is_expired());
+    }
+
     #[test]
     fn test_auth_token_can_refresh() {
         let mut token = AuthToken::new("test".to_string());
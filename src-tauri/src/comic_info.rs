@@ -0,0 +1,154 @@
+//! ComicInfo.xml metadata embedded in comic archives
+//!
+//! `<Title>`, `<Series>` and `<Number>` fill in a new book's title and series on
+//! import. The `<Pages>` list tags the n-th image of the archive (in reading order)
+//! with a page type via `<Page Image="n" Type="..."/>`. Pages typed as ads, deleted
+//! pages or the front cover are not part of the story, so they are left out of
+//! `story_page_count`, which progress can optionally be based on.
+
+/// Name of the metadata file, matched case-insensitively at any depth
+pub const COMIC_INFO_FILENAME: &str = "comicinfo.xml";
+
+/// Page types that don't count as story pages
+const NON_STORY_PAGE_TYPES: &[&str] = &["advertisement", "deleted", "frontcover"];
+
+/// Whether an archive entry is a ComicInfo.xml file
+pub fn is_comic_info_entry(name: &str) -> bool {
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    file_name.eq_ignore_ascii_case(COMIC_INFO_FILENAME)
+}
+
+/// Book fields read from ComicInfo.xml; each is None when missing or blank
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ComicMetadata {
+    pub title: Option<String>,
+    pub series: Option<String>,
+    /// Issue or volume number; fractional for side stories (e.g. 1.5)
+    pub number: Option<f64>,
+}
+
+/// Read the title, series and number of a ComicInfo.xml document
+///
+/// Returns `None` when the XML can't be parsed. A `Number` that isn't numeric
+/// (e.g. "Special") is ignored.
+pub fn parse_metadata(xml: &str) -> Option<ComicMetadata> {
+    let document = match roxmltree::Document::parse(xml) {
+        Ok(document) => document,
+        Err(e) => {
+            log::debug!("Ignoring unparsable ComicInfo.xml: {}", e);
+            return None;
+        }
+    };
+
+    let root = document.root_element();
+    let field = |name: &str| {
+        root.children()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+    };
+
+    Some(ComicMetadata {
+        title: field("Title"),
+        series: field("Series"),
+        number: field("Number")
+            .and_then(|number| number.parse::<f64>().ok())
+            .filter(|number| number.is_finite() && *number >= 0.0),
+    })
+}
+
+/// Count the story pages of an archive with `total_pages` images
+///
+/// Returns `None` when the XML can't be parsed or has no page list, so callers
+/// fall back to the raw page count. Page entries pointing past the last image
+/// are ignored.
+pub fn story_page_count(xml: &str, total_pages: i32) -> Option<i32> {
+    let document = match roxmltree::Document::parse(xml) {
+        Ok(document) => document,
+        Err(e) => {
+            log::debug!("Ignoring unparsable ComicInfo.xml: {}", e);
+            return None;
+        }
+    };
+
+    let pages = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("Pages"))?;
+
+    let mut non_story = std::collections::HashSet::new();
+    for page in pages.children().filter(|node| node.has_tag_name("Page")) {
+        let Some(image) = page.attribute("Image").and_then(|v| v.trim().parse::<i32>().ok()) else {
+            continue;
+        };
+        let is_non_story = page
+            .attribute("Type")
+            .map(|page_type| NON_STORY_PAGE_TYPES.contains(&page_type.trim().to_lowercase().as_str()))
+            .unwrap_or(false);
+        if is_non_story && (0..total_pages).contains(&image) {
+            non_story.insert(image);
+        }
+    }
+
+    Some(total_pages - non_story.len() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_story_page_count() {
+        let xml = r#"<?xml version="1.0"?>
+            <ComicInfo xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+              <Title>Padded</Title>
+              <Pages>
+                <Page Image="0" Type="FrontCover" />
+                <Page Image="1" />
+                <Page Image="2" Type="Story" />
+                <Page Image="8" Type="Advertisement" />
+                <Page Image="9" Type="advertisement" />
+                <Page Image="9" Type="Deleted" />
+                <Page Image="42" Type="Advertisement" />
+              </Pages>
+            </ComicInfo>"#;
+
+        // Cover and two ad pages dropped; duplicate and out-of-range entries ignored
+        assert_eq!(story_page_count(xml, 10), Some(7));
+
+        assert_eq!(story_page_count("<ComicInfo><Title>No pages</Title></ComicInfo>", 10), None);
+        assert_eq!(story_page_count("not xml", 10), None);
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let xml = r#"<?xml version="1.0"?>
+            <ComicInfo>
+              <Title> Kakashi's Story </Title>
+              <Series>Naruto</Series>
+              <Number>10.5</Number>
+              <Writer>Masashi Kishimoto</Writer>
+            </ComicInfo>"#;
+        assert_eq!(
+            parse_metadata(xml),
+            Some(ComicMetadata {
+                title: Some("Kakashi's Story".to_string()),
+                series: Some("Naruto".to_string()),
+                number: Some(10.5),
+            })
+        );
+
+        let partial = "<ComicInfo><Title></Title><Number>Special</Number></ComicInfo>";
+        assert_eq!(parse_metadata(partial), Some(ComicMetadata::default()));
+        assert_eq!(parse_metadata("<ComicInfo><Title>Broken"), None);
+    }
+
+    #[test]
+    fn test_is_comic_info_entry() {
+        assert!(is_comic_info_entry("ComicInfo.xml"));
+        assert!(is_comic_info_entry("Series/comicinfo.XML"));
+        assert!(!is_comic_info_entry("ComicInfo.xml.bak"));
+        assert!(!is_comic_info_entry("page01.jpg"));
+    }
+}
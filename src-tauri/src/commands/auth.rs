@@ -4,7 +4,7 @@
 //! Desktop: Uses local HTTP server callback
 //! Mobile: Uses deep link callback (handled in frontend)
 
-use crate::auth::{self, AuthStatus, AuthToken};
+use crate::auth::{self, AccountSummary, AuthStatus, AuthToken, TokenValidation};
 use rand::Rng;
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
@@ -415,13 +415,8 @@ async fn exchange_code_for_tokens(
         token_response.refresh_token.is_some()
     );
 
-    // Calculate expiration time
-    let expires_at = token_response.expires_in.map(|expires_in| {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64 + expires_in)
-            .unwrap_or(0)
-    });
+    // Calculate expiration time (assumed when Google omits expires_in)
+    let expires_at = Some(AuthToken::expires_at_from_now(token_response.expires_in));
 
     // Fetch user info
     let user_info = fetch_user_info(&token_response.access_token).await.ok();
@@ -497,13 +492,8 @@ pub async fn refresh_token_internal(
         .await
         .map_err(|e| crate::error::AppError::sync_failed(format!("Failed to parse token response: {}", e)))?;
 
-    // Calculate expiration time
-    let expires_at = token_response.expires_in.map(|expires_in| {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64 + expires_in)
-            .unwrap_or(0)
-    });
+    // Calculate expiration time (assumed when Google omits expires_in)
+    let expires_at = Some(AuthToken::expires_at_from_now(token_response.expires_in));
 
     // Update token (keep existing refresh token if new one not provided)
     let mut new_token = AuthToken::new(token_response.access_token);
@@ -538,6 +528,77 @@ async fn fetch_user_info(access_token: &str) -> Result<GoogleUserInfo, String> {
         .map_err(|e| format!("Failed to parse user info: {}", e))
 }
 
+/// Call the userinfo endpoint; `Ok(None)` means Google rejected the token (401)
+async fn request_user_info(access_token: &str) -> Result<Option<GoogleUserInfo>, String> {
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get("https://www.googleapis.com/oauth2/v2/userinfo")
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch user info: {}", e))?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch user info: HTTP {}", response.status()));
+    }
+
+    response
+        .json()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to parse user info: {}", e))
+}
+
+/// Check with Google whether the stored token is actually valid
+///
+/// Unlike the local expiry check this catches revoked tokens and clock skew.
+/// On a 401 the token is refreshed and checked once more. The stored email and
+/// display name are updated from the response.
+#[tauri::command]
+pub async fn validate_token(app: tauri::AppHandle) -> Result<TokenValidation, String> {
+    let mut token = match auth::load_token(&app) {
+        Ok(token) => token,
+        Err(_) => {
+            return Ok(TokenValidation {
+                valid: false,
+                refreshed: false,
+                status: AuthStatus::not_authenticated(),
+            })
+        }
+    };
+
+    let mut refreshed = false;
+    let mut user_info = request_user_info(&token.access_token).await?;
+
+    if user_info.is_none() {
+        if let Some((client_id, client_secret)) = token.refresh_client() {
+            log::info!("Access token rejected by Google, refreshing before retry");
+            token = refresh_token_internal(&client_id, &client_secret, &token)
+                .await
+                .map_err(String::from)?;
+            refreshed = true;
+            user_info = request_user_info(&token.access_token).await?;
+        }
+    }
+
+    if let Some(ref info) = user_info {
+        token.update_profile(info.email.clone(), info.name.clone());
+    }
+    if user_info.is_some() || refreshed {
+        auth::save_token(&app, &token).map_err(String::from)?;
+    }
+
+    Ok(TokenValidation {
+        valid: user_info.is_some(),
+        refreshed,
+        status: AuthStatus::from_token(&token),
+    })
+}
+
 /// Save Google auth token (called after OAuth flow)
 #[tauri::command]
 pub async fn save_google_auth_token(
@@ -568,16 +629,34 @@ pub async fn set_auth_token(
     token.email = email;
     token.display_name = display_name;
 
-    let expires_at = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs() as i64 + 3600)
-        .ok();
-    token.expires_at = expires_at;
+    token.expires_at = Some(AuthToken::expires_at_from_now(None));
 
     auth::save_token(&app, &token).map_err(|e| String::from(e))?;
     Ok(AuthStatus::from_token(&token))
 }
 
+/// List the signed-in Google accounts
+#[tauri::command]
+pub async fn list_accounts(app: tauri::AppHandle) -> Result<Vec<AccountSummary>, String> {
+    auth::list_accounts(&app).map_err(|e| String::from(e))
+}
+
+/// Switch the account used for sync to another signed-in account
+#[tauri::command]
+pub async fn switch_account(app: tauri::AppHandle, email: String) -> Result<AuthStatus, String> {
+    auth::switch_account(&app, &email).map_err(|e| String::from(e))
+}
+
+/// Re-encrypt stored credentials with a new vault key, kept for later launches
+#[tauri::command]
+pub async fn rotate_credential_key(
+    app: tauri::AppHandle,
+    old_key: String,
+    new_key: String,
+) -> Result<(), String> {
+    auth::rotate_vault_key(&app, &old_key, &new_key).map_err(|e| String::from(e))
+}
+
 /// Logout from Google (clear stored tokens)
 #[tauri::command]
 pub async fn google_logout(app: tauri::AppHandle) -> Result<AuthStatus, String> {
@@ -2,27 +2,31 @@
 //!
 //! Provides commands for managing books and collections
 
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_fs::FsExt;
 
 use crate::database::models::{
-    Book, BookSettings, BookWithDetails, Bookmark, Collection, CollectionWithCount, NewBookmark, NewCollection, UpdateBook,
+    ArchiveVerification, ArchiveVerifyProgress, Book, BookPage, BookSettings, BookWithDetails, Bookmark, BookmarkImportResult, Collection, CollectionDiff, CollectionNode, CollectionWithCount, DailyReadingStats, DayActivity,
+    DirectoryImportResult, DriveFileInfo, HashBackfillResult, HashScheme,
+    ImportBatchProgress, ImportProgress, ImportResult,
+    LibraryConsistencyReport, LibraryVerification, NewBookmark, NewCollection, NewReadingSession, NewTag, PageAudit, PageNavigation, PagesRead, QueueEntry, QueuedBook, ReaderConfig, ReadingSession, ResumeBook, SeriesSummary, Tag, UpdateBook,
     UpdateCollection,
 };
-use crate::database::operations;
-use crate::error::AppError;
-use crate::settings::storage;
+use crate::database::{operations, progress};
+use crate::error::{AppError, ErrorCode};
+use crate::settings::{storage, AppSettings, SettingValue};
 
 // ============================================================================
 // COLLECTION COMMANDS
 // ============================================================================
 
-/// Create a new collection
+/// Create a new collection, optionally nested under `parent_id`
 #[tauri::command]
 pub async fn create_collection(
     name: String,
     description: Option<String>,
+    parent_id: Option<i32>,
 ) -> Result<Collection, String> {
     let new_collection = NewCollection { 
         name, 
@@ -30,7 +34,7 @@ pub async fn create_collection(
         uuid: Some(uuid::Uuid::new_v4().to_string()),
     };
 
-    operations::create_collection(new_collection).map_err(|e| e.into())
+    operations::create_collection(new_collection, parent_id).map_err(|e| e.into())
 }
 
 /// Get all collections with book counts
@@ -39,6 +43,12 @@ pub async fn get_collections() -> Result<Vec<CollectionWithCount>, String> {
     operations::get_all_collections().map_err(|e| e.into())
 }
 
+/// Get the number of unread books in each collection (collections with none are omitted)
+#[tauri::command]
+pub async fn get_unread_counts() -> Result<std::collections::HashMap<i32, i64>, String> {
+    operations::get_unread_counts_by_collection().map_err(|e| e.into())
+}
+
 /// Get a single collection by ID
 #[tauri::command]
 pub async fn get_collection(collection_id: i32) -> Result<Collection, String> {
@@ -63,39 +73,285 @@ pub async fn update_collection(
 
 /// Delete a collection
 #[tauri::command]
-pub async fn delete_collection(collection_id: i32) -> Result<(), String> {
-    operations::delete_collection(collection_id).map_err(|e| e.into())
+pub async fn delete_collection(app: AppHandle, collection_id: i32) -> Result<(), String> {
+    operations::delete_collection(collection_id).map_err(|e: AppError| e)?;
+
+    let settings = storage::load_settings(&app).map_err(|e: AppError| e)?;
+    if configured_default_collection(&settings) == Some(collection_id) {
+        clear_default_collection(&app, settings);
+    }
+    Ok(())
+}
+
+/// Move a collection under another collection, or to the top level with no parent
+#[tauri::command]
+pub async fn move_collection(collection_id: i32, parent_id: Option<i32>) -> Result<Collection, String> {
+    operations::move_collection(collection_id, parent_id).map_err(|e| e.into())
+}
+
+/// Get the collections nested directly under a collection (top-level ones without a parent)
+#[tauri::command]
+pub async fn get_child_collections(parent_id: Option<i32>) -> Result<Vec<Collection>, String> {
+    operations::get_child_collections(parent_id).map_err(|e| e.into())
+}
+
+/// Get all collections as a tree, with book counts
+#[tauri::command]
+pub async fn get_collection_tree() -> Result<Vec<CollectionNode>, String> {
+    operations::get_collection_tree().map_err(|e| e.into())
+}
+
+/// Compare two collections: books only in A, only in B, and in both
+#[tauri::command]
+pub async fn diff_collections(collection_a: i32, collection_b: i32) -> Result<CollectionDiff, String> {
+    operations::diff_collections(collection_a, collection_b).map_err(|e| e.into())
+}
+
+/// Collection ID stored in `library.default_collection_id`, if any
+fn configured_default_collection(settings: &AppSettings) -> Option<i32> {
+    settings
+        .get(storage::DEFAULT_COLLECTION_KEY)
+        .and_then(|v| v.as_string())
+        .and_then(|id| id.trim().parse().ok())
+}
+
+/// Collection new imports go to when the caller didn't pick one
+///
+/// A default pointing at a deleted or missing collection is cleared.
+fn default_import_collection(app: &AppHandle, settings: &AppSettings) -> Option<i32> {
+    let collection_id = configured_default_collection(settings)?;
+
+    match operations::get_collection_by_id(collection_id) {
+        Ok(_) => Some(collection_id),
+        Err(e) => {
+            log::warn!("Default collection {} is unavailable ({}), clearing it", collection_id, e);
+            clear_default_collection(app, settings.clone());
+            None
+        }
+    }
+}
+
+fn clear_default_collection(app: &AppHandle, mut settings: AppSettings) {
+    // Device-local setting: saved without bumping the sync timestamp
+    settings.set(storage::DEFAULT_COLLECTION_KEY, SettingValue::String(String::new()));
+    if let Err(e) = storage::save_settings_no_timestamp(app, &settings) {
+        log::warn!("Failed to clear default collection: {}", e);
+    }
 }
 
 // ============================================================================
 // BOOK COMMANDS
 // ============================================================================
 
-/// Get all books with optional filtering
+/// Get a page of books with optional filtering
+///
+/// `sort` is `"sort_key"` for the same title order on every device; by default the
+/// most recently read books come first. `total_count` counts every matching book,
+/// not just the returned page.
 #[tauri::command]
 pub async fn get_books(
     collection_id: Option<i32>,
     status: Option<String>,
     favorites_only: bool,
-) -> Result<Vec<BookWithDetails>, String> {
-    operations::get_all_books(collection_id, status, favorites_only).map_err(|e| e.into())
+    sort: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<BookPage, String> {
+    progress::flush_reading_positions(None).map_err(|e: AppError| e)?;
+    operations::get_all_books(collection_id, status, favorites_only, sort, limit, offset)
+        .map_err(|e| e.into())
+}
+
+/// Search books by title and filename, best matches first
+#[tauri::command]
+pub async fn search_books(query: String, limit: Option<i64>) -> Result<Vec<BookWithDetails>, String> {
+    progress::flush_reading_positions(None).map_err(|e: AppError| e)?;
+    operations::search_books(&query, limit).map_err(|e| e.into())
 }
 
 /// Get a single book by ID
 #[tauri::command]
 pub async fn get_book(book_id: i32) -> Result<Book, String> {
+    progress::flush_reading_positions(Some(book_id)).map_err(|e: AppError| e)?;
     operations::get_book_by_id(book_id).map_err(|e| e.into())
 }
 
+/// Get the most recently read in-progress book and its resume page
+#[tauri::command]
+pub async fn get_last_read_book() -> Result<Option<ResumeBook>, String> {
+    progress::flush_reading_positions(None).map_err(|e: AppError| e)?;
+    operations::get_last_read_book().map_err(|e| e.into())
+}
+
+/// Get in-progress books not read for at least `days` days, oldest first
+#[tauri::command]
+pub async fn get_stale_in_progress(days: i64, limit: i64) -> Result<Vec<BookWithDetails>, String> {
+    progress::flush_reading_positions(None).map_err(|e: AppError| e)?;
+    operations::get_stale_in_progress(days, limit).map_err(|e| e.into())
+}
+
+/// Get favorite books that aren't in any collection yet
+#[tauri::command]
+pub async fn get_unfiled_favorites() -> Result<Vec<BookWithDetails>, String> {
+    operations::get_favorites_without_collection().map_err(|e| e.into())
+}
+
+/// Get next/previous page indices for the reader, honouring reading direction and double pages
+#[tauri::command]
+pub async fn get_page_navigation(
+    app: AppHandle,
+    book_id: i32,
+    current_page: i32,
+) -> Result<PageNavigation, String> {
+    let settings = storage::load_settings(&app).map_err(|e: AppError| e)?;
+    let default_direction = settings
+        .get("reading.direction")
+        .and_then(|v| v.as_string())
+        .unwrap_or("rtl");
+    let default_display_mode = settings
+        .get("reading.page_display_mode")
+        .and_then(|v| v.as_string())
+        .unwrap_or("single");
+
+    operations::get_page_navigation(book_id, current_page, default_direction, default_display_mode)
+        .map_err(|e| e.into())
+}
+
+/// Get the reading options and resume position for opening a book in the reader
+#[tauri::command]
+pub async fn get_reader_config(app: AppHandle, book_id: i32) -> Result<ReaderConfig, String> {
+    let settings = storage::load_settings(&app).map_err(|e: AppError| e)?;
+    let setting = |key: &str, default: &'static str| {
+        settings
+            .get(key)
+            .and_then(|v| v.as_string())
+            .unwrap_or(default)
+            .to_string()
+    };
+
+    operations::get_reader_config(
+        book_id,
+        &setting("reading.direction", "rtl"),
+        &setting("reading.page_display_mode", "single"),
+        &setting("reading.image_fit_mode", "fit_width"),
+        &setting("reading.background", "black"),
+    )
+    .map_err(|e| e.into())
+}
+
+/// Get the ordered image entry names (pages) of a book
+#[tauri::command]
+pub async fn get_page_names(book_id: i32) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || crate::protocol::get_page_names(book_id))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Count a book's pages again after its file was replaced on disk
+///
+/// Updates `total_pages`, keeps the reading position inside the book and drops
+/// the cached page list. Returns the updated book.
+#[tauri::command]
+pub async fn rescan_book(book_id: i32) -> Result<Book, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        // The buffered position may lie past the new last page, so clamp it too
+        progress::flush_reading_positions(Some(book_id))?;
+        let book = operations::rescan_book(book_id)?;
+        crate::protocol::invalidate_image_cache(book_id);
+        Ok(book)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+    .map_err(|e: AppError| e.into())
+}
+
+/// Enter the password of a book's encrypted archive for this session
+///
+/// The password is checked against the archive first and never saved; `None`
+/// forgets it. Fails with `ArchiveEncrypted` if it doesn't open the archive.
+/// A cloud-only book's password is kept unchecked and used to verify the download.
+#[tauri::command]
+pub fn set_archive_password(book_id: i32, password: Option<String>) -> Result<(), String> {
+    set_archive_password_impl(book_id, password).map_err(|e| e.into())
+}
+
+fn set_archive_password_impl(book_id: i32, password: Option<String>) -> Result<(), AppError> {
+    if password.is_some() {
+        let book = operations::get_book_by_id(book_id)?;
+        if zip_archive_path(&book).is_some_and(|path| path.exists()) {
+            operations::check_zip_password(Path::new(&book.file_path), password.as_deref())?;
+        }
+    }
+    crate::archive_password::set(book_id, password);
+    // Pages decrypted with a forgotten password shouldn't stay readable
+    crate::protocol::invalidate_image_cache(book_id);
+    Ok(())
+}
+
+/// Whether a book's archive needs a password that wasn't entered this session
+///
+/// The reader asks for the password when this is true, before showing pages.
+#[tauri::command]
+pub fn archive_password_required(book_id: i32) -> Result<bool, String> {
+    archive_password_required_impl(book_id).map_err(|e| e.into())
+}
+
+fn archive_password_required_impl(book_id: i32) -> Result<bool, AppError> {
+    let book = operations::get_book_by_id(book_id)?;
+    let Some(path) = zip_archive_path(&book) else {
+        return Ok(false);
+    };
+    match operations::check_zip_password(path, crate::archive_password::get(book_id).as_deref()) {
+        Ok(()) => Ok(false),
+        Err(e) if matches!(e.code, ErrorCode::ArchiveEncrypted) => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+/// Path of a book's local ZIP/CBZ archive; `None` for cloud-only, manifest and other archives
+fn zip_archive_path(book: &Book) -> Option<&Path> {
+    if book.file_path.starts_with("cloud://") || crate::manifest::manifest_path(&book.file_path).is_some() {
+        return None;
+    }
+    let path = Path::new(&book.file_path);
+    matches!(operations::detect_archive_type(path), Ok(operations::ArchiveType::Zip)).then_some(path)
+}
+
+/// Find books by a short file hash prefix (for debugging sync and duplicates)
+#[tauri::command]
+pub async fn find_by_hash_prefix(prefix: String) -> Result<Vec<Book>, String> {
+    operations::find_books_by_hash_prefix(&prefix).map_err(|e| e.into())
+}
+
+/// Find the local book a Drive file (`book_{hash}.cbz` or bare hash) belongs to
+#[tauri::command]
+pub async fn resolve_drive_file(hash: String) -> Result<DriveFileInfo, String> {
+    operations::resolve_drive_file(&hash).map_err(|e| e.into())
+}
+
 /// Update a book
+///
+/// `page_fraction` (0.0-1.0) is how far into the current page the reader has
+/// scrolled in vertical mode, and `scroll_fraction` (0.0-1.0) how far into the
+/// whole strip. A blank `series_name` removes the book from its series.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_book(
     book_id: i32,
     title: Option<String>,
     current_page: Option<i32>,
+    page_fraction: Option<f64>,
+    scroll_fraction: Option<f64>,
     is_favorite: Option<bool>,
     reading_status: Option<String>,
+    series_name: Option<String>,
+    volume_number: Option<f64>,
 ) -> Result<Book, String> {
+    // A direct write supersedes any buffered position for this book
+    if current_page.is_some() {
+        progress::discard_reading_position(book_id);
+    }
+
     let updates = UpdateBook {
         title,
         current_page,
@@ -108,11 +364,44 @@ pub async fn update_book(
         updated_at: None,
         is_favorite,
         reading_status,
+        notes: None,
+        sort_key: None,
+        page_fraction: page_fraction.map(Some),
+        series_name: series_name.map(Some),
+        volume_number: volume_number.map(Some),
+        scroll_fraction: scroll_fraction.map(Some),
     };
 
     operations::update_book(book_id, updates).map_err(|e| e.into())
 }
 
+/// Record the reader's current page; written to the database once page turns settle
+#[tauri::command]
+pub async fn save_reading_position(book_id: i32, current_page: i32) -> Result<(), String> {
+    progress::queue_reading_position(book_id, current_page);
+    Ok(())
+}
+
+/// Write buffered reading positions now (call when closing the reader)
+///
+/// Flushes only `book_id` when given, otherwise every pending position.
+#[tauri::command]
+pub async fn flush_reading_position(book_id: Option<i32>) -> Result<usize, String> {
+    progress::flush_reading_positions(book_id).map_err(|e| e.into())
+}
+
+/// Count an opening of the book in the reader
+#[tauri::command]
+pub async fn mark_book_opened(book_id: i32) -> Result<Book, String> {
+    operations::mark_book_opened(book_id).map_err(|e| e.into())
+}
+
+/// Set or clear the freeform note for a book
+#[tauri::command]
+pub async fn set_book_note(book_id: i32, note: Option<String>) -> Result<Book, String> {
+    operations::set_book_note(book_id, note).map_err(|e| e.into())
+}
+
 /// Set the collections for a book (replaces existing)
 #[tauri::command]
 pub async fn set_book_collections(book_id: i32, collection_ids: Vec<i32>) -> Result<(), String> {
@@ -141,7 +430,6 @@ pub async fn delete_book(app: AppHandle, book_id: i32) -> Result<(), String> {
 
 async fn delete_book_impl(app: &AppHandle, book_id: i32) -> Result<(), AppError> {
     use crate::auth;
-    use crate::sync::DriveSync;
     
     // Get the book first to access its file path and hash
     let book = operations::get_book_by_id(book_id)?;
@@ -153,6 +441,8 @@ async fn delete_book_impl(app: &AppHandle, book_id: i32) -> Result<(), AppError>
     
     // Delete local file only if it's stored in app data (not external reference)
     if !book.file_path.starts_with("cloud://") {
+        // Release the open archive first and drop pages that are about to disappear
+        crate::protocol::invalidate_image_cache(book_id);
         let path = std::path::Path::new(&book.file_path);
         
         // Only delete if the file is within app data directory
@@ -204,8 +494,11 @@ async fn delete_book_impl(app: &AppHandle, book_id: i32) -> Result<(), AppError>
                     };
                     
                     if let Some(access_token) = access_token {
-                        let drive = DriveSync::with_token(access_token);
-                        match drive.delete_book_file(file_hash).await {
+                        let deleted = match crate::commands::sync::backend_for(app, access_token) {
+                            Ok(drive) => drive.delete_book_file(file_hash).await,
+                            Err(e) => Err(e),
+                        };
+                        match deleted {
                             Ok(deleted) => {
                                 if deleted {
                                     log::info!("Deleted cloud file for book {}", book_id);
@@ -228,163 +521,271 @@ async fn delete_book_impl(app: &AppHandle, book_id: i32) -> Result<(), AppError>
     Ok(())
 }
 
-/// Import a single book from a zip/cbz/rar/cbr archive file
-/// Each archive is treated as a single book regardless of internal structure
-#[tauri::command]
-pub async fn import_book_from_archive(
-    app: AppHandle,
-    file_path: String,
+/// Event emitted as `import_book_from_archive` moves through its phases
+pub const IMPORT_PROGRESS_EVENT: &str = "import-progress";
+
+/// Event emitted before each file of `import_books_from_archives`
+pub const IMPORT_BATCH_PROGRESS_EVENT: &str = "import-batch-progress";
+
+/// Settings and directories shared by every archive of an import
+struct ArchiveImportContext {
+    settings: AppSettings,
+    save_to_app_storage: bool,
+    hash_scheme: HashScheme,
+    library_dir: PathBuf,
+    temp_dir: PathBuf,
     collection_id: Option<i32>,
-    original_filename: Option<String>,
-) -> Result<Book, String> {
-    use std::io::{Read, Write};
+}
 
-    let settings = storage::load_settings(&app).map_err(|e: AppError| e)?;
-    let save_to_app_storage = settings
-        .get("library.save_to_app_storage")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+impl ArchiveImportContext {
+    fn load(app: &AppHandle, collection_id: Option<i32>) -> Result<Self, AppError> {
+        let settings = storage::load_settings(app)?;
+        let save_to_app_storage = settings
+            .get("library.save_to_app_storage")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let fast_import = settings
+            .get("library.fast_import")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let hash_scheme = if fast_import { HashScheme::File } else { HashScheme::Content };
 
-    // Get default reading settings to apply to the new book
-    let default_reading_direction = settings
-        .get("reading.direction")
-        .and_then(|v| v.as_string())
-        .map(|s| s.to_string());
-    let default_page_display_mode = settings
-        .get("reading.page_display_mode")
-        .and_then(|v| v.as_string())
-        .map(|s| s.to_string());
-    let default_image_fit_mode = settings
-        .get("reading.image_fit_mode")
-        .and_then(|v| v.as_string())
-        .map(|s| s.to_string());
+        let library_dir = storage::get_library_dir(app)?;
+        let collection_id = collection_id.or_else(|| default_import_collection(app, &settings));
 
-    let library_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {}", e))?
-        .join("library");
+        let temp_dir = storage::get_temp_dir(app)?;
 
-    let cache_dir = app
-        .path()
-        .app_cache_dir()
-        .map_err(|e| format!("Failed to get app cache directory: {}", e))?;
+        log::info!("Library dir: {:?}, Temp dir: {:?}", library_dir, temp_dir);
 
-    log::info!("Library dir: {:?}, Cache dir: {:?}", library_dir, cache_dir);
+        Ok(Self {
+            settings,
+            save_to_app_storage,
+            hash_scheme,
+            library_dir,
+            temp_dir,
+            collection_id,
+        })
+    }
 
-    // Determine if this is an Android content URI or a regular file path
-    let is_content_uri = file_path.starts_with("content://");
+    /// Start hashing page contents in the background once a fast import is done
+    fn finish_import(&self) {
+        if self.hash_scheme == HashScheme::File {
+            schedule_hash_backfill();
+        }
+    }
+}
 
-    // For Android content URIs, file MUST be saved to app storage because the cache is temporary
-    // and content URIs can't be referenced later (system restriction)
-    let effective_save_to_storage = if is_content_uri { true } else { save_to_app_storage };
+/// Set while a content hash backfill is running
+static HASH_BACKFILL_RUNNING: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
 
-    // Get the actual file path to process
-    let (archive_path, temp_file_path) = if is_content_uri {
-        log::info!(
-            "Processing Android content URI: {}",
-            &file_path[..80.min(file_path.len())]
-        );
-
-        log::info!("Creating cache directory: {:?}", cache_dir);
-        if let Err(e) = std::fs::create_dir_all(&cache_dir) {
-            return Err(format!(
-                "Failed to create cache directory {:?}: {}",
-                cache_dir, e
-            ));
+/// Set when another backfill pass is wanted, e.g. after an import during a running pass
+static HASH_BACKFILL_REQUESTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// Replace fast-import file hashes with content hashes on a background thread
+///
+/// Only one backfill runs at a time; a request while one is running makes it do
+/// another pass afterwards, so books imported in the meantime aren't missed.
+pub fn schedule_hash_backfill() {
+    use std::sync::atomic::Ordering;
+
+    HASH_BACKFILL_REQUESTED.store(true, Ordering::SeqCst);
+    if HASH_BACKFILL_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn_blocking(|| loop {
+        while HASH_BACKFILL_REQUESTED.swap(false, Ordering::SeqCst) {
+            if let Err(e) = operations::backfill_content_hashes() {
+                log::warn!("Content hash backfill failed: {}", e);
+            }
         }
+        HASH_BACKFILL_RUNNING.store(false, Ordering::SeqCst);
 
-        if !cache_dir.exists() {
-            return Err(format!(
-                "Cache directory doesn't exist after creation: {:?}",
-                cache_dir
-            ));
+        // A request may have arrived after the last pass but before the flag was cleared
+        if !HASH_BACKFILL_REQUESTED.load(Ordering::SeqCst)
+            || HASH_BACKFILL_RUNNING.swap(true, Ordering::SeqCst)
+        {
+            break;
         }
-        log::info!("Cache directory verified: {:?}", cache_dir);
+    });
+}
 
-        let fs_scope = app.fs_scope();
+/// Exclusion patterns the stored page numbers match while a remap is running
+static PAGES_COUNTED_WITH: std::sync::Mutex<Option<crate::page_filter::PageFilter>> =
+    std::sync::Mutex::new(None);
 
-        fs_scope
-            .allow_file(&file_path)
-            .map_err(|e| format!("Failed to allow file access: {}", e))?;
+/// Remap stored page numbers to the active exclusion patterns on a background thread
+///
+/// `previous` are the patterns the stored page numbers were counted with. Only one
+/// remap runs at a time; it repeats until it has caught up with the active
+/// patterns, so changes made while it runs aren't missed.
+pub fn schedule_page_remap(previous: crate::page_filter::PageFilter) {
+    {
+        let mut counted_with = PAGES_COUNTED_WITH.lock().unwrap_or_else(|e| e.into_inner());
+        if counted_with.is_some() {
+            return;
+        }
+        *counted_with = Some(previous);
+    }
 
-        let file_url = tauri::Url::parse(&file_path)
-            .map_err(|e| format!("Failed to parse content URI: {}", e))?;
+    tauri::async_runtime::spawn_blocking(|| loop {
+        let current = crate::page_filter::current();
+        let previous = {
+            let mut counted_with = PAGES_COUNTED_WITH.lock().unwrap_or_else(|e| e.into_inner());
+            match counted_with.clone() {
+                Some(previous) if previous != current => previous,
+                _ => {
+                    *counted_with = None;
+                    break;
+                }
+            }
+        };
+        if let Err(e) = operations::remap_pages_for_filter(&previous, &current) {
+            log::warn!("Remapping pages to new exclusion patterns failed: {}", e);
+        }
+        *PAGES_COUNTED_WITH.lock().unwrap_or_else(|e| e.into_inner()) = Some(current);
+    });
+}
+
+/// Copy an Android content URI into the temp directory so it can be opened as a file
+///
+/// `temp_dir` comes from `storage::get_temp_dir`, which has checked it is writable.
+fn copy_content_uri_to_temp_dir(
+    app: &AppHandle,
+    temp_dir: &Path,
+    file_path: &str,
+    original_filename: Option<&str>,
+) -> Result<PathBuf, AppError> {
+    use std::io::{Read, Write};
+
+    let io_error = |message: String| AppError::new(ErrorCode::IoError, message);
+
+    log::info!(
+        "Processing Android content URI: {}",
+        &file_path[..80.min(file_path.len())]
+    );
+
+    app.fs_scope()
+        .allow_file(file_path)
+        .map_err(|e| io_error(format!("Failed to allow file access: {}", e)))?;
 
-        log::debug!("Opening content URI...");
-        let mut file = app
-            .fs()
-            .open(
-                file_url,
-                tauri_plugin_fs::OpenOptions::new().read(true).clone(),
-            )
-            .map_err(|e| format!("Failed to open file: {}", e))?;
+    let file_url = tauri::Url::parse(file_path)
+        .map_err(|e| io_error(format!("Failed to parse content URI: {}", e)))?;
 
-        let mut content = Vec::new();
-        file.read_to_end(&mut content)
-            .map_err(|e| format!("Failed to read file content: {}", e))?;
+    log::debug!("Opening content URI...");
+    let mut file = app
+        .fs()
+        .open(
+            file_url,
+            tauri_plugin_fs::OpenOptions::new().read(true).clone(),
+        )
+        .map_err(|e| io_error(format!("Failed to open file: {}", e)))?;
+
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)
+        .map_err(|e| io_error(format!("Failed to read file content: {}", e)))?;
 
-        log::info!("Read {} bytes from content URI", content.len());
+    log::info!("Read {} bytes from content URI", content.len());
 
-        let filename = original_filename
-            .clone()
-            .unwrap_or_else(|| format!("import_{}.cbz", chrono::Utc::now().timestamp_millis()));
-        let temp_path = cache_dir.join(&filename);
+    let filename = original_filename
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("import_{}.cbz", chrono::Utc::now().timestamp_millis()));
+    let temp_path = temp_dir.join(&filename);
 
-        log::debug!("Writing to temp file: {:?}", temp_path);
+    log::debug!("Writing to temp file: {:?}", temp_path);
 
-        let mut temp_file = std::fs::File::create(&temp_path)
-            .map_err(|e| format!("Failed to create temp file at {:?}: {}", temp_path, e))?;
-        temp_file
-            .write_all(&content)
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+    let mut temp_file = std::fs::File::create(&temp_path)
+        .map_err(|e| io_error(format!("Failed to create temp file at {:?}: {}", temp_path, e)))?;
+    temp_file
+        .write_all(&content)
+        .map_err(|e| io_error(format!("Failed to write temp file: {}", e)))?;
 
-        log::info!("Copied content URI to temp file: {:?}", temp_path);
+    log::info!("Copied content URI to temp file: {:?}", temp_path);
+
+    Ok(temp_path)
+}
 
+/// Import one archive (a file path or an Android content URI), blocking
+///
+/// Emits `import-progress` for each phase and creates the default book settings
+/// for the new book. The password of an encrypted archive is kept for the session.
+fn import_archive_blocking(
+    app: &AppHandle,
+    context: &ArchiveImportContext,
+    file_path: &str,
+    original_filename: Option<String>,
+    password: Option<String>,
+) -> Result<Book, AppError> {
+    // Determine if this is an Android content URI or a regular file path
+    let is_content_uri = file_path.starts_with("content://");
+
+    // For Android content URIs, file MUST be saved to app storage because the cache is temporary
+    // and content URIs can't be referenced later (system restriction)
+    let effective_save_to_storage = is_content_uri || context.save_to_app_storage;
+
+    // Get the actual file path to process
+    let (archive_path, temp_file_path) = if is_content_uri {
+        let temp_path = copy_content_uri_to_temp_dir(
+            app,
+            &context.temp_dir,
+            file_path,
+            original_filename.as_deref(),
+        )?;
         (temp_path.clone(), Some(temp_path))
     } else {
         // Regular file path (desktop)
-        let path = PathBuf::from(&file_path);
+        let path = PathBuf::from(file_path);
         if !path.exists() {
-            return Err("File does not exist".into());
+            return Err(AppError::new(ErrorCode::IoError, "File does not exist"));
         }
         (path, None)
     };
 
-    let ext = archive_path
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_lowercase());
-
-    if !matches!(
-        ext.as_deref(),
-        Some("zip") | Some("cbz") | Some("rar") | Some("cbr")
-    ) {
+    if !operations::has_archive_extension(&archive_path) {
         // Clean up temp file
         if let Some(ref temp_path) = temp_file_path {
             let _ = std::fs::remove_file(temp_path);
         }
-        return Err("Only .zip, .cbz, .rar, and .cbr files are supported".into());
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Only .zip, .cbz, .rar, .cbr, .7z, and .cb7 files are supported",
+        ));
     }
 
     if effective_save_to_storage {
-        std::fs::create_dir_all(&library_dir)
-            .map_err(|e| format!("Failed to create library directory: {}", e))?;
+        std::fs::create_dir_all(&context.library_dir).map_err(|e| {
+            AppError::new(ErrorCode::IoError, format!("Failed to create library directory: {}", e))
+        })?;
     }
 
-    // Run blocking I/O operations on a separate thread
-    let result = tauri::async_runtime::spawn_blocking(move || {
-        operations::import_book_from_archive(
-            &archive_path,
-            collection_id,
-            effective_save_to_storage,
-            &library_dir,
-            original_filename,
-        )
-        .map_err(|e| e.into())
-    })
-    .await
-    .map_err(|e| format!("Task failed: {}", e))?;
+    let progress_filename = original_filename.clone().unwrap_or_else(|| {
+        archive_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let result = operations::import_book_from_archive(
+        &archive_path,
+        context.collection_id,
+        effective_save_to_storage,
+        &context.library_dir,
+        original_filename,
+        context.hash_scheme,
+        password.as_deref(),
+        |phase, bytes_copied, total_bytes| {
+            let progress = ImportProgress {
+                filename: progress_filename.clone(),
+                phase,
+                bytes_copied,
+                total_bytes,
+            };
+            if let Err(e) = app.emit(IMPORT_PROGRESS_EVENT, progress) {
+                log::warn!("Failed to emit {}: {}", IMPORT_PROGRESS_EVENT, e);
+            }
+        },
+    );
 
     if let Some(temp_path) = temp_file_path {
         let _ = std::fs::remove_file(&temp_path);
@@ -393,31 +794,303 @@ pub async fn import_book_from_archive(
 
     // If import was successful, create default book settings
     if let Ok(ref book) = result {
-        // Only create settings if we have non-default values from app settings
-        let has_custom_defaults = default_reading_direction.is_some() 
-            || default_page_display_mode.is_some()
-            || default_image_fit_mode.is_some();
-        
-        if has_custom_defaults {
-            if let Err(e) = operations::update_book_settings(
-                book.id,
-                default_reading_direction.map(Some),
-                default_page_display_mode.map(Some),
-                default_image_fit_mode.map(Some),
-                None, // sync_progress - use global default
-            ) {
-                log::warn!("Failed to create default book settings for book {}: {}", book.id, e);
-            }
+        apply_default_book_settings(&context.settings, book.id);
+        if password.is_some() {
+            crate::archive_password::set(book.id, password);
         }
     }
 
     result
 }
 
+/// Import a single book from a zip/cbz/rar/cbr/7z/cb7 archive file
+/// Each archive is treated as a single book regardless of internal structure
+///
+/// Emits `import-progress` for each phase, and with byte counts while copying
+/// the archive into the library. An encrypted ZIP/CBZ needs its `password`;
+/// without it (or with a wrong one) the import fails with `ArchiveEncrypted`.
+#[tauri::command]
+pub async fn import_book_from_archive(
+    app: AppHandle,
+    file_path: String,
+    collection_id: Option<i32>,
+    original_filename: Option<String>,
+    password: Option<String>,
+) -> Result<Book, String> {
+    let context = ArchiveImportContext::load(&app, collection_id)?;
+
+    // Run blocking I/O operations on a separate thread
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = import_archive_blocking(&app, &context, &file_path, original_filename, password);
+        context.finish_import();
+        result
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+    .map_err(|e| e.into())
+}
+
+/// Import several archives in one call
+///
+/// Files are imported one after another on a single blocking thread. A file that
+/// fails or is already in the library is reported in its result and doesn't stop
+/// the batch. Emits `import-batch-progress` before each file, in addition to the
+/// per-file `import-progress` events.
+#[tauri::command]
+pub async fn import_books_from_archives(
+    app: AppHandle,
+    file_paths: Vec<String>,
+    collection_id: Option<i32>,
+) -> Result<Vec<ImportResult>, String> {
+    let context = ArchiveImportContext::load(&app, collection_id)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = file_paths.len();
+        let results = file_paths
+            .into_iter()
+            .enumerate()
+            .map(|(index, file_path)| {
+                emit_batch_progress(&app, &file_path, index + 1, total);
+                match import_archive_blocking(&app, &context, &file_path, None, None) {
+                    Ok(book) => ImportResult::Imported { file_path, book },
+                    Err(e) if matches!(e.code, ErrorCode::DuplicateEntry) => ImportResult::Duplicate {
+                        file_path,
+                        existing_title: e.details.into_iter().next().unwrap_or_default(),
+                    },
+                    Err(e) => {
+                        log::warn!("Failed to import {}: {}", file_path, e);
+                        ImportResult::Failed { file_path, error: e.message }
+                    }
+                }
+            })
+            .collect();
+        context.finish_import();
+        results
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Import every archive in a directory, optionally including subdirectories
+///
+/// Files without a supported archive extension are ignored and archives already in
+/// the library are skipped quietly. Emits `import-batch-progress` before each archive.
+/// Not available on Android, where files are picked as content URIs instead of paths.
+#[tauri::command]
+pub async fn import_directory(
+    app: AppHandle,
+    dir_path: String,
+    collection_id: Option<i32>,
+    recursive: bool,
+) -> Result<DirectoryImportResult, String> {
+    #[cfg(target_os = "android")]
+    {
+        let _ = (app, dir_path, collection_id, recursive);
+        return Err(AppError::new(
+            ErrorCode::InvalidInput,
+            "Importing a directory is not supported on Android. Please select the files instead.",
+        )
+        .into());
+    }
+
+    #[cfg(not(target_os = "android"))]
+    {
+        let dir = PathBuf::from(&dir_path);
+        if !dir.is_dir() {
+            return Err(AppError::invalid_input(format!("{} is not a directory", dir_path)).into());
+        }
+
+        let context = ArchiveImportContext::load(&app, collection_id)?;
+
+        tauri::async_runtime::spawn_blocking(move || {
+            let archives = operations::find_archives_in_dir(&dir, recursive)?;
+            log::info!("Importing {} archive(s) from {:?}", archives.len(), dir);
+
+            let mut result = DirectoryImportResult::default();
+            for (index, path) in archives.iter().enumerate() {
+                let file_path = path.to_string_lossy().to_string();
+                emit_batch_progress(&app, &file_path, index + 1, archives.len());
+
+                match import_archive_blocking(&app, &context, &file_path, None, None) {
+                    Ok(_) => result.imported += 1,
+                    Err(e) if matches!(e.code, ErrorCode::DuplicateEntry) => result.skipped += 1,
+                    Err(e) => {
+                        log::warn!("Failed to import {}: {}", file_path, e);
+                        result.failed += 1;
+                    }
+                }
+            }
+
+            log::info!(
+                "Directory import finished: {} imported, {} skipped, {} failed",
+                result.imported,
+                result.skipped,
+                result.failed
+            );
+            context.finish_import();
+            Ok::<_, AppError>(result)
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| e.into())
+    }
+}
+
+/// Report the file a batch import is about to process
+fn emit_batch_progress(app: &AppHandle, file_path: &str, current: usize, total: usize) {
+    let progress = ImportBatchProgress {
+        filename: display_filename(file_path),
+        current,
+        total,
+    };
+    if let Err(e) = app.emit(IMPORT_BATCH_PROGRESS_EVENT, progress) {
+        log::warn!("Failed to emit {}: {}", IMPORT_BATCH_PROGRESS_EVENT, e);
+    }
+}
+
+/// Last path segment of a file path or URI, for progress display
+fn display_filename(file_path: &str) -> String {
+    file_path
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(file_path)
+        .to_string()
+}
+
+/// Import a book stored as loose image files with a `pages.json` manifest
+/// Pages are served in manifest order; the folder is referenced in place, never copied
+#[tauri::command]
+pub async fn import_book_from_manifest(
+    app: AppHandle,
+    manifest_path: String,
+    collection_id: Option<i32>,
+) -> Result<Book, String> {
+    let settings = storage::load_settings(&app).map_err(|e: AppError| e)?;
+    let collection_id = collection_id.or_else(|| default_import_collection(&app, &settings));
+
+    let book = tauri::async_runtime::spawn_blocking(move || {
+        operations::import_book_from_manifest(&PathBuf::from(manifest_path), collection_id)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+
+    apply_default_book_settings(&settings, book.id);
+    Ok(book)
+}
+
+/// Copy the global reading defaults onto a newly imported book
+fn apply_default_book_settings(settings: &AppSettings, book_id: i32) {
+    let default_reading_direction = settings
+        .get("reading.direction")
+        .and_then(|v| v.as_string())
+        .map(|s| s.to_string());
+    let default_page_display_mode = settings
+        .get("reading.page_display_mode")
+        .and_then(|v| v.as_string())
+        .map(|s| s.to_string());
+    let default_image_fit_mode = settings
+        .get("reading.image_fit_mode")
+        .and_then(|v| v.as_string())
+        .map(|s| s.to_string());
+
+    // Only create settings if we have non-default values from app settings
+    let has_custom_defaults = default_reading_direction.is_some()
+        || default_page_display_mode.is_some()
+        || default_image_fit_mode.is_some();
+
+    if has_custom_defaults {
+        if let Err(e) = operations::update_book_settings(
+            book_id,
+            default_reading_direction.map(Some),
+            default_page_display_mode.map(Some),
+            default_image_fit_mode.map(Some),
+            None, // sync_progress - use global default
+            None, // reader_background - follows the global setting
+        ) {
+            log::warn!("Failed to create default book settings for book {}: {}", book_id, e);
+        }
+    }
+}
+
+// ============================================================================
+// TAG COMMANDS
+// ============================================================================
+
+/// Create a new tag
+#[tauri::command]
+pub async fn create_tag(name: String) -> Result<Tag, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Tag name cannot be empty").into());
+    }
+
+    let new_tag = NewTag {
+        name,
+        uuid: Some(uuid::Uuid::new_v4().to_string()),
+    };
+    operations::create_tag(new_tag).map_err(|e| e.into())
+}
+
+/// Get all tags ordered by name
+#[tauri::command]
+pub async fn get_tags() -> Result<Vec<Tag>, String> {
+    operations::get_tags().map_err(|e| e.into())
+}
+
+/// Delete a tag (soft delete)
+#[tauri::command]
+pub async fn delete_tag(tag_id: i32) -> Result<(), String> {
+    operations::delete_tag(tag_id).map_err(|e| e.into())
+}
+
+/// Add a tag to a book
+#[tauri::command]
+pub async fn add_tag_to_book(book_id: i32, tag_id: i32) -> Result<(), String> {
+    operations::add_tag_to_book(book_id, tag_id)
+        .map(|_| ())
+        .map_err(|e| e.into())
+}
+
+/// Remove a tag from a book
+#[tauri::command]
+pub async fn remove_tag_from_book(book_id: i32, tag_id: i32) -> Result<(), String> {
+    operations::remove_tag_from_book(book_id, tag_id).map_err(|e| e.into())
+}
+
+/// Get the books with a tag
+#[tauri::command]
+pub async fn get_books_by_tag(tag_id: i32) -> Result<Vec<Book>, String> {
+    operations::get_books_by_tag(tag_id).map_err(|e| e.into())
+}
+
+// ============================================================================
+// SERIES COMMANDS
+// ============================================================================
+
+/// Get the volumes of a series ordered by volume number
+#[tauri::command]
+pub async fn get_series(name: String) -> Result<Vec<Book>, String> {
+    operations::get_series(&name).map_err(|e| e.into())
+}
+
+/// Get every series with its volume count and reading progress
+#[tauri::command]
+pub async fn get_all_series() -> Result<Vec<SeriesSummary>, String> {
+    operations::get_all_series().map_err(|e| e.into())
+}
+
 // ============================================================================
 // BOOK SETTINGS COMMANDS
 // ============================================================================
 
+/// Set the reading direction ("ltr", "rtl" or "vertical") of every book in a collection
+///
+/// Returns the number of books updated.
+#[tauri::command]
+pub async fn set_collection_reading_direction(collection_id: i32, direction: String) -> Result<usize, String> {
+    operations::set_collection_reading_direction(collection_id, &direction).map_err(|e| e.into())
+}
+
 /// Get book settings by book ID
 #[tauri::command]
 pub async fn get_book_settings(book_id: i32) -> Result<Option<BookSettings>, String> {
@@ -432,6 +1105,7 @@ pub async fn update_book_settings(
     page_display_mode: Option<Option<String>>,
     image_fit_mode: Option<Option<String>>,
     sync_progress: Option<Option<bool>>,
+    reader_background: Option<Option<String>>,
 ) -> Result<BookSettings, String> {
     operations::update_book_settings(
         book_id,
@@ -439,6 +1113,7 @@ pub async fn update_book_settings(
         page_display_mode,
         image_fit_mode,
         sync_progress,
+        reader_background,
     )
     .map_err(|e| e.into())
 }
@@ -487,3 +1162,305 @@ pub async fn update_bookmark(
 pub async fn delete_bookmark(bookmark_id: i32) -> Result<(), String> {
     operations::delete_bookmark(bookmark_id).map_err(|e| e.into())
 }
+
+/// Export a book's bookmarks to a shareable JSON file, returning the number written
+#[tauri::command]
+pub async fn export_bookmarks(book_id: i32, path: String) -> Result<usize, String> {
+    operations::export_bookmarks(book_id, &PathBuf::from(path)).map_err(|e| e.into())
+}
+
+/// Import bookmarks from a shared JSON file into the matching local book
+#[tauri::command]
+pub async fn import_bookmarks(path: String) -> Result<BookmarkImportResult, String> {
+    operations::import_bookmarks(&PathBuf::from(path)).map_err(|e| e.into())
+}
+
+// ============================================================================
+// READING QUEUE COMMANDS
+// ============================================================================
+
+/// Append a book to the end of the reading queue
+#[tauri::command]
+pub async fn add_to_queue(book_id: i32) -> Result<QueueEntry, String> {
+    operations::add_to_queue(book_id).map_err(|e| e.into())
+}
+
+/// Get the reading queue in order
+#[tauri::command]
+pub async fn get_queue() -> Result<Vec<QueuedBook>, String> {
+    operations::get_queue().map_err(|e| e.into())
+}
+
+/// Remove a book from the reading queue
+#[tauri::command]
+pub async fn remove_from_queue(book_id: i32) -> Result<(), String> {
+    operations::remove_from_queue(book_id).map_err(|e| e.into())
+}
+
+/// Reorder the reading queue by book IDs, returning the new order
+#[tauri::command]
+pub async fn reorder_queue(book_ids: Vec<i32>) -> Result<Vec<QueuedBook>, String> {
+    operations::reorder_queue(book_ids).map_err(|e| e.into())
+}
+
+// ============================================================================
+// READING ACTIVITY COMMANDS
+// ============================================================================
+
+/// Reading activity per day for the last `days` days, oldest first, for a heatmap
+///
+/// `utc_offset_minutes` is the local time zone's offset east of UTC, so days
+/// match the user's calendar.
+#[tauri::command]
+pub async fn get_reading_activity(days: u32, utc_offset_minutes: i32) -> Result<Vec<DayActivity>, String> {
+    operations::get_activity_by_day(days, utc_offset_minutes).map_err(|e| e.into())
+}
+
+/// Record a reader session; called when the reader closes
+///
+/// `started_at` and `ended_at` are Unix timestamps in milliseconds.
+#[tauri::command]
+pub async fn record_reading_session(
+    book_id: i32,
+    page_from: i32,
+    page_to: i32,
+    started_at: i64,
+    ended_at: i64,
+) -> Result<ReadingSession, String> {
+    let to_datetime = |millis: i64| {
+        chrono::DateTime::from_timestamp_millis(millis)
+            .map(|dt| dt.naive_utc())
+            .ok_or_else(|| String::from(AppError::invalid_input("Invalid session timestamp")))
+    };
+
+    let new_session = NewReadingSession {
+        book_id,
+        page_from,
+        page_to,
+        started_at: to_datetime(started_at)?,
+        ended_at: to_datetime(ended_at)?,
+    };
+    operations::record_reading_session(new_session).map_err(|e| e.into())
+}
+
+/// Get a book's reader sessions, most recent first
+#[tauri::command]
+pub async fn get_reading_history(book_id: i32) -> Result<Vec<ReadingSession>, String> {
+    operations::get_reading_history(book_id).map_err(|e| e.into())
+}
+
+/// Pages and minutes read per day over the last 30 days, in the caller's time zone
+#[tauri::command]
+pub async fn get_reading_stats(utc_offset_minutes: i32) -> Result<Vec<DailyReadingStats>, String> {
+    operations::get_reading_stats(utc_offset_minutes).map_err(|e| e.into())
+}
+
+/// Pages read since `since_ts` (Unix timestamp in milliseconds), in total and per reading status
+#[tauri::command]
+pub async fn get_pages_read(since_ts: i64) -> Result<PagesRead, String> {
+    let since = chrono::DateTime::from_timestamp_millis(since_ts)
+        .map(|dt| dt.naive_utc())
+        .ok_or_else(|| String::from(AppError::invalid_input("Invalid timestamp")))?;
+    operations::get_pages_read(since).map_err(|e| e.into())
+}
+
+// ============================================================================
+// MAINTENANCE COMMANDS
+// ============================================================================
+
+/// Report orphaned collection links, bookmarks and book settings, optionally removing them
+#[tauri::command]
+pub async fn verify_library_consistency(cleanup: bool) -> Result<LibraryConsistencyReport, String> {
+    operations::verify_library_consistency(cleanup).map_err(|e| e.into())
+}
+
+/// Decompress a book's file and report corrupt entries, a missing file or a page count mismatch
+#[tauri::command]
+pub async fn verify_archive(book_id: i32) -> Result<ArchiveVerification, String> {
+    let book = operations::get_book_by_id(book_id).map_err(String::from)?;
+    tauri::async_runtime::spawn_blocking(move || operations::verify_archive(&book))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Hash the page contents of fast-imported books now, reporting any duplicates found
+#[tauri::command]
+pub async fn backfill_content_hashes() -> Result<HashBackfillResult, String> {
+    tauri::async_runtime::spawn_blocking(operations::backfill_content_hashes)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| e.into())
+}
+
+/// Set by `cancel_verify_archives`; checked between books
+static VERIFY_ALL_CANCELLED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// Event emitted after each book checked by `verify_all_archives`
+pub const VERIFY_PROGRESS_EVENT: &str = "verify-progress";
+
+/// Verify the file of every book stored on this device, returning the books with problems
+///
+/// Cloud-only books are skipped. Runs on a blocking thread, emits `verify-progress`
+/// after each book and stops early on `cancel_verify_archives`.
+#[tauri::command]
+pub async fn verify_all_archives(app: AppHandle) -> Result<LibraryVerification, String> {
+    use std::sync::atomic::Ordering;
+
+    VERIFY_ALL_CANCELLED.store(false, Ordering::SeqCst);
+    let books = operations::get_local_books().map_err(String::from)?;
+    log::info!("Verifying {} local book(s)", books.len());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut result = LibraryVerification::default();
+
+        for (index, book) in books.iter().enumerate() {
+            if VERIFY_ALL_CANCELLED.load(Ordering::SeqCst) {
+                log::info!("Archive verification cancelled after {} book(s)", index);
+                result.cancelled = true;
+                break;
+            }
+
+            let verification = operations::verify_archive(book);
+            result.checked += 1;
+            if !verification.is_ok() {
+                log::warn!("Problems found in {}: {:?}", book.title, verification.problems);
+                result.problems.push(verification);
+            }
+
+            let progress = ArchiveVerifyProgress {
+                book_id: book.id,
+                title: book.title.clone(),
+                completed: index + 1,
+                total: books.len(),
+            };
+            if let Err(e) = app.emit(VERIFY_PROGRESS_EVENT, progress) {
+                log::warn!("Failed to emit {}: {}", VERIFY_PROGRESS_EVENT, e);
+            }
+        }
+
+        result
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+/// Find the books of a collection whose stored page count differs from their file
+///
+/// Every archive is recounted on a blocking thread.
+#[tauri::command]
+pub async fn audit_collection_pages(collection_id: i32) -> Result<PageAudit, String> {
+    tauri::async_runtime::spawn_blocking(move || operations::audit_collection_pages(collection_id))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| e.into())
+}
+
+/// Stop a running `verify_all_archives` after the current book
+#[tauri::command]
+pub fn cancel_verify_archives() {
+    VERIFY_ALL_CANCELLED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Move managed book files to a new library directory and remember it for future imports
+///
+/// Returns the number of files moved.
+#[tauri::command]
+pub async fn set_library_directory(app: AppHandle, new_path: String) -> Result<usize, String> {
+    let new_dir = PathBuf::from(new_path.trim());
+    if !new_dir.is_absolute() {
+        return Err("Library directory must be an absolute path".into());
+    }
+
+    let old_dir = storage::get_library_dir(&app).map_err(|e: AppError| e)?;
+    let target_dir = new_dir.clone();
+    crate::protocol::close_zip_handles(None);
+
+    let moved = tauri::async_runtime::spawn_blocking(move || {
+        operations::move_library_files(&old_dir, &target_dir)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+    .map_err(|e: AppError| e)?;
+
+    // Device-local setting: saved without bumping the sync timestamp
+    let mut settings = storage::load_settings(&app).map_err(|e: AppError| e)?;
+    settings.set(
+        storage::LIBRARY_DIR_KEY,
+        SettingValue::String(new_dir.to_string_lossy().to_string()),
+    );
+    storage::save_settings_no_timestamp(&app, &settings).map_err(|e: AppError| e)?;
+
+    Ok(moved)
+}
+
+/// Clamp reading positions that fall outside their book's pages
+///
+/// Returns the number of books fixed.
+#[tauri::command]
+pub async fn fix_invalid_progress() -> Result<usize, String> {
+    progress::flush_reading_positions(None).map_err(|e: AppError| e)?;
+    operations::clamp_invalid_progress().map_err(|e| e.into())
+}
+
+/// Link cloud-only books to matching archives already present locally
+///
+/// Scans `dir`, or the library directory when omitted, and returns the linked books.
+#[tauri::command]
+pub async fn reconcile_local_files(app: AppHandle, dir: Option<String>) -> Result<Vec<Book>, String> {
+    let dir = match dir {
+        Some(dir) => PathBuf::from(dir),
+        None => storage::get_library_dir(&app).map_err(|e: AppError| e)?,
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::reconcile_local_files(&dir).map_err(|e| e.into())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Event emitted for each page written by `convert_to_cbz`
+pub const CONVERT_PROGRESS_EVENT: &str = "convert-progress";
+
+/// Convert a CBR/RAR book to CBZ so it can be read on Android (desktop only)
+///
+/// Emits `convert-progress` as pages are written. With `relink`, the book is
+/// switched over to the new CBZ; its content hash stays the same.
+#[tauri::command]
+pub async fn convert_to_cbz(
+    app: AppHandle,
+    book_id: i32,
+    dest_path: String,
+    relink: bool,
+) -> Result<Book, String> {
+    #[cfg(not(target_os = "android"))]
+    {
+        use crate::database::models::ConversionProgress;
+
+        let book = tauri::async_runtime::spawn_blocking(move || {
+            operations::convert_book_to_cbz(book_id, &PathBuf::from(dest_path), relink, |current, total| {
+                let progress = ConversionProgress { book_id, current, total };
+                if let Err(e) = app.emit(CONVERT_PROGRESS_EVENT, progress) {
+                    log::warn!("Failed to emit {}: {}", CONVERT_PROGRESS_EVENT, e);
+                }
+            })
+        })
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+
+        if relink {
+            // The new file may enumerate its pages differently
+            if let Err(e) = crate::protocol::rebuild_image_cache(book_id) {
+                log::warn!("Failed to rebuild image list for book {}: {}", book_id, e);
+            }
+        }
+        Ok(book)
+    }
+
+    #[cfg(target_os = "android")]
+    {
+        let _ = (app, book_id, dest_path, relink);
+        Err(AppError::invalid_input("CBR conversion is only available on desktop").into())
+    }
+}
@@ -1,6 +1,6 @@
 //! Settings-related Tauri commands
 
-use crate::settings::{self, AppSettings, SettingCategory, SettingValue};
+use crate::settings::{self, AppSettings, ModifiedSetting, SettingCategory, SettingValue};
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -33,6 +33,42 @@ pub async fn get_setting(
     Ok(settings.get(&key).cloned())
 }
 
+/// List settings whose value differs from their default, with both values
+#[tauri::command]
+pub async fn get_modified_settings(app: tauri::AppHandle) -> Result<Vec<ModifiedSetting>, String> {
+    settings::get_modified_settings(&app).map_err(|e| e.into())
+}
+
+/// Write the settings schema with default values to a JSON file, returning the number of settings
+#[tauri::command]
+pub async fn export_settings_schema(path: String) -> Result<usize, String> {
+    settings::export_settings_schema(std::path::Path::new(&path)).map_err(|e| e.into())
+}
+
+/// Export the current settings as a JSON string, for moving them to another install
+#[tauri::command]
+pub async fn export_settings(app: tauri::AppHandle) -> Result<String, String> {
+    settings::export_settings(&app).map_err(|e| e.into())
+}
+
+/// Import settings from a JSON string produced by `export_settings`
+#[tauri::command]
+pub async fn import_settings(app: tauri::AppHandle, json: String) -> Result<AppSettings, String> {
+    settings::import_settings(&app, &json).map_err(|e| e.into())
+}
+
+/// List the names of the built-in settings presets
+#[tauri::command]
+pub async fn list_settings_presets() -> Result<Vec<String>, String> {
+    Ok(settings::list_settings_presets())
+}
+
+/// Apply a built-in settings preset (e.g. "manga" or "webtoon")
+#[tauri::command]
+pub async fn apply_settings_preset(app: tauri::AppHandle, name: String) -> Result<AppSettings, String> {
+    settings::apply_settings_preset(&app, &name).map_err(|e| e.into())
+}
+
 /// Update settings from UI form data
 #[tauri::command]
 pub async fn save_settings_from_schema(
@@ -1,12 +1,36 @@
 //! Sync-related Tauri commands
 
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter};
 
 use crate::auth;
 use crate::commands::device::get_device_id;
-use crate::error::AppError;
-use crate::settings::{load_settings, SettingValue};
-use crate::sync::{DriveSync, MergeEngine, SyncOptions, SyncResult, SyncStatus, ConflictStrategy};
+use crate::error::{AppError, ErrorCode};
+use crate::settings::{load_settings, SettingValue, SYNC_NAMESPACE_KEY};
+use crate::sync::encryption;
+use crate::sync::{
+    BookConflict, BookDownloadOutcome, BookDownloadProgress, BookFileProgress, ConflictStrategy, DownloadAllResult, DriveSync,
+    SyncBackend,
+    is_first_sync, MergeEngine, SnapshotSource, SyncChange, SyncOptions, SyncPhase, SyncProgress, SyncResult,
+    SyncStatus, SyncUploadEstimate,
+};
+
+/// Event emitted with the new `SyncStatus` when a sync starts and when it ends
+pub const SYNC_STATUS_EVENT: &str = "sync-status";
+
+/// Event emitted with a `SyncProgress` as a sync moves through its phases
+pub const SYNC_PROGRESS_EVENT: &str = "sync-progress";
+
+fn emit_sync_status(app: &AppHandle, status: SyncStatus) {
+    if let Err(e) = app.emit(SYNC_STATUS_EVENT, status) {
+        log::warn!("Failed to emit {}: {}", SYNC_STATUS_EVENT, e);
+    }
+}
+
+fn emit_sync_progress(app: &AppHandle, progress: SyncProgress) {
+    if let Err(e) = app.emit(SYNC_PROGRESS_EVENT, progress) {
+        log::warn!("Failed to emit {}: {}", SYNC_PROGRESS_EVENT, e);
+    }
+}
 
 #[tauri::command]
 pub fn get_sync_status(app: AppHandle) -> Result<SyncStatus, String> {
@@ -22,34 +46,199 @@ fn get_sync_status_impl(app: &AppHandle) -> Result<SyncStatus, AppError> {
     }
 
     // Get last sync from database
+    let state = load_sync_state(&mut *crate::database::get_connection()?)?;
+    Ok(status_of(state))
+}
+
+fn load_sync_state(
+    conn: &mut diesel::SqliteConnection,
+) -> Result<Option<crate::database::models::SyncState>, AppError> {
+    use diesel::prelude::*;
+    use crate::schema::sync_state;
+
+    sync_state::table
+        .find(1)
+        .first(conn)
+        .optional()
+        .map_err(|e| AppError::database_error(e.to_string()))
+}
+
+/// Status of an authenticated device with the given sync state
+fn status_of(state: Option<crate::database::models::SyncState>) -> SyncStatus {
+    let Some(state) = state else {
+        return SyncStatus::NeverSynced;
+    };
+
+    // Errors are cleared on every successful sync, so any left over mean the last attempt failed
+    if let Some(error) = state.errors().into_iter().next() {
+        let last_attempt_at = state
+            .last_attempt_at
+            .map(|t| t.and_utc().timestamp_millis())
+            .unwrap_or_default();
+        return SyncStatus::Failed { error, last_attempt_at };
+    }
+
+    match state.last_sync_at {
+        Some(last_sync) => SyncStatus::Synced { 
+            last_sync_at: last_sync.and_utc().timestamp_millis() 
+        },
+        None => SyncStatus::NeverSynced,
+    }
+}
+
+/// Get the errors recorded by the last sync attempt (empty if it succeeded)
+#[tauri::command]
+pub fn get_last_sync_errors() -> Result<Vec<String>, String> {
+    get_last_sync_errors_impl().map_err(|e| e.into())
+}
+
+fn get_last_sync_errors_impl() -> Result<Vec<String>, AppError> {
+    let state = load_sync_state(&mut *crate::database::get_connection()?)?;
+    Ok(state.map(|s| s.errors()).unwrap_or_default())
+}
+
+/// Get what recent syncs changed, newest first, for the activity feed
+///
+/// Downloaded entries name the device that uploaded the snapshot. Empty before the first
+/// sync that changed anything.
+#[tauri::command]
+pub fn get_recent_sync_changes() -> Result<Vec<SyncChange>, String> {
+    get_recent_sync_changes_impl().map_err(|e| e.into())
+}
+
+fn get_recent_sync_changes_impl() -> Result<Vec<SyncChange>, AppError> {
     use diesel::prelude::*;
     use crate::database::get_connection;
     use crate::schema::sync_state;
-    use crate::database::models::SyncState;
 
     let mut conn = get_connection()?;
-    let state: Option<SyncState> = sync_state::table
+    let last_changes: Option<Option<String>> = sync_state::table
         .find(1)
+        .select(sync_state::last_changes)
         .first(&mut conn)
         .optional()
         .map_err(|e| AppError::database_error(e.to_string()))?;
 
-    match state.and_then(|s| s.last_sync_at) {
-        Some(last_sync) => Ok(SyncStatus::Synced { 
-            last_sync_at: last_sync.and_utc().timestamp_millis() 
-        }),
-        None => Ok(SyncStatus::NeverSynced),
-    }
+    Ok(last_changes
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// Persist the outcome of a sync attempt so it can be shown after the fact
+fn record_sync_attempt(conn: &mut diesel::SqliteConnection, errors: &[String]) -> Result<(), AppError> {
+    use diesel::prelude::*;
+    use crate::schema::sync_state;
+
+    let last_errors = if errors.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(errors).map_err(|e| AppError::database_error(e.to_string()))?)
+    };
+
+    diesel::update(sync_state::table.find(1))
+        .set((
+            sync_state::last_attempt_at.eq(Some(chrono::Utc::now().naive_utc())),
+            sync_state::last_errors.eq(last_errors),
+        ))
+        .execute(conn)
+        .map_err(|e| AppError::database_error(e.to_string()))?;
+
+    Ok(())
 }
 
 /// Trigger a manual sync
+///
+/// A device that has never synced gets a pull-dominant merge (see `initial_sync`).
+/// Emits `sync-status` (`Syncing`, then the recorded outcome) and `sync-progress`.
 #[tauri::command]
 pub async fn sync_now(app: AppHandle) -> Result<SyncResult, String> {
-    sync_now_impl(&app).await.map_err(|e| e.into())
+    run_sync(&app, false, None).await
+}
+
+/// First sync of a device, called by the onboarding flow
+///
+/// Always merges pull-dominant: remote data wins and nothing on Drive is deleted or
+/// overwritten because it is missing or older locally. Local-only data is still uploaded.
+#[tauri::command]
+pub async fn initial_sync(app: AppHandle) -> Result<SyncResult, String> {
+    run_sync(&app, true, None).await
+}
+
+/// Run a single sync that resolves conflicts with `strategy`
+///
+/// For recovering from unwanted overwrites, e.g. `local_wins` to push this device's
+/// version back to Drive. The `sync.conflict_strategy` setting is left unchanged.
+#[tauri::command]
+pub async fn sync_with_strategy(app: AppHandle, strategy: ConflictStrategy) -> Result<SyncResult, String> {
+    run_sync(&app, false, Some(strategy)).await
+}
+
+/// Preview what a sync would change without changing anything
+///
+/// Downloads the snapshot and merges it in a transaction that is rolled back. Nothing
+/// is uploaded, no book files are transferred, and neither `sync-status` nor
+/// `sync-progress` is emitted. The planned changes are listed in `SyncResult::changes`.
+#[tauri::command]
+pub async fn preview_sync(app: AppHandle) -> Result<SyncResult, String> {
+    sync_now_impl(&app, false, None, true).await.map_err(|e| e.into())
 }
 
-async fn sync_now_impl(app: &AppHandle) -> Result<SyncResult, AppError> {
-    log::info!("Starting manual sync...");
+/// Run a sync, record its outcome and announce the resulting status
+///
+/// Without a `strategy`, conflicts are resolved as configured in `sync.conflict_strategy`.
+async fn run_sync(
+    app: &AppHandle,
+    initial: bool,
+    strategy: Option<ConflictStrategy>,
+) -> Result<SyncResult, String> {
+    emit_sync_status(app, SyncStatus::Syncing);
+    let outcome = sync_now_impl(app, initial, strategy, false).await;
+
+    let errors = match &outcome {
+        Ok(result) => result.errors.clone(),
+        Err(e) => vec![e.message.clone()],
+    };
+    let recorded = crate::database::get_connection()
+        .and_then(|mut conn| record_sync_attempt(&mut conn, &errors));
+    if let Err(e) = recorded {
+        log::warn!("Failed to record sync outcome: {}", e);
+    }
+
+    // Same status `get_sync_status` reports from now on
+    let status = get_sync_status_impl(app).unwrap_or_else(|e| SyncStatus::Failed {
+        error: errors.first().cloned().unwrap_or(e.message),
+        last_attempt_at: chrono::Utc::now().timestamp_millis(),
+    });
+    emit_sync_status(app, status);
+
+    outcome.map_err(|e| e.into())
+}
+
+async fn sync_now_impl(
+    app: &AppHandle,
+    initial: bool,
+    strategy: Option<ConflictStrategy>,
+    dry_run: bool,
+) -> Result<SyncResult, AppError> {
+    let strategy = match strategy {
+        Some(strategy) => strategy,
+        None => load_conflict_strategy(app)?,
+    };
+    // A preview isn't a sync as far as the UI is concerned
+    let progress = |phase| {
+        if !dry_run {
+            emit_sync_progress(app, SyncProgress::phase(phase));
+        }
+    };
+    let kind = if dry_run {
+        "preview"
+    } else if initial {
+        "initial"
+    } else {
+        "manual"
+    };
+    log::info!("Starting {} sync ({:?})...", kind, strategy);
     
     // Check authentication
     let auth_status = auth::get_auth_status(app)?;
@@ -57,14 +246,10 @@ async fn sync_now_impl(app: &AppHandle) -> Result<SyncResult, AppError> {
         return Err(AppError::not_authenticated());
     }
 
-    // Load sync options from user settings
-    let settings = load_settings(app)?;
-    let sync_options = SyncOptions {
-        sync_books: matches!(settings.get("sync.books"), Some(SettingValue::Bool(true))),
-        sync_books_files: matches!(settings.get("sync.books"), Some(SettingValue::Bool(true))),
-        sync_settings: matches!(settings.get("sync.settings"), Some(SettingValue::Bool(true))),
-        sync_progress: matches!(settings.get("sync.progress"), Some(SettingValue::Bool(true))),
-    };
+    // Make sure buffered reading positions are part of this sync
+    crate::database::progress::flush_reading_positions(None)?;
+
+    let sync_options = SyncOptions { dry_run, ..load_sync_options(app)? };
 
     log::info!(
         "Sync options: books={}, files={}, settings={}, progress={}",
@@ -80,54 +265,10 @@ async fn sync_now_impl(app: &AppHandle) -> Result<SyncResult, AppError> {
         return Ok(SyncResult::empty());
     }
 
-    // Check if token needs refresh
-    let token = auth::load_token(app)?;
-    
-    log::info!(
-        "Token status: is_expired={}, can_refresh={}, has_refresh_token={}",
-        token.is_expired(),
-        token.can_refresh(),
-        token.refresh_token.is_some()
-    );
-    
-    let access_token = if token.is_expired() {
-        if !token.can_refresh() {
-            log::error!("Access token expired and no refresh token available - user needs to re-authenticate");
-            return Err(AppError::not_authenticated());
-        }
-        
-        // Refresh the token
-        log::info!("Access token expired, attempting refresh...");
-        let client_id = token.client_id.as_ref()
-            .ok_or_else(|| AppError::config_read_failed("OAuth client_id not stored - please sign in again"))?;
-        let client_secret = token.client_secret.as_ref()
-            .ok_or_else(|| AppError::config_read_failed("OAuth client_secret not stored - please sign in again"))?;
-        
-        match crate::commands::auth::refresh_token_internal(client_id, client_secret, &token).await {
-            Ok(new_token) => {
-                log::info!(
-                    "Token refreshed successfully, new expiration: {:?}",
-                    new_token.expires_at
-                );
-                auth::save_token(app, &new_token)?;
-                new_token.access_token
-            }
-            Err(e) => {
-                log::error!("Failed to refresh token: {:?}", e);
-                // Clear the stored token so user knows they need to re-auth
-                if let Err(clear_err) = auth::clear_token(app) {
-                    log::warn!("Failed to clear invalid token: {:?}", clear_err);
-                }
-                return Err(AppError::sync_failed(format!(
-                    "Your Google Drive access has expired. Please sign in again. ({})", e
-                )));
-            }
-        }
-    } else {
-        token.access_token
-    };
+    let access_token = fresh_access_token(app).await?;
 
-    let drive = DriveSync::with_token(access_token.clone());
+    let drive = backend_for(app, access_token.clone())?;
+    drive.check_snapshot_key()?;
     
     // Read cached sync file ID from database
     use diesel::prelude::*;
@@ -135,34 +276,49 @@ async fn sync_now_impl(app: &AppHandle) -> Result<SyncResult, AppError> {
     use crate::schema::sync_state;
     use crate::database::models::SyncState;
     
-    let cached_file_id: Option<String> = {
+    let (cached_file_id, first_sync): (Option<String>, bool) = {
         let mut conn = get_connection()?;
-        sync_state::table
+        let cached_file_id = sync_state::table
             .find(1)
             .first::<SyncState>(&mut conn)
             .ok()
-            .and_then(|s| s.sync_file_id)
+            .and_then(|s| s.sync_file_id);
+        (cached_file_id, is_first_sync(&mut conn)?)
     };
     
     // Download remote snapshot
     log::info!("Downloading remote snapshot...");
+    progress(SyncPhase::Downloading);
     let remote_snapshot = drive.download_snapshot(cached_file_id.as_deref()).await?;
     let existing_file_id = drive.find_sync_file(cached_file_id.as_deref()).await?;
     
     // Merge local and remote
     log::info!("Merging local and remote data...");
+    progress(SyncPhase::Merging);
     let device_id = get_device_id(app).unwrap_or_else(|| format!("device-{}", uuid::Uuid::new_v4()));
-    let engine = MergeEngine::new(device_id, ConflictStrategy::default(), sync_options.clone());
+    let pull_dominant = initial || first_sync;
+    if pull_dominant {
+        log::info!("First sync of this device, remote data takes precedence");
+    }
+    let engine = MergeEngine::new(device_id, strategy, sync_options.clone())
+        .pull_dominant(pull_dominant);
     let (updated_snapshot, mut result) = engine.sync(app, remote_snapshot)?;
+    if dry_run {
+        log::info!("Sync preview: {} changes planned", result.changes.len());
+        return Ok(result);
+    }
     
     // Upload updated snapshot
     log::info!("Uploading updated snapshot...");
+    progress(SyncPhase::Uploading);
     let file_id = drive.upload_snapshot(&updated_snapshot, existing_file_id.as_deref()).await?;
+    crate::sync::merge::save_recent_changes(&mut *get_connection()?, &result.changes)?;
     
     // Sync book files if enabled
     if sync_options.sync_books_files {
         log::info!("Syncing book files...");
-        sync_book_files(app, &drive, &updated_snapshot, &mut result).await?;
+        progress(SyncPhase::SyncingFiles);
+        sync_book_files(app, drive.as_ref(), &updated_snapshot, &mut result).await?;
     }
     
     // Save file ID to local state
@@ -183,14 +339,238 @@ async fn sync_now_impl(app: &AppHandle) -> Result<SyncResult, AppError> {
     Ok(result)
 }
 
-/// Sync book files between local storage and Google Drive
-/// Only uploads local files to Drive - downloads happen on-demand when user tries to read
-async fn sync_book_files(
-    _app: &AppHandle,
-    drive: &DriveSync,
-    _snapshot: &crate::sync::SyncSnapshot,
-    result: &mut SyncResult,
-) -> Result<(), AppError> {
+/// Load sync options from user settings
+fn load_sync_options(app: &AppHandle) -> Result<SyncOptions, AppError> {
+    let settings = load_settings(app)?;
+    Ok(SyncOptions {
+        sync_books: matches!(settings.get("sync.books"), Some(SettingValue::Bool(true))),
+        sync_books_files: matches!(settings.get("sync.books"), Some(SettingValue::Bool(true))),
+        sync_settings: matches!(settings.get("sync.settings"), Some(SettingValue::Bool(true))),
+        sync_progress: matches!(settings.get("sync.progress"), Some(SettingValue::Bool(true))),
+        dry_run: false,
+    })
+}
+
+/// Write a sync snapshot to a local file as pretty-printed JSON, for inspection
+///
+/// `Remote` downloads the snapshot stored on Drive (which lives in the hidden app data
+/// folder); `Local` builds the one this device's data would produce, using the current
+/// sync settings. Nothing is uploaded or changed either way.
+#[tauri::command]
+pub async fn export_snapshot(app: AppHandle, path: String, source: SnapshotSource) -> Result<(), String> {
+    export_snapshot_impl(&app, &path, source).await.map_err(|e| e.into())
+}
+
+async fn export_snapshot_impl(app: &AppHandle, path: &str, source: SnapshotSource) -> Result<(), AppError> {
+    use diesel::prelude::*;
+    use crate::database::get_connection;
+    use crate::database::models::SyncState;
+    use crate::schema::sync_state;
+
+    let snapshot = match source {
+        SnapshotSource::Remote => {
+            let auth_status = auth::get_auth_status(app)?;
+            if !auth_status.is_authenticated {
+                return Err(AppError::not_authenticated());
+            }
+
+            let cached_file_id = {
+                let mut conn = get_connection()?;
+                sync_state::table
+                    .find(1)
+                    .first::<SyncState>(&mut conn)
+                    .ok()
+                    .and_then(|s| s.sync_file_id)
+            };
+            let drive = backend_for(app, fresh_access_token(app).await?)?;
+            drive
+                .download_snapshot(cached_file_id.as_deref())
+                .await?
+                .ok_or_else(|| AppError::new(ErrorCode::SyncFailed, "There is no sync snapshot on Drive yet"))?
+        }
+        SnapshotSource::Local => {
+            crate::database::progress::flush_reading_positions(None)?;
+            let device_id = get_device_id(app).unwrap_or_else(|| format!("device-{}", uuid::Uuid::new_v4()));
+            MergeEngine::new(device_id, ConflictStrategy::default(), load_sync_options(app)?)
+                .local_snapshot(app)?
+        }
+    };
+
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| AppError::new(ErrorCode::SyncFailed, format!("Failed to serialize snapshot: {}", e)))?;
+    std::fs::write(path, json).map_err(|e| {
+        AppError::new(ErrorCode::IoError, format!("Failed to write snapshot to {}: {}", path, e))
+    })?;
+
+    log::info!("Exported {:?} sync snapshot to {}", source, path);
+    Ok(())
+}
+
+/// Show the local and Drive versions of a book side by side, without syncing anything
+#[tauri::command]
+pub async fn get_book_conflict(app: AppHandle, book_id: i32) -> Result<BookConflict, String> {
+    get_book_conflict_impl(&app, book_id).await.map_err(|e| e.into())
+}
+
+async fn get_book_conflict_impl(app: &AppHandle, book_id: i32) -> Result<BookConflict, AppError> {
+    let auth_status = auth::get_auth_status(app)?;
+    if !auth_status.is_authenticated {
+        return Err(AppError::not_authenticated());
+    }
+
+    // Compare against the latest local position, not a buffered one
+    crate::database::progress::flush_reading_positions(Some(book_id))?;
+    let local = crate::database::operations::get_book_by_id(book_id)?;
+
+    let sync_state_record = load_sync_state(&mut *crate::database::get_connection()?)?;
+    let cached_file_id = sync_state_record.as_ref().and_then(|s| s.sync_file_id.clone());
+    let last_sync_at = sync_state_record
+        .and_then(|s| s.last_sync_at)
+        .map(|dt| crate::sync::to_timestamp(&dt));
+
+    let drive = backend_for(app, fresh_access_token(app).await?)?;
+    let snapshot = drive.download_snapshot(cached_file_id.as_deref()).await?;
+    Ok(book_conflict(local, snapshot, last_sync_at))
+}
+
+/// Compare `local` with its entry in `snapshot`, matched by UUID
+fn book_conflict(
+    local: crate::database::models::Book,
+    snapshot: Option<crate::sync::SyncSnapshot>,
+    last_sync_at: Option<i64>,
+) -> BookConflict {
+    let remote = local
+        .uuid
+        .as_ref()
+        .and_then(|uuid| snapshot.and_then(|mut s| s.books.remove(uuid)));
+
+    let local_updated_at = crate::sync::to_timestamp(&local.updated_at);
+    let remote_updated_at = remote.as_ref().map(|r| r.updated_at);
+    let since = last_sync_at.unwrap_or(0);
+
+    BookConflict {
+        local_changed: local_updated_at > since,
+        remote_changed: remote_updated_at.is_some_and(|ts| ts > since),
+        local,
+        remote,
+        local_updated_at,
+        remote_updated_at,
+        last_sync_at,
+    }
+}
+
+/// Conflict strategy from the `sync.conflict_strategy` setting
+///
+/// Unknown values fall back to the default (most recent change wins).
+fn load_conflict_strategy(app: &AppHandle) -> Result<ConflictStrategy, AppError> {
+    let settings = load_settings(app)?;
+    Ok(match settings.get("sync.conflict_strategy") {
+        Some(SettingValue::String(value)) => serde_json::from_value(serde_json::Value::String(value.clone()))
+            .unwrap_or_else(|_| {
+                log::warn!("Unknown conflict strategy {:?}, using the default", value);
+                ConflictStrategy::default()
+            }),
+        _ => ConflictStrategy::default(),
+    })
+}
+
+/// Sync backend picked by the `sync.backend` setting
+///
+/// Unknown values fall back to Google Drive, the only backend so far.
+pub(crate) fn backend_for(app: &AppHandle, access_token: String) -> Result<Box<dyn SyncBackend>, AppError> {
+    let settings = load_settings(app)?;
+    match settings.get("sync.backend") {
+        Some(SettingValue::String(backend)) if backend != crate::sync::backend::GOOGLE_DRIVE_BACKEND => {
+            log::warn!("Unknown sync backend {:?}, using Google Drive", backend);
+        }
+        _ => {}
+    }
+    Ok(Box::new(drive_for(app, access_token)?))
+}
+
+/// Drive client for this device's sync namespace (the `sync.namespace` setting)
+///
+/// Snapshots are encrypted when `sync.encrypt` is on.
+fn drive_for(app: &AppHandle, access_token: String) -> Result<DriveSync, AppError> {
+    let settings = load_settings(app)?;
+    let namespace = match settings.get(SYNC_NAMESPACE_KEY) {
+        Some(SettingValue::String(namespace)) => namespace.as_str(),
+        _ => "",
+    };
+    let encrypt = matches!(settings.get("sync.encrypt"), Some(SettingValue::Bool(true)));
+    Ok(DriveSync::with_token(access_token)
+        .with_namespace(namespace)?
+        .with_encryption(encrypt))
+}
+
+/// Set the passphrase sync snapshots are encrypted with, for this session only
+///
+/// It is kept in memory and never saved; `None` or an empty string forgets it.
+#[tauri::command]
+pub fn set_sync_passphrase(passphrase: Option<String>) {
+    encryption::set_passphrase(passphrase);
+}
+
+/// Whether a sync passphrase was entered this session
+#[tauri::command]
+pub fn has_sync_passphrase() -> bool {
+    encryption::passphrase().is_some()
+}
+
+/// Load the stored Google token, refreshing it first if it has expired
+async fn fresh_access_token(app: &AppHandle) -> Result<String, AppError> {
+    // Check if token needs refresh
+    let token = auth::load_token(app)?;
+    
+    log::info!(
+        "Token status: is_expired={}, can_refresh={}, has_refresh_token={}",
+        token.is_expired(),
+        token.can_refresh(),
+        token.refresh_token.is_some()
+    );
+    
+    if token.is_expired() {
+        if !token.can_refresh() {
+            log::error!("Access token expired and no refresh token available - user needs to re-authenticate");
+            return Err(AppError::not_authenticated());
+        }
+        
+        // Refresh the token
+        log::info!("Access token expired, attempting refresh...");
+        let client_id = token.client_id.as_ref()
+            .ok_or_else(|| AppError::config_read_failed("OAuth client_id not stored - please sign in again"))?;
+        let client_secret = token.client_secret.as_ref()
+            .ok_or_else(|| AppError::config_read_failed("OAuth client_secret not stored - please sign in again"))?;
+        
+        match crate::commands::auth::refresh_token_internal(client_id, client_secret, &token).await {
+            Ok(new_token) => {
+                log::info!(
+                    "Token refreshed successfully, new expiration: {:?}",
+                    new_token.expires_at
+                );
+                auth::save_token(app, &new_token)?;
+                Ok(new_token.access_token)
+            }
+            Err(e) => {
+                log::error!("Failed to refresh token: {:?}", e);
+                // Clear the stored token so user knows they need to re-auth
+                if let Err(clear_err) = auth::clear_token(app) {
+                    log::warn!("Failed to clear invalid token: {:?}", clear_err);
+                }
+                Err(AppError::sync_failed(format!(
+                    "Your Google Drive access has expired. Please sign in again. ({})", e
+                )))
+            }
+        }
+    } else {
+        Ok(token.access_token)
+    }
+}
+
+/// Local (non-deleted, hashed) books whose file exists on disk but not yet on Drive
+async fn books_missing_on_drive(
+    drive: &dyn SyncBackend,
+) -> Result<Vec<crate::database::models::Book>, AppError> {
     use crate::database::get_connection;
     use crate::schema::books;
     use diesel::prelude::*;
@@ -198,10 +578,12 @@ async fn sync_book_files(
     
     let mut conn = get_connection()?;
     
-    // Get all local books with file_hash (non-deleted)
+    // Get all local books with file_hash (non-deleted). Drive files are named by
+    // content hash, so fast-imported books wait for their hash backfill.
     let local_books: Vec<Book> = books::table
         .filter(books::deleted_at.is_null())
         .filter(books::file_hash.is_not_null())
+        .filter(books::hash_scheme.eq(crate::database::models::HashScheme::Content.as_str()))
         .load(&mut conn)
         .map_err(|e| AppError::database_error(e.to_string()))?;
     
@@ -213,24 +595,98 @@ async fn sync_book_files(
         .map(|f| f.file_hash.clone())
         .collect();
     
+    Ok(not_on_drive(local_books, &remote_hashes))
+}
+
+/// Books among `local_books` with an uploadable file here and no file on Drive
+fn not_on_drive(
+    local_books: Vec<crate::database::models::Book>,
+    remote_hashes: &std::collections::HashSet<String>,
+) -> Vec<crate::database::models::Book> {
+    local_books
+        .into_iter()
+        .filter(|book| {
+            book.file_hash
+                .as_ref()
+                .is_some_and(|hash| !remote_hashes.contains(hash))
+        })
+        .filter(|book| {
+            // Manifest books are folders of loose files, not a single uploadable archive
+            if book.file_path.starts_with(crate::manifest::MANIFEST_SCHEME) {
+                return false;
+            }
+            // Check if the local file exists
+            let exists = std::path::Path::new(&book.file_path).exists();
+            if !exists {
+                log::warn!("Book file not found locally: {}", book.file_path);
+            }
+            exists
+        })
+        .collect()
+}
+
+/// Estimate how much a book-file sync would upload, without uploading anything
+#[tauri::command]
+pub async fn estimate_sync_upload_size(app: AppHandle) -> Result<SyncUploadEstimate, String> {
+    estimate_sync_upload_size_impl(&app).await.map_err(|e| e.into())
+}
+
+async fn estimate_sync_upload_size_impl(app: &AppHandle) -> Result<SyncUploadEstimate, AppError> {
+    let auth_status = auth::get_auth_status(app)?;
+    if !auth_status.is_authenticated {
+        return Err(AppError::not_authenticated());
+    }
+
+    let drive = backend_for(app, fresh_access_token(app).await?)?;
+    let pending = books_missing_on_drive(drive.as_ref()).await?;
+    Ok(upload_estimate(&pending))
+}
+
+/// Size of uploading `pending`, from the files on disk or else their stored size
+fn upload_estimate(pending: &[crate::database::models::Book]) -> SyncUploadEstimate {
+    let total_bytes = pending
+        .iter()
+        .map(|book| {
+            std::fs::metadata(&book.file_path)
+                .map(|meta| meta.len())
+                .unwrap_or_else(|_| book.file_size.unwrap_or(0).max(0) as u64)
+        })
+        .sum();
+
+    SyncUploadEstimate {
+        file_count: pending.len(),
+        total_bytes,
+    }
+}
+
+/// Sync book files between local storage and Google Drive
+/// Only uploads local files to Drive - downloads happen on-demand when user tries to read
+///
+/// Emits a `SyncingFiles` progress event before each upload.
+async fn sync_book_files(
+    app: &AppHandle,
+    drive: &dyn SyncBackend,
+    _snapshot: &crate::sync::SyncSnapshot,
+    result: &mut SyncResult,
+) -> Result<(), AppError> {
     // Upload local books that aren't on Drive yet
-    for book in &local_books {
+    let missing = books_missing_on_drive(drive).await?;
+    for (index, book) in missing.iter().enumerate() {
         if let Some(ref file_hash) = book.file_hash {
-            if !remote_hashes.contains(file_hash) {
-                // Check if the local file exists
-                if std::path::Path::new(&book.file_path).exists() {
-                    log::info!("Uploading book file: {} ({})", book.title, file_hash);
-                    match drive.upload_book_file(&book.file_path, file_hash).await {
-                        Ok(_) => {
-                            result.books_uploaded += 1;
-                        }
-                        Err(e) => {
-                            log::error!("Failed to upload book {}: {}", book.title, e);
-                            result.errors.push(format!("Failed to upload {}: {}", book.title, e));
-                        }
-                    }
-                } else {
-                    log::warn!("Book file not found locally: {}", book.file_path);
+            emit_sync_progress(app, SyncProgress {
+                phase: SyncPhase::SyncingFiles,
+                index: Some(index + 1),
+                total: Some(missing.len()),
+                title: Some(book.title.clone()),
+            });
+            log::info!("Uploading book file: {} ({})", book.title, file_hash);
+            match drive.upload_book_file(&book.file_path, file_hash).await {
+                Ok(_) => {
+                    result.books_uploaded += 1;
+                }
+                Err(e) => {
+                    log::error!("Failed to upload book {}: {}", book.title, e);
+                    result.errors.push(format!("Failed to upload {}: {}", book.title, e));
                 }
             }
         }
@@ -242,104 +698,405 @@ async fn sync_book_files(
     Ok(())
 }
 
-/// Download a cloud-only book file from Google Drive
+/// Event emitted with a `BookFileProgress` while `download_book` downloads a file
+pub const BOOK_FILE_PROGRESS_EVENT: &str = "book-file-progress";
+
+/// Download a cloud-only book file from the sync backend
 /// This is called when user tries to read a book that has cloud:// file path
+///
+/// Emits `book-file-progress` as the file comes in. Returns the book with its
+/// local file path.
 #[tauri::command]
-pub async fn download_cloud_book(app: AppHandle, book_id: i32) -> Result<crate::database::models::Book, String> {
-    download_cloud_book_impl(&app, book_id).await.map_err(|e| e.into())
+pub async fn download_book(app: AppHandle, book_id: i32) -> Result<crate::database::models::Book, String> {
+    download_book_impl(&app, book_id).await.map_err(|e| e.into())
 }
 
-async fn download_cloud_book_impl(app: &AppHandle, book_id: i32) -> Result<crate::database::models::Book, AppError> {
-    use crate::database::get_connection;
-    use crate::database::models::Book;
-    use crate::schema::books;
-    use diesel::prelude::*;
-
+async fn download_book_impl(app: &AppHandle, book_id: i32) -> Result<crate::database::models::Book, AppError> {
     log::info!("Downloading cloud book with id: {}", book_id);
 
-    // Get the book from database
-    let mut conn = get_connection()?;
-    let book: Book = books::table
-        .find(book_id)
-        .first(&mut conn)
-        .map_err(|e| AppError::database_error(format!("Book not found: {}", e)))?;
+    let book = crate::database::operations::get_book_by_id(book_id)?;
 
     // Verify it's a cloud-only book
     if !book.file_path.starts_with("cloud://") {
         return Err(AppError::sync_failed("Book is not cloud-only, file already exists locally"));
     }
 
-    // Get file_hash - required for cloud books
-    let file_hash = book.file_hash.as_ref()
-        .ok_or_else(|| AppError::sync_failed("Cloud book missing file_hash"))?;
-
     // Check authentication
     let auth_status = auth::get_auth_status(app)?;
     if !auth_status.is_authenticated {
         return Err(AppError::not_authenticated());
     }
 
-    // Get access token (refresh if needed)
-    let token = auth::load_token(app)?;
-    let access_token = if token.is_expired() {
-        if !token.can_refresh() {
-            return Err(AppError::not_authenticated());
-        }
-        
-        let client_id = token.client_id.as_ref()
-            .ok_or_else(|| AppError::config_read_failed("OAuth client_id not stored - please sign in again"))?;
-        let client_secret = token.client_secret.as_ref()
-            .ok_or_else(|| AppError::config_read_failed("OAuth client_secret not stored - please sign in again"))?;
-        
-        match crate::commands::auth::refresh_token_internal(client_id, client_secret, &token).await {
-            Ok(new_token) => {
-                auth::save_token(app, &new_token)?;
-                new_token.access_token
-            }
-            Err(e) => {
-                return Err(AppError::sync_failed(format!("Token refresh failed: {}", e)));
-            }
+    let drive = backend_for(app, fresh_access_token(app).await?)?;
+    let report = |downloaded_bytes: u64, total_bytes: Option<u64>| {
+        let progress = BookFileProgress {
+            book_id,
+            downloaded_bytes,
+            total_bytes,
+        };
+        if let Err(e) = app.emit(BOOK_FILE_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to emit {}: {}", BOOK_FILE_PROGRESS_EVENT, e);
         }
-    } else {
-        token.access_token
     };
+    let (updated_book, _) = download_into_library(app, drive.as_ref(), &book, &report)
+        .await
+        .map_err(|e| explain_missing_file(e, drive.name(), &book.title))?;
 
-    let drive = DriveSync::with_token(access_token);
+    log::info!("Successfully downloaded cloud book: {}", updated_book.title);
+
+    Ok(updated_book)
+}
+
+/// Replace a `CloudFileMissing` error with one telling the user how to get the file back
+///
+/// Other errors are returned unchanged.
+fn explain_missing_file(error: AppError, backend_name: &str, book_title: &str) -> AppError {
+    if !matches!(error.code, ErrorCode::CloudFileMissing) {
+        return error;
+    }
+    AppError::new(
+        ErrorCode::CloudFileMissing,
+        format!(
+            "The file for '{}' is no longer on {}. Sync from the device that has it to upload it again.",
+            book_title, backend_name
+        ),
+    )
+}
+
+/// Download a cloud-only book into the library directory, verify it and relink the book
+///
+/// The downloaded archive must hash to the book's `file_hash`; otherwise it is
+/// removed and the book keeps its `cloud://` path. An encrypted archive is hashed
+/// with the password entered for the book and fails with `ArchiveEncrypted` without
+/// one. Returns the relinked book and the size of the downloaded file.
+async fn download_into_library(
+    app: &AppHandle,
+    drive: &dyn SyncBackend,
+    book: &crate::database::models::Book,
+    on_progress: &(dyn Fn(u64, Option<u64>) + Send + Sync),
+) -> Result<(crate::database::models::Book, u64), AppError> {
+    use crate::database::{get_connection, models::Book, operations};
+    use crate::schema::books;
+    use diesel::prelude::*;
+
+    // Get file_hash - required for cloud books
+    let file_hash = book.file_hash.clone()
+        .ok_or_else(|| AppError::sync_failed("Cloud book missing file_hash"))?;
 
     // Determine local storage path
-    let app_data_dir = app.path()
-        .app_data_dir()
-        .map_err(|e| AppError::config_read_failed(format!("Failed to get app data dir: {}", e)))?;
-    
-    let library_dir = app_data_dir.join("library");
+    let library_dir = crate::settings::get_library_dir(app)?;
     std::fs::create_dir_all(&library_dir)
         .map_err(|e| AppError::sync_failed(format!("Failed to create library directory: {}", e)))?;
 
     // Use the original filename for the local file
-    let target_path = library_dir.join(&book.filename);
+    let target_path = operations::unique_library_path(&library_dir.join(&book.filename));
     let target_path_str = target_path.to_string_lossy().to_string();
 
     log::info!("Downloading book to: {}", target_path_str);
 
     // Download the file
-    drive.download_book_file(file_hash, &target_path_str).await?;
+    drive.download_book_file(&file_hash, &target_path_str, on_progress).await?;
+
+    // Make sure Drive gave us the same content before pointing the book at it
+    let verify_path = target_path.clone();
+    let password = crate::archive_password::get(book.id);
+    let verified = tauri::async_runtime::spawn_blocking(move || {
+        operations::verify_downloaded_archive(&verify_path, &file_hash, password.as_deref())
+    })
+    .await
+    .map_err(|e| AppError::sync_failed(format!("Verification task failed: {}", e)))?;
+
+    if let Err(e) = verified {
+        let _ = std::fs::remove_file(&target_path);
+        return Err(match e.code {
+            ErrorCode::ArchiveEncrypted => AppError::archive_encrypted(format!(
+                "'{}' is password protected. Enter its password, then download it again.",
+                book.title
+            )),
+            _ => AppError::sync_failed(format!(
+                "Downloaded file for '{}' does not match the expected content",
+                book.title
+            )),
+        });
+    }
+
+    let bytes = std::fs::metadata(&target_path).map(|m| m.len()).unwrap_or(0);
 
     // Update the book's file_path in the database
-    diesel::update(books::table.find(book_id))
+    let mut conn = get_connection()?;
+    let updated_book: Book = diesel::update(books::table.find(book.id))
         .set((
             books::file_path.eq(&target_path_str),
+            books::archive_type.eq(None::<String>),
             books::updated_at.eq(chrono::Utc::now().naive_utc()),
         ))
-        .execute(&mut conn)
+        .returning(Book::as_returning())
+        .get_result(&mut conn)
         .map_err(|e| AppError::database_error(format!("Failed to update book path: {}", e)))?;
+    crate::protocol::invalidate_image_cache(book.id);
 
-    // Return the updated book
-    let updated_book: Book = books::table
-        .find(book_id)
-        .first(&mut conn)
-        .map_err(|e| AppError::database_error(format!("Failed to fetch updated book: {}", e)))?;
+    Ok((updated_book, bytes))
+}
 
-    log::info!("Successfully downloaded cloud book: {}", updated_book.title);
+/// Set by `cancel_download_all`; checked between books
+static DOWNLOAD_ALL_CANCELLED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
 
-    Ok(updated_book)
+/// Event emitted after each book handled by `download_all_books`
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+
+/// Download every cloud-only book for offline reading, optionally within one collection
+///
+/// Books are downloaded one at a time; a failure is recorded and the rest continue.
+/// Emits `download-progress` after each book and stops early on `cancel_download_all`.
+#[tauri::command]
+pub async fn download_all_books(
+    app: AppHandle,
+    collection_id: Option<i32>,
+) -> Result<DownloadAllResult, String> {
+    download_all_books_impl(&app, collection_id).await.map_err(|e| e.into())
+}
+
+async fn download_all_books_impl(
+    app: &AppHandle,
+    collection_id: Option<i32>,
+) -> Result<DownloadAllResult, AppError> {
+    use std::sync::atomic::Ordering;
+
+    DOWNLOAD_ALL_CANCELLED.store(false, Ordering::SeqCst);
+
+    let auth_status = auth::get_auth_status(app)?;
+    if !auth_status.is_authenticated {
+        return Err(AppError::not_authenticated());
+    }
+
+    let books = crate::database::operations::get_cloud_only_books(collection_id)?;
+    log::info!("Downloading {} cloud-only book(s)", books.len());
+
+    let drive = backend_for(app, fresh_access_token(app).await?)?;
+    let mut result = DownloadAllResult {
+        books: Vec::with_capacity(books.len()),
+        total_bytes: 0,
+        cancelled: false,
+    };
+
+    for (index, book) in books.iter().enumerate() {
+        if DOWNLOAD_ALL_CANCELLED.load(Ordering::SeqCst) {
+            log::info!("Bulk download cancelled after {} book(s)", index);
+            result.cancelled = true;
+            break;
+        }
+
+        let outcome = match download_into_library(app, drive.as_ref(), book, &|_, _| {}).await {
+            Ok((_, bytes)) => {
+                result.total_bytes += bytes;
+                BookDownloadOutcome {
+                    book_id: book.id,
+                    title: book.title.clone(),
+                    success: true,
+                    error: None,
+                    bytes,
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to download {}: {}", book.title, e);
+                BookDownloadOutcome {
+                    book_id: book.id,
+                    title: book.title.clone(),
+                    success: false,
+                    error: Some(e.message),
+                    bytes: 0,
+                }
+            }
+        };
+        result.books.push(outcome);
+
+        let progress = BookDownloadProgress {
+            book_id: book.id,
+            title: book.title.clone(),
+            completed: index + 1,
+            total: books.len(),
+        };
+        if let Err(e) = app.emit(DOWNLOAD_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to emit {}: {}", DOWNLOAD_PROGRESS_EVENT, e);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Stop a running `download_all_books` after the current book
+#[tauri::command]
+pub fn cancel_download_all() {
+    DOWNLOAD_ALL_CANCELLED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::sync_state;
+    use diesel::prelude::*;
+
+    fn test_db() -> diesel::SqliteConnection {
+        use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+        let mut conn = diesel::SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_failed_sync_reported_until_next_success() {
+        let mut conn = test_db();
+        assert!(matches!(status_of(load_sync_state(&mut conn).unwrap()), SyncStatus::NeverSynced));
+
+        let errors = vec!["Drive quota exceeded".to_string(), "Upload failed".to_string()];
+        record_sync_attempt(&mut conn, &errors).unwrap();
+        let state = load_sync_state(&mut conn).unwrap().unwrap();
+        assert_eq!(state.errors(), errors);
+        match status_of(Some(state)) {
+            SyncStatus::Failed { error, last_attempt_at } => {
+                assert_eq!(error, "Drive quota exceeded");
+                assert!(last_attempt_at > 0);
+            }
+            other => panic!("expected a failed status, got {:?}", other),
+        }
+
+        // A successful sync clears the errors
+        diesel::update(sync_state::table.find(1))
+            .set(sync_state::last_sync_at.eq(Some(chrono::Utc::now().naive_utc())))
+            .execute(&mut conn)
+            .unwrap();
+        record_sync_attempt(&mut conn, &[]).unwrap();
+        let state = load_sync_state(&mut conn).unwrap().unwrap();
+        assert!(state.errors().is_empty());
+        assert!(matches!(status_of(Some(state)), SyncStatus::Synced { .. }));
+    }
+
+    #[test]
+    fn test_upload_estimate_counts_books_missing_on_drive() {
+        use crate::database::models::{Book, NewBook};
+        use crate::schema::books;
+
+        let dir = std::env::temp_dir().join(format!("yomiyougu_estimate_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut conn = test_db();
+        // (hash, file contents, or None when the file isn't here)
+        for (hash, contents) in [("uploaded", Some("12345")), ("pending", Some("1234567")), ("gone", None)] {
+            let path = dir.join(format!("{}.cbz", hash));
+            if let Some(contents) = contents {
+                std::fs::write(&path, contents).unwrap();
+            }
+            diesel::insert_into(books::table)
+                .values(&NewBook {
+                    uuid: None,
+                    file_path: path.to_string_lossy().to_string(),
+                    filename: format!("{}.cbz", hash),
+                    file_size: Some(1_000),
+                    file_hash: Some(hash.to_string()),
+                    title: hash.to_string(),
+                    current_page: 0,
+                    total_pages: 1,
+                    series_name: None,
+                    volume_number: None,
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+        let local_books: Vec<Book> = books::table.load(&mut conn).unwrap();
+        let remote_hashes = ["uploaded".to_string()].into_iter().collect();
+
+        let pending = not_on_drive(local_books, &remote_hashes);
+        let titles: Vec<&str> = pending.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["pending"]);
+        let estimate = upload_estimate(&pending);
+        assert_eq!((estimate.file_count, estimate.total_bytes), (1, 7));
+
+        // A file that went away since is counted by its stored size
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(upload_estimate(&pending).total_bytes, 1_000);
+        assert_eq!(upload_estimate(&[]).file_count, 0);
+    }
+
+    #[test]
+    fn test_book_conflict_matches_remote_by_uuid() {
+        use crate::database::models::{Book, NewBook};
+        use crate::schema::books;
+        use crate::sync::{to_timestamp, RemoteBookState, SyncSnapshot};
+
+        let mut conn = test_db();
+        let local: Book = diesel::insert_into(books::table)
+            .values(&NewBook {
+                uuid: Some("book-a".to_string()),
+                file_path: "/manga/a.cbz".to_string(),
+                filename: "a.cbz".to_string(),
+                file_size: None,
+                file_hash: Some("hash-a".to_string()),
+                title: "Local title".to_string(),
+                current_page: 4,
+                total_pages: 20,
+                series_name: None,
+                volume_number: None,
+            })
+            .returning(Book::as_returning())
+            .get_result(&mut conn)
+            .unwrap();
+        let local_updated_at = to_timestamp(&local.updated_at);
+
+        let remote = |uuid: &str, updated_at: i64| RemoteBookState {
+            uuid: uuid.to_string(),
+            file_hash: Some("hash-a".to_string()),
+            title: "Remote title".to_string(),
+            filename: "a.cbz".to_string(),
+            current_page: 9,
+            total_pages: 20,
+            is_favorite: false,
+            reading_status: "reading".to_string(),
+            last_read_at: None,
+            added_at: updated_at,
+            updated_at,
+            deleted_at: None,
+            notes: None,
+            open_count: 0,
+            completed_count: 0,
+            sort_key: None,
+            page_fraction: None,
+            scroll_fraction: None,
+            series_name: None,
+            volume_number: None,
+        };
+        let mut snapshot = SyncSnapshot::default();
+        // Same file under another UUID isn't this book's entry
+        snapshot.books.insert("book-b".to_string(), remote("book-b", local_updated_at + 2_000));
+        snapshot.books.insert("book-a".to_string(), remote("book-a", local_updated_at - 2_000));
+
+        // Only the local copy changed since the last sync
+        let conflict = book_conflict(local.clone(), Some(snapshot.clone()), Some(local_updated_at - 1_000));
+        assert_eq!(conflict.remote.as_ref().map(|r| r.title.as_str()), Some("Remote title"));
+        assert_eq!(conflict.remote_updated_at, Some(local_updated_at - 2_000));
+        assert!(conflict.local_changed && !conflict.remote_changed);
+
+        // Both changed when there was no sync yet
+        let conflict = book_conflict(local.clone(), Some(snapshot), None);
+        assert!(conflict.local_changed && conflict.remote_changed);
+
+        // Nothing on Drive yet
+        let conflict = book_conflict(local, None, None);
+        assert!(conflict.remote.is_none() && conflict.remote_updated_at.is_none());
+        assert!(!conflict.remote_changed);
+    }
+
+    #[test]
+    fn test_missing_file_error_names_backend_and_book() {
+        let missing = AppError::new(ErrorCode::CloudFileMissing, "No file with hash abc");
+        let err = explain_missing_file(missing, "Google Drive", "Berserk 01");
+        assert!(matches!(err.code, ErrorCode::CloudFileMissing));
+        assert!(err.message.contains("'Berserk 01'"));
+        assert!(err.message.contains("no longer on Google Drive"));
+
+        let other = explain_missing_file(AppError::sync_failed("Network down"), "Google Drive", "Berserk 01");
+        assert!(matches!(other.code, ErrorCode::SyncFailed));
+        assert!(other.message.contains("Network down"));
+    }
 }
@@ -108,6 +108,12 @@ fn run_migrations(pool: &DbPool) -> Result<(), AppError> {
         )
     })?;
 
+    // Derived columns that SQL can't compute during the migration itself
+    let backfilled = crate::database::operations::backfill_sort_keys(&mut conn)?;
+    if backfilled > 0 {
+        log::info!("Backfilled sort keys for {} book(s)", backfilled);
+    }
+
     Ok(())
 }
 
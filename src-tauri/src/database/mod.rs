@@ -4,10 +4,12 @@
 //! - `models` - Diesel model structs for database tables
 //! - `connection` - Connection pool management
 //! - `operations` - CRUD operations for books and collections
+//! - `progress` - Write-behind buffer for reading positions
 
 pub mod connection;
 pub mod models;
 pub mod operations;
+pub mod progress;
 
 #[cfg(test)]
 mod tests;
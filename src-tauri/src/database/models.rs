@@ -3,7 +3,10 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::schema::{book_collections, book_settings, bookmarks, books, collections, sync_state};
+use crate::schema::{
+    book_collections, book_settings, book_tags, bookmarks, books, collections, reading_history,
+    reading_queue, sync_state, tags,
+};
 
 // ============================================================================
 // COLLECTIONS
@@ -21,6 +24,8 @@ pub struct Collection {
     pub updated_at: chrono::NaiveDateTime,
     pub uuid: Option<String>,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    /// Collection this one is nested under; None for top-level collections
+    pub parent_id: Option<i32>,
 }
 
 /// New collection for insertion
@@ -72,6 +77,58 @@ pub struct NewBookCollection {
     pub uuid: Option<String>,
 }
 
+// ============================================================================
+// TAGS
+// ============================================================================
+
+/// Tag model for ad-hoc labels on books
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub uuid: Option<String>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+/// New tag for insertion
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = tags)]
+pub struct NewTag {
+    pub name: String,
+    pub uuid: Option<String>,
+}
+
+/// Junction table model for many-to-many book-tag relationship
+#[derive(
+    Debug, Clone, Queryable, Identifiable, Selectable, Associations, Serialize, Deserialize,
+)]
+#[diesel(table_name = book_tags)]
+#[diesel(belongs_to(Book))]
+#[diesel(belongs_to(Tag))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct BookTag {
+    pub id: i32,
+    pub book_id: i32,
+    pub tag_id: i32,
+    pub added_at: chrono::NaiveDateTime,
+    pub uuid: Option<String>,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+/// New book-tag relationship for insertion
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = book_tags)]
+pub struct NewBookTag {
+    pub book_id: i32,
+    pub tag_id: i32,
+    pub uuid: Option<String>,
+}
+
 // ============================================================================
 // BOOKS
 // ============================================================================
@@ -110,6 +167,36 @@ impl ReadingStatus {
     }
 }
 
+/// How a book's `file_hash` was computed
+///
+/// Hashes of different schemes never agree for the same book, so duplicates are
+/// only looked up among books hashed the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashScheme {
+    /// SHA-256 of the archive's images in reading order
+    Content,
+    /// SHA-256 of the raw archive file, set by fast import until the content hash is backfilled
+    File,
+}
+
+impl HashScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashScheme::Content => "content",
+            HashScheme::File => "file",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "content" => Some(HashScheme::Content),
+            "file" => Some(HashScheme::File),
+            _ => None,
+        }
+    }
+}
+
 /// Book model for manga/comics
 #[derive(Debug, Clone, Queryable, Identifiable, Selectable, Serialize, Deserialize)]
 #[diesel(table_name = books)]
@@ -130,6 +217,29 @@ pub struct Book {
     pub reading_status: String,
     pub uuid: Option<String>,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub notes: Option<String>,
+    /// Detected archive format ("zip"/"rar"), None until known
+    pub archive_type: Option<String>,
+    /// Times the book was opened in the reader
+    pub open_count: i32,
+    /// Times the book was marked completed
+    pub completed_count: i32,
+    /// Normalized title for a device-independent library order
+    pub sort_key: Option<String>,
+    /// Fraction (0.0-1.0) of the current page scrolled past in vertical mode
+    pub page_fraction: Option<f64>,
+    /// Pages left after those ComicInfo.xml marks as ads, deleted or the front cover
+    pub story_page_count: Option<i32>,
+    /// How `file_hash` was computed ("content" or "file")
+    pub hash_scheme: String,
+    /// Series the book is a volume of
+    pub series_name: Option<String>,
+    /// Position within the series; fractional for side stories (e.g. 1.5)
+    pub volume_number: Option<f64>,
+    /// Fraction (0.0-1.0) of the whole strip scrolled past in vertical mode
+    pub scroll_fraction: Option<f64>,
+    /// Book this fast-imported book duplicates, found by the content hash backfill
+    pub content_duplicate_of: Option<i32>,
 }
 
 impl Book {
@@ -138,13 +248,31 @@ impl Book {
         ReadingStatus::from_str(&self.reading_status).unwrap_or(ReadingStatus::Unread)
     }
 
+    /// Get hash scheme as enum
+    pub fn hash_scheme(&self) -> HashScheme {
+        HashScheme::from_str(&self.hash_scheme).unwrap_or(HashScheme::Content)
+    }
+
     /// Calculate reading progress as percentage
     /// Note: current_page is 0-indexed, 1 is added for calculation
     pub fn progress(&self) -> f32 {
-        if self.total_pages == 0 {
+        self.progress_with(false)
+    }
+
+    /// Calculate reading progress, optionally against the story pages only
+    ///
+    /// With `story_pages_only`, books with ComicInfo page metadata are measured
+    /// against `story_page_count` (capped at 100%), so trailing ads don't keep a
+    /// finished story below 100%. Other books use the raw page count.
+    pub fn progress_with(&self, story_pages_only: bool) -> f32 {
+        let total = match self.story_page_count {
+            Some(story_pages) if story_pages_only && story_pages > 0 => story_pages,
+            _ => self.total_pages,
+        };
+        if total == 0 {
             0.0
         } else {
-            ((self.current_page + 1) as f32 / self.total_pages as f32) * 100.0
+            ((self.current_page + 1) as f32 / total as f32 * 100.0).min(100.0)
         }
     }
 }
@@ -161,6 +289,8 @@ pub struct NewBook {
     pub current_page: i32,
     pub total_pages: i32,
     pub uuid: Option<String>,
+    pub series_name: Option<String>,
+    pub volume_number: Option<f64>,
 }
 
 /// Book update (partial)
@@ -174,6 +304,12 @@ pub struct UpdateBook {
     pub updated_at: Option<chrono::NaiveDateTime>,
     pub is_favorite: Option<bool>,
     pub reading_status: Option<String>,
+    pub notes: Option<Option<String>>,
+    pub sort_key: Option<Option<String>>,
+    pub page_fraction: Option<Option<f64>>,
+    pub series_name: Option<Option<String>>,
+    pub volume_number: Option<Option<f64>>,
+    pub scroll_fraction: Option<Option<f64>>,
 }
 
 // ============================================================================
@@ -210,6 +346,173 @@ pub struct NewBookmark {
     pub uuid: Option<String>,
 }
 
+/// Shareable bookmark file for a single book, matched on import by `file_hash`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookmarkExport {
+    pub version: u32,
+    pub file_hash: String,
+    pub title: String,
+    pub bookmarks: Vec<ExportedBookmark>,
+}
+
+impl BookmarkExport {
+    pub const CURRENT_VERSION: u32 = 1;
+}
+
+/// Bookmark entry in an export file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedBookmark {
+    pub name: String,
+    pub page: i32,
+    pub description: Option<String>,
+}
+
+/// Outcome of importing a bookmark file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookmarkImportResult {
+    pub book_id: i32,
+    pub imported: Vec<Bookmark>,
+    /// Bookmarks skipped as duplicates or because the page is out of range
+    pub skipped: usize,
+}
+
+/// Payload of the `convert-progress` event emitted while converting a book to CBZ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionProgress {
+    pub book_id: i32,
+    pub current: usize,
+    pub total: usize,
+}
+
+/// Step of an archive import, reported through `import-progress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportPhase {
+    Counting,
+    Hashing,
+    Copying,
+    Inserting,
+}
+
+/// Payload of the `import-progress` event emitted while importing an archive
+///
+/// Byte counts are only filled in during the copy phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub filename: String,
+    pub phase: ImportPhase,
+    pub bytes_copied: u64,
+    pub total_bytes: u64,
+}
+
+/// Outcome of one archive in a batch import
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportResult {
+    Imported { file_path: String, book: Book },
+    /// The archive is already in the library as `existing_title`
+    Duplicate { file_path: String, existing_title: String },
+    Failed { file_path: String, error: String },
+}
+
+/// Payload of the `import-batch-progress` event, emitted before each file of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportBatchProgress {
+    pub filename: String,
+    /// 1-based position of the file in the batch
+    pub current: usize,
+    pub total: usize,
+}
+
+/// Counts of a directory import
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DirectoryImportResult {
+    pub imported: usize,
+    /// Archives already in the library
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+// ============================================================================
+// READING QUEUE
+// ============================================================================
+
+/// Entry in the global "up next" queue, ordered by `position`
+#[derive(
+    Debug, Clone, Queryable, Identifiable, Selectable, Associations, Serialize, Deserialize,
+)]
+#[diesel(table_name = reading_queue)]
+#[diesel(belongs_to(Book))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct QueueEntry {
+    pub id: i32,
+    pub book_id: i32,
+    pub position: i32,
+    pub added_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    pub uuid: Option<String>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+/// New queue entry for insertion
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = reading_queue)]
+pub struct NewQueueEntry {
+    pub book_id: i32,
+    pub position: i32,
+    pub uuid: Option<String>,
+}
+
+// ============================================================================
+// READING HISTORY
+// ============================================================================
+
+/// One reader session of a book
+#[derive(
+    Debug, Clone, Queryable, Identifiable, Selectable, Associations, Serialize, Deserialize,
+)]
+#[diesel(table_name = reading_history)]
+#[diesel(belongs_to(Book))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingSession {
+    pub id: i32,
+    pub book_id: i32,
+    /// Page the reader opened on (0-indexed)
+    pub page_from: i32,
+    /// Page the reader was closed on (0-indexed)
+    pub page_to: i32,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: chrono::NaiveDateTime,
+}
+
+/// New reader session for insertion
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = reading_history)]
+pub struct NewReadingSession {
+    pub book_id: i32,
+    pub page_from: i32,
+    pub page_to: i32,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: chrono::NaiveDateTime,
+}
+
+/// Pages and minutes read on one calendar day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyReadingStats {
+    /// Day in the caller's local time
+    pub date: chrono::NaiveDate,
+    pub pages_read: i64,
+    pub minutes_read: f64,
+}
+
+/// Pages read since a point in time, in total and by the books' current reading status
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PagesRead {
+    pub total: i64,
+    /// Keyed by reading status ("reading", "completed", ...); statuses without pages are left out
+    pub by_status: std::collections::HashMap<String, i64>,
+}
+
 // ============================================================================
 // BOOK SETTINGS
 // ============================================================================
@@ -278,6 +581,8 @@ pub struct BookSettings {
     pub updated_at: chrono::NaiveDateTime,
     pub uuid: Option<String>,
     pub deleted_at: Option<chrono::NaiveDateTime>,
+    /// Reader background ("white", "black", "gray" or "sepia")
+    pub reader_background: Option<String>,
 }
 
 /// New book settings for insertion
@@ -290,6 +595,7 @@ pub struct NewBookSettings {
     pub image_fit_mode: Option<String>,
     pub sync_progress: Option<bool>,
     pub uuid: Option<String>,
+    pub reader_background: Option<String>,
 }
 
 /// Book settings update (partial)
@@ -301,6 +607,7 @@ pub struct UpdateBookSettings {
     pub image_fit_mode: Option<Option<String>>,
     pub sync_progress: Option<Option<bool>>,
     pub updated_at: Option<chrono::NaiveDateTime>,
+    pub reader_background: Option<Option<String>>,
 }
 
 // ============================================================================
@@ -318,6 +625,28 @@ pub struct BookWithDetails {
     pub bookmark_count: i64,
 }
 
+/// One page of the library listing with the number of books matching the filters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookPage {
+    pub books: Vec<BookWithDetails>,
+    pub total_count: i64,
+}
+
+/// Most recently read in-progress book together with the page to resume at
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumeBook {
+    pub book: BookWithDetails,
+    pub resume_page: i32,
+}
+
+/// Queued book with its place in the reading queue
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueuedBook {
+    pub position: i32,
+    pub added_at: chrono::NaiveDateTime,
+    pub book: Book,
+}
+
 /// Collection with book count
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CollectionWithCount {
@@ -326,6 +655,262 @@ pub struct CollectionWithCount {
     pub book_count: i64,
 }
 
+/// A series with its volume count and reading progress across all volumes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeriesSummary {
+    pub name: String,
+    pub volume_count: i64,
+    pub completed_volumes: i64,
+    pub pages_read: i64,
+    pub total_pages: i64,
+    /// Pages read across all volumes as a percentage (0-100)
+    pub progress: f32,
+}
+
+/// Collection in the collection hierarchy, with its nested collections
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionNode {
+    #[serde(flatten)]
+    pub collection: CollectionWithCount,
+    pub children: Vec<CollectionNode>,
+}
+
+/// Books of two collections split by membership, each list ordered by sort key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionDiff {
+    pub only_in_a: Vec<Book>,
+    pub only_in_b: Vec<Book>,
+    pub in_both: Vec<Book>,
+}
+
+/// How a Drive book file relates to the local library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriveFileStatus {
+    /// A live book whose file is on this device
+    Local,
+    /// A live book that only exists in the cloud (`cloud://` path)
+    CloudOnly,
+    /// Only a soft-deleted book has this hash
+    Deleted,
+    /// No local book has this hash
+    Orphaned,
+}
+
+/// The local book a Drive file (`book_{hash}.cbz`) belongs to, if any
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveFileInfo {
+    pub file_hash: String,
+    pub status: DriveFileStatus,
+    pub book_id: Option<i32>,
+    pub title: Option<String>,
+    /// Whether the book's file exists at its recorded path
+    pub file_exists: bool,
+}
+
+/// Result of a library consistency check (row IDs of orphaned records)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LibraryConsistencyReport {
+    /// Junction rows pointing at missing or deleted books/collections
+    pub orphaned_book_collections: Vec<i32>,
+    /// Bookmarks whose book no longer exists
+    pub orphaned_bookmarks: Vec<i32>,
+    /// Book settings whose book no longer exists
+    pub orphaned_book_settings: Vec<i32>,
+    /// Whether the orphaned rows were removed
+    pub cleaned_up: bool,
+}
+
+impl LibraryConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.orphaned_book_collections.is_empty()
+            && self.orphaned_bookmarks.is_empty()
+            && self.orphaned_book_settings.is_empty()
+    }
+}
+
+/// A fast-imported book whose content hash matches a book already in the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashDuplicate {
+    pub book_id: i32,
+    pub title: String,
+    pub existing_book_id: i32,
+    pub existing_title: String,
+}
+
+/// Result of replacing fast-import file hashes with content hashes
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HashBackfillResult {
+    /// Books that now have a content hash
+    pub hashed: usize,
+    /// Books left with their file hash because their content is already in the library
+    pub duplicates: Vec<HashDuplicate>,
+    /// Books whose archive couldn't be read
+    pub failed: usize,
+}
+
+/// Problem found while verifying a book's file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveProblem {
+    /// The book's file (or page manifest) no longer exists
+    MissingFile,
+    /// The archive couldn't be opened or its headers are damaged
+    Unreadable { error: String },
+    /// An entry failed its CRC check or couldn't be decompressed
+    CorruptEntry { entry: String, error: String },
+    /// The manifest lists a page file that doesn't exist
+    MissingPage { page: String },
+    /// The file holds a different number of pages than the library has stored
+    PageCountMismatch { stored: i32, actual: i32 },
+}
+
+/// Result of verifying one book's file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveVerification {
+    pub book_id: i32,
+    pub title: String,
+    pub file_path: String,
+    pub problems: Vec<ArchiveProblem>,
+}
+
+impl ArchiveVerification {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Progress of `verify_all_archives`, emitted after each book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveVerifyProgress {
+    pub book_id: i32,
+    pub title: String,
+    /// Books checked so far, including this one
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Result of verifying every local book
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LibraryVerification {
+    /// Books with at least one problem
+    pub problems: Vec<ArchiveVerification>,
+    /// Number of books checked
+    pub checked: usize,
+    /// Whether the run was stopped by `cancel_verify_archives`
+    pub cancelled: bool,
+}
+
+/// A book whose file holds a different number of pages than the library has stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCountMismatch {
+    pub book_id: i32,
+    pub title: String,
+    pub stored_pages: i32,
+    pub actual_pages: i32,
+}
+
+/// A book whose pages couldn't be recounted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageAuditError {
+    pub book_id: i32,
+    pub title: String,
+    pub error: String,
+}
+
+/// Result of recounting the pages of a collection's books
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PageAudit {
+    /// Books recounted (cloud-only and unreadable books excluded)
+    pub checked: usize,
+    pub mismatches: Vec<PageCountMismatch>,
+    pub unreadable: Vec<PageAuditError>,
+}
+
+/// Reading activity on one calendar day, for the activity heatmap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayActivity {
+    /// Day in the caller's local time
+    pub date: chrono::NaiveDate,
+    pub count: i64,
+}
+
+/// Reading options and resume position for opening a book in the reader
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReaderConfig {
+    pub book_id: i32,
+    pub current_page: i32,
+    pub total_pages: i32,
+    pub reading_direction: String,
+    pub page_display_mode: String,
+    pub image_fit_mode: String,
+    pub reader_background: String,
+    /// Position within the current page; only set in vertical mode
+    pub page_fraction: Option<f64>,
+    /// Position within the whole strip; only set in vertical mode
+    pub scroll_fraction: Option<f64>,
+}
+
+/// Resolved page navigation for the reader
+///
+/// Page indices are 0-based. In double-page mode pages are grouped in pairs
+/// starting at an even index, and `visible_pages` is in on-screen order from
+/// left to right (so the pair is reversed for right-to-left reading).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageNavigation {
+    pub current_page: i32,
+    pub total_pages: i32,
+    pub visible_pages: Vec<i32>,
+    pub next_page: Option<i32>,
+    pub previous_page: Option<i32>,
+    pub reading_direction: String,
+    pub page_display_mode: String,
+}
+
+impl PageNavigation {
+    /// Compute navigation for a page given the resolved direction and display mode
+    pub fn new(
+        current_page: i32,
+        total_pages: i32,
+        reading_direction: &str,
+        page_display_mode: &str,
+    ) -> Self {
+        let last_page = (total_pages - 1).max(0);
+        let current = current_page.clamp(0, last_page);
+
+        let is_double = page_display_mode == "double";
+        let is_rtl = reading_direction == ReadingDirection::Rtl.as_str();
+
+        // Double-page spreads always start on an even page
+        let (first, step) = if is_double {
+            (current - current % 2, 2)
+        } else {
+            (current, 1)
+        };
+
+        let mut visible_pages = vec![first];
+        if is_double && first + 1 <= last_page {
+            visible_pages.push(first + 1);
+        }
+        if is_rtl {
+            visible_pages.reverse();
+        }
+
+        let next_page = (first + step <= last_page && total_pages > 0).then_some(first + step);
+        let previous_page = (first - step >= 0).then_some(first - step);
+
+        Self {
+            current_page: first,
+            total_pages,
+            visible_pages,
+            next_page,
+            previous_page,
+            reading_direction: reading_direction.to_string(),
+            page_display_mode: page_display_mode.to_string(),
+        }
+    }
+}
+
 // ============================================================================
 // SYNC STATE
 // ============================================================================
@@ -339,6 +924,21 @@ pub struct SyncState {
     pub last_sync_at: Option<chrono::NaiveDateTime>,
     pub last_sync_device: Option<String>,
     pub sync_file_id: Option<String>,
+    pub last_attempt_at: Option<chrono::NaiveDateTime>,
+    /// JSON array of error messages from the last sync attempt
+    pub last_errors: Option<String>,
+    /// JSON array of the changes made by the last successful sync
+    pub last_changes: Option<String>,
+}
+
+impl SyncState {
+    /// Errors recorded by the last sync attempt (empty if it succeeded)
+    pub fn errors(&self) -> Vec<String> {
+        self.last_errors
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
 }
 
 /// Sync state update
@@ -348,4 +948,7 @@ pub struct UpdateSyncState {
     pub last_sync_at: Option<Option<chrono::NaiveDateTime>>,
     pub last_sync_device: Option<Option<String>>,
     pub sync_file_id: Option<Option<String>>,
+    pub last_attempt_at: Option<Option<chrono::NaiveDateTime>>,
+    pub last_errors: Option<Option<String>>,
+    pub last_changes: Option<Option<String>>,
 }
@@ -7,27 +7,50 @@ use log::{debug, error, info, warn};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
 use crate::database::connection::establish_connection;
 use crate::database::models::*;
+use crate::archive_pages;
+use crate::archive_password;
+use crate::comic_info;
 use crate::error::{AppError, ErrorCode};
-use crate::schema::{book_collections, book_settings, bookmarks, books, collections};
+use crate::manifest;
+use crate::page_filter::{self, PageFilter};
+use crate::schema::{
+    book_collections, book_settings, book_tags, bookmarks, books, collections, reading_history,
+    reading_queue, tags,
+};
 
 // ============================================================================
 // COLLECTIONS
 // ============================================================================
 
-/// Create a new collection
-pub fn create_collection(new_collection: NewCollection) -> Result<Collection, AppError> {
+/// Create a new collection, nested under `parent_id` when given
+pub fn create_collection(new_collection: NewCollection, parent_id: Option<i32>) -> Result<Collection, AppError> {
     info!("Creating new collection: {}", new_collection.name);
     let mut conn = establish_connection()?;
+    insert_collection(&mut conn, &new_collection, parent_id)
+}
+
+/// Insert a collection under `parent_id`
+///
+/// The parent is checked first and written by the insert itself, so a missing
+/// parent leaves no collection behind.
+pub(crate) fn insert_collection(
+    conn: &mut SqliteConnection,
+    new_collection: &NewCollection,
+    parent_id: Option<i32>,
+) -> Result<Collection, AppError> {
+    if let Some(parent_id) = parent_id {
+        check_parent_collection(conn, parent_id)?;
+    }
 
     diesel::insert_into(collections::table)
-        .values(&new_collection)
+        .values((new_collection, collections::parent_id.eq(parent_id)))
         .returning(Collection::as_returning())
-        .get_result(&mut conn)
+        .get_result(conn)
         .map(|collection: Collection| {
             info!(
                 "Collection created successfully: {} (ID: {})",
@@ -52,10 +75,18 @@ pub fn get_all_collections() -> Result<Vec<CollectionWithCount>, AppError> {
     debug!("Fetching all collections with book counts");
     let mut conn = establish_connection()?;
 
+    let result = load_collections_with_counts(&mut conn)?;
+    info!("Retrieved {} collections", result.len());
+    Ok(result)
+}
+
+pub(crate) fn load_collections_with_counts(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<CollectionWithCount>, AppError> {
     let collections_list: Vec<Collection> = collections::table
         .filter(collections::deleted_at.is_null())
         .select(Collection::as_select())
-        .load(&mut conn)
+        .load(conn)
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
@@ -68,12 +99,17 @@ pub fn get_all_collections() -> Result<Vec<CollectionWithCount>, AppError> {
         .filter(books::deleted_at.is_null())
         .group_by(book_collections::collection_id)
         .select((book_collections::collection_id, diesel::dsl::count(book_collections::book_id)))
-        .load(&mut conn)
-        .unwrap_or_default();
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to count collection books: {}", e),
+            )
+        })?;
 
     let count_map: std::collections::HashMap<i32, i64> = counts.into_iter().collect();
 
-    let result: Vec<CollectionWithCount> = collections_list
+    Ok(collections_list
         .into_iter()
         .map(|collection| {
             let book_count = *count_map.get(&collection.id).unwrap_or(&0);
@@ -82,10 +118,36 @@ pub fn get_all_collections() -> Result<Vec<CollectionWithCount>, AppError> {
                 book_count,
             }
         })
-        .collect();
+        .collect())
+}
 
-    info!("Retrieved {} collections", result.len());
-    Ok(result)
+/// Count unread books per collection, for collection badges
+///
+/// Collections without unread books are left out of the map.
+pub fn get_unread_counts_by_collection() -> Result<std::collections::HashMap<i32, i64>, AppError> {
+    let mut conn = establish_connection()?;
+    unread_counts_by_collection(&mut conn)
+}
+
+pub(crate) fn unread_counts_by_collection(
+    conn: &mut SqliteConnection,
+) -> Result<std::collections::HashMap<i32, i64>, AppError> {
+    let counts: Vec<(i32, i64)> = book_collections::table
+        .inner_join(books::table)
+        .filter(book_collections::deleted_at.is_null())
+        .filter(books::deleted_at.is_null())
+        .filter(books::reading_status.eq("unread"))
+        .group_by(book_collections::collection_id)
+        .select((book_collections::collection_id, diesel::dsl::count(book_collections::book_id)))
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to count unread books: {}", e),
+            )
+        })?;
+
+    Ok(counts.into_iter().collect())
 }
 
 /// Get a single collection by ID (returns error if soft-deleted)
@@ -158,25 +220,266 @@ pub fn delete_collection(collection_id: i32) -> Result<(), AppError> {
     // Append deletion timestamp to name to free up the name for reuse
     let deleted_name = format!("{}__deleted_{}", collection.name, timestamp);
     
+    conn.transaction(|conn| {
+        diesel::update(collections::table.find(collection_id))
+            .set((
+                collections::name.eq(deleted_name),
+                collections::deleted_at.eq(Some(now)),
+                collections::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        // Nested collections move up to the deleted collection's parent
+        diesel::update(collections::table.filter(collections::parent_id.eq(collection_id)))
+            .set((
+                collections::parent_id.eq(collection.parent_id),
+                collections::updated_at.eq(now),
+            ))
+            .execute(conn)
+    })
+    .map_err(|e: diesel::result::Error| {
+        error!("Failed to delete collection {}: {}", collection_id, e);
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to delete collection: {}", e),
+        )
+    })?;
+
+    info!("Collection {} soft-deleted successfully", collection_id);
+    Ok(())
+}
+
+/// Move a collection under another collection, or to the top level with `None`
+pub fn move_collection(collection_id: i32, parent_id: Option<i32>) -> Result<Collection, AppError> {
+    info!("Moving collection {} under {:?}", collection_id, parent_id);
+    let mut conn = establish_connection()?;
+    set_collection_parent(&mut conn, collection_id, parent_id)
+}
+
+pub(crate) fn set_collection_parent(
+    conn: &mut SqliteConnection,
+    collection_id: i32,
+    parent_id: Option<i32>,
+) -> Result<Collection, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to move collection: {}", e),
+        )
+    };
+
+    if let Some(parent_id) = parent_id {
+        check_parent_collection(conn, parent_id)?;
+        if creates_collection_cycle(conn, collection_id, parent_id).map_err(query_error)? {
+            return Err(AppError::invalid_input(
+                "A collection can't be nested under itself or one of its subcollections",
+            ));
+        }
+    }
+
     diesel::update(collections::table.find(collection_id))
         .set((
-            collections::name.eq(deleted_name),
-            collections::deleted_at.eq(Some(now)),
-            collections::updated_at.eq(now),
+            collections::parent_id.eq(parent_id),
+            collections::updated_at.eq(chrono::Utc::now().naive_utc()),
         ))
-        .execute(&mut conn)
+        .returning(Collection::as_returning())
+        .get_result(conn)
+        .map_err(query_error)
+}
+
+/// Whether nesting `collection_id` under `parent_id` would make it its own ancestor
+/// Check that a collection can take children: it exists and isn't deleted
+fn check_parent_collection(conn: &mut SqliteConnection, parent_id: i32) -> Result<(), AppError> {
+    let parent_exists = collections::table
+        .find(parent_id)
+        .filter(collections::deleted_at.is_null())
+        .count()
+        .get_result::<i64>(conn)
         .map_err(|e| {
-            error!("Failed to delete collection {}: {}", collection_id, e);
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to delete collection: {}", e),
+                format!("Failed to find collection: {}", e),
             )
-        })?;
-
-    info!("Collection {} soft-deleted successfully", collection_id);
+        })?
+        > 0;
+    if !parent_exists {
+        return Err(AppError::invalid_input(format!("Collection {} not found", parent_id)));
+    }
     Ok(())
 }
 
+pub(crate) fn creates_collection_cycle(
+    conn: &mut SqliteConnection,
+    collection_id: i32,
+    parent_id: i32,
+) -> Result<bool, diesel::result::Error> {
+    let mut seen = std::collections::HashSet::new();
+    let mut current = Some(parent_id);
+    while let Some(id) = current {
+        if id == collection_id {
+            return Ok(true);
+        }
+        // Stop at a cycle further up rather than walking it forever
+        if !seen.insert(id) {
+            return Ok(false);
+        }
+        current = collections::table
+            .find(id)
+            .select(collections::parent_id)
+            .first::<Option<i32>>(conn)
+            .optional()?
+            .flatten();
+    }
+    Ok(false)
+}
+
+/// Get the collections nested directly under a collection, or the top-level ones for `None`
+pub fn get_child_collections(parent_id: Option<i32>) -> Result<Vec<Collection>, AppError> {
+    let mut conn = establish_connection()?;
+
+    let mut query = collections::table
+        .filter(collections::deleted_at.is_null())
+        .into_boxed();
+    query = match parent_id {
+        Some(parent_id) => query.filter(collections::parent_id.eq(parent_id)),
+        None => query.filter(collections::parent_id.is_null()),
+    };
+
+    query
+        .order(collections::name.asc())
+        .select(Collection::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load child collections: {}", e),
+            )
+        })
+}
+
+/// Get all collections as a tree of top-level collections and their nested ones
+pub fn get_collection_tree() -> Result<Vec<CollectionNode>, AppError> {
+    let mut conn = establish_connection()?;
+    load_collection_tree(&mut conn)
+}
+
+pub(crate) fn load_collection_tree(conn: &mut SqliteConnection) -> Result<Vec<CollectionNode>, AppError> {
+    type Groups = std::collections::HashMap<Option<i32>, Vec<CollectionWithCount>>;
+
+    fn sort_by_name(nodes: &mut [CollectionNode]) {
+        nodes.sort_by_key(|node| node.collection.collection.name.to_lowercase());
+    }
+
+    fn attach(parent_id: Option<i32>, groups: &mut Groups) -> Vec<CollectionNode> {
+        let mut nodes: Vec<CollectionNode> = groups
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|collection| {
+                let children = attach(Some(collection.collection.id), groups);
+                CollectionNode { collection, children }
+            })
+            .collect();
+        sort_by_name(&mut nodes);
+        nodes
+    }
+
+    let collections = load_collections_with_counts(conn)?;
+    let live_ids: std::collections::HashSet<i32> =
+        collections.iter().map(|c| c.collection.id).collect();
+
+    let mut groups = Groups::new();
+    for collection in collections {
+        // A deleted or missing parent leaves the collection at the top level
+        let parent_id = collection.collection.parent_id.filter(|id| live_ids.contains(id));
+        groups.entry(parent_id).or_default().push(collection);
+    }
+
+    let mut roots = attach(None, &mut groups);
+
+    // Collections in a parent cycle (only possible through conflicting syncs) can't be
+    // reached from the top level; list them there instead of hiding them
+    while let Some(&parent_id) = groups.keys().next() {
+        for collection in groups.remove(&parent_id).unwrap_or_default() {
+            let children = attach(Some(collection.collection.id), &mut groups);
+            roots.push(CollectionNode { collection, children });
+        }
+    }
+    sort_by_name(&mut roots);
+
+    Ok(roots)
+}
+
+/// Compare the books of two collections
+/// Returns the books only in `collection_a`, only in `collection_b` and in both
+pub fn diff_collections(collection_a: i32, collection_b: i32) -> Result<CollectionDiff, AppError> {
+    let mut conn = establish_connection()?;
+    compare_collections(&mut conn, collection_a, collection_b)
+}
+
+pub(crate) fn compare_collections(
+    conn: &mut SqliteConnection,
+    collection_a: i32,
+    collection_b: i32,
+) -> Result<CollectionDiff, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to compare collections: {}", e),
+        )
+    };
+
+    let mut member_ids = |collection_id: i32| -> Result<std::collections::HashSet<i32>, AppError> {
+        let exists = collections::table
+            .find(collection_id)
+            .filter(collections::deleted_at.is_null())
+            .count()
+            .get_result::<i64>(conn)
+            .map_err(query_error)?
+            > 0;
+        if !exists {
+            return Err(AppError::invalid_input(format!("Collection {} not found", collection_id)));
+        }
+
+        let ids: Vec<i32> = book_collections::table
+            .inner_join(books::table)
+            .filter(book_collections::collection_id.eq(collection_id))
+            .filter(book_collections::deleted_at.is_null())
+            .filter(books::deleted_at.is_null())
+            .select(book_collections::book_id)
+            .load(conn)
+            .map_err(query_error)?;
+        Ok(ids.into_iter().collect())
+    };
+
+    let ids_a = member_ids(collection_a)?;
+    let ids_b = member_ids(collection_b)?;
+
+    let all_ids: Vec<i32> = ids_a.union(&ids_b).copied().collect();
+    let members: Vec<Book> = books::table
+        .filter(books::id.eq_any(&all_ids))
+        .order(books::sort_key.asc())
+        .then_order_by(books::title.asc())
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(query_error)?;
+
+    let mut diff = CollectionDiff {
+        only_in_a: Vec::new(),
+        only_in_b: Vec::new(),
+        in_both: Vec::new(),
+    };
+    for book in members {
+        match (ids_a.contains(&book.id), ids_b.contains(&book.id)) {
+            (true, true) => diff.in_both.push(book),
+            (true, false) => diff.only_in_a.push(book),
+            _ => diff.only_in_b.push(book),
+        }
+    }
+
+    Ok(diff)
+}
+
 // ============================================================================
 // BOOKS
 // ============================================================================
@@ -190,7 +493,7 @@ pub fn create_book(new_book: NewBook) -> Result<Book, AppError> {
     let mut conn = establish_connection()?;
 
     diesel::insert_into(books::table)
-        .values(&new_book)
+        .values((&new_book, books::sort_key.eq(book_sort_key(&new_book.title))))
         .returning(Book::as_returning())
         .get_result(&mut conn)
         .map(|book: Book| {
@@ -209,59 +512,142 @@ pub fn create_book(new_book: NewBook) -> Result<Book, AppError> {
         })
 }
 
-/// Get all books with optional filtering
+/// Normalized title used as `books.sort_key`
+///
+/// Lowercased with whitespace collapsed, a leading "the"/"a"/"an" dropped and digit
+/// runs zero-padded so "Vol 2" sorts before "Vol 10". Depends only on the title, so
+/// every device computes the same key.
+pub fn book_sort_key(title: &str) -> String {
+    let lower = title.to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let words = match words.as_slice() {
+        [article, rest @ ..] if !rest.is_empty() && matches!(*article, "the" | "a" | "an") => rest,
+        all => all,
+    };
+
+    let mut key = String::with_capacity(title.len());
+    let mut digits = String::new();
+    for c in words.join(" ").chars().chain(std::iter::once('\0')) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if !digits.is_empty() {
+            let trimmed = digits.trim_start_matches('0');
+            key.push_str(&format!("{:0>8}", if trimmed.is_empty() { "0" } else { trimmed }));
+            digits.clear();
+        }
+        if c != '\0' {
+            key.push(c);
+        }
+    }
+    key
+}
+
+/// Get a page of books with optional filtering
+///
+/// `sort` is `"sort_key"` for the device-independent title order; anything else
+/// (or `None`) lists the most recently read books first. Without a `limit` every
+/// book after `offset` is returned.
 pub fn get_all_books(
     collection_id: Option<i32>,
     status: Option<String>,
     favorites_only: bool,
-) -> Result<Vec<BookWithDetails>, AppError> {
+    sort: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<BookPage, AppError> {
     debug!(
-        "Fetching books - collection: {:?}, status: {:?}, favorites: {}",
-        collection_id, status, favorites_only
+        "Fetching books - collection: {:?}, status: {:?}, favorites: {}, sort: {:?}, limit: {:?}, offset: {:?}",
+        collection_id, status, favorites_only, sort, limit, offset
     );
     let mut conn = establish_connection()?;
+    let page = books_page(
+        &mut conn,
+        collection_id,
+        status.as_deref(),
+        favorites_only,
+        sort.as_deref(),
+        limit,
+        offset,
+    )?;
+    info!("Retrieved {} of {} books", page.books.len(), page.total_count);
+    Ok(page)
+}
 
-    // If filtering by collection, get book IDs from junction table first
-    let book_ids_in_collection: Option<Vec<i32>> = if let Some(cid) = collection_id {
-        Some(
-            book_collections::table
-                .filter(book_collections::collection_id.eq(cid))
-                .filter(book_collections::deleted_at.is_null())
-                .select(book_collections::book_id)
-                .load(&mut conn)
-                .map_err(|e| {
-                    AppError::new(
-                        ErrorCode::DatabaseQueryFailed,
-                        format!("Failed to load book collection mappings: {}", e),
-                    )
-                })?,
-        )
-    } else {
-        None
-    };
-
-    // Start with filter for non-deleted books
+/// Non-deleted books matching the library filters, unordered
+fn filtered_books<'a>(
+    collection_id: Option<i32>,
+    status: Option<&'a str>,
+    favorites_only: bool,
+) -> books::BoxedQuery<'a, diesel::sqlite::Sqlite> {
     let mut query = books::table
         .filter(books::deleted_at.is_null())
         .into_boxed();
 
-    if let Some(ref ids) = book_ids_in_collection {
-        query = query.filter(books::id.eq_any(ids));
+    if let Some(cid) = collection_id {
+        query = query.filter(
+            books::id.eq_any(
+                book_collections::table
+                    .filter(book_collections::collection_id.eq(cid))
+                    .filter(book_collections::deleted_at.is_null())
+                    .select(book_collections::book_id),
+            ),
+        );
     }
 
-    if let Some(status_str) = status {
-        query = query.filter(books::reading_status.eq(status_str));
+    if let Some(status) = status {
+        query = query.filter(books::reading_status.eq(status));
     }
 
     if favorites_only {
         query = query.filter(books::is_favorite.eq(true));
     }
 
+    query
+}
+
+pub(crate) fn books_page(
+    conn: &mut SqliteConnection,
+    collection_id: Option<i32>,
+    status: Option<&str>,
+    favorites_only: bool,
+    sort: Option<&str>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<BookPage, AppError> {
+    let total_count: i64 = filtered_books(collection_id, status, favorites_only)
+        .count()
+        .get_result(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to count books: {}", e),
+            )
+        })?;
+
+    let mut query = filtered_books(collection_id, status, favorites_only);
+    query = if sort == Some("sort_key") {
+        query
+            .order(books::sort_key.asc())
+            .then_order_by(books::title.asc())
+            .then_order_by(books::uuid.asc())
+    } else {
+        query
+            .order(books::last_read_at.desc())
+            .then_order_by(books::added_at.desc())
+            .then_order_by(books::id.desc())
+    };
+    if let Some(limit) = limit {
+        query = query.limit(limit.max(0));
+    }
+    if let Some(offset) = offset {
+        query = query.offset(offset.max(0));
+    }
+
     let books_list = query
         .select(Book::as_select())
-        .order(books::last_read_at.desc())
-        .then_order_by(books::added_at.desc())
-        .load(&mut conn)
+        .load(conn)
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
@@ -269,9 +655,8 @@ pub fn get_all_books(
             )
         })?;
 
-    // Batch load all book-collection associations
+    // Load the collections of the whole page in one query
     let book_ids: Vec<i32> = books_list.iter().map(|b| b.id).collect();
-    
     let all_book_collections: Vec<(i32, i32, String)> = if !book_ids.is_empty() {
         book_collections::table
             .inner_join(collections::table)
@@ -279,14 +664,19 @@ pub fn get_all_books(
             .filter(book_collections::deleted_at.is_null())
             .filter(collections::deleted_at.is_null())
             .select((book_collections::book_id, collections::id, collections::name))
-            .load(&mut conn)
-            .unwrap_or_default()
+            .load(conn)
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::DatabaseQueryFailed,
+                    format!("Failed to load book collection mappings: {}", e),
+                )
+            })?
     } else {
         Vec::new()
     };
 
     // Build a map of book_id -> Vec<(collection_id, collection_name)>
-    let mut collections_map: std::collections::HashMap<i32, Vec<(i32, String)>> = 
+    let mut collections_map: std::collections::HashMap<i32, Vec<(i32, String)>> =
         std::collections::HashMap::new();
     for (book_id, coll_id, coll_name) in all_book_collections {
         collections_map
@@ -295,20 +685,14 @@ pub fn get_all_books(
             .push((coll_id, coll_name));
     }
 
-    // Build result with O(1) collection lookup
-    let result: Vec<BookWithDetails> = books_list
+    let books = books_list
         .into_iter()
         .map(|book| {
-            let book_collections_data = collections_map
+            let (collection_ids, collection_names) = collections_map
                 .remove(&book.id)
-                .unwrap_or_default();
-
-            let collection_ids: Vec<i32> =
-                book_collections_data.iter().map(|(id, _)| *id).collect();
-            let collection_names: Vec<String> = book_collections_data
+                .unwrap_or_default()
                 .into_iter()
-                .map(|(_, name)| name)
-                .collect();
+                .unzip();
 
             BookWithDetails {
                 book,
@@ -320,922 +704,3027 @@ pub fn get_all_books(
         })
         .collect();
 
-    info!("Retrieved {} books", result.len());
-    Ok(result)
+    Ok(BookPage { books, total_count })
 }
 
-/// Get a single book by ID
-pub fn get_book_by_id(book_id: i32) -> Result<Book, AppError> {
+/// Get the single most recently read in-progress book, if any
+///
+/// Completed, unstarted, on-hold and dropped books are skipped.
+pub fn get_last_read_book() -> Result<Option<ResumeBook>, AppError> {
+    debug!("Fetching last read book");
     let mut conn = establish_connection()?;
+    last_read_book(&mut conn)
+}
 
-    books::table
-        .find(book_id)
+pub(crate) fn last_read_book(conn: &mut SqliteConnection) -> Result<Option<ResumeBook>, AppError> {
+    let book: Option<Book> = books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::reading_status.eq(ReadingStatus::Reading.as_str()))
+        .filter(books::last_read_at.is_not_null())
+        .order(books::last_read_at.desc())
         .select(Book::as_select())
-        .first(&mut conn)
+        .first(conn)
+        .optional()
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to find book: {}", e),
+                format!("Failed to load last read book: {}", e),
             )
-        })
-}
+        })?;
 
-/// Update a book
-pub fn update_book(book_id: i32, updates: UpdateBook) -> Result<Book, AppError> {
-    info!("Updating book ID: {}", book_id);
-    let mut conn = establish_connection()?;
+    let Some(book) = book else {
+        return Ok(None);
+    };
 
-    let mut final_updates = updates;
-    final_updates.updated_at = Some(chrono::Utc::now().naive_utc());
+    // Guard against progress that is out of range for the archive
+    let resume_page = book.current_page.clamp(0, (book.total_pages - 1).max(0));
+    let details = load_book_details(conn, book)?;
 
-    diesel::update(books::table.find(book_id))
-        .set(&final_updates)
-        .returning(Book::as_returning())
-        .get_result(&mut conn)
-        .map(|book: Book| {
-            info!("Book {} updated successfully", book_id);
-            book
-        })
-        .map_err(|e| {
-            error!("Failed to update book {}: {}", book_id, e);
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to update book: {}", e),
-            )
-        })
+    Ok(Some(ResumeBook {
+        book: details,
+        resume_page,
+    }))
 }
 
-/// Delete a book (soft delete - sets deleted_at)
-pub fn delete_book(book_id: i32) -> Result<(), AppError> {
-    info!("Soft-deleting book ID: {}", book_id);
+/// Get in-progress books not read for at least `days` days, oldest read first
+///
+/// For a "catch up on these" shelf; at most `limit` books are returned.
+pub fn get_stale_in_progress(days: i64, limit: i64) -> Result<Vec<BookWithDetails>, AppError> {
+    debug!("Fetching in-progress books not read for {} days", days);
     let mut conn = establish_connection()?;
+    stale_in_progress(&mut conn, chrono::Utc::now().naive_utc(), days, limit)
+}
 
-    let now = chrono::Utc::now().naive_utc();
-    
-    diesel::update(books::table.find(book_id))
-        .set((
-            books::deleted_at.eq(Some(now)),
-            books::updated_at.eq(now),
-        ))
-        .execute(&mut conn)
+pub(crate) fn stale_in_progress(
+    conn: &mut SqliteConnection,
+    now: chrono::NaiveDateTime,
+    days: i64,
+    limit: i64,
+) -> Result<Vec<BookWithDetails>, AppError> {
+    if days < 0 || limit < 1 {
+        return Err(AppError::invalid_input("days must be >= 0 and limit >= 1"));
+    }
+
+    let cutoff = now - chrono::Duration::days(days);
+
+    let stale: Vec<Book> = books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::reading_status.eq(ReadingStatus::Reading.as_str()))
+        .filter(books::last_read_at.lt(cutoff))
+        .order(books::last_read_at.asc())
+        .limit(limit)
+        .select(Book::as_select())
+        .load(conn)
         .map_err(|e| {
-            error!("Failed to delete book {}: {}", book_id, e);
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to delete book: {}", e),
+                format!("Failed to load stale books: {}", e),
             )
         })?;
 
-    info!("Book {} soft-deleted successfully", book_id);
-    Ok(())
+    load_books_details(conn, stale)
 }
 
-/// Check if a file hash already exists in the database (excludes soft-deleted)
-pub fn find_book_by_hash(file_hash: &str) -> Result<Option<Book>, AppError> {
+/// Get live favorite books that aren't in any collection, by title
+///
+/// Links to deleted collections don't count as being in a collection.
+pub fn get_favorites_without_collection() -> Result<Vec<BookWithDetails>, AppError> {
+    debug!("Fetching favorites without a collection");
     let mut conn = establish_connection()?;
+    favorites_without_collection(&mut conn)
+}
 
-    books::table
-        .filter(books::file_hash.eq(file_hash))
+pub(crate) fn favorites_without_collection(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<BookWithDetails>, AppError> {
+    let live_collections = collections::table
+        .filter(collections::deleted_at.is_null())
+        .select(collections::id);
+
+    let unfiled: Vec<Book> = books::table
+        .left_join(
+            book_collections::table.on(book_collections::book_id
+                .eq(books::id)
+                .and(book_collections::deleted_at.is_null())
+                .and(book_collections::collection_id.eq_any(live_collections))),
+        )
+        .filter(book_collections::id.is_null())
         .filter(books::deleted_at.is_null())
+        .filter(books::is_favorite.eq(true))
+        .order((books::sort_key.asc(), books::title.asc()))
         .select(Book::as_select())
-        .first(&mut conn)
-        .optional()
+        .load(conn)
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to check for duplicates: {}", e),
+                format!("Failed to load unfiled favorites: {}", e),
             )
-        })
+        })?;
+
+    load_books_details(conn, unfiled)
 }
 
-/// Find a soft-deleted book by hash (for restoration)
-pub fn find_deleted_book_by_hash(file_hash: &str) -> Result<Option<Book>, AppError> {
+/// Default number of results returned by `search_books`
+pub const DEFAULT_SEARCH_LIMIT: i64 = 50;
+
+/// Search live books by title and filename (case-insensitive)
+///
+/// Results are ranked: exact title match, then title prefix, then title substring,
+/// then filename-only matches.
+pub fn search_books(query: &str, limit: Option<i64>) -> Result<Vec<BookWithDetails>, AppError> {
     let mut conn = establish_connection()?;
+    find_books_matching(&mut conn, query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+}
 
-    books::table
-        .filter(books::file_hash.eq(file_hash))
-        .filter(books::deleted_at.is_not_null())
+pub(crate) fn find_books_matching(
+    conn: &mut SqliteConnection,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<BookWithDetails>, AppError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(AppError::invalid_input("search query must not be empty"));
+    }
+    if limit < 1 {
+        return Err(AppError::invalid_input("limit must be >= 1"));
+    }
+    debug!("Searching books for {:?} (limit {})", query, limit);
+
+    // Match the user's text literally, not as LIKE wildcards
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let pattern = format!("%{}%", escaped);
+
+    let matches: Vec<Book> = books::table
+        .filter(books::deleted_at.is_null())
+        .filter(
+            books::title
+                .like(&pattern)
+                .escape('\\')
+                .or(books::filename.like(&pattern).escape('\\')),
+        )
         .select(Book::as_select())
-        .first(&mut conn)
-        .optional()
+        .load(conn)
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to find deleted book: {}", e),
+                format!("Failed to search books: {}", e),
             )
-        })
-}
+        })?;
 
-/// Check if a book with the given file path exists (includes soft-deleted due to UNIQUE constraint)
-pub fn find_book_by_path(file_path: &str) -> Result<Option<Book>, AppError> {
+    let needle = query.to_lowercase();
+    let mut ranked: Vec<(u8, String, Book)> = matches
+        .into_iter()
+        .map(|book| {
+            let title = book.title.to_lowercase();
+            let rank = if title == needle {
+                0
+            } else if title.starts_with(&needle) {
+                1
+            } else if title.contains(&needle) {
+                2
+            } else {
+                3
+            };
+            (rank, title, book)
+        })
+        .collect();
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+    let matches = ranked
+        .into_iter()
+        .take(limit as usize)
+        .map(|(_, _, book)| book)
+        .collect();
+    load_books_details(conn, matches)
+}
+
+/// Load collections, settings and bookmark count for a single book
+fn load_book_details(conn: &mut SqliteConnection, book: Book) -> Result<BookWithDetails, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to load book details: {}", e),
+        )
+    };
+
+    let book_collections_data: Vec<(i32, String)> = book_collections::table
+        .inner_join(collections::table)
+        .filter(book_collections::book_id.eq(book.id))
+        .filter(book_collections::deleted_at.is_null())
+        .filter(collections::deleted_at.is_null())
+        .select((collections::id, collections::name))
+        .load(conn)
+        .map_err(query_error)?;
+
+    let settings = book_settings::table
+        .filter(book_settings::book_id.eq(book.id))
+        .filter(book_settings::deleted_at.is_null())
+        .select(BookSettings::as_select())
+        .first(conn)
+        .optional()
+        .map_err(query_error)?;
+
+    let bookmark_count: i64 = bookmarks::table
+        .filter(bookmarks::book_id.eq(book.id))
+        .filter(bookmarks::deleted_at.is_null())
+        .count()
+        .get_result(conn)
+        .map_err(query_error)?;
+
+    let (collection_ids, collection_names) = book_collections_data.into_iter().unzip();
+
+    Ok(BookWithDetails {
+        book,
+        collection_names,
+        collection_ids,
+        settings,
+        bookmark_count,
+    })
+}
+
+/// Load collections, settings and bookmark counts for a list of books, keeping their order
+///
+/// Uses one query per kind of detail however many books there are.
+fn load_books_details(conn: &mut SqliteConnection, books: Vec<Book>) -> Result<Vec<BookWithDetails>, AppError> {
+    if books.is_empty() {
+        return Ok(Vec::new());
+    }
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to load book details: {}", e),
+        )
+    };
+    let book_ids: Vec<i32> = books.iter().map(|b| b.id).collect();
+
+    let mut collections_by_book: std::collections::HashMap<i32, Vec<(i32, String)>> = std::collections::HashMap::new();
+    let book_collections_data: Vec<(i32, i32, String)> = book_collections::table
+        .inner_join(collections::table)
+        .filter(book_collections::book_id.eq_any(&book_ids))
+        .filter(book_collections::deleted_at.is_null())
+        .filter(collections::deleted_at.is_null())
+        .select((book_collections::book_id, collections::id, collections::name))
+        .load(conn)
+        .map_err(query_error)?;
+    for (book_id, collection_id, name) in book_collections_data {
+        collections_by_book.entry(book_id).or_default().push((collection_id, name));
+    }
+
+    let mut settings_by_book: std::collections::HashMap<i32, BookSettings> = book_settings::table
+        .filter(book_settings::book_id.eq_any(&book_ids))
+        .filter(book_settings::deleted_at.is_null())
+        .select(BookSettings::as_select())
+        .load(conn)
+        .map_err(query_error)?
+        .into_iter()
+        .map(|settings: BookSettings| (settings.book_id, settings))
+        .collect();
+
+    let bookmark_counts: std::collections::HashMap<i32, i64> = bookmarks::table
+        .filter(bookmarks::book_id.eq_any(&book_ids))
+        .filter(bookmarks::deleted_at.is_null())
+        .group_by(bookmarks::book_id)
+        .select((bookmarks::book_id, diesel::dsl::count_star()))
+        .load::<(i32, i64)>(conn)
+        .map_err(query_error)?
+        .into_iter()
+        .collect();
+
+    Ok(books
+        .into_iter()
+        .map(|book| {
+            let (collection_ids, collection_names) =
+                collections_by_book.remove(&book.id).unwrap_or_default().into_iter().unzip();
+            BookWithDetails {
+                settings: settings_by_book.remove(&book.id),
+                bookmark_count: bookmark_counts.get(&book.id).copied().unwrap_or(0),
+                collection_ids,
+                collection_names,
+                book,
+            }
+        })
+        .collect())
+}
+
+/// Get a single book by ID
+pub fn get_book_by_id(book_id: i32) -> Result<Book, AppError> {
     let mut conn = establish_connection()?;
 
     books::table
-        .filter(books::file_path.eq(file_path))
+        .find(book_id)
         .select(Book::as_select())
         .first(&mut conn)
-        .optional()
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to check for path conflict: {}", e),
+                format!("Failed to find book: {}", e),
             )
         })
 }
 
-/// Restore a soft-deleted book with a new file path and filename
-pub fn restore_deleted_book(book_id: i32, new_file_path: &str, new_filename: &str) -> Result<Book, AppError> {
-    info!("Restoring soft-deleted book ID: {} with path: {}", book_id, new_file_path);
+/// Resolve page navigation for a book, using its settings overrides or the given defaults
+pub fn get_page_navigation(
+    book_id: i32,
+    current_page: i32,
+    default_direction: &str,
+    default_display_mode: &str,
+) -> Result<PageNavigation, AppError> {
+    let book = get_book_by_id(book_id)?;
+    let settings = get_book_settings(book_id)?;
+
+    let reading_direction = settings
+        .as_ref()
+        .and_then(|s| s.reading_direction.clone())
+        .unwrap_or_else(|| default_direction.to_string());
+    let page_display_mode = settings
+        .and_then(|s| s.page_display_mode)
+        .unwrap_or_else(|| default_display_mode.to_string());
+
+    Ok(PageNavigation::new(
+        current_page,
+        book.total_pages,
+        &reading_direction,
+        &page_display_mode,
+    ))
+}
+
+/// Resolve the reader configuration for a book, using its settings overrides or the given defaults
+pub fn get_reader_config(
+    book_id: i32,
+    default_direction: &str,
+    default_display_mode: &str,
+    default_fit_mode: &str,
+    default_background: &str,
+) -> Result<ReaderConfig, AppError> {
     let mut conn = establish_connection()?;
+    reader_config(
+        &mut conn,
+        book_id,
+        default_direction,
+        default_display_mode,
+        default_fit_mode,
+        default_background,
+    )
+}
 
-    let now = chrono::Utc::now().naive_utc();
+/// Build a book's reader configuration on an existing connection
+///
+/// Scroll positions only apply to vertical mode and are left out for paged modes.
+pub(crate) fn reader_config(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    default_direction: &str,
+    default_display_mode: &str,
+    default_fit_mode: &str,
+    default_background: &str,
+) -> Result<ReaderConfig, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to load reader config: {}", e),
+        )
+    };
+
+    let book: Book = books::table
+        .find(book_id)
+        .filter(books::deleted_at.is_null())
+        .select(Book::as_select())
+        .first(conn)
+        .map_err(query_error)?;
+    let settings: Option<BookSettings> = book_settings::table
+        .filter(book_settings::book_id.eq(book_id))
+        .filter(book_settings::deleted_at.is_null())
+        .select(BookSettings::as_select())
+        .first(conn)
+        .optional()
+        .map_err(query_error)?;
+
+    let reading_direction = settings
+        .as_ref()
+        .and_then(|s| s.reading_direction.clone())
+        .unwrap_or_else(|| default_direction.to_string());
+    let page_display_mode = settings
+        .as_ref()
+        .and_then(|s| s.page_display_mode.clone())
+        .unwrap_or_else(|| default_display_mode.to_string());
+    let image_fit_mode = settings
+        .as_ref()
+        .and_then(|s| s.image_fit_mode.clone())
+        .unwrap_or_else(|| default_fit_mode.to_string());
+    let reader_background = settings
+        .and_then(|s| s.reader_background)
+        .unwrap_or_else(|| default_background.to_string());
+
+    let vertical = reading_direction == ReadingDirection::Vertical.as_str();
+    Ok(ReaderConfig {
+        book_id,
+        current_page: book.current_page,
+        total_pages: book.total_pages,
+        reading_direction,
+        page_display_mode,
+        image_fit_mode,
+        reader_background,
+        page_fraction: book.page_fraction.filter(|_| vertical),
+        scroll_fraction: book.scroll_fraction.filter(|_| vertical),
+    })
+}
+
+/// Update a book
+pub fn update_book(book_id: i32, updates: UpdateBook) -> Result<Book, AppError> {
+    info!("Updating book ID: {}", book_id);
+    for (name, fraction) in [("page_fraction", updates.page_fraction), ("scroll_fraction", updates.scroll_fraction)] {
+        if let Some(Some(fraction)) = fraction {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(AppError::invalid_input(format!("{} must be between 0.0 and 1.0", name)));
+            }
+        }
+    }
+    if let Some(Some(volume)) = updates.volume_number {
+        if !volume.is_finite() || volume < 0.0 {
+            return Err(AppError::invalid_input("volume_number must be a non-negative number"));
+        }
+    }
+    let mut conn = establish_connection()?;
+
+    let mut final_updates = updates;
+    final_updates.updated_at = Some(chrono::Utc::now().naive_utc());
+    // A blank series name takes the book out of its series, volume number included
+    if let Some(Some(name)) = &final_updates.series_name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            final_updates.series_name = Some(None);
+            final_updates.volume_number = Some(None);
+        } else {
+            final_updates.series_name = Some(Some(name));
+        }
+    }
+    if let Some(title) = &final_updates.title {
+        final_updates.sort_key = Some(Some(book_sort_key(title)));
+    }
+    let completing = final_updates.reading_status.as_deref() == Some(ReadingStatus::Completed.as_str());
+
+    conn.transaction(|conn| {
+        let (current_status, current_total, stored_page): (String, i32, i32) = books::table
+            .find(book_id)
+            .select((books::reading_status, books::total_pages, books::current_page))
+            .first(conn)?;
+
+        // Keep the page inside the book, whichever page count applies after the update
+        if let Some(page) = final_updates.current_page {
+            let total_pages = final_updates.total_pages.unwrap_or(current_total);
+            let page = clamp_page(page, total_pages);
+            final_updates.current_page = Some(page);
+            // Fractions given with the new page are written by the update below
+            if page != stored_page {
+                clear_page_fractions(conn, book_id)?;
+            }
+        }
+
+        // Count a completion only on the transition into "completed"
+        let was_completed = current_status == ReadingStatus::Completed.as_str();
+
+        let book: Book = diesel::update(books::table.find(book_id))
+            .set(&final_updates)
+            .returning(Book::as_returning())
+            .get_result(conn)?;
+
+        if completing && !was_completed {
+            return diesel::update(books::table.find(book_id))
+                .set(books::completed_count.eq(books::completed_count + 1))
+                .returning(Book::as_returning())
+                .get_result(conn);
+        }
+        Ok(book)
+    })
+    .map(|book: Book| {
+        info!("Book {} updated successfully", book_id);
+        book
+    })
+    .map_err(|e: diesel::result::Error| {
+        error!("Failed to update book {}: {}", book_id, e);
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to update book: {}", e),
+        )
+    })
+}
+
+/// Clamp a 0-indexed page into `0..total_pages` (only the lower bound if the page count is unknown)
+pub(crate) fn clamp_page(page: i32, total_pages: i32) -> i32 {
+    if total_pages > 0 {
+        page.clamp(0, total_pages - 1)
+    } else {
+        page.max(0)
+    }
+}
 
+/// Clear the position within the current page of a book that moved to another page
+///
+/// `page_fraction` and `scroll_fraction` only apply to the page they were recorded
+/// on. Every write that changes `current_page` (`update_book`, buffered positions,
+/// new page counts) clears them here, so the new page opens at its top.
+pub(crate) fn clear_page_fractions(conn: &mut SqliteConnection, book_id: i32) -> diesel::QueryResult<Book> {
     diesel::update(books::table.find(book_id))
         .set((
-            books::deleted_at.eq(None::<chrono::NaiveDateTime>),
-            books::file_path.eq(new_file_path),
-            books::filename.eq(new_filename),
-            books::updated_at.eq(now),
+            books::page_fraction.eq(None::<f64>),
+            books::scroll_fraction.eq(None::<f64>),
         ))
         .returning(Book::as_returning())
-        .get_result(&mut conn)
-        .map(|book| {
-            info!("Book {} restored successfully", book_id);
-            book
-        })
-        .map_err(|e| {
-            error!("Failed to restore book {}: {}", book_id, e);
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to restore book: {}", e),
-            )
-        })
+        .get_result(conn)
 }
 
-// ============================================================================
-// FILE PROCESSING HELPERS
-// ============================================================================
-
-/// Archive type detected from magic bytes
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ArchiveType {
-    Zip,
-    #[cfg(not(target_os = "android"))]
-    Rar,
+/// What rescanning a book's file found
+pub(crate) struct RescannedFile {
+    pub total_pages: i32,
+    pub story_page_count: Option<i32>,
+    pub file_hash: String,
+    /// Value for `books.archive_type`; a file replaced by another format is read differently
+    pub archive_type: &'static str,
 }
 
-/// Detect archive type from magic bytes (file signature)
-fn detect_archive_type(path: &Path) -> Result<ArchiveType, AppError> {
-    let mut file = fs::File::open(path)
-        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open file: {}", e)))?;
-
-    let mut magic = [0u8; 8];
-    file.read(&mut magic).map_err(|e| {
-        AppError::new(
-            ErrorCode::IoError,
-            format!("Failed to read file header: {}", e),
-        )
-    })?;
+/// Count a book's pages again after its file was replaced in place
+///
+/// Updates `total_pages`, the ComicInfo.xml story page count, the archive type and
+/// the content hash, and moves the reading position back inside the book if it now
+/// lies past the last page. With the new hash the next sync uploads the replaced
+/// file instead of pointing other devices at the old one. Cloud-only books and books
+/// whose file is gone can't be rescanned.
+pub fn rescan_book(book_id: i32) -> Result<Book, AppError> {
+    let book = get_book_by_id(book_id)?;
+    let rescanned = scan_book_file(&book, &page_filter::current())?;
+
+    info!("Rescanned book {}: {} -> {} page(s)", book_id, book.total_pages, rescanned.total_pages);
+    let mut conn = establish_connection()?;
+    store_rescan(&mut conn, book_id, &rescanned)
+}
 
-    // ZIP: starts with "PK" (0x50 0x4B)
-    if magic[0] == 0x50 && magic[1] == 0x4B {
-        return Ok(ArchiveType::Zip);
+/// Count, hash and detect the format of a book's file as it is now, skipping pages `filter` excludes
+pub(crate) fn scan_book_file(book: &Book, filter: &PageFilter) -> Result<RescannedFile, AppError> {
+    if book.file_path.starts_with("cloud://") {
+        return Err(AppError::invalid_input(
+            "Book is stored in cloud. Please download it before rescanning.",
+        ));
     }
 
-    // RAR: starts with "Rar!" (0x52 0x61 0x72 0x21)
-    #[cfg(not(target_os = "android"))]
-    if magic[0] == 0x52 && magic[1] == 0x61 && magic[2] == 0x72 && magic[3] == 0x21 {
-        return Ok(ArchiveType::Rar);
+    if let Some(manifest_path) = manifest::manifest_path(&book.file_path) {
+        let page_manifest = manifest::read_manifest(manifest_path)?;
+        let total_pages = count_manifest_pages(&page_manifest, filter);
+        if total_pages == 0 {
+            return Err(AppError::new(
+                ErrorCode::IoError,
+                "No pages left in manifest after exclusions",
+            ));
+        }
+        return Ok(RescannedFile {
+            total_pages,
+            story_page_count: None,
+            file_hash: manifest::hash_pages(manifest::manifest_dir(manifest_path), &page_manifest)?,
+            archive_type: manifest::MANIFEST_ARCHIVE_TYPE,
+        });
     }
 
-    // On Android, RAR is not supported
-    #[cfg(target_os = "android")]
-    if magic[0] == 0x52 && magic[1] == 0x61 && magic[2] == 0x72 && magic[3] == 0x21 {
+    let archive_path = Path::new(&book.file_path);
+    if !archive_path.exists() {
         return Err(AppError::new(
             ErrorCode::IoError,
-            "RAR/CBR archives are not supported on Android. Please convert to CBZ format.",
+            format!("Book file not found: {}", book.file_path),
         ));
     }
-
-    // If we can't detect, try to infer from extension as fallback
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_lowercase());
-
-    match ext.as_deref() {
-        Some("zip") | Some("cbz") => Ok(ArchiveType::Zip),
-        #[cfg(not(target_os = "android"))]
-        Some("rar") | Some("cbr") => Ok(ArchiveType::Rar),
-        #[cfg(target_os = "android")]
-        Some("rar") | Some("cbr") => Err(AppError::new(
-            ErrorCode::IoError,
-            "RAR/CBR archives are not supported on Android. Please convert to CBZ format.",
-        )),
-        _ => Err(AppError::new(
-            ErrorCode::IoError,
-            "Unsupported or unrecognized archive format",
-        )),
+    let archive_type = detect_archive_type(archive_path)?;
+    let total_pages = count_archive_images(archive_path, filter)?;
+    if total_pages == 0 {
+        return Err(AppError::new(ErrorCode::IoError, "No images found in archive"));
     }
+    let (_, story_page_count) = read_archive_metadata(archive_path, archive_type, total_pages);
+    let password = archive_password::get(book.id);
+    Ok(RescannedFile {
+        total_pages,
+        story_page_count,
+        file_hash: calculate_archive_hash_with_password(archive_path, password.as_deref())?,
+        archive_type: archive_type.as_str(),
+    })
 }
 
-/// Check if a file is an image based on extension
-fn is_image_file(name: &str) -> bool {
-    let lower = name.to_lowercase();
-    lower.ends_with(".jpg") || lower.ends_with(".jpeg") || lower.ends_with(".png")
+/// Store a rescanned page count, archive type and content hash, clamping the
+/// reading position into the new count
+///
+/// A position that had to move starts at the top of its new page.
+pub(crate) fn store_rescan(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    rescanned: &RescannedFile,
+) -> Result<Book, AppError> {
+    let total_pages = rescanned.total_pages;
+    conn.transaction(|conn| {
+        let current_page: i32 = books::table
+            .find(book_id)
+            .select(books::current_page)
+            .first(conn)?;
+        let clamped = clamp_page(current_page, total_pages);
+
+        let book: Book = diesel::update(books::table.find(book_id))
+            .set((
+                books::total_pages.eq(total_pages),
+                books::story_page_count.eq(rescanned.story_page_count),
+                books::file_hash.eq(Some(&rescanned.file_hash)),
+                books::hash_scheme.eq(HashScheme::Content.as_str()),
+                books::archive_type.eq(Some(rescanned.archive_type)),
+                books::current_page.eq(clamped),
+                books::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(Book::as_returning())
+            .get_result(conn)?;
+
+        if clamped == current_page {
+            return Ok(book);
+        }
+        clear_page_fractions(conn, book_id)
+    })
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to store rescanned book: {}", e),
+        )
+    })
 }
 
-/// Extract book title from filename (removes only archive extensions)
-fn extract_title(filename: &str) -> String {
-    let lower = filename.to_lowercase();
-
-    // Remove only known archive extensions
-    if lower.ends_with(".cbz") {
-        filename[..filename.len() - 4].to_string()
-    } else if lower.ends_with(".zip") {
-        filename[..filename.len() - 4].to_string()
-    } else if lower.ends_with(".cbr") {
-        filename[..filename.len() - 4].to_string()
-    } else if lower.ends_with(".rar") {
-        filename[..filename.len() - 4].to_string()
-    } else if lower.ends_with(".cb7") {
-        filename[..filename.len() - 4].to_string()
-    } else if lower.ends_with(".7z") {
-        filename[..filename.len() - 3].to_string()
-    } else {
-        filename.to_string()
+/// Remap local books' page numbers after the page exclusion patterns changed
+///
+/// Books whose file can't be listed are skipped. Returns the number of books whose
+/// page list changed.
+pub fn remap_pages_for_filter(previous: &PageFilter, current: &PageFilter) -> Result<usize, AppError> {
+    let mut conn = establish_connection()?;
+    let mut remapped = 0;
+    for book in get_local_books()? {
+        let pages = match crate::protocol::unfiltered_page_names(&book) {
+            Ok(pages) => pages,
+            Err(e) => {
+                warn!("Can't list pages of {} to remap them: {}", book.title, e);
+                continue;
+            }
+        };
+        if remap_book_pages(&mut conn, book.id, &pages, previous, current)? {
+            remapped += 1;
+        }
     }
+    info!("Remapped page numbers of {} book(s) to the new exclusion patterns", remapped);
+    Ok(remapped)
 }
 
-/// Calculate hash for a specific book (folder) within an archive
-fn calculate_archive_hash(archive_path: &Path) -> Result<String, AppError> {
-    match detect_archive_type(archive_path)? {
-        ArchiveType::Zip => calculate_zip_hash(archive_path),
-        #[cfg(not(target_os = "android"))]
-        ArchiveType::Rar => calculate_rar_hash(archive_path),
+/// Move a book's page count, reading position and bookmarks from the pages shown
+/// under `previous` to those shown under `current`
+///
+/// `pages` is the book's full page list. A page still shown keeps its image; one
+/// that is now hidden moves to the next page shown. `updated_at` is left alone, as
+/// nothing about the book changed. Returns whether the shown pages differ; if
+/// `current` hides every page, the book is left as it is.
+pub(crate) fn remap_book_pages(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    pages: &[String],
+    previous: &PageFilter,
+    current: &PageFilter,
+) -> Result<bool, AppError> {
+    let shown = |filter: &PageFilter| -> Vec<usize> {
+        (0..pages.len()).filter(|&i| !filter.is_excluded(&pages[i])).collect()
+    };
+    let (shown_before, shown_now) = (shown(previous), shown(current));
+    if shown_before == shown_now {
+        return Ok(false);
     }
-}
-
-/// Calculate hash for all images in a ZIP/CBZ archive
-fn calculate_zip_hash(archive_path: &Path) -> Result<String, AppError> {
-    let file = fs::File::open(archive_path)
-        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+    if shown_now.is_empty() {
+        warn!("Every page of book {} is excluded, keeping its page numbers", book_id);
+        return Ok(false);
+    }
+    let remap = |page: i32| remap_page(&shown_before, &shown_now, page);
+    let total_pages = shown_now.len() as i32;
+
+    conn.transaction(|conn| {
+        let (current_page, story_page_count): (i32, Option<i32>) = books::table
+            .find(book_id)
+            .select((books::current_page, books::story_page_count))
+            .first(conn)?;
+        let new_page = remap(current_page);
+        diesel::update(books::table.find(book_id))
+            .set((
+                books::total_pages.eq(total_pages),
+                books::story_page_count.eq(story_page_count.map(|count| count.min(total_pages))),
+                books::current_page.eq(new_page),
+            ))
+            .execute(conn)?;
+        if new_page != current_page {
+            clear_page_fractions(conn, book_id)?;
+        }
 
-    let mut archive = ZipArchive::new(file).map_err(|e| {
+        let marks: Vec<(i32, i32)> = bookmarks::table
+            .filter(bookmarks::book_id.eq(book_id))
+            .select((bookmarks::id, bookmarks::page))
+            .load(conn)?;
+        for (bookmark_id, page) in marks {
+            let new_page = remap(page);
+            if new_page != page {
+                diesel::update(bookmarks::table.find(bookmark_id))
+                    .set(bookmarks::page.eq(new_page))
+                    .execute(conn)?;
+            }
+        }
+        Ok(())
+    })
+    .map_err(|e: diesel::result::Error| {
         AppError::new(
-            ErrorCode::IoError,
-            format!("Failed to read zip archive: {}", e),
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to remap pages of book {}: {}", book_id, e),
         )
     })?;
+    Ok(true)
+}
 
-    let mut hasher = Sha256::new();
-    let mut image_files: Vec<String> = Vec::new();
+/// Index in `shown_now` of the page at `page` in `shown_before`, or of the next page
+/// still shown if it is now hidden
+///
+/// Both hold positions in the same full page list; `shown_now` must not be empty.
+fn remap_page(shown_before: &[usize], shown_now: &[usize], page: i32) -> i32 {
+    let last = shown_now.len() - 1;
+    let index = match shown_before.get(page.max(0) as usize) {
+        Some(&position) => shown_now.partition_point(|&shown| shown < position).min(last),
+        None => last,
+    };
+    index as i32
+}
 
-    // Collect all image file names
-    for i in 0..archive.len() {
-        let file = archive.by_index(i).map_err(|e| {
+/// Count an opening of the book in the reader
+pub fn mark_book_opened(book_id: i32) -> Result<Book, AppError> {
+    debug!("Marking book {} opened", book_id);
+    let mut conn = establish_connection()?;
+    record_book_opened(&mut conn, book_id)
+}
+
+/// Add one to the book's open count
+///
+/// `updated_at` is left alone: opening a book whose progress is behind another
+/// device's must not win the last-write-wins book merge. Sync merges the counter
+/// by taking the larger value on both sides.
+pub(crate) fn record_book_opened(conn: &mut SqliteConnection, book_id: i32) -> Result<Book, AppError> {
+    diesel::update(books::table.find(book_id))
+        .set(books::open_count.eq(books::open_count + 1))
+        .returning(Book::as_returning())
+        .get_result(conn)
+        .map_err(|e| {
             AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to read archive entry: {}", e),
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to mark book opened: {}", e),
             )
-        })?;
+        })
+}
 
-        let file_name = file.name().to_string();
-        if !file.is_dir()
-            && is_image_file(&file_name)
-            && !file_name.starts_with('.')
-            && !file_name.contains("/.")
-        {
-            image_files.push(file_name);
-        }
-    }
+/// Set or clear the freeform note for a book
+///
+/// Blank notes are stored as NULL so clearing a note syncs as a removal.
+pub fn set_book_note(book_id: i32, note: Option<String>) -> Result<Book, AppError> {
+    let note = note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
 
-    // Sort for consistent hashing
-    image_files.sort();
+    update_book(
+        book_id,
+        UpdateBook {
+            notes: Some(note),
+            ..Default::default()
+        },
+    )
+}
 
-    // Hash all image content
-    for file_name in &image_files {
-        let mut file = archive.by_name(file_name).map_err(|e| {
-            AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to read file '{}': {}", file_name, e),
-            )
-        })?;
+/// Delete a book (soft delete - sets deleted_at)
+///
+/// The book's collection memberships are soft-deleted with it so the removal syncs;
+/// restoring the book brings them back.
+pub fn delete_book(book_id: i32) -> Result<(), AppError> {
+    info!("Soft-deleting book ID: {}", book_id);
+    let mut conn = establish_connection()?;
 
-        let mut buffer = [0u8; 8192];
-        loop {
-            let bytes_read = file.read(&mut buffer).map_err(|e| {
-                AppError::new(
-                    ErrorCode::IoError,
-                    format!("Failed to read file content: {}", e),
-                )
-            })?;
+    soft_delete_book(&mut conn, book_id).map_err(|e| {
+        error!("Failed to delete book {}: {}", book_id, e);
+        e
+    })?;
 
-            if bytes_read == 0 {
-                break;
-            }
+    info!("Book {} soft-deleted successfully", book_id);
+    Ok(())
+}
 
-            hasher.update(&buffer[..bytes_read]);
-        }
+/// Connection-level soft delete used by `delete_book`
+pub(crate) fn soft_delete_book(conn: &mut SqliteConnection, book_id: i32) -> Result<(), AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    conn.transaction(|conn| {
+        diesel::update(books::table.find(book_id))
+            .set((
+                books::deleted_at.eq(Some(now)),
+                books::updated_at.eq(now),
+            ))
+            .execute(conn)?;
+
+        // Stamped with the book's own deleted_at so a restore can tell these
+        // apart from memberships that were removed earlier
+        diesel::update(
+            book_collections::table
+                .filter(book_collections::book_id.eq(book_id))
+                .filter(book_collections::deleted_at.is_null()),
+        )
+        .set((
+            book_collections::deleted_at.eq(Some(now)),
+            book_collections::updated_at.eq(Some(now)),
+        ))
+        .execute(conn)?;
+
+        Ok(())
+    })
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to delete book: {}", e),
+        )
+    })
+}
+
+/// Get books whose file only exists on Drive (`cloud://` paths), optionally within a collection
+pub fn get_cloud_only_books(collection_id: Option<i32>) -> Result<Vec<Book>, AppError> {
+    let mut conn = establish_connection()?;
+
+    let mut query = books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::file_path.like("cloud://%"))
+        .into_boxed();
+
+    if let Some(cid) = collection_id {
+        let in_collection = book_collections::table
+            .filter(book_collections::collection_id.eq(cid))
+            .filter(book_collections::deleted_at.is_null())
+            .select(book_collections::book_id);
+        query = query.filter(books::id.eq_any(in_collection));
     }
 
-    Ok(format!("{:x}", hasher.finalize()))
+    query
+        .order(books::title.asc())
+        .select(Book::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load cloud-only books: {}", e),
+            )
+        })
 }
 
-/// Calculate hash for all images in a RAR/CBR archive (desktop only)
-#[cfg(not(target_os = "android"))]
-fn calculate_rar_hash(archive_path: &Path) -> Result<String, AppError> {
-    let mut hasher = Sha256::new();
-    let mut image_entries: Vec<(String, Vec<u8>)> = Vec::new();
+/// Check if a file hash of the given scheme already exists in the database (excludes soft-deleted)
+pub fn find_book_by_hash(file_hash: &str, scheme: HashScheme) -> Result<Option<Book>, AppError> {
+    lookup_book_by_hash(&mut *establish_connection()?, file_hash, scheme)
+}
 
-    let archive = unrar::Archive::new(archive_path)
-        .open_for_processing()
+pub(crate) fn lookup_book_by_hash(
+    conn: &mut SqliteConnection,
+    file_hash: &str,
+    scheme: HashScheme,
+) -> Result<Option<Book>, AppError> {
+    books::table
+        .filter(books::file_hash.eq(file_hash))
+        .filter(books::hash_scheme.eq(scheme.as_str()))
+        .filter(books::deleted_at.is_null())
+        .select(Book::as_select())
+        .first(conn)
+        .optional()
         .map_err(|e| {
             AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to open RAR archive: {}", e),
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to check for duplicates: {}", e),
             )
-        })?;
+        })
+}
 
-    let mut current_archive = archive;
-    loop {
-        // Read header first to move to CursorBeforeFile state
-        let header_result = current_archive.read_header();
-        match header_result {
-            Ok(Some(header)) => {
-                let file_name = header.entry().filename.to_string_lossy().to_string();
-                let is_dir = header.entry().is_directory();
+/// Find a soft-deleted book by hash of the given scheme (for restoration)
+pub fn find_deleted_book_by_hash(file_hash: &str, scheme: HashScheme) -> Result<Option<Book>, AppError> {
+    let mut conn = establish_connection()?;
 
-                if !is_dir
-                    && is_image_file(&file_name)
-                    && !file_name.starts_with('.')
-                    && !file_name.contains("/.")
-                {
-                    // Read the file content
-                    let (data, next) = header.read().map_err(|e| {
-                        AppError::new(
-                            ErrorCode::IoError,
-                            format!("Failed to read RAR entry: {}", e),
-                        )
-                    })?;
-                    image_entries.push((file_name, data));
-                    current_archive = next;
-                } else {
-                    // Skip non-image files
-                    current_archive = header.skip().map_err(|e| {
-                        AppError::new(
-                            ErrorCode::IoError,
-                            format!("Failed to skip RAR entry: {}", e),
-                        )
-                    })?;
-                }
-            }
-            Ok(None) => break, // End of archive
-            Err(e) => {
-                return Err(AppError::new(
-                    ErrorCode::IoError,
-                    format!("Failed to read RAR header: {}", e),
-                ));
-            }
-        }
-    }
+    books::table
+        .filter(books::file_hash.eq(file_hash))
+        .filter(books::hash_scheme.eq(scheme.as_str()))
+        .filter(books::deleted_at.is_not_null())
+        .select(Book::as_select())
+        .first(&mut conn)
+        .optional()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to find deleted book: {}", e),
+            )
+        })
+}
 
-    // Sort by filename for consistent hashing
-    image_entries.sort_by(|a, b| a.0.cmp(&b.0));
+/// Shortest hash prefix accepted by `find_books_by_hash_prefix`
+pub const MIN_HASH_PREFIX_LEN: usize = 6;
 
-    // Hash all image content
-    for (_, data) in &image_entries {
-        hasher.update(data);
+/// Find books whose file hash starts with `prefix` (includes soft-deleted)
+///
+/// Like git short SHAs, for matching a Drive `book_{hash}` file or a log line to a book.
+pub fn find_books_by_hash_prefix(prefix: &str) -> Result<Vec<Book>, AppError> {
+    let mut conn = establish_connection()?;
+    books_by_hash_prefix(&mut conn, prefix)
+}
+
+pub(crate) fn books_by_hash_prefix(conn: &mut SqliteConnection, prefix: &str) -> Result<Vec<Book>, AppError> {
+    let prefix = prefix.trim().to_lowercase();
+    if prefix.len() < MIN_HASH_PREFIX_LEN {
+        return Err(AppError::invalid_input(format!(
+            "hash prefix must be at least {} characters",
+            MIN_HASH_PREFIX_LEN
+        )));
+    }
+    // Hashes are hex, which also keeps LIKE wildcards out of the pattern
+    if !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::invalid_input("hash prefix must be hexadecimal"));
     }
 
-    Ok(format!("{:x}", hasher.finalize()))
+    books::table
+        .filter(books::file_hash.like(format!("{}%", prefix)))
+        .order(books::file_hash.asc())
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to search by hash prefix: {}", e),
+            )
+        })
 }
 
-/// Count images in a ZIP/CBZ archive
-fn count_zip_images(archive_path: &Path) -> Result<i32, AppError> {
-    let file = fs::File::open(archive_path)
-        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+/// Find the local book a Drive file belongs to
+///
+/// Accepts a bare hash or a Drive file name (`book_{hash}.cbz`, or
+/// `{namespace}_book_{hash}.cbz` for a sync namespace).
+pub fn resolve_drive_file(name_or_hash: &str) -> Result<DriveFileInfo, AppError> {
+    let mut conn = establish_connection()?;
+    lookup_drive_file(&mut conn, name_or_hash)
+}
 
-    let mut archive = ZipArchive::new(file).map_err(|e| {
-        AppError::new(
-            ErrorCode::IoError,
-            format!("Failed to read zip archive: {}", e),
-        )
-    })?;
+pub(crate) fn lookup_drive_file(
+    conn: &mut SqliteConnection,
+    name_or_hash: &str,
+) -> Result<DriveFileInfo, AppError> {
+    let name = name_or_hash.trim();
+    let file_hash = name
+        .rsplit_once("book_")
+        .map_or(name, |(_, hash)| hash)
+        .trim_end_matches(".cbz")
+        .to_lowercase();
+    if file_hash.is_empty() || !file_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::invalid_input(format!(
+            "'{}' is not a book hash or Drive book file name",
+            name
+        )));
+    }
 
-    let mut count = 0;
-    for i in 0..archive.len() {
-        let file = archive.by_index(i).map_err(|e| {
+    let candidates: Vec<Book> = books::table
+        .filter(books::file_hash.eq(&file_hash))
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(|e| {
             AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to read archive entry: {}", e),
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to look up book by hash: {}", e),
             )
         })?;
 
-        let file_name = file.name().to_string();
-        if !file.is_dir()
-            && is_image_file(&file_name)
-            && !file_name.starts_with('.')
-            && !file_name.contains("/.")
-        {
-            count += 1;
-        }
-    }
+    // Prefer the live book; a deleted one only explains the file if nothing else does
+    let book = candidates
+        .iter()
+        .find(|b| b.deleted_at.is_none())
+        .or_else(|| candidates.first());
+
+    let Some(book) = book else {
+        return Ok(DriveFileInfo {
+            file_hash,
+            status: DriveFileStatus::Orphaned,
+            book_id: None,
+            title: None,
+            file_exists: false,
+        });
+    };
 
-    Ok(count)
+    let is_cloud = book.file_path.starts_with("cloud://");
+    let status = if book.deleted_at.is_some() {
+        DriveFileStatus::Deleted
+    } else if is_cloud {
+        DriveFileStatus::CloudOnly
+    } else {
+        DriveFileStatus::Local
+    };
+
+    Ok(DriveFileInfo {
+        file_hash,
+        status,
+        book_id: Some(book.id),
+        title: Some(book.title.clone()),
+        file_exists: !is_cloud && Path::new(&book.file_path).exists(),
+    })
 }
 
-/// Count images in a RAR/CBR archive (desktop only)
-#[cfg(not(target_os = "android"))]
-fn count_rar_images(archive_path: &Path) -> Result<i32, AppError> {
-    let archive = unrar::Archive::new(archive_path)
-        .open_for_listing()
+/// Check if a book with the given file path exists (includes soft-deleted due to UNIQUE constraint)
+pub fn find_book_by_path(file_path: &str) -> Result<Option<Book>, AppError> {
+    let mut conn = establish_connection()?;
+
+    books::table
+        .filter(books::file_path.eq(file_path))
+        .select(Book::as_select())
+        .first(&mut conn)
+        .optional()
         .map_err(|e| {
             AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to open RAR archive for listing: {}", e),
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to check for path conflict: {}", e),
             )
-        })?;
+        })
+}
 
-    let mut count = 0;
-    for entry in archive {
-        let entry = entry.map_err(|e| {
-            AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to read RAR entry: {}", e),
-            )
-        })?;
+/// Restore a soft-deleted book with a new file path and filename
+///
+/// Drops the book's cached image list, which may still describe the old file.
+pub fn restore_deleted_book(book_id: i32, new_file_path: &str, new_filename: &str) -> Result<Book, AppError> {
+    info!("Restoring soft-deleted book ID: {} with path: {}", book_id, new_file_path);
+    let mut conn = establish_connection()?;
 
-        let file_name = entry.filename.to_string_lossy().to_string();
-        if !entry.is_directory()
-            && is_image_file(&file_name)
-            && !file_name.starts_with('.')
-            && !file_name.contains("/.")
-        {
-            count += 1;
-        }
-    }
+    restore_book(&mut conn, book_id, new_file_path, new_filename)
+        .map(|book| {
+            crate::protocol::invalidate_image_cache(book_id);
+            info!("Book {} restored successfully", book_id);
+            book
+        })
+        .map_err(|e| {
+            error!("Failed to restore book {}: {}", book_id, e);
+            e
+        })
+}
 
-    Ok(count)
+/// Connection-level restore used by `restore_deleted_book`
+///
+/// Collection memberships removed together with the book (or by a consistency
+/// cleanup afterwards) are re-activated, as long as their collection still exists.
+pub(crate) fn restore_book(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    new_file_path: &str,
+    new_filename: &str,
+) -> Result<Book, AppError> {
+    conn.transaction(|conn| restore_book_rows(conn, book_id, new_file_path, new_filename))
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to restore book: {}", e),
+        )
+    })
 }
 
-/// Count images in an archive (detects format using magic bytes)
-fn count_archive_images(archive_path: &Path) -> Result<i32, AppError> {
-    match detect_archive_type(archive_path)? {
-        ArchiveType::Zip => count_zip_images(archive_path),
-        #[cfg(not(target_os = "android"))]
-        ArchiveType::Rar => count_rar_images(archive_path),
+/// Statements of `restore_book`, for callers that run them inside their own transaction
+fn restore_book_rows(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    new_file_path: &str,
+    new_filename: &str,
+) -> diesel::QueryResult<Book> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let deleted_at: Option<chrono::NaiveDateTime> = books::table
+        .find(book_id)
+        .select(books::deleted_at)
+        .first(conn)?;
+
+    let book = diesel::update(books::table.find(book_id))
+        .set((
+            books::deleted_at.eq(None::<chrono::NaiveDateTime>),
+            books::file_path.eq(new_file_path),
+            books::filename.eq(new_filename),
+            books::archive_type.eq(None::<String>),
+            books::updated_at.eq(now),
+        ))
+        .returning(Book::as_returning())
+        .get_result(conn)?;
+
+    if let Some(deleted_at) = deleted_at {
+        let live_collections = collections::table
+            .filter(collections::deleted_at.is_null())
+            .select(collections::id);
+
+        diesel::update(
+            book_collections::table
+                .filter(book_collections::book_id.eq(book_id))
+                .filter(book_collections::deleted_at.ge(deleted_at))
+                .filter(book_collections::collection_id.eq_any(live_collections)),
+        )
+        .set((
+            book_collections::deleted_at.eq(None::<chrono::NaiveDateTime>),
+            book_collections::updated_at.eq(Some(now)),
+        ))
+        .execute(conn)?;
     }
+
+    Ok(book)
 }
 
 // ============================================================================
-// ARCHIVE IMPORT
+// FILE PROCESSING HELPERS
 // ============================================================================
 
-/// Import a single book from a zip/cbz/rar/cbr archive
-/// Archive type is detected using magic bytes, not file extension
-/// Each archive is treated as a single book regardless of internal structure
-/// If backup_files is true, copies the archive to library_dir before importing
-/// Returns the imported Book or an error if the book is a duplicate
-/// original_filename can be provided to override the filename extracted from the path
-pub fn import_book_from_archive(
-    archive_path: &Path,
-    collection_id: Option<i32>,
-    backup_files: bool,
-    library_dir: &Path,
-    original_filename: Option<String>,
-) -> Result<Book, AppError> {
-    info!(
-        "Starting import from archive: {:?} (backup: {})",
-        archive_path, backup_files
-    );
-
-    // Validate file exists
-    if !archive_path.exists() {
-        return Err(AppError::new(
-            ErrorCode::IoError,
-            "Archive file does not exist",
-        ));
-    }
-
-    // Detect archive type using magic bytes
-    let archive_type = detect_archive_type(archive_path)?;
-    info!("Detected archive type: {:?}", archive_type);
-
-    // Use original_filename if provided (for Android content URIs), otherwise extract from path
-    let archive_filename = original_filename.unwrap_or_else(|| {
-        archive_path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_string()
-    });
-
-    // Count images in the archive
-    let total_pages = count_archive_images(archive_path)?;
-    info!("Found {} image(s) in archive", total_pages);
-
-    if total_pages == 0 {
-        return Err(AppError::new(
-            ErrorCode::IoError,
-            "No images found in archive",
-        ));
-    }
-
-    // Calculate hash for duplicate detection
-    let book_hash = calculate_archive_hash(archive_path)?;
+/// Archive type detected from magic bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ArchiveType {
+    Zip,
+    #[cfg(not(target_os = "android"))]
+    Rar,
+    SevenZip,
+}
 
-    // Check for active duplicates before backing up
-    if let Some(existing_book) = find_book_by_hash(&book_hash)? {
-        warn!(
-            "Duplicate book detected: {} (hash: {}...)",
-            archive_filename,
-            &book_hash[..16]
-        );
-        return Err(AppError::new(
-            ErrorCode::DuplicateEntry,
-            format!("Duplicate of existing book '{}'", existing_book.title),
-        ));
+impl ArchiveType {
+    /// Value stored in `books.archive_type`
+    fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveType::Zip => "zip",
+            #[cfg(not(target_os = "android"))]
+            ArchiveType::Rar => "rar",
+            ArchiveType::SevenZip => "7z",
+        }
     }
+}
 
-    // Check if this book was previously deleted - if so, we'll restore it
-    let deleted_book = find_deleted_book_by_hash(&book_hash)?;
+/// Record which scheme a book's `file_hash` was computed with (no timestamp bump)
+pub fn set_book_hash_scheme(book_id: i32, scheme: HashScheme) -> Result<(), AppError> {
+    let mut conn = establish_connection()?;
 
-    // Backup the file if enabled
-    let effective_path = if backup_files {
-        info!("Backing up archive to library directory");
-        // Ensure library directory exists
-        fs::create_dir_all(library_dir).map_err(|e| {
+    diesel::update(books::table.find(book_id))
+        .set(books::hash_scheme.eq(scheme.as_str()))
+        .execute(&mut conn)
+        .map(|_| ())
+        .map_err(|e| {
             AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to create library directory: {}", e),
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to store hash scheme: {}", e),
             )
-        })?;
-
-        // Generate destination path
-        let mut dest_path = library_dir.join(&archive_filename);
-
-        // Handle filename conflicts by appending a number
-        // Check both filesystem and database to avoid UNIQUE constraint violations
-        let stem = dest_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("archive")
-            .to_string();
-        let ext = dest_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("cbz")
-            .to_string();
+        })
+}
 
-        let mut counter = 0;
-        loop {
-            let path_str = dest_path.to_string_lossy().to_string();
-            let file_exists = dest_path.exists();
-            let db_exists = find_book_by_path(&path_str)?.is_some();
-            
-            if !file_exists && !db_exists {
-                break;
-            }
-            
-            counter += 1;
-            dest_path = library_dir.join(format!("{}_{}.{}", stem, counter, ext));
-        }
+/// Remember a book's archive type so reads can skip detection (not synced, no timestamp bump)
+pub fn set_book_archive_type(book_id: i32, archive_type: Option<&str>) -> Result<(), AppError> {
+    let mut conn = establish_connection()?;
 
-        // Copy the file to the library directory
-        fs::copy(archive_path, &dest_path).map_err(|e| {
+    diesel::update(books::table.find(book_id))
+        .set(books::archive_type.eq(archive_type))
+        .execute(&mut conn)
+        .map(|_| ())
+        .map_err(|e| {
             AppError::new(
-                ErrorCode::IoError,
-                format!("Failed to copy archive to library: {}", e),
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to store archive type: {}", e),
             )
-        })?;
-
-        info!("Archive backed up to: {:?}", dest_path);
-        dest_path
-    } else {
-        archive_path.to_path_buf()
-    };
+        })
+}
 
-    // Get archive metadata
-    let file_size: Option<i32> = fs::metadata(&effective_path)
-        .ok()
-        .and_then(|m| m.len().try_into().ok());
+/// File extensions `import_book_from_archive` accepts
+pub const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "cbz", "rar", "cbr", "7z", "cb7"];
 
-    let effective_filename = effective_path
-        .file_name()
+/// Whether a path has one of the supported archive extensions (case-insensitive)
+pub fn has_archive_extension(path: &Path) -> bool {
+    path.extension()
         .and_then(|s| s.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+        .map(|ext| ARCHIVE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
 
-    // Extract title from filename
-    let title = extract_title(&effective_filename);
+/// Collect the archives in `dir`, including subdirectories when `recursive` is set
+///
+/// Other files are ignored. Paths are returned in natural order so a series is
+/// imported volume by volume. Unreadable subdirectories are logged and skipped.
+pub fn find_archives_in_dir(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, AppError> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to read directory {:?}: {}", dir, e),
+        )
+    })?;
 
-    // Either restore deleted book or create new one
-    let book = if let Some(deleted) = deleted_book {
-        info!("Restoring previously deleted book: {} (ID: {})", deleted.title, deleted.id);
-        restore_deleted_book(deleted.id, &effective_path.to_string_lossy(), &effective_filename)?
-    } else {
-        // Create the book entry
-        let new_book = NewBook {
-            file_path: effective_path.to_string_lossy().to_string(),
-            filename: effective_filename,
-            file_size,
-            file_hash: Some(book_hash),
-            title: title.clone(),
-            current_page: 0,
-            total_pages,
-            uuid: Some(uuid::Uuid::new_v4().to_string()),
+    let mut archives = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
         };
-
-        create_book(new_book)?
-    };
-    
-    info!("Imported book: {} (ID: {})", book.title, book.id);
-
-    // Add to collection if specified
-    if let Some(cid) = collection_id {
-        add_book_to_collection(book.id, cid)?;
+        // Symlinked directories are never followed; one pointing at an ancestor would
+        // recurse forever
+        if file_type.is_symlink() && path.is_dir() {
+            continue;
+        }
+        if file_type.is_dir() {
+            if recursive {
+                match find_archives_in_dir(&path, true) {
+                    Ok(nested) => archives.extend(nested),
+                    Err(e) => warn!("Skipping {:?}: {}", path, e.message),
+                }
+            }
+        } else if path.is_file() && has_archive_extension(&path) {
+            archives.push(path);
+        }
     }
 
-    Ok(book)
+    archives.sort_by(|a, b| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+    Ok(archives)
 }
 
-// ============================================================================
-// BOOK-COLLECTION OPERATIONS
-// ============================================================================
-
-/// Add a book to a collection
-pub fn add_book_to_collection(
-    book_id: i32,
-    collection_id: i32,
-) -> Result<BookCollection, AppError> {
-    info!("Adding book {} to collection {}", book_id, collection_id);
+/// Store the story page count read from a book's ComicInfo.xml
+pub fn set_story_page_count(book_id: i32, story_page_count: Option<i32>) -> Result<(), AppError> {
     let mut conn = establish_connection()?;
 
-    let new_entry = NewBookCollection {
-        book_id,
-        collection_id,
-        uuid: Some(uuid::Uuid::new_v4().to_string()),
-    };
-
-    diesel::insert_into(book_collections::table)
-        .values(&new_entry)
-        .returning(BookCollection::as_returning())
-        .get_result(&mut conn)
-        .map(|entry: BookCollection| {
-            info!(
-                "Book {} added to collection {} successfully",
-                book_id, collection_id
-            );
-            entry
-        })
+    diesel::update(books::table.find(book_id))
+        .set(books::story_page_count.eq(story_page_count))
+        .execute(&mut conn)
+        .map(|_| ())
         .map_err(|e| {
-            error!(
-                "Failed to add book {} to collection {}: {}",
-                book_id, collection_id, e
-            );
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to add book to collection: {}", e),
+                format!("Failed to store story page count: {}", e),
             )
         })
 }
 
-/// Remove a book from a collection
-pub fn remove_book_from_collection(book_id: i32, collection_id: i32) -> Result<(), AppError> {
-    info!(
-        "Removing book {} from collection {}",
-        book_id, collection_id
-    );
-    let mut conn = establish_connection()?;
+/// Signature at the start of every 7z archive
+pub(crate) const SEVEN_ZIP_MAGIC: [u8; 6] = [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C];
 
-    diesel::delete(
-        book_collections::table
-            .filter(book_collections::book_id.eq(book_id))
-            .filter(book_collections::collection_id.eq(collection_id)),
-    )
-    .execute(&mut conn)
-    .map_err(|e| {
-        error!(
-            "Failed to remove book {} from collection {}: {}",
-            book_id, collection_id, e
-        );
+/// Detect archive type from magic bytes (file signature)
+pub(crate) fn detect_archive_type(path: &Path) -> Result<ArchiveType, AppError> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open file: {}", e)))?;
+
+    let mut magic = [0u8; 8];
+    file.read(&mut magic).map_err(|e| {
         AppError::new(
-            ErrorCode::DatabaseQueryFailed,
-            format!("Failed to remove book from collection: {}", e),
+            ErrorCode::IoError,
+            format!("Failed to read file header: {}", e),
         )
     })?;
 
-    info!(
-        "Book {} removed from collection {} successfully",
-        book_id, collection_id
-    );
-    Ok(())
-}
-
-/// Set the collections for a book (replaces existing)
-pub fn set_book_collections(book_id: i32, collection_ids: Vec<i32>) -> Result<(), AppError> {
-    info!(
-        "Setting collections for book {}: {:?}",
-        book_id, collection_ids
-    );
-    let mut conn = establish_connection()?;
+    // ZIP: starts with "PK" (0x50 0x4B)
+    if magic[0] == 0x50 && magic[1] == 0x4B {
+        return Ok(ArchiveType::Zip);
+    }
 
-    // Remove all existing collection associations
-    diesel::delete(book_collections::table.filter(book_collections::book_id.eq(book_id)))
-        .execute(&mut conn)
-        .map_err(|e| {
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to clear book collections: {}", e),
-            )
-        })?;
+    // RAR: starts with "Rar!" (0x52 0x61 0x72 0x21)
+    #[cfg(not(target_os = "android"))]
+    if magic[0] == 0x52 && magic[1] == 0x61 && magic[2] == 0x72 && magic[3] == 0x21 {
+        return Ok(ArchiveType::Rar);
+    }
 
-    // Add new collection associations
-    for cid in collection_ids {
-        let new_entry = NewBookCollection {
-            book_id,
-            collection_id: cid,
-            uuid: Some(uuid::Uuid::new_v4().to_string()),
-        };
+    // On Android, RAR is not supported
+    #[cfg(target_os = "android")]
+    if magic[0] == 0x52 && magic[1] == 0x61 && magic[2] == 0x72 && magic[3] == 0x21 {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            "RAR/CBR archives are not supported on Android. Please convert to CBZ format.",
+        ));
+    }
 
-        diesel::insert_into(book_collections::table)
-            .values(&new_entry)
-            .execute(&mut conn)
-            .map_err(|e| {
-                AppError::new(
-                    ErrorCode::DatabaseQueryFailed,
-                    format!("Failed to add book to collection {}: {}", cid, e),
-                )
-            })?;
+    // 7z: starts with "7z\xBC\xAF\x27\x1C"
+    if magic[..6] == SEVEN_ZIP_MAGIC {
+        return Ok(ArchiveType::SevenZip);
     }
 
-    info!("Book {} collections updated successfully", book_id);
-    Ok(())
+    // If we can't detect, try to infer from extension as fallback
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+
+    match ext.as_deref() {
+        Some("zip") | Some("cbz") => Ok(ArchiveType::Zip),
+        #[cfg(not(target_os = "android"))]
+        Some("rar") | Some("cbr") => Ok(ArchiveType::Rar),
+        #[cfg(target_os = "android")]
+        Some("rar") | Some("cbr") => Err(AppError::new(
+            ErrorCode::IoError,
+            "RAR/CBR archives are not supported on Android. Please convert to CBZ format.",
+        )),
+        Some("7z") | Some("cb7") => Ok(ArchiveType::SevenZip),
+        _ => Err(AppError::new(
+            ErrorCode::IoError,
+            "Unsupported or unrecognized archive format",
+        )),
+    }
 }
 
-// ============================================================================
-// BOOK SETTINGS
-// ============================================================================
+/// Extract book title from filename (removes only archive extensions)
+fn extract_title(filename: &str) -> String {
+    let lower = filename.to_lowercase();
 
-/// Get book settings by book ID
-pub fn get_book_settings(book_id: i32) -> Result<Option<BookSettings>, AppError> {
-    debug!("Fetching settings for book {}", book_id);
-    let mut conn = establish_connection()?;
+    // Remove only known archive extensions
+    if lower.ends_with(".cbz") {
+        filename[..filename.len() - 4].to_string()
+    } else if lower.ends_with(".zip") {
+        filename[..filename.len() - 4].to_string()
+    } else if lower.ends_with(".cbr") {
+        filename[..filename.len() - 4].to_string()
+    } else if lower.ends_with(".rar") {
+        filename[..filename.len() - 4].to_string()
+    } else if lower.ends_with(".cb7") {
+        filename[..filename.len() - 4].to_string()
+    } else if lower.ends_with(".7z") {
+        filename[..filename.len() - 3].to_string()
+    } else {
+        filename.to_string()
+    }
+}
 
-    book_settings::table
-        .filter(book_settings::book_id.eq(book_id))
-        .filter(book_settings::deleted_at.is_null())
-        .select(BookSettings::as_select())
-        .first(&mut conn)
-        .optional()
-        .map_err(|e| {
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to load book settings: {}", e),
-            )
-        })
+/// SHA-256 of the raw archive file, as used by fast import
+///
+/// Much cheaper than `calculate_archive_hash` since nothing is decompressed, but
+/// repacking the same images gives a different hash.
+pub(crate) fn calculate_file_hash(path: &Path) -> Result<String, AppError> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to read archive: {}", e)))?;
+
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
-/// Update book settings (creates if not exists)
-pub fn update_book_settings(
-    book_id: i32,
-    reading_direction: Option<Option<String>>,
-    page_display_mode: Option<Option<String>>,
-    image_fit_mode: Option<Option<String>>,
-    sync_progress: Option<Option<bool>>,
-) -> Result<BookSettings, AppError> {
-    info!("Updating settings for book {}", book_id);
-    let mut conn = establish_connection()?;
+/// Calculate hash for a specific book (folder) within an archive
+pub(crate) fn calculate_archive_hash(archive_path: &Path) -> Result<String, AppError> {
+    calculate_archive_hash_with_password(archive_path, None)
+}
 
-    // Check if settings exist
-    let existing: Option<BookSettings> = book_settings::table
-        .filter(book_settings::book_id.eq(book_id))
-        .filter(book_settings::deleted_at.is_null())
-        .select(BookSettings::as_select())
-        .first(&mut conn)
-        .optional()
-        .map_err(|e| {
+/// `calculate_archive_hash` for an archive that may be encrypted
+///
+/// Encrypted ZIP entries are hashed decrypted, so a protected archive hashes the
+/// same as an unprotected copy of its pages.
+pub(crate) fn calculate_archive_hash_with_password(
+    archive_path: &Path,
+    password: Option<&str>,
+) -> Result<String, AppError> {
+    match detect_archive_type(archive_path)? {
+        ArchiveType::Zip => calculate_zip_hash(archive_path, password),
+        #[cfg(not(target_os = "android"))]
+        ArchiveType::Rar => calculate_rar_hash(archive_path),
+        ArchiveType::SevenZip => calculate_7z_hash(archive_path),
+    }
+}
+
+/// Calculate hash for all images in a ZIP/CBZ archive
+fn calculate_zip_hash(archive_path: &Path, password: Option<&str>) -> Result<String, AppError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+
+    let mut archive = ZipArchive::new(file).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to read zip archive: {}", e),
+        )
+    })?;
+
+    let mut hasher = Sha256::new();
+    let mut image_files: Vec<(String, usize)> = Vec::new();
+
+    // Collect all image file names (readable without decrypting)
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).map_err(|e| {
             AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to check book settings: {}", e),
+                ErrorCode::IoError,
+                format!("Failed to read archive entry: {}", e),
             )
         })?;
 
-    let now = chrono::Utc::now().naive_utc();
+        let file_name = file.name().to_string();
+        if !file.is_dir() && archive_pages::is_hashed_entry(&file_name) {
+            image_files.push((file_name, i));
+        }
+    }
 
-    if let Some(_settings) = existing {
-        // Update existing settings
-        let updates = UpdateBookSettings {
-            reading_direction,
-            page_display_mode,
-            image_fit_mode,
-            sync_progress,
-            updated_at: Some(now),
-        };
+    // Hash all image content
+    for (_, index) in &archive_pages::hash_order(image_files) {
+        let mut file = archive_password::zip_entry(&mut archive, *index, password)?;
 
-        diesel::update(book_settings::table.filter(book_settings::book_id.eq(book_id)))
-            .set(&updates)
-            .execute(&mut conn)
-            .map_err(|e| {
-                error!("Failed to update book settings for {}: {}", book_id, e);
+        let mut buffer = [0u8; 8192];
+        loop {
+            let bytes_read = file.read(&mut buffer).map_err(|e| {
                 AppError::new(
-                    ErrorCode::DatabaseQueryFailed,
-                    format!("Failed to update book settings: {}", e),
+                    ErrorCode::IoError,
+                    format!("Failed to read file content: {}", e),
                 )
             })?;
-    } else {
-        // Create new settings
-        let new_settings = NewBookSettings {
-            book_id,
-            reading_direction: reading_direction.flatten(),
-            page_display_mode: page_display_mode.flatten(),
-            image_fit_mode: image_fit_mode.flatten(),
-            sync_progress: sync_progress.flatten(),
-            uuid: Some(uuid::Uuid::new_v4().to_string()),
-        };
 
-        diesel::insert_into(book_settings::table)
-            .values(&new_settings)
-            .execute(&mut conn)
-            .map_err(|e| {
-                error!("Failed to create book settings for {}: {}", book_id, e);
-                AppError::new(
-                    ErrorCode::DatabaseQueryFailed,
-                    format!("Failed to create book settings: {}", e),
-                )
-            })?;
+            if bytes_read == 0 {
+                break;
+            }
+
+            hasher.update(&buffer[..bytes_read]);
+        }
     }
 
-    // Return the updated settings
-    book_settings::table
-        .filter(book_settings::book_id.eq(book_id))
-        .select(BookSettings::as_select())
-        .first(&mut conn)
-        .map_err(|e| {
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to retrieve book settings: {}", e),
-            )
-        })
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
-// ============================================================================
-// BOOKMARKS
-// ============================================================================
+/// Calculate hash for all images in a RAR/CBR archive (desktop only)
+#[cfg(not(target_os = "android"))]
+fn calculate_rar_hash(archive_path: &Path) -> Result<String, AppError> {
+    let mut hasher = Sha256::new();
+    let image_entries = read_rar_images(archive_path, archive_pages::is_hashed_entry)?;
 
-/// Create a new bookmark
-pub fn create_bookmark(new_bookmark: NewBookmark) -> Result<Bookmark, AppError> {
-    info!(
-        "Creating bookmark '{}' for book {} at page {}",
-        new_bookmark.name, new_bookmark.book_id, new_bookmark.page
-    );
-    let mut conn = establish_connection()?;
+    // Hash all image content, in the same order as ZIP and 7z
+    for (_, data) in &archive_pages::hash_order(image_entries) {
+        hasher.update(data);
+    }
 
-    diesel::insert_into(bookmarks::table)
-        .values(&new_bookmark)
-        .returning(Bookmark::as_returning())
-        .get_result(&mut conn)
-        .map(|bookmark: Bookmark| {
-            info!(
-                "Bookmark created: {} (ID: {})",
-                bookmark.name, bookmark.id
-            );
-            bookmark
-        })
-        .map_err(|e| {
-            error!("Failed to create bookmark: {}", e);
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to create bookmark: {}", e),
-            )
-        })
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
-/// Get all bookmarks for a book (excludes soft-deleted)
-pub fn get_bookmarks_for_book(book_id: i32) -> Result<Vec<Bookmark>, AppError> {
-    debug!("Fetching bookmarks for book {}", book_id);
-    let mut conn = establish_connection()?;
+/// Read the entries of a RAR/CBR archive accepted by `include`, in archive order (desktop only)
+#[cfg(not(target_os = "android"))]
+fn read_rar_images(archive_path: &Path, include: fn(&str) -> bool) -> Result<Vec<(String, Vec<u8>)>, AppError> {
+    let mut image_entries: Vec<(String, Vec<u8>)> = Vec::new();
 
-    bookmarks::table
-        .filter(bookmarks::book_id.eq(book_id))
-        .filter(bookmarks::deleted_at.is_null())
-        .order(bookmarks::page.asc())
-        .select(Bookmark::as_select())
-        .load(&mut conn)
+    let archive = unrar::Archive::new(archive_path)
+        .open_for_processing()
         .map_err(|e| {
             AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to load bookmarks: {}", e),
+                ErrorCode::IoError,
+                format!("Failed to open RAR archive: {}", e),
             )
-        })
-}
-
-/// Get a single bookmark by ID
-pub fn get_bookmark_by_id(bookmark_id: i32) -> Result<Bookmark, AppError> {
-    let mut conn = establish_connection()?;
+        })?;
 
-    bookmarks::table
+    let mut current_archive = archive;
+    loop {
+        // Read header first to move to CursorBeforeFile state
+        let header_result = current_archive.read_header();
+        match header_result {
+            Ok(Some(header)) => {
+                let file_name = header.entry().filename.to_string_lossy().to_string();
+                let is_dir = header.entry().is_directory();
+
+                if !is_dir && include(&file_name) {
+                    // Read the file content
+                    let (data, next) = header.read().map_err(|e| {
+                        AppError::new(
+                            ErrorCode::IoError,
+                            format!("Failed to read RAR entry: {}", e),
+                        )
+                    })?;
+                    image_entries.push((file_name, data));
+                    current_archive = next;
+                } else {
+                    // Skip non-image files
+                    current_archive = header.skip().map_err(|e| {
+                        AppError::new(
+                            ErrorCode::IoError,
+                            format!("Failed to skip RAR entry: {}", e),
+                        )
+                    })?;
+                }
+            }
+            Ok(None) => break, // End of archive
+            Err(e) => {
+                return Err(AppError::new(
+                    ErrorCode::IoError,
+                    format!("Failed to read RAR header: {}", e),
+                ));
+            }
+        }
+    }
+
+    Ok(image_entries)
+}
+
+/// Check that a downloaded book file has the content `expected_hash` describes
+///
+/// Fails with `ArchiveEncrypted` if the archive can't be hashed without a
+/// (correct) password, and with `SyncFailed` if the content differs.
+pub(crate) fn verify_downloaded_archive(
+    archive_path: &Path,
+    expected_hash: &str,
+    password: Option<&str>,
+) -> Result<(), AppError> {
+    let hash = match calculate_archive_hash_with_password(archive_path, password) {
+        Err(e) if matches!(e.code, ErrorCode::ArchiveEncrypted) => return Err(e),
+        Err(e) => return Err(AppError::sync_failed(format!("Can't hash the downloaded file: {}", e.message))),
+        Ok(hash) => hash,
+    };
+    if hash != expected_hash {
+        return Err(AppError::sync_failed("Downloaded file doesn't match the expected content"));
+    }
+    Ok(())
+}
+
+/// Check that `password` opens a ZIP/CBZ archive's encrypted entries
+///
+/// Archives without encrypted entries pass whatever the password. Fails with
+/// `ArchiveEncrypted` when the password is missing or wrong.
+pub(crate) fn check_zip_password(archive_path: &Path, password: Option<&str>) -> Result<(), AppError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| {
+        AppError::new(ErrorCode::IoError, format!("Failed to read zip archive: {}", e))
+    })?;
+
+    match archive_password::first_encrypted_entry(&mut archive) {
+        Some(index) => archive_password::zip_entry(&mut archive, index, password).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Count images in a ZIP/CBZ archive
+pub(crate) fn count_zip_images(archive_path: &Path, filter: &PageFilter) -> Result<i32, AppError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+
+    let mut archive = ZipArchive::new(file).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to read zip archive: {}", e),
+        )
+    })?;
+
+    let mut count = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).map_err(|e| {
+            AppError::new(
+                ErrorCode::IoError,
+                format!("Failed to read archive entry: {}", e),
+            )
+        })?;
+
+        let file_name = file.name().to_string();
+        if !file.is_dir()
+            && archive_pages::is_page_entry(&file_name)
+            && !filter.is_excluded(&file_name)
+        {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Count images in a RAR/CBR archive (desktop only)
+#[cfg(not(target_os = "android"))]
+fn count_rar_images(archive_path: &Path, filter: &PageFilter) -> Result<i32, AppError> {
+    let archive = unrar::Archive::new(archive_path)
+        .open_for_listing()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::IoError,
+                format!("Failed to open RAR archive for listing: {}", e),
+            )
+        })?;
+
+    let mut count = 0;
+    for entry in archive {
+        let entry = entry.map_err(|e| {
+            AppError::new(
+                ErrorCode::IoError,
+                format!("Failed to read RAR entry: {}", e),
+            )
+        })?;
+
+        let file_name = entry.filename.to_string_lossy().to_string();
+        if !entry.is_directory()
+            && archive_pages::is_page_entry(&file_name)
+            && !filter.is_excluded(&file_name)
+        {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Open a 7z/CB7 archive
+fn open_7z(archive_path: &Path) -> Result<sevenz_rust::SevenZReader<fs::File>, AppError> {
+    sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())
+        .map_err(|e| sevenz_error("open 7z archive", e))
+}
+
+/// Map a 7z error, calling out compression methods the pure-Rust decoder can't handle
+pub(crate) fn sevenz_error(action: &str, e: sevenz_rust::Error) -> AppError {
+    match e {
+        sevenz_rust::Error::UnsupportedCompressionMethod(method) => AppError::new(
+            ErrorCode::IoError,
+            format!(
+                "This 7z/CB7 archive uses an unsupported compression method ({}). Please convert to CBZ format.",
+                method
+            ),
+        ),
+        e => AppError::new(ErrorCode::IoError, format!("Failed to {}: {}", action, e)),
+    }
+}
+
+/// Count images in a 7z/CB7 archive
+fn count_7z_images(archive_path: &Path, filter: &PageFilter) -> Result<i32, AppError> {
+    let archive = open_7z(archive_path)?;
+
+    let count = archive
+        .archive()
+        .files
+        .iter()
+        .filter(|entry| {
+            let file_name = entry.name();
+            !entry.is_directory()
+                && archive_pages::is_page_entry(file_name)
+                && !filter.is_excluded(file_name)
+        })
+        .count();
+
+    Ok(count as i32)
+}
+
+/// Calculate hash for all images in a 7z/CB7 archive
+///
+/// Entries are hashed in `archive_pages::hash_order`, like ZIP and RAR.
+/// 7z archives are usually solid, so entries can only be decoded front to back;
+/// the images are buffered and sorted afterwards, as for RAR.
+fn calculate_7z_hash(archive_path: &Path) -> Result<String, AppError> {
+    let mut archive = open_7z(archive_path)?;
+    let mut image_entries: Vec<(String, Vec<u8>)> = Vec::new();
+
+    archive
+        .for_each_entries(|entry, reader| {
+            let file_name = entry.name();
+            if !entry.is_directory() && archive_pages::is_hashed_entry(file_name) {
+                let mut data = Vec::with_capacity(entry.size() as usize);
+                reader.read_to_end(&mut data)?;
+                image_entries.push((file_name.to_string(), data));
+            }
+            Ok(true)
+        })
+        .map_err(|e| sevenz_error("read 7z archive", e))?;
+
+    let mut hasher = Sha256::new();
+    for (_, data) in &archive_pages::hash_order(image_entries) {
+        hasher.update(data);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Count images in an archive (detects format using magic bytes)
+///
+/// Entries hidden by `filter` aren't counted.
+pub(crate) fn count_archive_images(archive_path: &Path, filter: &PageFilter) -> Result<i32, AppError> {
+    match detect_archive_type(archive_path)? {
+        ArchiveType::Zip => count_zip_images(archive_path, filter),
+        #[cfg(not(target_os = "android"))]
+        ArchiveType::Rar => count_rar_images(archive_path, filter),
+        ArchiveType::SevenZip => count_7z_images(archive_path, filter),
+    }
+}
+
+/// Number of pages of a manifest book that `filter` leaves shown
+fn count_manifest_pages(page_manifest: &manifest::PageManifest, filter: &PageFilter) -> i32 {
+    page_manifest
+        .pages
+        .iter()
+        .filter(|page| !filter.is_excluded(page))
+        .count() as i32
+}
+
+/// Read a new book's ComicInfo.xml metadata and story page count from its archive
+///
+/// Archives without a (parsable) ComicInfo.xml give empty metadata, so the book
+/// is named after its file.
+pub(crate) fn read_archive_metadata(
+    archive_path: &Path,
+    archive_type: ArchiveType,
+    total_pages: i32,
+) -> (comic_info::ComicMetadata, Option<i32>) {
+    let Some(xml) = read_comic_info(archive_path, archive_type) else {
+        return (comic_info::ComicMetadata::default(), None);
+    };
+    (
+        comic_info::parse_metadata(&xml).unwrap_or_default(),
+        comic_info::story_page_count(&xml, total_pages),
+    )
+}
+
+/// Title for an imported book: the ComicInfo.xml title, else the archive's filename
+pub(crate) fn import_title(metadata: &comic_info::ComicMetadata, filename: &str) -> String {
+    metadata.title.clone().unwrap_or_else(|| extract_title(filename))
+}
+
+/// Read the archive's ComicInfo.xml, if it has one
+///
+/// Metadata is optional, so read failures are logged and treated as no metadata.
+fn read_comic_info(archive_path: &Path, archive_type: ArchiveType) -> Option<String> {
+    let result = match archive_type {
+        ArchiveType::Zip => read_zip_comic_info(archive_path),
+        #[cfg(not(target_os = "android"))]
+        ArchiveType::Rar => read_rar_comic_info(archive_path),
+        ArchiveType::SevenZip => read_7z_comic_info(archive_path),
+    };
+
+    match result {
+        Ok(xml) => xml,
+        Err(e) => {
+            warn!("Failed to read ComicInfo.xml from {:?}: {}", archive_path, e.message);
+            None
+        }
+    }
+}
+
+fn read_zip_comic_info(archive_path: &Path) -> Result<Option<String>, AppError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| {
+        AppError::new(ErrorCode::IoError, format!("Failed to read zip archive: {}", e))
+    })?;
+
+    let Some(name) = archive
+        .file_names()
+        .find(|name| comic_info::is_comic_info_entry(name))
+        .map(|name| name.to_string())
+    else {
+        return Ok(None);
+    };
+
+    let mut xml = String::new();
+    archive
+        .by_name(&name)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to read '{}': {}", name, e)))?
+        .read_to_string(&mut xml)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to read '{}': {}", name, e)))?;
+    Ok(Some(xml))
+}
+
+#[cfg(not(target_os = "android"))]
+fn read_rar_comic_info(archive_path: &Path) -> Result<Option<String>, AppError> {
+    fn rar_error(action: &str, e: impl std::fmt::Display) -> AppError {
+        AppError::new(ErrorCode::IoError, format!("Failed to {}: {}", action, e))
+    }
+
+    let mut archive = unrar::Archive::new(archive_path)
+        .open_for_processing()
+        .map_err(|e| rar_error("open RAR archive", e))?;
+
+    while let Some(header) = archive.read_header().map_err(|e| rar_error("read RAR header", e))? {
+        let file_name = header.entry().filename.to_string_lossy().to_string();
+        if !header.entry().is_directory() && comic_info::is_comic_info_entry(&file_name) {
+            let (data, _) = header.read().map_err(|e| rar_error("read RAR entry", e))?;
+            return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+        }
+        archive = header.skip().map_err(|e| rar_error("skip RAR entry", e))?;
+    }
+
+    Ok(None)
+}
+
+fn read_7z_comic_info(archive_path: &Path) -> Result<Option<String>, AppError> {
+    let mut archive = open_7z(archive_path)?;
+    let has_comic_info = archive
+        .archive()
+        .files
+        .iter()
+        .any(|entry| !entry.is_directory() && comic_info::is_comic_info_entry(entry.name()));
+    if !has_comic_info {
+        return Ok(None);
+    }
+
+    let mut found = None;
+    archive
+        .for_each_entries(|entry, reader| {
+            if entry.is_directory() || !comic_info::is_comic_info_entry(entry.name()) {
+                // Solid blocks must still be decoded past skipped entries
+                std::io::copy(reader, &mut std::io::sink())?;
+                return Ok(true);
+            }
+            let mut xml = String::new();
+            reader.read_to_string(&mut xml)?;
+            found = Some(xml);
+            Ok(false)
+        })
+        .map_err(|e| sevenz_error("read 7z archive", e))?;
+
+    Ok(found)
+}
+
+// ============================================================================
+// ARCHIVE IMPORT
+// ============================================================================
+
+/// Import a single book from a zip/cbz/rar/cbr/7z/cb7 archive
+/// Archive type is detected using magic bytes, not file extension
+/// Each archive is treated as a single book regardless of internal structure
+/// If backup_files is true, copies the archive to library_dir before importing
+/// Returns the imported Book or an error if the book is a duplicate
+/// original_filename can be provided to override the filename extracted from the path
+/// hash_scheme selects the duplicate check: `File` (fast import) leaves the content hash
+/// to `backfill_content_hashes`
+/// The book and its collection link are written in one transaction: if collection_id
+/// no longer exists the import fails and leaves neither a book row nor a copied file
+pub fn import_book_from_archive(
+    archive_path: &Path,
+    collection_id: Option<i32>,
+    backup_files: bool,
+    library_dir: &Path,
+    original_filename: Option<String>,
+    hash_scheme: HashScheme,
+    password: Option<&str>,
+    mut on_progress: impl FnMut(ImportPhase, u64, u64),
+) -> Result<Book, AppError> {
+    info!(
+        "Starting import from archive: {:?} (backup: {})",
+        archive_path, backup_files
+    );
+
+    // Validate file exists
+    if !archive_path.exists() {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            "Archive file does not exist",
+        ));
+    }
+
+    // Fail before any work if the target collection is gone
+    if let Some(cid) = collection_id {
+        let mut conn = establish_connection()?;
+        let exists = collection_is_live(&mut conn, cid).map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to find collection: {}", e),
+            )
+        })?;
+        if !exists {
+            return Err(missing_import_collection(cid));
+        }
+    }
+
+    // Detect archive type using magic bytes
+    let archive_type = detect_archive_type(archive_path)?;
+    info!("Detected archive type: {:?}", archive_type);
+    if archive_type == ArchiveType::Zip {
+        check_zip_password(archive_path, password)?;
+    }
+
+    // Use original_filename if provided (for Android content URIs), otherwise extract from path
+    let archive_filename = original_filename.unwrap_or_else(|| {
+        archive_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown")
+            .to_string()
+    });
+
+    // Count images in the archive
+    on_progress(ImportPhase::Counting, 0, 0);
+    let total_pages = count_archive_images(archive_path, &page_filter::current())?;
+    info!("Found {} image(s) in archive", total_pages);
+
+    if total_pages == 0 {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            "No images found in archive",
+        ));
+    }
+
+    let (metadata, story_page_count) = read_archive_metadata(archive_path, archive_type, total_pages);
+
+    // Calculate hash for duplicate detection
+    on_progress(ImportPhase::Hashing, 0, 0);
+    let book_hash = match hash_scheme {
+        HashScheme::Content => calculate_archive_hash_with_password(archive_path, password)?,
+        HashScheme::File => calculate_file_hash(archive_path)?,
+    };
+
+    // Check for active duplicates before backing up
+    if let Some(existing_book) = find_book_by_hash(&book_hash, hash_scheme)? {
+        warn!(
+            "Duplicate book detected: {} (hash: {}...)",
+            archive_filename,
+            &book_hash[..16]
+        );
+        return Err(AppError::duplicate_book(&existing_book.title));
+    }
+
+    // Check if this book was previously deleted - if so, we'll restore it
+    let deleted_book = find_deleted_book_by_hash(&book_hash, hash_scheme)?;
+
+    // Backup the file if enabled
+    let effective_path = if backup_files {
+        info!("Backing up archive to library directory");
+        // Ensure library directory exists
+        fs::create_dir_all(library_dir).map_err(|e| {
+            AppError::new(
+                ErrorCode::IoError,
+                format!("Failed to create library directory: {}", e),
+            )
+        })?;
+
+        // Generate destination path
+        let mut dest_path = library_dir.join(&archive_filename);
+
+        // Handle filename conflicts by appending a number
+        // Check both filesystem and database to avoid UNIQUE constraint violations
+        let stem = dest_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("archive")
+            .to_string();
+        let ext = dest_path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("cbz")
+            .to_string();
+
+        let mut counter = 0;
+        loop {
+            let path_str = dest_path.to_string_lossy().to_string();
+            let file_exists = dest_path.exists();
+            let db_exists = find_book_by_path(&path_str)?.is_some();
+            
+            if !file_exists && !db_exists {
+                break;
+            }
+            
+            counter += 1;
+            dest_path = library_dir.join(format!("{}_{}.{}", stem, counter, ext));
+        }
+
+        // Copy the file to the library directory
+        if let Err(e) = copy_with_progress(archive_path, &dest_path, |copied, total| {
+            on_progress(ImportPhase::Copying, copied, total)
+        }) {
+            let _ = fs::remove_file(&dest_path);
+            return Err(AppError::new(
+                ErrorCode::IoError,
+                format!("Failed to copy archive to library: {}", e),
+            ));
+        }
+
+        info!("Archive backed up to: {:?}", dest_path);
+        dest_path
+    } else {
+        archive_path.to_path_buf()
+    };
+
+    // Get archive metadata
+    let file_size: Option<i32> = fs::metadata(&effective_path)
+        .ok()
+        .and_then(|m| m.len().try_into().ok());
+
+    let effective_filename = effective_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let title = import_title(&metadata, &effective_filename);
+
+    // Either restore deleted book or create new one, together with its collection link
+    on_progress(ImportPhase::Inserting, 0, 0);
+    if let Some(ref deleted) = deleted_book {
+        info!("Restoring previously deleted book: {} (ID: {})", deleted.title, deleted.id);
+    }
+    let new_book = NewBook {
+        file_path: effective_path.to_string_lossy().to_string(),
+        filename: effective_filename,
+        file_size,
+        file_hash: Some(book_hash),
+        title,
+        current_page: 0,
+        total_pages,
+        uuid: Some(uuid::Uuid::new_v4().to_string()),
+        series_name: metadata.series,
+        volume_number: metadata.number,
+    };
+
+    let mut conn = establish_connection()?;
+    let result = insert_imported_book(
+        &mut conn,
+        &new_book,
+        deleted_book.map(|deleted| deleted.id),
+        archive_type.as_str(),
+        story_page_count,
+        hash_scheme,
+        collection_id,
+    );
+
+    match result {
+        Ok(book) => {
+            info!("Imported book: {} (ID: {})", book.title, book.id);
+            Ok(book)
+        }
+        Err(e) => {
+            // Nothing was written to the database, so don't keep the copy either
+            if backup_files {
+                let _ = fs::remove_file(&effective_path);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Error for an import into a collection that doesn't exist (anymore)
+fn missing_import_collection(collection_id: i32) -> AppError {
+    AppError::invalid_input(format!(
+        "The collection to import into (ID {}) no longer exists. Please choose another collection.",
+        collection_id
+    ))
+}
+
+/// Whether a collection exists and isn't soft-deleted
+fn collection_is_live(conn: &mut SqliteConnection, collection_id: i32) -> diesel::QueryResult<bool> {
+    collections::table
+        .find(collection_id)
+        .filter(collections::deleted_at.is_null())
+        .select(collections::id)
+        .first::<i32>(conn)
+        .optional()
+        .map(|id| id.is_some())
+}
+
+/// Write an imported book and its collection link in one transaction
+///
+/// Restores the soft-deleted book `restore_book_id` (with the new path and filename)
+/// or creates `new_book`, records how it was read, and links it to `collection_id`.
+/// If the collection is gone nothing is written.
+pub(crate) fn insert_imported_book(
+    conn: &mut SqliteConnection,
+    new_book: &NewBook,
+    restore_book_id: Option<i32>,
+    archive_type: &str,
+    story_page_count: Option<i32>,
+    hash_scheme: HashScheme,
+    collection_id: Option<i32>,
+) -> Result<Book, AppError> {
+    let outcome = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            // Checked again here: the collection may have been deleted while the archive was read
+            if let Some(cid) = collection_id {
+                if !collection_is_live(conn, cid)? {
+                    return Ok(Err(missing_import_collection(cid)));
+                }
+            }
+
+            let book_id = match restore_book_id {
+                Some(id) => restore_book_rows(conn, id, &new_book.file_path, &new_book.filename)?.id,
+                None => diesel::insert_into(books::table)
+                    .values((new_book, books::sort_key.eq(book_sort_key(&new_book.title))))
+                    .returning(books::id)
+                    .get_result(conn)?,
+            };
+
+            let book = diesel::update(books::table.find(book_id))
+                .set((
+                    books::archive_type.eq(archive_type),
+                    books::story_page_count.eq(story_page_count),
+                    books::hash_scheme.eq(hash_scheme.as_str()),
+                ))
+                .returning(Book::as_returning())
+                .get_result(conn)?;
+
+            if let Some(cid) = collection_id {
+                // A restored book may still have its old (possibly removed) link
+                let relinked = diesel::update(
+                    book_collections::table
+                        .filter(book_collections::book_id.eq(book_id))
+                        .filter(book_collections::collection_id.eq(cid)),
+                )
+                .set((
+                    book_collections::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                    book_collections::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+                ))
+                .execute(conn)?;
+
+                if relinked == 0 {
+                    diesel::insert_into(book_collections::table)
+                        .values(&NewBookCollection {
+                            book_id,
+                            collection_id: cid,
+                            uuid: Some(uuid::Uuid::new_v4().to_string()),
+                        })
+                        .execute(conn)?;
+                }
+            }
+
+            Ok(Ok(book))
+        })
+        .map_err(|e| {
+            error!("Failed to store imported book '{}': {}", new_book.title, e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to store imported book: {}", e),
+            )
+        })?;
+
+    outcome
+}
+
+/// Bytes copied between `copy_with_progress` callbacks
+const COPY_PROGRESS_INTERVAL: u64 = 4 * 1024 * 1024;
+
+/// Copy a file in chunks, reporting `(bytes copied, total bytes)` as it goes
+fn copy_with_progress(
+    source: &Path,
+    dest: &Path,
+    mut on_progress: impl FnMut(u64, u64),
+) -> std::io::Result<u64> {
+    use std::io::Write;
+
+    let mut reader = fs::File::open(source)?;
+    let total = reader.metadata()?.len();
+    let mut writer = std::io::BufWriter::new(fs::File::create(dest)?);
+
+    let mut buffer = vec![0u8; 256 * 1024];
+    let mut copied = 0u64;
+    let mut last_reported = 0u64;
+    on_progress(0, total);
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+        if copied - last_reported >= COPY_PROGRESS_INTERVAL {
+            on_progress(copied, total);
+            last_reported = copied;
+        }
+    }
+    writer.flush()?;
+
+    if last_reported != copied {
+        on_progress(copied, total);
+    }
+    Ok(copied)
+}
+
+/// Import a book stored as loose image files listed in a `pages.json` manifest
+///
+/// The folder stays where it is; the book's file path is `manifest://{manifest path}`.
+/// Fails with every missing entry listed in the error details if the manifest
+/// references files that aren't in the folder.
+pub fn import_book_from_manifest(
+    manifest_path: &Path,
+    collection_id: Option<i32>,
+) -> Result<Book, AppError> {
+    info!("Starting import from page manifest: {:?}", manifest_path);
+
+    let manifest_path = manifest_path.canonicalize().map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Page manifest not found: {}", e),
+        )
+    })?;
+    let book_dir = manifest::manifest_dir(&manifest_path);
+    let page_manifest = manifest::read_manifest(&manifest_path)?;
+
+    let missing = manifest::missing_pages(book_dir, &page_manifest);
+    if !missing.is_empty() {
+        warn!("Page manifest {:?} references {} missing file(s)", manifest_path, missing.len());
+        return Err(AppError {
+            details: missing,
+            ..AppError::invalid_input("Page manifest references files that are missing from the folder")
+        });
+    }
+
+    let total_pages = count_manifest_pages(&page_manifest, &page_filter::current());
+    if total_pages == 0 {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            "No pages left in manifest after exclusions",
+        ));
+    }
+
+    let book_hash = manifest::hash_pages(book_dir, &page_manifest)?;
+    if let Some(existing_book) = find_book_by_hash(&book_hash, HashScheme::Content)? {
+        return Err(AppError::duplicate_book(&existing_book.title));
+    }
+
+    let file_path = manifest::manifest_file_path(&manifest_path);
+    let folder_name = book_dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let book = if let Some(deleted) = find_deleted_book_by_hash(&book_hash, HashScheme::Content)? {
+        info!("Restoring previously deleted book: {} (ID: {})", deleted.title, deleted.id);
+        restore_deleted_book(deleted.id, &file_path, &folder_name)?
+    } else {
+        create_book(NewBook {
+            file_path,
+            file_size: None,
+            file_hash: Some(book_hash),
+            title: page_manifest.title.clone().unwrap_or_else(|| folder_name.clone()),
+            filename: folder_name,
+            current_page: 0,
+            total_pages,
+            uuid: Some(uuid::Uuid::new_v4().to_string()),
+            series_name: None,
+            volume_number: None,
+        })?
+    };
+
+    set_book_archive_type(book.id, Some(manifest::MANIFEST_ARCHIVE_TYPE))?;
+    let book = Book {
+        archive_type: Some(manifest::MANIFEST_ARCHIVE_TYPE.to_string()),
+        ..book
+    };
+
+    info!("Imported manifest book: {} (ID: {})", book.title, book.id);
+
+    if let Some(cid) = collection_id {
+        add_book_to_collection(book.id, cid)?;
+    }
+
+    Ok(book)
+}
+
+// ============================================================================
+// FORMAT CONVERSION
+// ============================================================================
+
+/// Convert a RAR/CBR book into a CBZ at `dest_path` (desktop only)
+///
+/// Entry names are kept and pages are written in reading order. The content hash
+/// covers the image bytes taken in name order, so the CBZ hashes the same as the CBR.
+/// With `relink`, the book is pointed at the new CBZ; the CBR is left in place.
+/// `on_progress` is called with (pages written, total pages).
+#[cfg(not(target_os = "android"))]
+pub fn convert_book_to_cbz(
+    book_id: i32,
+    dest_path: &Path,
+    relink: bool,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<Book, AppError> {
+    let book = get_book_by_id(book_id)?;
+    let source_path = Path::new(&book.file_path);
+    if !source_path.exists() {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            format!("Book file not found: {}", book.file_path),
+        ));
+    }
+    if detect_archive_type(source_path)? != ArchiveType::Rar {
+        return Err(AppError::invalid_input("Book is not a RAR/CBR archive"));
+    }
+    if dest_path.exists() {
+        return Err(AppError::invalid_input(format!(
+            "Destination already exists: {}",
+            dest_path.display()
+        )));
+    }
+
+    info!("Converting book {} to CBZ: {:?}", book_id, dest_path);
+
+    // Hashed entries come along even where the page rules skip them, so the CBZ
+    // keeps the book's content hash
+    let mut image_entries = read_rar_images(source_path, |name| {
+        archive_pages::is_page_entry(name) || archive_pages::is_hashed_entry(name)
+    })?;
+    archive_pages::sort_pages(&mut image_entries);
+    write_cbz(&image_entries, dest_path, &mut on_progress)?;
+
+    info!("Converted {} page(s) of book {} to CBZ", image_entries.len(), book_id);
+
+    if !relink {
+        return Ok(book);
+    }
+
+    let mut conn = establish_connection()?;
+    relink_to_cbz(&mut conn, book_id, dest_path)
+}
+
+/// Write `entries` to a new CBZ in the given order, removing it again on failure
+#[cfg(not(target_os = "android"))]
+pub(crate) fn write_cbz(
+    entries: &[(String, Vec<u8>)],
+    dest_path: &Path,
+    on_progress: &mut impl FnMut(usize, usize),
+) -> Result<(), AppError> {
+    use std::io::Write;
+
+    let write_error = |e: &dyn std::fmt::Display| {
+        AppError::new(ErrorCode::IoError, format!("Failed to write CBZ: {}", e))
+    };
+
+    let file = fs::File::create(dest_path).map_err(|e| write_error(&e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    // Images are already compressed, so store them as-is
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored);
+
+    let written = (|| {
+        for (index, (name, data)) in entries.iter().enumerate() {
+            zip.start_file(name.as_str(), options).map_err(|e| write_error(&e))?;
+            zip.write_all(data).map_err(|e| write_error(&e))?;
+            on_progress(index + 1, entries.len());
+        }
+        zip.finish().map_err(|e| write_error(&e))
+    })();
+
+    if written.is_err() {
+        let _ = fs::remove_file(dest_path);
+    }
+    written.map(|_| ())
+}
+
+/// Point a book at the CBZ converted from its file
+///
+/// Drops the cached image list, which describes the old archive.
+#[cfg(not(target_os = "android"))]
+pub(crate) fn relink_to_cbz(conn: &mut SqliteConnection, book_id: i32, dest_path: &Path) -> Result<Book, AppError> {
+    let filename = dest_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let file_size: Option<i32> = fs::metadata(dest_path)
+        .ok()
+        .and_then(|m| m.len().try_into().ok());
+
+    let book = diesel::update(books::table.find(book_id))
+        .set((
+            books::file_path.eq(dest_path.to_string_lossy().to_string()),
+            books::filename.eq(filename),
+            books::file_size.eq(file_size),
+            books::archive_type.eq(Some(ArchiveType::Zip.as_str())),
+            books::updated_at.eq(chrono::Utc::now().naive_utc()),
+        ))
+        .returning(Book::as_returning())
+        .get_result(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to relink book to CBZ: {}", e),
+            )
+        })?;
+
+    crate::protocol::invalidate_image_cache(book_id);
+    Ok(book)
+}
+
+// ============================================================================
+// BOOK-COLLECTION OPERATIONS
+// ============================================================================
+
+/// Add a book to a collection
+pub fn add_book_to_collection(
+    book_id: i32,
+    collection_id: i32,
+) -> Result<BookCollection, AppError> {
+    info!("Adding book {} to collection {}", book_id, collection_id);
+    let mut conn = establish_connection()?;
+
+    let new_entry = NewBookCollection {
+        book_id,
+        collection_id,
+        uuid: Some(uuid::Uuid::new_v4().to_string()),
+    };
+
+    diesel::insert_into(book_collections::table)
+        .values(&new_entry)
+        .returning(BookCollection::as_returning())
+        .get_result(&mut conn)
+        .map(|entry: BookCollection| {
+            info!(
+                "Book {} added to collection {} successfully",
+                book_id, collection_id
+            );
+            entry
+        })
+        .map_err(|e| {
+            error!(
+                "Failed to add book {} to collection {}: {}",
+                book_id, collection_id, e
+            );
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to add book to collection: {}", e),
+            )
+        })
+}
+
+/// Remove a book from a collection
+pub fn remove_book_from_collection(book_id: i32, collection_id: i32) -> Result<(), AppError> {
+    info!(
+        "Removing book {} from collection {}",
+        book_id, collection_id
+    );
+    let mut conn = establish_connection()?;
+
+    diesel::delete(
+        book_collections::table
+            .filter(book_collections::book_id.eq(book_id))
+            .filter(book_collections::collection_id.eq(collection_id)),
+    )
+    .execute(&mut conn)
+    .map_err(|e| {
+        error!(
+            "Failed to remove book {} from collection {}: {}",
+            book_id, collection_id, e
+        );
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to remove book from collection: {}", e),
+        )
+    })?;
+
+    info!(
+        "Book {} removed from collection {} successfully",
+        book_id, collection_id
+    );
+    Ok(())
+}
+
+/// Set the collections for a book (replaces existing)
+pub fn set_book_collections(book_id: i32, collection_ids: Vec<i32>) -> Result<(), AppError> {
+    info!(
+        "Setting collections for book {}: {:?}",
+        book_id, collection_ids
+    );
+    let mut conn = establish_connection()?;
+
+    // Remove all existing collection associations
+    diesel::delete(book_collections::table.filter(book_collections::book_id.eq(book_id)))
+        .execute(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to clear book collections: {}", e),
+            )
+        })?;
+
+    // Add new collection associations
+    for cid in collection_ids {
+        let new_entry = NewBookCollection {
+            book_id,
+            collection_id: cid,
+            uuid: Some(uuid::Uuid::new_v4().to_string()),
+        };
+
+        diesel::insert_into(book_collections::table)
+            .values(&new_entry)
+            .execute(&mut conn)
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::DatabaseQueryFailed,
+                    format!("Failed to add book to collection {}: {}", cid, e),
+                )
+            })?;
+    }
+
+    info!("Book {} collections updated successfully", book_id);
+    Ok(())
+}
+
+// ============================================================================
+// TAGS
+// ============================================================================
+
+/// Create a new tag
+pub fn create_tag(new_tag: NewTag) -> Result<Tag, AppError> {
+    info!("Creating new tag: {}", new_tag.name);
+    let mut conn = establish_connection()?;
+
+    diesel::insert_into(tags::table)
+        .values(&new_tag)
+        .returning(Tag::as_returning())
+        .get_result(&mut conn)
+        .map_err(|e| {
+            error!("Failed to create tag '{}': {}", new_tag.name, e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to create tag: {}", e),
+            )
+        })
+}
+
+/// Get all tags ordered by name (excludes soft-deleted)
+pub fn get_tags() -> Result<Vec<Tag>, AppError> {
+    let mut conn = establish_connection()?;
+
+    tags::table
+        .filter(tags::deleted_at.is_null())
+        .order(tags::name.asc())
+        .select(Tag::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load tags: {}", e),
+            )
+        })
+}
+
+/// Delete a tag (soft delete - sets deleted_at)
+/// Also modifies the name to avoid UNIQUE constraint conflicts with new tags
+pub fn delete_tag(tag_id: i32) -> Result<(), AppError> {
+    info!("Soft-deleting tag ID: {}", tag_id);
+    let mut conn = establish_connection()?;
+
+    let now = chrono::Utc::now().naive_utc();
+    let tag: Tag = tags::table
+        .find(tag_id)
+        .select(Tag::as_select())
+        .first(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to find tag: {}", e),
+            )
+        })?;
+
+    // Append deletion timestamp to name to free up the name for reuse
+    let deleted_name = format!("{}__deleted_{}", tag.name, now.and_utc().timestamp());
+
+    diesel::update(tags::table.find(tag_id))
+        .set((
+            tags::name.eq(deleted_name),
+            tags::deleted_at.eq(Some(now)),
+            tags::updated_at.eq(now),
+        ))
+        .execute(&mut conn)
+        .map_err(|e| {
+            error!("Failed to delete tag {}: {}", tag_id, e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to delete tag: {}", e),
+            )
+        })?;
+
+    Ok(())
+}
+
+/// Tag a book
+pub fn add_tag_to_book(book_id: i32, tag_id: i32) -> Result<BookTag, AppError> {
+    info!("Adding tag {} to book {}", tag_id, book_id);
+    let mut conn = establish_connection()?;
+    attach_tag(&mut conn, book_id, tag_id)
+}
+
+/// Link a book to a tag, restoring an earlier removed link so its UUID stays stable across syncs
+pub(crate) fn attach_tag(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    tag_id: i32,
+) -> Result<BookTag, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to add tag to book: {}", e),
+        )
+    };
+
+    let existing: Option<BookTag> = book_tags::table
+        .filter(book_tags::book_id.eq(book_id))
+        .filter(book_tags::tag_id.eq(tag_id))
+        .select(BookTag::as_select())
+        .first(conn)
+        .optional()
+        .map_err(query_error)?;
+
+    match existing {
+        Some(link) if link.deleted_at.is_none() => Ok(link),
+        Some(link) => diesel::update(book_tags::table.find(link.id))
+            .set((
+                book_tags::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                book_tags::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(BookTag::as_returning())
+            .get_result(conn)
+            .map_err(query_error),
+        None => diesel::insert_into(book_tags::table)
+            .values(&NewBookTag {
+                book_id,
+                tag_id,
+                uuid: Some(uuid::Uuid::new_v4().to_string()),
+            })
+            .returning(BookTag::as_returning())
+            .get_result(conn)
+            .map_err(query_error),
+    }
+}
+
+/// Remove a tag from a book
+pub fn remove_tag_from_book(book_id: i32, tag_id: i32) -> Result<(), AppError> {
+    info!("Removing tag {} from book {}", tag_id, book_id);
+    let mut conn = establish_connection()?;
+    detach_tag(&mut conn, book_id, tag_id)
+}
+
+/// Soft-delete the link between a book and a tag so the removal syncs to other devices
+pub(crate) fn detach_tag(conn: &mut SqliteConnection, book_id: i32, tag_id: i32) -> Result<(), AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    diesel::update(
+        book_tags::table
+            .filter(book_tags::book_id.eq(book_id))
+            .filter(book_tags::tag_id.eq(tag_id))
+            .filter(book_tags::deleted_at.is_null()),
+    )
+    .set((
+        book_tags::deleted_at.eq(Some(now)),
+        book_tags::updated_at.eq(Some(now)),
+    ))
+    .execute(conn)
+    .map(|_| ())
+    .map_err(|e| {
+        error!("Failed to remove tag {} from book {}: {}", tag_id, book_id, e);
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to remove tag from book: {}", e),
+        )
+    })
+}
+
+/// Get the books with a tag, in library order (excludes soft-deleted)
+pub fn get_books_by_tag(tag_id: i32) -> Result<Vec<Book>, AppError> {
+    let mut conn = establish_connection()?;
+    load_books_with_tag(&mut conn, tag_id)
+}
+
+pub(crate) fn load_books_with_tag(conn: &mut SqliteConnection, tag_id: i32) -> Result<Vec<Book>, AppError> {
+    book_tags::table
+        .inner_join(books::table)
+        .inner_join(tags::table)
+        .filter(book_tags::tag_id.eq(tag_id))
+        .filter(book_tags::deleted_at.is_null())
+        .filter(books::deleted_at.is_null())
+        .filter(tags::deleted_at.is_null())
+        .order((books::sort_key.asc(), books::title.asc()))
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load books by tag: {}", e),
+            )
+        })
+}
+
+// ============================================================================
+// SERIES
+// ============================================================================
+
+/// Get the volumes of a series ordered by volume number (excludes soft-deleted)
+///
+/// Volumes without a number come last, in library order.
+pub fn get_series(name: &str) -> Result<Vec<Book>, AppError> {
+    let mut conn = establish_connection()?;
+    load_series_volumes(&mut conn, name)
+}
+
+pub(crate) fn load_series_volumes(conn: &mut SqliteConnection, name: &str) -> Result<Vec<Book>, AppError> {
+    books::table
+        .filter(books::series_name.eq(name))
+        .filter(books::deleted_at.is_null())
+        .order((
+            books::volume_number.is_null(),
+            books::volume_number.asc(),
+            books::sort_key.asc(),
+            books::title.asc(),
+        ))
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load series: {}", e),
+            )
+        })
+}
+
+/// Get every series with its volume count and reading progress, ordered by name
+pub fn get_all_series() -> Result<Vec<SeriesSummary>, AppError> {
+    let mut conn = establish_connection()?;
+    load_series_summaries(&mut conn)
+}
+
+pub(crate) fn load_series_summaries(conn: &mut SqliteConnection) -> Result<Vec<SeriesSummary>, AppError> {
+    let volumes: Vec<Book> = books::table
+        .filter(books::series_name.is_not_null())
+        .filter(books::deleted_at.is_null())
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load series: {}", e),
+            )
+        })?;
+
+    let mut by_name: std::collections::BTreeMap<String, SeriesSummary> = std::collections::BTreeMap::new();
+    for book in volumes {
+        let Some(name) = book.series_name.clone() else {
+            continue;
+        };
+        let summary = by_name.entry(name.clone()).or_insert_with(|| SeriesSummary {
+            name,
+            volume_count: 0,
+            completed_volumes: 0,
+            pages_read: 0,
+            total_pages: 0,
+            progress: 0.0,
+        });
+
+        // Unread volumes sit on page 0 without having been read
+        let pages_read = match book.status() {
+            ReadingStatus::Completed => book.total_pages,
+            ReadingStatus::Unread => 0,
+            _ => (book.current_page + 1).min(book.total_pages),
+        };
+        summary.volume_count += 1;
+        if book.status() == ReadingStatus::Completed {
+            summary.completed_volumes += 1;
+        }
+        summary.pages_read += pages_read.max(0) as i64;
+        summary.total_pages += book.total_pages.max(0) as i64;
+    }
+
+    Ok(by_name
+        .into_values()
+        .map(|mut summary| {
+            if summary.total_pages > 0 {
+                summary.progress = summary.pages_read as f32 / summary.total_pages as f32 * 100.0;
+            }
+            summary
+        })
+        .collect())
+}
+
+// ============================================================================
+// BOOK SETTINGS
+// ============================================================================
+
+/// Get book settings by book ID
+pub fn get_book_settings(book_id: i32) -> Result<Option<BookSettings>, AppError> {
+    debug!("Fetching settings for book {}", book_id);
+    let mut conn = establish_connection()?;
+
+    book_settings::table
+        .filter(book_settings::book_id.eq(book_id))
+        .filter(book_settings::deleted_at.is_null())
+        .select(BookSettings::as_select())
+        .first(&mut conn)
+        .optional()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load book settings: {}", e),
+            )
+        })
+}
+
+/// Update book settings (creates if not exists)
+pub fn update_book_settings(
+    book_id: i32,
+    reading_direction: Option<Option<String>>,
+    page_display_mode: Option<Option<String>>,
+    image_fit_mode: Option<Option<String>>,
+    sync_progress: Option<Option<bool>>,
+    reader_background: Option<Option<String>>,
+) -> Result<BookSettings, AppError> {
+    info!("Updating settings for book {}", book_id);
+    let mut conn = establish_connection()?;
+
+    // Check if settings exist
+    let existing: Option<BookSettings> = book_settings::table
+        .filter(book_settings::book_id.eq(book_id))
+        .filter(book_settings::deleted_at.is_null())
+        .select(BookSettings::as_select())
+        .first(&mut conn)
+        .optional()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to check book settings: {}", e),
+            )
+        })?;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    if let Some(_settings) = existing {
+        // Update existing settings
+        let updates = UpdateBookSettings {
+            reading_direction,
+            page_display_mode,
+            image_fit_mode,
+            sync_progress,
+            updated_at: Some(now),
+            reader_background,
+        };
+
+        diesel::update(book_settings::table.filter(book_settings::book_id.eq(book_id)))
+            .set(&updates)
+            .execute(&mut conn)
+            .map_err(|e| {
+                error!("Failed to update book settings for {}: {}", book_id, e);
+                AppError::new(
+                    ErrorCode::DatabaseQueryFailed,
+                    format!("Failed to update book settings: {}", e),
+                )
+            })?;
+    } else {
+        // Create new settings
+        let new_settings = NewBookSettings {
+            book_id,
+            reading_direction: reading_direction.flatten(),
+            page_display_mode: page_display_mode.flatten(),
+            image_fit_mode: image_fit_mode.flatten(),
+            sync_progress: sync_progress.flatten(),
+            reader_background: reader_background.flatten(),
+            uuid: Some(uuid::Uuid::new_v4().to_string()),
+        };
+
+        diesel::insert_into(book_settings::table)
+            .values(&new_settings)
+            .execute(&mut conn)
+            .map_err(|e| {
+                error!("Failed to create book settings for {}: {}", book_id, e);
+                AppError::new(
+                    ErrorCode::DatabaseQueryFailed,
+                    format!("Failed to create book settings: {}", e),
+                )
+            })?;
+    }
+
+    // Return the updated settings
+    book_settings::table
+        .filter(book_settings::book_id.eq(book_id))
+        .select(BookSettings::as_select())
+        .first(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to retrieve book settings: {}", e),
+            )
+        })
+}
+
+/// Set the reading direction of every book in a collection, creating settings where absent
+///
+/// Returns the number of books updated.
+pub fn set_collection_reading_direction(collection_id: i32, direction: &str) -> Result<usize, AppError> {
+    info!("Setting reading direction {} for collection {}", direction, collection_id);
+    let mut conn = establish_connection()?;
+    apply_collection_reading_direction(&mut conn, collection_id, direction)
+}
+
+pub(crate) fn apply_collection_reading_direction(
+    conn: &mut SqliteConnection,
+    collection_id: i32,
+    direction: &str,
+) -> Result<usize, AppError> {
+    let direction = ReadingDirection::from_str(direction)
+        .ok_or_else(|| AppError::invalid_input(format!("Unknown reading direction: {}", direction)))?;
+
+    let collection_exists: i64 = collections::table
+        .find(collection_id)
+        .filter(collections::deleted_at.is_null())
+        .count()
+        .get_result(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load collection: {}", e),
+            )
+        })?;
+    if collection_exists == 0 {
+        return Err(AppError::invalid_input(format!("Collection {} not found", collection_id)));
+    }
+
+    conn.transaction(|conn| {
+        let book_ids: Vec<i32> = book_collections::table
+            .inner_join(books::table)
+            .filter(book_collections::collection_id.eq(collection_id))
+            .filter(book_collections::deleted_at.is_null())
+            .filter(books::deleted_at.is_null())
+            .select(books::id)
+            .load(conn)?;
+
+        let now = chrono::Utc::now().naive_utc();
+        for &book_id in &book_ids {
+            // book_id is unique, so a soft-deleted row is revived rather than duplicated
+            let existing: Option<BookSettings> = book_settings::table
+                .filter(book_settings::book_id.eq(book_id))
+                .select(BookSettings::as_select())
+                .first(conn)
+                .optional()?;
+
+            match existing {
+                Some(settings) if settings.deleted_at.is_none() => {
+                    diesel::update(book_settings::table.find(settings.id))
+                        .set((
+                            book_settings::reading_direction.eq(direction.as_str()),
+                            book_settings::updated_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                }
+                Some(settings) => {
+                    diesel::update(book_settings::table.find(settings.id))
+                        .set((
+                            book_settings::reading_direction.eq(direction.as_str()),
+                            book_settings::page_display_mode.eq(None::<String>),
+                            book_settings::image_fit_mode.eq(None::<String>),
+                            book_settings::sync_progress.eq(None::<bool>),
+                            book_settings::reader_background.eq(None::<String>),
+                            book_settings::updated_at.eq(now),
+                            book_settings::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                        ))
+                        .execute(conn)?;
+                }
+                None => {
+                    diesel::insert_into(book_settings::table)
+                        .values(&NewBookSettings {
+                            book_id,
+                            reading_direction: Some(direction.as_str().to_string()),
+                            page_display_mode: None,
+                            image_fit_mode: None,
+                            sync_progress: None,
+                            reader_background: None,
+                            uuid: Some(uuid::Uuid::new_v4().to_string()),
+                        })
+                        .execute(conn)?;
+                }
+            }
+        }
+
+        Ok(book_ids.len())
+    })
+    .map_err(|e: diesel::result::Error| {
+        error!("Failed to set reading direction for collection {}: {}", collection_id, e);
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to update book settings: {}", e),
+        )
+    })
+}
+
+// ============================================================================
+// BOOKMARKS
+// ============================================================================
+
+/// Create a new bookmark
+pub fn create_bookmark(new_bookmark: NewBookmark) -> Result<Bookmark, AppError> {
+    info!(
+        "Creating bookmark '{}' for book {} at page {}",
+        new_bookmark.name, new_bookmark.book_id, new_bookmark.page
+    );
+    insert_bookmark(&mut *establish_connection()?, new_bookmark)
+}
+
+pub(crate) fn insert_bookmark(conn: &mut SqliteConnection, new_bookmark: NewBookmark) -> Result<Bookmark, AppError> {
+    diesel::insert_into(bookmarks::table)
+        .values(&new_bookmark)
+        .returning(Bookmark::as_returning())
+        .get_result(conn)
+        .map(|bookmark: Bookmark| {
+            info!(
+                "Bookmark created: {} (ID: {})",
+                bookmark.name, bookmark.id
+            );
+            bookmark
+        })
+        .map_err(|e| {
+            error!("Failed to create bookmark: {}", e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to create bookmark: {}", e),
+            )
+        })
+}
+
+/// Get all bookmarks for a book (excludes soft-deleted)
+pub fn get_bookmarks_for_book(book_id: i32) -> Result<Vec<Bookmark>, AppError> {
+    debug!("Fetching bookmarks for book {}", book_id);
+    load_bookmarks(&mut *establish_connection()?, book_id)
+}
+
+pub(crate) fn load_bookmarks(conn: &mut SqliteConnection, book_id: i32) -> Result<Vec<Bookmark>, AppError> {
+    bookmarks::table
+        .filter(bookmarks::book_id.eq(book_id))
+        .filter(bookmarks::deleted_at.is_null())
+        .order(bookmarks::page.asc())
+        .select(Bookmark::as_select())
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load bookmarks: {}", e),
+            )
+        })
+}
+
+/// Get a single bookmark by ID
+pub fn get_bookmark_by_id(bookmark_id: i32) -> Result<Bookmark, AppError> {
+    let mut conn = establish_connection()?;
+
+    bookmarks::table
         .find(bookmark_id)
         .filter(bookmarks::deleted_at.is_null())
         .select(Bookmark::as_select())
@@ -1243,59 +3732,1537 @@ pub fn get_bookmark_by_id(bookmark_id: i32) -> Result<Bookmark, AppError> {
         .map_err(|e| {
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to find bookmark: {}", e),
+                format!("Failed to find bookmark: {}", e),
+            )
+        })
+}
+
+/// Update a bookmark
+pub fn update_bookmark(
+    bookmark_id: i32,
+    name: String,
+    description: Option<String>,
+) -> Result<Bookmark, AppError> {
+    info!("Updating bookmark {}", bookmark_id);
+    let mut conn = establish_connection()?;
+
+    let now = chrono::Utc::now().naive_utc();
+    diesel::update(bookmarks::table.find(bookmark_id))
+        .set((
+            bookmarks::name.eq(&name),
+            bookmarks::description.eq(&description),
+            bookmarks::updated_at.eq(Some(now)),
+        ))
+        .execute(&mut conn)
+        .map_err(|e| {
+            error!("Failed to update bookmark {}: {}", bookmark_id, e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to update bookmark: {}", e),
+            )
+        })?;
+
+    get_bookmark_by_id(bookmark_id)
+}
+
+/// Delete a bookmark (soft-delete)
+pub fn delete_bookmark(bookmark_id: i32) -> Result<(), AppError> {
+    info!("Deleting bookmark {}", bookmark_id);
+    let mut conn = establish_connection()?;
+
+    let now = chrono::Utc::now().naive_utc();
+    diesel::update(bookmarks::table.find(bookmark_id))
+        .set((
+            bookmarks::deleted_at.eq(Some(now)),
+            bookmarks::updated_at.eq(Some(now)),
+        ))
+        .execute(&mut conn)
+        .map_err(|e| {
+            error!("Failed to delete bookmark {}: {}", bookmark_id, e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to delete bookmark: {}", e),
+            )
+        })?;
+
+    info!("Bookmark {} deleted successfully", bookmark_id);
+    Ok(())
+}
+
+/// Export a book's bookmarks to a JSON file keyed by the book's content hash
+pub fn export_bookmarks(book_id: i32, path: &Path) -> Result<usize, AppError> {
+    info!("Exporting bookmarks for book {} to {:?}", book_id, path);
+    write_bookmark_export(&mut *establish_connection()?, book_id, path)
+}
+
+pub(crate) fn write_bookmark_export(
+    conn: &mut SqliteConnection,
+    book_id: i32,
+    path: &Path,
+) -> Result<usize, AppError> {
+    let book: Book = books::table
+        .find(book_id)
+        .select(Book::as_select())
+        .first(conn)
+        .optional()
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to find book: {}", e),
+            )
+        })?
+        .ok_or_else(|| AppError::invalid_input(format!("Book {} not found", book_id)))?;
+
+    // Other installs find the book by its content hash; a fast-import file hash won't match
+    let file_hash = book
+        .file_hash
+        .clone()
+        .filter(|_| book.hash_scheme() == HashScheme::Content)
+        .ok_or_else(|| AppError::invalid_input("book has no content hash, bookmarks cannot be shared"))?;
+
+    let bookmarks: Vec<ExportedBookmark> = load_bookmarks(conn, book_id)?
+        .into_iter()
+        .map(|b| ExportedBookmark {
+            name: b.name,
+            page: b.page,
+            description: b.description,
+        })
+        .collect();
+    let count = bookmarks.len();
+
+    let export = BookmarkExport {
+        version: BookmarkExport::CURRENT_VERSION,
+        file_hash,
+        title: book.title,
+        bookmarks,
+    };
+
+    let json = serde_json::to_string_pretty(&export).map_err(AppError::serialization_failed)?;
+    fs::write(path, json).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to write bookmark file: {}", e),
+        )
+    })?;
+
+    info!("Exported {} bookmarks", count);
+    Ok(count)
+}
+
+/// Import bookmarks from an export file into the local book with the same content hash
+///
+/// Bookmarks already present (same page and name) or pointing past the local
+/// book's last page are skipped.
+pub fn import_bookmarks(path: &Path) -> Result<BookmarkImportResult, AppError> {
+    info!("Importing bookmarks from {:?}", path);
+    import_bookmark_file(&mut *establish_connection()?, path)
+}
+
+pub(crate) fn import_bookmark_file(
+    conn: &mut SqliteConnection,
+    path: &Path,
+) -> Result<BookmarkImportResult, AppError> {
+    let json = fs::read_to_string(path).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to read bookmark file: {}", e),
+        )
+    })?;
+    let export: BookmarkExport =
+        serde_json::from_str(&json).map_err(AppError::config_parse_failed)?;
+
+    let book = lookup_book_by_hash(conn, &export.file_hash, HashScheme::Content)?.ok_or_else(|| {
+        AppError::invalid_input(format!("no book in the library matches '{}'", export.title))
+    })?;
+
+    let existing = load_bookmarks(conn, book.id)?;
+    let mut imported = Vec::new();
+    let mut skipped = 0;
+
+    for entry in export.bookmarks {
+        let duplicate = existing
+            .iter()
+            .chain(imported.iter())
+            .any(|b: &Bookmark| b.page == entry.page && b.name == entry.name);
+
+        if duplicate || entry.page < 0 || entry.page >= book.total_pages {
+            skipped += 1;
+            continue;
+        }
+
+        let bookmark = insert_bookmark(conn, NewBookmark {
+            book_id: book.id,
+            name: entry.name,
+            description: entry.description,
+            page: entry.page,
+            uuid: Some(uuid::Uuid::new_v4().to_string()),
+        })?;
+        imported.push(bookmark);
+    }
+
+    info!(
+        "Imported {} bookmarks into book {} ({} skipped)",
+        imported.len(),
+        book.id,
+        skipped
+    );
+
+    Ok(BookmarkImportResult {
+        book_id: book.id,
+        imported,
+        skipped,
+    })
+}
+
+// ============================================================================
+// READING QUEUE
+// ============================================================================
+
+/// Append a book to the end of the reading queue
+///
+/// A book already in the queue keeps its place. A previously removed entry is
+/// revived so its UUID (and sync history) is reused.
+pub fn add_to_queue(book_id: i32) -> Result<QueueEntry, AppError> {
+    info!("Adding book {} to reading queue", book_id);
+    let mut conn = establish_connection()?;
+    enqueue(&mut conn, book_id)
+}
+
+/// Remove a book from the reading queue (soft-delete)
+pub fn remove_from_queue(book_id: i32) -> Result<(), AppError> {
+    info!("Removing book {} from reading queue", book_id);
+    let mut conn = establish_connection()?;
+    dequeue(&mut conn, book_id)
+}
+
+/// Reorder the reading queue to follow `book_ids`
+///
+/// Queued books missing from the list keep their relative order after the listed ones.
+pub fn reorder_queue(book_ids: Vec<i32>) -> Result<Vec<QueuedBook>, AppError> {
+    info!("Reordering reading queue: {:?}", book_ids);
+    let mut conn = establish_connection()?;
+    reorder(&mut conn, &book_ids)?;
+    load_queue(&mut conn)
+}
+
+/// Get the reading queue in order, skipping books that have been deleted
+pub fn get_queue() -> Result<Vec<QueuedBook>, AppError> {
+    debug!("Fetching reading queue");
+    let mut conn = establish_connection()?;
+    load_queue(&mut conn)
+}
+
+/// Connection-level enqueue used by `add_to_queue`
+pub(crate) fn enqueue(conn: &mut SqliteConnection, book_id: i32) -> Result<QueueEntry, AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    conn.transaction(|conn| {
+        let existing: Option<QueueEntry> = reading_queue::table
+            .filter(reading_queue::book_id.eq(book_id))
+            .order(reading_queue::deleted_at.is_null().desc())
+            .select(QueueEntry::as_select())
+            .first(conn)
+            .optional()?;
+
+        if let Some(entry) = &existing {
+            if entry.deleted_at.is_none() {
+                return Ok(entry.clone());
+            }
+        }
+
+        let last: Option<i32> = reading_queue::table
+            .filter(reading_queue::deleted_at.is_null())
+            .select(diesel::dsl::max(reading_queue::position))
+            .first(conn)?;
+        let position = last.map_or(0, |p| p + 1);
+
+        match existing {
+            Some(entry) => diesel::update(reading_queue::table.find(entry.id))
+                .set((
+                    reading_queue::position.eq(position),
+                    reading_queue::added_at.eq(now),
+                    reading_queue::updated_at.eq(now),
+                    reading_queue::deleted_at.eq(None::<chrono::NaiveDateTime>),
+                ))
+                .returning(QueueEntry::as_returning())
+                .get_result(conn),
+            None => diesel::insert_into(reading_queue::table)
+                .values(&NewQueueEntry {
+                    book_id,
+                    position,
+                    uuid: Some(uuid::Uuid::new_v4().to_string()),
+                })
+                .returning(QueueEntry::as_returning())
+                .get_result(conn),
+        }
+    })
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to add book to reading queue: {}", e),
+        )
+    })
+}
+
+/// Connection-level dequeue used by `remove_from_queue`
+pub(crate) fn dequeue(conn: &mut SqliteConnection, book_id: i32) -> Result<(), AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    diesel::update(
+        reading_queue::table
+            .filter(reading_queue::book_id.eq(book_id))
+            .filter(reading_queue::deleted_at.is_null()),
+    )
+    .set((
+        reading_queue::deleted_at.eq(Some(now)),
+        reading_queue::updated_at.eq(now),
+    ))
+    .execute(conn)
+    .map_err(|e| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to remove book from reading queue: {}", e),
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Connection-level reorder used by `reorder_queue`
+///
+/// Positions are rewritten as 0..n; only entries whose position changes get a new
+/// `updated_at`, so an unchanged entry doesn't win a sync merge it had no part in.
+pub(crate) fn reorder(conn: &mut SqliteConnection, book_ids: &[i32]) -> Result<(), AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let entries: Vec<QueueEntry> = reading_queue::table
+        .filter(reading_queue::deleted_at.is_null())
+        .order((reading_queue::position.asc(), reading_queue::added_at.asc()))
+        .select(QueueEntry::as_select())
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load reading queue: {}", e),
+            )
+        })?;
+
+    if let Some(unknown) = book_ids
+        .iter()
+        .find(|id| !entries.iter().any(|entry| entry.book_id == **id))
+    {
+        return Err(AppError::invalid_input(format!(
+            "Book {} is not in the reading queue",
+            unknown
+        )));
+    }
+    if let Some((_, repeated)) = book_ids
+        .iter()
+        .enumerate()
+        .find(|(index, id)| book_ids[..*index].contains(id))
+    {
+        return Err(AppError::invalid_input(format!(
+            "Book {} is listed more than once",
+            repeated
+        )));
+    }
+
+    let listed = book_ids
+        .iter()
+        .filter_map(|id| entries.iter().find(|entry| entry.book_id == *id));
+    let rest = entries.iter().filter(|entry| !book_ids.contains(&entry.book_id));
+
+    conn.transaction(|conn| {
+        for (position, entry) in listed.chain(rest).enumerate() {
+            let position = position as i32;
+            if entry.position == position {
+                continue;
+            }
+            diesel::update(reading_queue::table.find(entry.id))
+                .set((
+                    reading_queue::position.eq(position),
+                    reading_queue::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    })
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to reorder reading queue: {}", e),
+        )
+    })
+}
+
+/// Connection-level queue listing used by `get_queue`
+pub(crate) fn load_queue(conn: &mut SqliteConnection) -> Result<Vec<QueuedBook>, AppError> {
+    reading_queue::table
+        .inner_join(books::table)
+        .filter(reading_queue::deleted_at.is_null())
+        .filter(books::deleted_at.is_null())
+        .order((reading_queue::position.asc(), reading_queue::added_at.asc()))
+        .select((QueueEntry::as_select(), Book::as_select()))
+        .load::<(QueueEntry, Book)>(conn)
+        .map(|rows| {
+            rows.into_iter()
+                .map(|(entry, book)| QueuedBook {
+                    position: entry.position,
+                    added_at: entry.added_at,
+                    book,
+                })
+                .collect()
+        })
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load reading queue: {}", e),
+            )
+        })
+}
+
+// ============================================================================
+// READING ACTIVITY
+// ============================================================================
+
+/// Longest period `get_activity_by_day` covers
+const MAX_ACTIVITY_DAYS: u32 = 366 * 5;
+
+/// UTC offsets range from -12:00 to +14:00
+const UTC_OFFSET_RANGE_MINUTES: std::ops::RangeInclusive<i32> = -12 * 60..=14 * 60;
+
+/// Reading activity per calendar day for the last `days` days, oldest first
+///
+/// Days are bucketed in the caller's local time, given as minutes east of UTC.
+/// Every day of the range is included, days without activity with a count of 0.
+pub fn get_activity_by_day(days: u32, utc_offset_minutes: i32) -> Result<Vec<DayActivity>, AppError> {
+    let mut conn = establish_connection()?;
+    activity_by_day(&mut conn, chrono::Utc::now().naive_utc(), days, utc_offset_minutes)
+}
+
+/// `get_activity_by_day` relative to `now` (UTC)
+///
+/// Each live book counts once, on the day it was last read.
+pub(crate) fn activity_by_day(
+    conn: &mut SqliteConnection,
+    now: chrono::NaiveDateTime,
+    days: u32,
+    utc_offset_minutes: i32,
+) -> Result<Vec<DayActivity>, AppError> {
+    let days_range = LocalDays::ending_at(now, days, utc_offset_minutes)?;
+
+    let read_times: Vec<Option<chrono::NaiveDateTime>> = books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::last_read_at.ge(days_range.since()))
+        .select(books::last_read_at)
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load reading activity: {}", e),
+            )
+        })?;
+
+    let mut activity: Vec<DayActivity> = days_range
+        .dates()
+        .map(|date| DayActivity { date, count: 0 })
+        .collect();
+    for read_at in read_times.into_iter().flatten() {
+        // Reads stamped in the future (clock skew between devices) fall outside the range
+        if let Some(day) = days_range.index_of(read_at).and_then(|i| activity.get_mut(i)) {
+            day.count += 1;
+        }
+    }
+
+    Ok(activity)
+}
+
+/// The last `days` calendar days in a local time zone, up to and including today
+struct LocalDays {
+    first_day: chrono::NaiveDate,
+    days: u32,
+    offset: chrono::Duration,
+}
+
+impl LocalDays {
+    /// `utc_offset_minutes` is the time zone's offset east of UTC
+    fn ending_at(now: chrono::NaiveDateTime, days: u32, utc_offset_minutes: i32) -> Result<Self, AppError> {
+        if days == 0 || days > MAX_ACTIVITY_DAYS {
+            return Err(AppError::invalid_input(format!(
+                "days must be between 1 and {}",
+                MAX_ACTIVITY_DAYS
+            )));
+        }
+        if !UTC_OFFSET_RANGE_MINUTES.contains(&utc_offset_minutes) {
+            return Err(AppError::invalid_input("utc_offset_minutes must be between -720 and 840"));
+        }
+
+        let offset = chrono::Duration::minutes(utc_offset_minutes as i64);
+        let today = (now + offset).date();
+        Ok(Self {
+            first_day: today - chrono::Duration::days(days as i64 - 1),
+            days,
+            offset,
+        })
+    }
+
+    /// Start of the first day, in UTC
+    fn since(&self) -> chrono::NaiveDateTime {
+        self.first_day.and_time(chrono::NaiveTime::MIN) - self.offset
+    }
+
+    fn dates(&self) -> impl Iterator<Item = chrono::NaiveDate> {
+        self.first_day.iter_days().take(self.days as usize)
+    }
+
+    /// Position of the local day a UTC time falls on, if it is in the range
+    fn index_of(&self, at: chrono::NaiveDateTime) -> Option<usize> {
+        let index = usize::try_from(((at + self.offset).date() - self.first_day).num_days()).ok()?;
+        (index < self.days as usize).then_some(index)
+    }
+}
+
+/// Days covered by `get_reading_stats`
+pub const READING_STATS_DAYS: u32 = 30;
+
+/// Record a reader session, from opening the book to closing the reader
+pub fn record_reading_session(new_session: NewReadingSession) -> Result<ReadingSession, AppError> {
+    debug!(
+        "Recording reading session for book {}: pages {}-{}",
+        new_session.book_id, new_session.page_from, new_session.page_to
+    );
+    let mut conn = establish_connection()?;
+    insert_reading_session(&mut conn, new_session)
+}
+
+pub(crate) fn insert_reading_session(
+    conn: &mut SqliteConnection,
+    new_session: NewReadingSession,
+) -> Result<ReadingSession, AppError> {
+    if new_session.ended_at < new_session.started_at {
+        return Err(AppError::invalid_input("A reading session can't end before it starts"));
+    }
+    if new_session.page_from < 0 || new_session.page_to < 0 {
+        return Err(AppError::invalid_input("Session pages must not be negative"));
+    }
+
+    diesel::insert_into(reading_history::table)
+        .values(&new_session)
+        .returning(ReadingSession::as_returning())
+        .get_result(conn)
+        .map_err(|e| {
+            error!("Failed to record reading session for book {}: {}", new_session.book_id, e);
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to record reading session: {}", e),
+            )
+        })
+}
+
+/// Get a book's reader sessions, most recent first
+pub fn get_reading_history(book_id: i32) -> Result<Vec<ReadingSession>, AppError> {
+    let mut conn = establish_connection()?;
+
+    reading_history::table
+        .filter(reading_history::book_id.eq(book_id))
+        .order(reading_history::started_at.desc())
+        .select(ReadingSession::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load reading history: {}", e),
+            )
+        })
+}
+
+/// Pages and minutes read per day over the last `READING_STATS_DAYS` days, oldest first
+///
+/// Days are in the caller's local time (minutes east of UTC). Overlapping sessions
+/// of a book are merged as in `get_pages_read`, and count towards the day the first
+/// of them started on.
+pub fn get_reading_stats(utc_offset_minutes: i32) -> Result<Vec<DailyReadingStats>, AppError> {
+    let mut conn = establish_connection()?;
+    reading_stats(&mut conn, chrono::Utc::now().naive_utc(), utc_offset_minutes)
+}
+
+pub(crate) fn reading_stats(
+    conn: &mut SqliteConnection,
+    now: chrono::NaiveDateTime,
+    utc_offset_minutes: i32,
+) -> Result<Vec<DailyReadingStats>, AppError> {
+    let days_range = LocalDays::ending_at(now, READING_STATS_DAYS, utc_offset_minutes)?;
+
+    let sessions: Vec<(ReadingSession, ())> = reading_history::table
+        .filter(reading_history::started_at.ge(days_range.since()))
+        .order((reading_history::book_id, reading_history::started_at))
+        .select(ReadingSession::as_select())
+        .load(conn)
+        .map(|sessions| sessions.into_iter().map(|session| (session, ())).collect())
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load reading stats: {}", e),
+            )
+        })?;
+
+    let mut stats: Vec<DailyReadingStats> = days_range
+        .dates()
+        .map(|date| DailyReadingStats { date, pages_read: 0, minutes_read: 0.0 })
+        .collect();
+    for group in session_groups(sessions) {
+        if let Some(day) = days_range.index_of(group.started_at).and_then(|i| stats.get_mut(i)) {
+            day.pages_read += group.pages;
+            day.minutes_read += (group.ended_at - group.started_at).num_seconds() as f64 / 60.0;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Pages read in sessions started at or after `since` (UTC)
+pub fn get_pages_read(since: chrono::NaiveDateTime) -> Result<PagesRead, AppError> {
+    let mut conn = establish_connection()?;
+    pages_read(&mut conn, since)
+}
+
+/// `get_pages_read` on a connection
+///
+/// Only forward progress counts. Sessions of the same book that overlap in time
+/// (e.g. a reader left open on two devices) count each page once; reading the same
+/// pages again in a later session counts them again.
+pub(crate) fn pages_read(
+    conn: &mut SqliteConnection,
+    since: chrono::NaiveDateTime,
+) -> Result<PagesRead, AppError> {
+    let sessions: Vec<(ReadingSession, String)> = reading_history::table
+        .inner_join(books::table)
+        .filter(reading_history::started_at.ge(since))
+        .order((reading_history::book_id, reading_history::started_at))
+        .select((ReadingSession::as_select(), books::reading_status))
+        .load(conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load reading sessions: {}", e),
+            )
+        })?;
+
+    let mut result = PagesRead::default();
+    for group in session_groups(sessions) {
+        if group.pages > 0 {
+            result.total += group.pages;
+            *result.by_status.entry(group.data).or_insert(0) += group.pages;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reader sessions of one book that overlap in time, merged into one
+struct SessionGroup<T> {
+    started_at: chrono::NaiveDateTime,
+    ended_at: chrono::NaiveDateTime,
+    /// Pages moved forward, counting pages shared by the sessions once
+    pages: i64,
+    /// Data of the group's first session
+    data: T,
+}
+
+/// Merge sessions, ordered by book then start, into groups of overlapping sessions
+fn session_groups<T>(sessions: Vec<(ReadingSession, T)>) -> Vec<SessionGroup<T>> {
+    let mut groups: Vec<(i32, Vec<(i32, i32)>, SessionGroup<T>)> = Vec::new();
+    for (session, data) in sessions {
+        let range = (session.page_to > session.page_from).then_some((session.page_from, session.page_to));
+        match groups.last_mut() {
+            Some((book_id, ranges, group))
+                if *book_id == session.book_id && session.started_at <= group.ended_at =>
+            {
+                ranges.extend(range);
+                group.ended_at = group.ended_at.max(session.ended_at);
+            }
+            _ => groups.push((
+                session.book_id,
+                range.into_iter().collect(),
+                SessionGroup { started_at: session.started_at, ended_at: session.ended_at, pages: 0, data },
+            )),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(_, mut ranges, group)| SessionGroup { pages: union_length(&mut ranges), ..group })
+        .collect()
+}
+
+/// Total length of a set of half-open page ranges, counting shared pages once
+fn union_length(ranges: &mut [(i32, i32)]) -> i64 {
+    ranges.sort_unstable();
+    let mut total = 0i64;
+    let mut covered_to = i32::MIN;
+    for &(from, to) in ranges.iter() {
+        let from = from.max(covered_to);
+        if to > from {
+            total += (to - from) as i64;
+            covered_to = to;
+        }
+    }
+    total
+}
+
+// ============================================================================
+// MAINTENANCE
+// ============================================================================
+
+/// Check for orphaned junction rows, bookmarks and book settings, optionally removing them
+pub fn verify_library_consistency(cleanup: bool) -> Result<LibraryConsistencyReport, AppError> {
+    info!("Verifying library consistency (cleanup: {})", cleanup);
+    let mut conn = establish_connection()?;
+
+    let report = check_library_consistency(&mut conn, cleanup)?;
+
+    if report.is_consistent() {
+        info!("Library is consistent");
+    } else {
+        warn!(
+            "Library inconsistencies: {} book-collection links, {} bookmarks, {} book settings{}",
+            report.orphaned_book_collections.len(),
+            report.orphaned_bookmarks.len(),
+            report.orphaned_book_settings.len(),
+            if report.cleaned_up { " (cleaned up)" } else { "" }
+        );
+    }
+
+    Ok(report)
+}
+
+/// Fix books whose reading position lies outside their pages
+///
+/// Pages are 0-indexed, so valid positions run from 0 to `total_pages - 1`.
+/// Returns the number of books fixed.
+pub fn clamp_invalid_progress() -> Result<usize, AppError> {
+    let mut conn = establish_connection()?;
+    let fixed = clamp_progress(&mut conn)?;
+    if fixed > 0 {
+        warn!("Clamped out-of-range reading position of {} book(s)", fixed);
+    }
+    Ok(fixed)
+}
+
+/// Fill in `sort_key` for books that don't have one yet (e.g. rows from before the column)
+///
+/// Runs at startup; returns the number of books updated. `updated_at` is left alone
+/// since the key is derived from the title and every device computes the same one.
+pub(crate) fn backfill_sort_keys(conn: &mut SqliteConnection) -> Result<usize, AppError> {
+    conn.transaction(|conn| {
+        let missing: Vec<(i32, String)> = books::table
+            .filter(books::sort_key.is_null())
+            .select((books::id, books::title))
+            .load(conn)?;
+
+        for (id, title) in &missing {
+            diesel::update(books::table.find(id))
+                .set(books::sort_key.eq(book_sort_key(title)))
+                .execute(conn)?;
+        }
+        Ok(missing.len())
+    })
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to backfill sort keys: {}", e),
+        )
+    })
+}
+
+/// Connection-level clamp used by `clamp_invalid_progress`
+///
+/// Fixed books get a new `updated_at` so the correction syncs to other devices.
+pub(crate) fn clamp_progress(conn: &mut SqliteConnection) -> Result<usize, AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    conn.transaction(|conn| {
+        let below = diesel::update(
+            books::table
+                .filter(books::deleted_at.is_null())
+                .filter(books::current_page.lt(0)),
+        )
+        .set((books::current_page.eq(0), books::updated_at.eq(now)))
+        .execute(conn)?;
+
+        let above = diesel::update(
+            books::table
+                .filter(books::deleted_at.is_null())
+                .filter(books::total_pages.gt(0))
+                .filter(books::current_page.ge(books::total_pages)),
+        )
+        .set((
+            books::current_page.eq(books::total_pages - 1),
+            books::updated_at.eq(now),
+        ))
+        .execute(conn)?;
+
+        Ok(below + above)
+    })
+    .map_err(|e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to clamp reading progress: {}", e),
+        )
+    })
+}
+
+/// Connection-level consistency check used by `verify_library_consistency`
+///
+/// Junction rows pointing at soft-deleted parents are soft-deleted so the removal
+/// syncs; rows whose parent is gone entirely are hard-deleted. Cleanup runs in
+/// a single transaction.
+pub(crate) fn check_library_consistency(
+    conn: &mut SqliteConnection,
+    cleanup: bool,
+) -> Result<LibraryConsistencyReport, AppError> {
+    use diesel::dsl::not;
+
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to verify library consistency: {}", e),
+        )
+    };
+
+    let orphaned_book_collections: Vec<i32> = book_collections::table
+        .filter(book_collections::deleted_at.is_null())
+        .filter(
+            not(book_collections::book_id.eq_any(
+                books::table.filter(books::deleted_at.is_null()).select(books::id),
+            ))
+            .or(not(book_collections::collection_id.eq_any(
+                collections::table
+                    .filter(collections::deleted_at.is_null())
+                    .select(collections::id),
+            ))),
+        )
+        .select(book_collections::id)
+        .load(conn)
+        .map_err(query_error)?;
+
+    let orphaned_bookmarks: Vec<i32> = bookmarks::table
+        .filter(not(bookmarks::book_id.eq_any(books::table.select(books::id))))
+        .select(bookmarks::id)
+        .load(conn)
+        .map_err(query_error)?;
+
+    let orphaned_book_settings: Vec<i32> = book_settings::table
+        .filter(not(book_settings::book_id.eq_any(books::table.select(books::id))))
+        .select(book_settings::id)
+        .load(conn)
+        .map_err(query_error)?;
+
+    let mut report = LibraryConsistencyReport {
+        orphaned_book_collections,
+        orphaned_bookmarks,
+        orphaned_book_settings,
+        cleaned_up: false,
+    };
+
+    if !cleanup || report.is_consistent() {
+        return Ok(report);
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        // Links whose book or collection no longer exists at all
+        diesel::delete(
+            book_collections::table.filter(
+                not(book_collections::book_id.eq_any(books::table.select(books::id))).or(not(
+                    book_collections::collection_id.eq_any(collections::table.select(collections::id)),
+                )),
+            ),
+        )
+        .execute(conn)?;
+
+        // Remaining links point at soft-deleted rows
+        diesel::update(
+            book_collections::table
+                .filter(book_collections::id.eq_any(&report.orphaned_book_collections))
+                .filter(book_collections::deleted_at.is_null()),
+        )
+        .set((
+            book_collections::deleted_at.eq(Some(now)),
+            book_collections::updated_at.eq(Some(now)),
+        ))
+        .execute(conn)?;
+
+        diesel::delete(bookmarks::table.filter(bookmarks::id.eq_any(&report.orphaned_bookmarks)))
+            .execute(conn)?;
+
+        diesel::delete(
+            book_settings::table.filter(book_settings::id.eq_any(&report.orphaned_book_settings)),
+        )
+        .execute(conn)?;
+
+        Ok(())
+    })
+    .map_err(|e| {
+        error!("Failed to clean up library inconsistencies: {}", e);
+        query_error(e)
+    })?;
+
+    report.cleaned_up = true;
+    Ok(report)
+}
+
+/// Replace the file hashes left by fast import with content hashes
+pub fn backfill_content_hashes() -> Result<HashBackfillResult, AppError> {
+    let mut conn = establish_connection()?;
+    let result = backfill_hashes(&mut conn)?;
+
+    if result.hashed > 0 || result.failed > 0 || !result.duplicates.is_empty() {
+        info!(
+            "Content hash backfill: {} hashed, {} duplicate(s), {} failed",
+            result.hashed,
+            result.duplicates.len(),
+            result.failed
+        );
+    }
+    Ok(result)
+}
+
+/// Hash the content of every live local book that still has a file hash
+///
+/// A book whose content hash already belongs to another live book keeps its file
+/// hash and is reported as a duplicate rather than being removed. It is remembered
+/// as a duplicate, and only hashed again once the book it duplicates is gone.
+pub(crate) fn backfill_hashes(conn: &mut SqliteConnection) -> Result<HashBackfillResult, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to backfill content hashes: {}", e),
+        )
+    };
+
+    let pending: Vec<Book> = books::table
+        .filter(books::hash_scheme.eq(HashScheme::File.as_str()))
+        .filter(books::deleted_at.is_null())
+        .filter(books::file_path.not_like("cloud://%"))
+        .order(books::id.asc())
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(query_error)?;
+
+    let mut result = HashBackfillResult::default();
+    for book in pending {
+        if let Some(existing_id) = book.content_duplicate_of {
+            let existing_is_live = books::table
+                .find(existing_id)
+                .filter(books::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(conn)
+                .map_err(query_error)?
+                > 0;
+            if existing_is_live {
+                continue;
+            }
+        }
+
+        let password = archive_password::get(book.id);
+        let content_hash = match calculate_archive_hash_with_password(Path::new(&book.file_path), password.as_deref()) {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("Failed to hash content of {}: {}", book.title, e.message);
+                result.failed += 1;
+                continue;
+            }
+        };
+
+        let existing: Option<Book> = books::table
+            .filter(books::file_hash.eq(&content_hash))
+            .filter(books::hash_scheme.eq(HashScheme::Content.as_str()))
+            .filter(books::deleted_at.is_null())
+            .filter(books::id.ne(book.id))
+            .select(Book::as_select())
+            .first(conn)
+            .optional()
+            .map_err(query_error)?;
+        if let Some(existing) = existing {
+            warn!("Fast-imported {} duplicates {}", book.title, existing.title);
+            diesel::update(books::table.find(book.id))
+                .set(books::content_duplicate_of.eq(Some(existing.id)))
+                .execute(conn)
+                .map_err(query_error)?;
+            result.duplicates.push(HashDuplicate {
+                book_id: book.id,
+                title: book.title,
+                existing_book_id: existing.id,
+                existing_title: existing.title,
+            });
+            continue;
+        }
+
+        // Bump updated_at so sync picks up the new hash
+        diesel::update(books::table.find(book.id))
+            .set((
+                books::file_hash.eq(Some(content_hash)),
+                books::hash_scheme.eq(HashScheme::Content.as_str()),
+                books::content_duplicate_of.eq(None::<i32>),
+                books::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+            .map_err(query_error)?;
+        result.hashed += 1;
+    }
+
+    Ok(result)
+}
+
+/// Books whose file is stored on this device (not deleted, not cloud-only)
+pub fn get_local_books() -> Result<Vec<Book>, AppError> {
+    let mut conn = establish_connection()?;
+
+    books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::file_path.not_like("cloud://%"))
+        .order(books::id.asc())
+        .select(Book::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            AppError::new(
+                ErrorCode::DatabaseQueryFailed,
+                format!("Failed to load local books: {}", e),
             )
         })
 }
 
-/// Update a bookmark
-pub fn update_bookmark(
-    bookmark_id: i32,
-    name: String,
-    description: Option<String>,
-) -> Result<Bookmark, AppError> {
-    info!("Updating bookmark {}", bookmark_id);
+/// Check a book's file for damage
+///
+/// Every entry of an archive is decompressed so its CRC is checked, and the number
+/// of pages is compared with the stored page count. Manifest books are checked for
+/// missing page files instead. Cloud-only books have nothing to check.
+pub fn verify_archive(book: &Book) -> ArchiveVerification {
+    let mut verification = ArchiveVerification {
+        book_id: book.id,
+        title: book.title.clone(),
+        file_path: book.file_path.clone(),
+        problems: Vec::new(),
+    };
+
+    if book.file_path.starts_with("cloud://") {
+        return verification;
+    }
+
+    let actual_pages = if let Some(manifest_path) = manifest::manifest_path(&book.file_path) {
+        verify_manifest_pages(manifest_path, &mut verification.problems)
+    } else {
+        verify_archive_entries(Path::new(&book.file_path), &mut verification.problems)
+    };
+
+    if let Some(actual) = actual_pages {
+        if actual != book.total_pages {
+            verification.problems.push(ArchiveProblem::PageCountMismatch {
+                stored: book.total_pages,
+                actual,
+            });
+        }
+    }
+
+    verification
+}
+
+/// Check that a manifest book's page files exist, returning its page count
+fn verify_manifest_pages(manifest_path: &Path, problems: &mut Vec<ArchiveProblem>) -> Option<i32> {
+    if !manifest_path.exists() {
+        problems.push(ArchiveProblem::MissingFile);
+        return None;
+    }
+
+    match manifest::read_manifest(manifest_path) {
+        Ok(page_manifest) => {
+            let dir = manifest::manifest_dir(manifest_path);
+            for page in manifest::missing_pages(dir, &page_manifest) {
+                problems.push(ArchiveProblem::MissingPage { page });
+            }
+            Some(count_manifest_pages(&page_manifest, &page_filter::current()))
+        }
+        Err(e) => {
+            problems.push(ArchiveProblem::Unreadable { error: e.message });
+            None
+        }
+    }
+}
+
+/// Decompress every entry of an archive, returning its page count if it could be read
+fn verify_archive_entries(archive_path: &Path, problems: &mut Vec<ArchiveProblem>) -> Option<i32> {
+    if !archive_path.exists() {
+        problems.push(ArchiveProblem::MissingFile);
+        return None;
+    }
+
+    let checked = detect_archive_type(archive_path).and_then(|archive_type| match archive_type {
+        ArchiveType::Zip => verify_zip_entries(archive_path, problems),
+        #[cfg(not(target_os = "android"))]
+        ArchiveType::Rar => verify_rar_entries(archive_path, problems),
+        ArchiveType::SevenZip => verify_7z_entries(archive_path, problems),
+    });
+    if let Err(e) = checked {
+        problems.push(ArchiveProblem::Unreadable { error: e.message });
+        return None;
+    }
+
+    match count_archive_images(archive_path, &page_filter::current()) {
+        Ok(count) => Some(count),
+        Err(e) => {
+            problems.push(ArchiveProblem::Unreadable { error: e.message });
+            None
+        }
+    }
+}
+
+fn verify_zip_entries(archive_path: &Path, problems: &mut Vec<ArchiveProblem>) -> Result<(), AppError> {
+    let file = fs::File::open(archive_path)
+        .map_err(|e| AppError::new(ErrorCode::IoError, format!("Failed to open archive: {}", e)))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| {
+        AppError::new(ErrorCode::IoError, format!("Failed to read zip archive: {}", e))
+    })?;
+
+    for index in 0..archive.len() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(e) => {
+                problems.push(ArchiveProblem::CorruptEntry {
+                    entry: format!("#{}", index),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            continue;
+        }
+        // The zip reader checks the CRC once the entry has been read to the end
+        if let Err(e) = std::io::copy(&mut entry, &mut std::io::sink()) {
+            problems.push(ArchiveProblem::CorruptEntry {
+                entry: entry.name().to_string(),
+                error: e.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "android"))]
+fn verify_rar_entries(archive_path: &Path, problems: &mut Vec<ArchiveProblem>) -> Result<(), AppError> {
+    fn rar_error(action: &str, e: impl std::fmt::Display) -> AppError {
+        AppError::new(ErrorCode::IoError, format!("Failed to {}: {}", action, e))
+    }
+
+    let mut archive = unrar::Archive::new(archive_path)
+        .open_for_processing()
+        .map_err(|e| rar_error("open RAR archive", e))?;
+
+    while let Some(header) = archive.read_header().map_err(|e| rar_error("read RAR header", e))? {
+        let file_name = header.entry().filename.to_string_lossy().to_string();
+        if header.entry().is_directory() {
+            archive = header.skip().map_err(|e| rar_error("skip RAR entry", e))?;
+            continue;
+        }
+        match header.test() {
+            Ok(next) => archive = next,
+            Err(e) => {
+                // The cursor is consumed, so the entries after a bad one can't be reached
+                problems.push(ArchiveProblem::CorruptEntry {
+                    entry: file_name,
+                    error: e.to_string(),
+                });
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_7z_entries(archive_path: &Path, problems: &mut Vec<ArchiveProblem>) -> Result<(), AppError> {
+    let mut archive = open_7z(archive_path)?;
+    let mut current_entry = String::new();
+
+    let result = archive.for_each_entries(|entry, reader| {
+        current_entry = entry.name().to_string();
+        std::io::copy(reader, &mut std::io::sink())?;
+        Ok(true)
+    });
+
+    // Solid blocks can't be decoded past a damaged entry, so decoding stops at the first one
+    if let Err(e) = result {
+        problems.push(ArchiveProblem::CorruptEntry {
+            entry: current_entry,
+            error: sevenz_error("read 7z archive", e).message,
+        });
+    }
+
+    Ok(())
+}
+
+/// Compare the stored page count of every book in a collection with a recount of its file
+///
+/// Nested collections are not included. Reads every archive, so callers should run
+/// it on a blocking thread.
+pub fn audit_collection_pages(collection_id: i32) -> Result<PageAudit, AppError> {
+    get_collection_by_id(collection_id)?;
     let mut conn = establish_connection()?;
 
-    let now = chrono::Utc::now().naive_utc();
-    diesel::update(bookmarks::table.find(bookmark_id))
-        .set((
-            bookmarks::name.eq(&name),
-            bookmarks::description.eq(&description),
-            bookmarks::updated_at.eq(Some(now)),
-        ))
-        .execute(&mut conn)
+    let in_collection = book_collections::table
+        .filter(book_collections::collection_id.eq(collection_id))
+        .filter(book_collections::deleted_at.is_null())
+        .select(book_collections::book_id);
+    let books: Vec<Book> = books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::id.eq_any(in_collection))
+        .order(books::title.asc())
+        .select(Book::as_select())
+        .load(&mut conn)
         .map_err(|e| {
-            error!("Failed to update bookmark {}: {}", bookmark_id, e);
             AppError::new(
                 ErrorCode::DatabaseQueryFailed,
-                format!("Failed to update bookmark: {}", e),
+                format!("Failed to load collection books: {}", e),
             )
         })?;
 
-    get_bookmark_by_id(bookmark_id)
+    info!("Auditing page counts of {} book(s) in collection {}", books.len(), collection_id);
+    Ok(audit_pages(&books))
 }
 
-/// Delete a bookmark (soft-delete)
-pub fn delete_bookmark(bookmark_id: i32) -> Result<(), AppError> {
-    info!("Deleting bookmark {}", bookmark_id);
+/// Recount the pages of each book, collecting the books whose stored count differs
+///
+/// Cloud-only books have no local file and are skipped.
+pub(crate) fn audit_pages(books: &[Book]) -> PageAudit {
+    let mut audit = PageAudit::default();
+
+    for book in books {
+        let actual = match recount_pages(book) {
+            Ok(Some(actual)) => actual,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Can't recount pages of {}: {}", book.title, e);
+                audit.unreadable.push(PageAuditError {
+                    book_id: book.id,
+                    title: book.title.clone(),
+                    error: e.message,
+                });
+                continue;
+            }
+        };
+
+        audit.checked += 1;
+        if actual != book.total_pages {
+            audit.mismatches.push(PageCountMismatch {
+                book_id: book.id,
+                title: book.title.clone(),
+                stored_pages: book.total_pages,
+                actual_pages: actual,
+            });
+        }
+    }
+
+    audit
+}
+
+/// Count the pages a book's file holds now, or `None` for cloud-only books
+pub(crate) fn recount_pages(book: &Book) -> Result<Option<i32>, AppError> {
+    if book.file_path.starts_with("cloud://") {
+        return Ok(None);
+    }
+
+    let manifest_path = manifest::manifest_path(&book.file_path);
+    let path = manifest_path.unwrap_or(Path::new(&book.file_path));
+    if !path.exists() {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            format!("File not found: {}", path.display()),
+        ));
+    }
+
+    let filter = page_filter::current();
+    match manifest_path {
+        Some(manifest_path) => Ok(Some(count_manifest_pages(&manifest::read_manifest(manifest_path)?, &filter))),
+        None => count_archive_images(path, &filter).map(Some),
+    }
+}
+
+// ============================================================================
+// LIBRARY DIRECTORY
+// ============================================================================
+
+/// Move every book file stored under `old_dir` into `new_dir` and repoint `books.file_path`
+///
+/// Returns the number of files moved.
+pub fn move_library_files(old_dir: &Path, new_dir: &Path) -> Result<usize, AppError> {
     let mut conn = establish_connection()?;
+    relocate_library_files(&mut conn, old_dir, new_dir)
+}
 
-    let now = chrono::Utc::now().naive_utc();
-    diesel::update(bookmarks::table.find(bookmark_id))
-        .set((
-            bookmarks::deleted_at.eq(Some(now)),
-            bookmarks::updated_at.eq(Some(now)),
-        ))
-        .execute(&mut conn)
-        .map_err(|e| {
-            error!("Failed to delete bookmark {}: {}", bookmark_id, e);
-            AppError::new(
-                ErrorCode::DatabaseQueryFailed,
-                format!("Failed to delete bookmark: {}", e),
-            )
-        })?;
+/// Relocate library files on the given connection
+///
+/// Each file is moved and its row updated as one step: if the row update fails the
+/// file is moved back. Stops at the first failure, so books moved before it stay valid.
+pub(crate) fn relocate_library_files(
+    conn: &mut SqliteConnection,
+    old_dir: &Path,
+    new_dir: &Path,
+) -> Result<usize, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to move library files: {}", e),
+        )
+    };
+
+    if new_dir.starts_with(old_dir) || old_dir.starts_with(new_dir) {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            "New library directory must not be the current one or nested inside it",
+        ));
+    }
+
+    let managed: Vec<Book> = books::table
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(query_error)?
+        .into_iter()
+        .filter(|book| Path::new(&book.file_path).starts_with(old_dir))
+        .collect();
+
+    let required_bytes: u64 = managed
+        .iter()
+        .filter_map(|book| fs::metadata(&book.file_path).ok())
+        .map(|meta| meta.len())
+        .sum();
+
+    ensure_library_dir_usable(new_dir, required_bytes)?;
+    info!(
+        "Moving {} library files ({} bytes) from {:?} to {:?}",
+        managed.len(),
+        required_bytes,
+        old_dir,
+        new_dir
+    );
+
+    let mut moved = 0;
+    for book in managed {
+        let source = PathBuf::from(&book.file_path);
+        if !source.exists() {
+            warn!("Skipping missing library file: {:?}", source);
+            continue;
+        }
+
+        let relative = source.strip_prefix(old_dir).unwrap_or(&source);
+        let dest = unique_library_path(&new_dir.join(relative));
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                AppError::new(
+                    ErrorCode::IoError,
+                    format!("Failed to create directory {:?}: {}", parent, e),
+                )
+            })?;
+        }
+
+        // Close the open archive first; Windows can't move a file that is in use
+        crate::protocol::invalidate_image_cache(book.id);
+        move_file(&source, &dest)?;
+
+        let updated = diesel::update(books::table.find(book.id))
+            .set(books::file_path.eq(dest.to_string_lossy().to_string()))
+            .execute(conn);
+
+        if let Err(e) = updated {
+            error!("Failed to update path for book {}: {}", book.id, e);
+            if let Err(rollback_err) = move_file(&dest, &source) {
+                error!(
+                    "Failed to move {:?} back to {:?}: {}",
+                    dest, source, rollback_err
+                );
+            }
+            return Err(query_error(e));
+        }
+
+        debug!("Moved book {} to {:?}", book.id, dest);
+        moved += 1;
+    }
+
+    info!("Moved {} library files", moved);
+    Ok(moved)
+}
+
+/// Check that `dir` can be created, written to, and has room for `required_bytes`
+fn ensure_library_dir_usable(dir: &Path, required_bytes: u64) -> Result<(), AppError> {
+    fs::create_dir_all(dir).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to create library directory: {}", e),
+        )
+    })?;
+
+    let probe = dir.join(format!(".write_test_{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"").map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Library directory is not writable: {}", e),
+        )
+    })?;
+    let _ = fs::remove_file(&probe);
+
+    let available = fs2::available_space(dir).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to check free space: {}", e),
+        )
+    })?;
+    if available < required_bytes {
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            format!(
+                "Not enough free space: {} bytes needed, {} available",
+                required_bytes, available
+            ),
+        ));
+    }
 
-    info!("Bookmark {} deleted successfully", bookmark_id);
     Ok(())
 }
+
+/// Append a counter to the file stem until the path is free
+pub(crate) fn unique_library_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("archive")
+        .to_string();
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("cbz")
+        .to_string();
+
+    let mut counter = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{}_{}.{}", stem, counter, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Move a file, falling back to copy + delete when crossing filesystems
+fn move_file(from: &Path, to: &Path) -> Result<(), AppError> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    if let Err(e) = fs::copy(from, to) {
+        let _ = fs::remove_file(to);
+        return Err(AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to copy {:?} to {:?}: {}", from, to, e),
+        ));
+    }
+
+    fs::remove_file(from).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to remove {:?} after copying: {}", from, e),
+        )
+    })
+}
+
+/// Link cloud-only books to matching archives found in `dir`
+///
+/// Archives are hashed and any whose hash matches a `cloud://` book takes over
+/// that book's `file_path`, as if it had been downloaded from Drive.
+/// Returns the books that were linked.
+pub fn reconcile_local_files(dir: &Path) -> Result<Vec<Book>, AppError> {
+    let mut conn = establish_connection()?;
+    link_local_files(&mut conn, dir)
+}
+
+pub(crate) fn link_local_files(conn: &mut SqliteConnection, dir: &Path) -> Result<Vec<Book>, AppError> {
+    let query_error = |e: diesel::result::Error| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to reconcile local files: {}", e),
+        )
+    };
+
+    let mut cloud_books: std::collections::HashMap<String, Book> = books::table
+        .filter(books::file_path.like("cloud://%"))
+        .filter(books::deleted_at.is_null())
+        .select(Book::as_select())
+        .load(conn)
+        .map_err(query_error)?
+        .into_iter()
+        .filter_map(|book| book.file_hash.clone().map(|hash| (hash, book)))
+        .collect();
+
+    if cloud_books.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(dir).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to read directory {:?}: {}", dir, e),
+        )
+    })?;
+
+    let mut linked = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || !has_archive_extension(&path) {
+            continue;
+        }
+
+        let path_str = path.to_string_lossy().to_string();
+        let known: i64 = books::table
+            .filter(books::file_path.eq(&path_str))
+            .count()
+            .get_result(conn)
+            .map_err(query_error)?;
+        if known > 0 {
+            continue;
+        }
+
+        let hash = match calculate_archive_hash(&path) {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("Skipping {:?}: {}", path, e.message);
+                continue;
+            }
+        };
+
+        if let Some(book) = cloud_books.remove(&hash) {
+            let updated = diesel::update(books::table.find(book.id))
+                .set((
+                    books::file_path.eq(&path_str),
+                    books::archive_type.eq(None::<String>),
+                    books::updated_at.eq(chrono::Utc::now().naive_utc()),
+                ))
+                .returning(Book::as_returning())
+                .get_result(conn)
+                .map_err(query_error)?;
+
+            crate::protocol::invalidate_image_cache(updated.id);
+            info!("Linked cloud book '{}' to local file {:?}", updated.title, path);
+            linked.push(updated);
+
+            if cloud_books.is_empty() {
+                break;
+            }
+        }
+    }
+
+    Ok(linked)
+}
@@ -0,0 +1,177 @@
+//! Write-behind buffer for reading positions
+//!
+//! Page turns queue the new position here instead of writing the book row each time.
+//! A position is written once its book has been idle for `FLUSH_DELAY`, or right away
+//! through `flush_reading_positions` (reader close, app background/exit, before sync).
+
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use log::{debug, error};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::database::connection::establish_connection;
+use crate::database::operations::{clamp_page, clear_page_fractions};
+use crate::error::{AppError, ErrorCode};
+use crate::schema::books;
+
+/// How long a book must go without page turns before its position is written
+const FLUSH_DELAY: Duration = Duration::from_secs(2);
+
+/// How often the background flusher checks for idle positions
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Latest unsaved position of a book
+struct PendingPosition {
+    page: i32,
+    read_at: chrono::NaiveDateTime,
+    queued_at: Instant,
+}
+
+#[derive(Default)]
+struct PositionBuffer {
+    pending: HashMap<i32, PendingPosition>,
+    flusher_running: bool,
+}
+
+static POSITION_BUFFER: Mutex<Option<PositionBuffer>> = Mutex::new(None);
+
+fn with_buffer<R>(f: impl FnOnce(&mut PositionBuffer) -> R) -> R {
+    let mut guard = POSITION_BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(PositionBuffer::default))
+}
+
+/// Queue a reading position, replacing any unsaved position for the same book
+pub fn queue_reading_position(book_id: i32, page: i32) {
+    let start_flusher = with_buffer(|buffer| {
+        buffer.pending.insert(
+            book_id,
+            PendingPosition {
+                page,
+                read_at: chrono::Utc::now().naive_utc(),
+                queued_at: Instant::now(),
+            },
+        );
+        !std::mem::replace(&mut buffer.flusher_running, true)
+    });
+
+    if start_flusher {
+        std::thread::spawn(run_flusher);
+    }
+}
+
+/// Drop an unsaved position, e.g. when the page was written directly
+pub fn discard_reading_position(book_id: i32) {
+    with_buffer(|buffer| buffer.pending.remove(&book_id));
+}
+
+/// Write unsaved positions now, for one book or all of them
+///
+/// Returns the number of positions written.
+pub fn flush_reading_positions(book_id: Option<i32>) -> Result<usize, AppError> {
+    let entries: Vec<(i32, PendingPosition)> = with_buffer(|buffer| match book_id {
+        Some(id) => buffer
+            .pending
+            .remove(&id)
+            .map(|position| vec![(id, position)])
+            .unwrap_or_default(),
+        None => buffer.pending.drain().collect(),
+    });
+
+    write_positions(entries)
+}
+
+/// Background loop writing positions once they go idle; exits when nothing is pending
+fn run_flusher() {
+    loop {
+        std::thread::sleep(FLUSH_POLL_INTERVAL);
+
+        let (idle, done) = with_buffer(|buffer| {
+            let idle_ids: Vec<i32> = buffer
+                .pending
+                .iter()
+                .filter(|(_, position)| position.queued_at.elapsed() >= FLUSH_DELAY)
+                .map(|(id, _)| *id)
+                .collect();
+            let idle: Vec<(i32, PendingPosition)> = idle_ids
+                .into_iter()
+                .filter_map(|id| buffer.pending.remove(&id).map(|position| (id, position)))
+                .collect();
+
+            let done = buffer.pending.is_empty();
+            if done {
+                buffer.flusher_running = false;
+            }
+            (idle, done)
+        });
+
+        if let Err(e) = write_positions(idle) {
+            error!("Failed to write reading positions: {}", e);
+        }
+        if done {
+            return;
+        }
+    }
+}
+
+fn write_positions(entries: Vec<(i32, PendingPosition)>) -> Result<usize, AppError> {
+    if entries.is_empty() {
+        return Ok(0);
+    }
+
+    let mut conn = establish_connection()?;
+    let positions: Vec<(i32, i32, chrono::NaiveDateTime)> = entries
+        .iter()
+        .map(|(book_id, position)| (*book_id, position.page, position.read_at))
+        .collect();
+    save_positions(&mut conn, &positions)?;
+
+    debug!("Saved {} buffered reading position(s)", entries.len());
+    Ok(entries.len())
+}
+
+/// Write `(book_id, page, read_at)` positions in one transaction
+///
+/// A position that lands on another page drops the fractions recorded for the old one.
+pub(crate) fn save_positions(
+    conn: &mut SqliteConnection,
+    positions: &[(i32, i32, chrono::NaiveDateTime)],
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    conn.transaction(|conn| {
+        let ids: Vec<i32> = positions.iter().map(|(id, _, _)| *id).collect();
+        let stored: HashMap<i32, (i32, i32)> = books::table
+            .filter(books::id.eq_any(&ids))
+            .select((books::id, books::current_page, books::total_pages))
+            .load::<(i32, i32, i32)>(conn)?
+            .into_iter()
+            .map(|(id, current_page, total_pages)| (id, (current_page, total_pages)))
+            .collect();
+
+        for (book_id, page, read_at) in positions {
+            let Some(&(current_page, total_pages)) = stored.get(book_id) else {
+                continue;
+            };
+            let page = clamp_page(*page, total_pages);
+            diesel::update(books::table.find(book_id))
+                .set((
+                    books::current_page.eq(page),
+                    books::last_read_at.eq(Some(*read_at)),
+                    books::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            if page != current_page {
+                clear_page_fractions(conn, *book_id)?;
+            }
+        }
+        Ok::<_, diesel::result::Error>(())
+    })
+    .map_err(|e| {
+        AppError::new(
+            ErrorCode::DatabaseQueryFailed,
+            format!("Failed to save reading positions: {}", e),
+        )
+    })
+}
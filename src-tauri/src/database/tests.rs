@@ -22,6 +22,59 @@ mod database_tests {
         Some(uuid::Uuid::new_v4().to_string())
     }
 
+    /// Insert a 10-page book stored at `/manga/<title>.cbz`; `configure` can change
+    /// the row before it is inserted
+    fn create_test_book_with(
+        conn: &mut SqliteConnection,
+        title: &str,
+        configure: impl FnOnce(&mut NewBook),
+    ) -> Book {
+        let mut new_book = NewBook {
+            uuid: test_uuid(),
+            file_path: format!("/manga/{}.cbz", title),
+            filename: format!("{}.cbz", title),
+            file_size: None,
+            file_hash: None,
+            title: title.to_string(),
+            current_page: 0,
+            total_pages: 10,
+            series_name: None,
+            volume_number: None,
+        };
+        configure(&mut new_book);
+
+        diesel::insert_into(books::table)
+            .values(&new_book)
+            .returning(Book::as_returning())
+            .get_result(conn)
+            .unwrap()
+    }
+
+    /// Insert a collection
+    fn create_test_collection(conn: &mut SqliteConnection, name: &str) -> Collection {
+        diesel::insert_into(collections::table)
+            .values(&NewCollection {
+                uuid: test_uuid(),
+                name: name.to_string(),
+                description: None,
+            })
+            .returning(Collection::as_returning())
+            .get_result(conn)
+            .unwrap()
+    }
+
+    /// Add a book to a collection
+    fn link_test_book(conn: &mut SqliteConnection, book_id: i32, collection_id: i32) {
+        diesel::insert_into(book_collections::table)
+            .values(&NewBookCollection {
+                uuid: test_uuid(),
+                book_id,
+                collection_id,
+            })
+            .execute(conn)
+            .unwrap();
+    }
+
     /// Create a fresh in-memory database with migrations applied
     fn setup_test_db() -> TestPool {
         let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
@@ -43,6 +96,72 @@ mod database_tests {
 
     mod collection_tests {
         use super::*;
+        use crate::database::operations::{insert_collection, load_collection_tree, set_collection_parent};
+
+        #[test]
+        fn test_collection_nesting() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let mut ids = Vec::new();
+            for name in ["Manga", "Shonen", "Naruto", "Comics"] {
+                let collection = create_test_collection(&mut conn, name);
+                ids.push(collection.id);
+            }
+            let (manga, shonen, naruto) = (ids[0], ids[1], ids[2]);
+
+            set_collection_parent(&mut conn, shonen, Some(manga)).unwrap();
+            set_collection_parent(&mut conn, naruto, Some(shonen)).unwrap();
+
+            // Nesting a collection under itself or a descendant is rejected
+            assert!(set_collection_parent(&mut conn, manga, Some(manga)).is_err());
+            assert!(set_collection_parent(&mut conn, manga, Some(naruto)).is_err());
+            let manga_row: Collection = collections::table.find(manga).first(&mut conn).unwrap();
+            assert!(manga_row.parent_id.is_none());
+
+            let tree = load_collection_tree(&mut conn).unwrap();
+            let names = |nodes: &[CollectionNode]| {
+                nodes.iter().map(|n| n.collection.collection.name.clone()).collect::<Vec<_>>()
+            };
+            assert_eq!(names(&tree), vec!["Comics", "Manga"]);
+            assert_eq!(names(&tree[1].children), vec!["Shonen"]);
+            assert_eq!(names(&tree[1].children[0].children), vec!["Naruto"]);
+
+            // A soft-deleted parent leaves its children at the top level
+            diesel::update(collections::table.find(manga))
+                .set(collections::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+            let tree = load_collection_tree(&mut conn).unwrap();
+            assert_eq!(names(&tree), vec!["Comics", "Shonen"]);
+
+            // A cycle that slipped in through sync still shows every collection
+            diesel::update(collections::table.find(shonen))
+                .set(collections::parent_id.eq(Some(naruto)))
+                .execute(&mut conn)
+                .unwrap();
+            let tree = load_collection_tree(&mut conn).unwrap();
+            assert_eq!(tree.len(), 2);
+            let nested = &tree.iter().find(|n| n.collection.collection.name != "Comics").unwrap().children;
+            assert_eq!(nested.len(), 1);
+        }
+
+        #[test]
+        fn test_create_nested_collection() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let new_collection = |name: &str| NewCollection { uuid: test_uuid(), name: name.to_string(), description: None };
+
+            let manga = insert_collection(&mut conn, &new_collection("Manga"), None).unwrap();
+            let shonen = insert_collection(&mut conn, &new_collection("Shonen"), Some(manga.id)).unwrap();
+            assert_eq!(shonen.parent_id, Some(manga.id));
+
+            // A missing parent leaves no stray collection behind
+            let err = insert_collection(&mut conn, &new_collection("Orphan"), Some(manga.id + 100)).unwrap_err();
+            assert!(matches!(err.code, crate::error::ErrorCode::InvalidInput));
+            let count: i64 = collections::table.count().get_result(&mut conn).unwrap();
+            assert_eq!(count, 2);
+        }
 
         #[test]
         fn test_create_collection() {
@@ -194,6 +313,186 @@ mod database_tests {
             assert_eq!(all_collections[0].name, "Collection 1");
             assert_eq!(all_collections[2].name, "Collection 3");
         }
+
+        #[test]
+        fn test_compare_collections() {
+            use crate::database::operations::compare_collections;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let [a, b] = ["Shelf A", "Shelf B"].map(|name| create_test_collection(&mut conn, name).id);
+
+            // (title, in A, in B)
+            let layout = [
+                ("Alpha", true, false),
+                ("Both", true, true),
+                ("Beta", false, true),
+                ("Deleted", true, false),
+            ];
+            for (title, in_a, in_b) in layout {
+                let book = create_test_book_with(&mut conn, title, |_| {});
+                for (collection_id, member) in [(a, in_a), (b, in_b)] {
+                    if member {
+                        link_test_book(&mut conn, book.id, collection_id);
+                    }
+                }
+            }
+            diesel::update(books::table.filter(books::title.eq("Deleted")))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+
+            let titles = |books: &[Book]| books.iter().map(|b| b.title.clone()).collect::<Vec<_>>();
+
+            let diff = compare_collections(&mut conn, a, b).unwrap();
+            assert_eq!(titles(&diff.only_in_a), vec!["Alpha"]);
+            assert_eq!(titles(&diff.only_in_b), vec!["Beta"]);
+            assert_eq!(titles(&diff.in_both), vec!["Both"]);
+
+            // Swapping the arguments swaps the one-sided lists
+            let swapped = compare_collections(&mut conn, b, a).unwrap();
+            assert_eq!(titles(&swapped.only_in_a), vec!["Beta"]);
+
+            assert!(compare_collections(&mut conn, a, 9999).is_err());
+        }
+
+        #[test]
+        fn test_unread_counts_by_collection() {
+            use crate::database::operations::unread_counts_by_collection;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let [unread, finished] = ["Unread", "Finished"].map(|name| create_test_collection(&mut conn, name).id);
+
+            // (title, collection, reading status, deleted)
+            let layout = [
+                ("One", unread, "unread", false),
+                ("Two", unread, "unread", false),
+                ("Started", unread, "reading", false),
+                ("Gone", unread, "unread", true),
+                ("Done", finished, "completed", false),
+            ];
+            for (title, collection_id, status, deleted) in layout {
+                let book = create_test_book_with(&mut conn, title, |_| {});
+                diesel::update(books::table.find(book.id))
+                    .set((
+                        books::reading_status.eq(status),
+                        books::deleted_at.eq(deleted.then(|| chrono::Utc::now().naive_utc())),
+                    ))
+                    .execute(&mut conn)
+                    .unwrap();
+                link_test_book(&mut conn, book.id, collection_id);
+            }
+
+            let counts = unread_counts_by_collection(&mut conn).unwrap();
+            assert_eq!(counts.get(&unread), Some(&2));
+            assert_eq!(counts.get(&finished), None);
+        }
+
+        #[test]
+        fn test_collection_book_counts() {
+            use crate::database::operations::load_collections_with_counts;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let [full, shared, empty] = ["Full", "Shared", "Empty"].map(|name| create_test_collection(&mut conn, name).id);
+
+            // (title, collections, book deleted, links deleted)
+            let layout: [(&str, &[i32], bool, bool); 5] = [
+                ("One", &[full, shared], false, false),
+                ("Two", &[full], false, false),
+                ("Three", &[full], false, false),
+                ("Trashed", &[full, shared], true, false),
+                ("Removed", &[shared], false, true),
+            ];
+            for (title, collections, deleted, unlinked) in layout {
+                let book = create_test_book_with(&mut conn, title, |_| {});
+                diesel::update(books::table.find(book.id))
+                    .set(books::deleted_at.eq(deleted.then(|| chrono::Utc::now().naive_utc())))
+                    .execute(&mut conn)
+                    .unwrap();
+                for &collection_id in collections {
+                    link_test_book(&mut conn, book.id, collection_id);
+                }
+                if unlinked {
+                    diesel::update(book_collections::table.filter(book_collections::book_id.eq(book.id)))
+                        .set(book_collections::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                        .execute(&mut conn)
+                        .unwrap();
+                }
+            }
+
+            let counts: std::collections::HashMap<i32, i64> = load_collections_with_counts(&mut conn)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.collection.id, c.book_count))
+                .collect();
+            assert_eq!(counts.len(), 3);
+            assert_eq!(counts[&full], 3);
+            assert_eq!(counts[&shared], 1);
+            assert_eq!(counts[&empty], 0);
+        }
+    }
+
+    // ========================================================================
+    // TAG TESTS
+    // ========================================================================
+
+    mod tag_tests {
+        use super::*;
+        use crate::database::operations::{attach_tag, detach_tag, load_books_with_tag};
+
+        #[test]
+        fn test_tag_books() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let tag: Tag = diesel::insert_into(tags::table)
+                .values(&NewTag { name: "color".to_string(), uuid: test_uuid() })
+                .returning(Tag::as_returning())
+                .get_result(&mut conn)
+                .unwrap();
+
+            let mut book_ids = Vec::new();
+            for title in ["Beta", "Alpha", "Gamma"] {
+                let book = create_test_book_with(&mut conn, title, |_| {});
+                book_ids.push(book.id);
+            }
+            let titles = |books: Vec<Book>| books.into_iter().map(|b| b.title).collect::<Vec<_>>();
+
+            for &book_id in &book_ids[..2] {
+                attach_tag(&mut conn, book_id, tag.id).unwrap();
+            }
+            // Tagging twice keeps a single link
+            let first = attach_tag(&mut conn, book_ids[0], tag.id).unwrap();
+            assert_eq!(titles(load_books_with_tag(&mut conn, tag.id).unwrap()), vec!["Alpha", "Beta"]);
+
+            // Removal is a soft delete, and re-tagging restores the same row
+            detach_tag(&mut conn, book_ids[0], tag.id).unwrap();
+            assert_eq!(titles(load_books_with_tag(&mut conn, tag.id).unwrap()), vec!["Alpha"]);
+            let removed: BookTag = book_tags::table.find(first.id).first(&mut conn).unwrap();
+            assert!(removed.deleted_at.is_some());
+
+            let restored = attach_tag(&mut conn, book_ids[0], tag.id).unwrap();
+            assert_eq!((restored.id, restored.uuid.clone()), (first.id, first.uuid.clone()));
+            assert!(restored.deleted_at.is_none());
+
+            // Soft-deleted books and tags drop out
+            diesel::update(books::table.find(book_ids[1]))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+            assert_eq!(titles(load_books_with_tag(&mut conn, tag.id).unwrap()), vec!["Beta"]);
+
+            diesel::update(tags::table.find(tag.id))
+                .set(tags::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+            assert!(load_books_with_tag(&mut conn, tag.id).unwrap().is_empty());
+        }
     }
 
     // ========================================================================
@@ -202,6 +501,357 @@ mod database_tests {
 
     mod book_tests {
         use super::*;
+        use crate::database::operations::{
+            books_page, calculate_archive_hash, calculate_archive_hash_with_password, check_zip_password,
+            count_archive_images, count_zip_images, detect_archive_type,
+            import_title, load_series_summaries, load_series_volumes, read_archive_metadata, scan_book_file,
+            store_rescan, verify_downloaded_archive, RescannedFile,
+        };
+        use crate::page_filter::PageFilter;
+
+        #[test]
+        fn test_encrypted_archive_hashes_like_plain_copy() {
+            use std::io::Write;
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_encrypted_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let write_cbz = |name: &str, password: Option<&str>| {
+                let path = dir.join(name);
+                let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+                let mut options = zip::write::SimpleFileOptions::default();
+                if let Some(password) = password {
+                    options = options.with_aes_encryption(zip::AesMode::Aes256, password);
+                }
+                for page in ["001.jpg", "002.jpg"] {
+                    zip.start_file(page, options).unwrap();
+                    zip.write_all(page.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+                path
+            };
+            let plain = write_cbz("plain.cbz", None);
+            let locked = write_cbz("locked.cbz", Some("open sesame"));
+
+            let missing = check_zip_password(&locked, None).unwrap_err();
+            assert!(matches!(missing.code, crate::error::ErrorCode::ArchiveEncrypted));
+            let wrong = check_zip_password(&locked, Some("guess")).unwrap_err();
+            assert!(matches!(wrong.code, crate::error::ErrorCode::ArchiveEncrypted));
+            check_zip_password(&locked, Some("open sesame")).unwrap();
+            check_zip_password(&plain, None).unwrap();
+
+            // Pages are counted without the password and hashed decrypted
+            assert_eq!(count_archive_images(&locked, &PageFilter::default()).unwrap(), 2);
+            assert!(calculate_archive_hash(&locked).is_err());
+            let plain_hash = calculate_archive_hash(&plain).unwrap();
+            assert_eq!(
+                calculate_archive_hash_with_password(&locked, Some("open sesame")).unwrap(),
+                plain_hash
+            );
+
+            // A download is only verified once the password is known
+            let unverified = verify_downloaded_archive(&locked, &plain_hash, None).unwrap_err();
+            assert!(matches!(unverified.code, crate::error::ErrorCode::ArchiveEncrypted));
+            verify_downloaded_archive(&locked, &plain_hash, Some("open sesame")).unwrap();
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn test_verify_downloaded_archive_compares_content() {
+            use std::io::Write;
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_verify_download_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let write_cbz = |name: &str, pages: &[&str]| {
+                let path = dir.join(name);
+                let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+                for page in pages {
+                    zip.start_file(*page, zip::write::SimpleFileOptions::default()).unwrap();
+                    zip.write_all(page.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+                path
+            };
+            let expected = calculate_archive_hash(&write_cbz("uploaded.cbz", &["1.jpg", "2.jpg"])).unwrap();
+
+            // The same pages repackaged still match
+            let downloaded = write_cbz("downloaded.cbz", &["2.jpg", "1.jpg"]);
+            verify_downloaded_archive(&downloaded, &expected, None).unwrap();
+
+            // Different content, or no archive at all, doesn't
+            let changed = write_cbz("changed.cbz", &["1.jpg", "2.jpg", "3.jpg"]);
+            let mismatch = verify_downloaded_archive(&changed, &expected, None).unwrap_err();
+            assert!(matches!(mismatch.code, crate::error::ErrorCode::SyncFailed));
+            assert!(mismatch.message.contains("doesn't match"));
+
+            let truncated = dir.join("truncated.cbz");
+            std::fs::write(&truncated, b"PK\x03\x04").unwrap();
+            let unreadable = verify_downloaded_archive(&truncated, &expected, None).unwrap_err();
+            assert!(matches!(unreadable.code, crate::error::ErrorCode::SyncFailed));
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn test_content_hash_keeps_stored_order() {
+            use sha2::{Digest, Sha256};
+            use std::io::Write;
+
+            let path = std::env::temp_dir().join(format!("yomiyougu_hash_order_{}.cbz", uuid::Uuid::new_v4()));
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for page in ["page2.jpg", "page10.jpg", "page1.jpg", "._page1.jpg"] {
+                zip.start_file(page, options).unwrap();
+                zip.write_all(page.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+
+            let hash = calculate_archive_hash(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            // Existing books were hashed in byte order of their names; that must not change
+            let mut expected = Sha256::new();
+            for page in ["page1.jpg", "page10.jpg", "page2.jpg"] {
+                expected.update(page.as_bytes());
+            }
+            assert_eq!(hash, format!("{:x}", expected.finalize()));
+        }
+
+        #[cfg(not(target_os = "android"))]
+        #[test]
+        fn test_converted_cbz_keeps_hash_and_relinks() {
+            use crate::database::operations::{relink_to_cbz, write_cbz};
+            use std::io::Write;
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_convert_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+
+            // The original archive, stored in another order with a junk entry
+            let original = dir.join("book.cbz");
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&original).unwrap());
+            for name in ["page10.jpg", "__MACOSX/._page1.jpg", "page1.jpg", "page2.jpg"] {
+                zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+                zip.write_all(name.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+
+            // The pages as the conversion reads them, in reading order
+            let entries: Vec<(String, Vec<u8>)> = ["page1.jpg", "page2.jpg", "page10.jpg"]
+                .iter()
+                .map(|name| (name.to_string(), name.as_bytes().to_vec()))
+                .collect();
+            let converted = dir.join("converted.cbz");
+            let mut progress = Vec::new();
+            write_cbz(&entries, &converted, &mut |done, total| progress.push((done, total))).unwrap();
+            assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+            assert_eq!(calculate_archive_hash(&converted).unwrap(), calculate_archive_hash(&original).unwrap());
+
+            // A failed write leaves nothing behind
+            let unwritable = dir.join("missing").join("book.cbz");
+            assert!(write_cbz(&entries, &unwritable, &mut |_, _| {}).is_err());
+            assert!(!unwritable.exists());
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let book = create_test_book_with(&mut conn, "Converted", |b| {
+                b.file_path = "/manga/Converted.cbr".to_string();
+                b.filename = "Converted.cbr".to_string();
+            });
+            diesel::update(books::table.find(book.id))
+                .set(books::archive_type.eq(Some("rar")))
+                .execute(&mut conn)
+                .unwrap();
+
+            let relinked = relink_to_cbz(&mut conn, book.id, &converted).unwrap();
+            assert_eq!(relinked.file_path, converted.to_string_lossy());
+            assert_eq!(relinked.filename, "converted.cbz");
+            assert_eq!(relinked.file_size, Some(std::fs::metadata(&converted).unwrap().len() as i32));
+            assert_eq!(relinked.archive_type.as_deref(), Some("zip"));
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn test_repackaged_archive_hashes_the_same() {
+            use std::io::Write;
+
+            // Same pages, different container, entry order and junk entries
+            let dir = std::env::temp_dir().join(format!("yomiyougu_repack_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let cbz = dir.join("book.cbz");
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&cbz).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for page in ["page2.jpg", "page10.jpg", "page1.jpg", "__MACOSX/._page1.jpg"] {
+                zip.start_file(page, options).unwrap();
+                zip.write_all(page.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+
+            let cb7 = dir.join("book.cb7");
+            let mut sevenz = sevenz_rust::SevenZWriter::create(&cb7).unwrap();
+            for page in ["page10.jpg", "page1.jpg", "ComicInfo.xml", "page2.jpg"] {
+                let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+                entry.name = page.to_string();
+                sevenz.push_archive_entry(entry, Some(page.as_bytes())).unwrap();
+            }
+            sevenz.finish().unwrap();
+
+            // Duplicate detection looks books up by this hash
+            let zip_hash = calculate_archive_hash(&cbz).unwrap();
+            let sevenz_hash = calculate_archive_hash(&cb7).unwrap();
+            std::fs::remove_dir_all(&dir).unwrap();
+            assert_eq!(zip_hash, sevenz_hash);
+        }
+
+        #[test]
+        fn test_webp_only_archive_has_pages() {
+            use std::io::Write;
+
+            let path = std::env::temp_dir().join(format!("yomiyougu_webp_{}.cbz", uuid::Uuid::new_v4()));
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for entry in ["001.webp", "002.WEBP", "003.avif", "004.gif"] {
+                zip.start_file(entry, options).unwrap();
+                zip.write_all(entry.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+
+            // Import rejects archives with no pages, so these must count
+            let counted = count_archive_images(&path, &PageFilter::default()).unwrap();
+            let hash = calculate_archive_hash(&path);
+            let pages = crate::protocol::get_zip_image_list(&path, &PageFilter::default()).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(counted, 4);
+            assert!(hash.is_ok());
+            assert_eq!(pages.len(), 4);
+        }
+
+        #[test]
+        fn test_macos_metadata_is_not_counted() {
+            use std::io::Write;
+
+            let path = std::env::temp_dir().join(format!("yomiyougu_macos_{}.cbz", uuid::Uuid::new_v4()));
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for entry in [
+                "Vol 1/001.jpg",
+                "Vol 1/002.jpg",
+                "Vol 1/._002.jpg",
+                "__MACOSX/Vol 1/._001.jpg",
+                "__MACOSX/Vol 1/001.jpg",
+                "._cover.jpg",
+            ] {
+                zip.start_file(entry, options).unwrap();
+                zip.write_all(entry.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+
+            let counted = count_zip_images(&path, &PageFilter::default()).unwrap();
+            let pages = crate::protocol::get_zip_image_list(&path, &PageFilter::default()).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(pages, vec!["Vol 1/001.jpg", "Vol 1/002.jpg"]);
+            assert_eq!(counted as usize, pages.len());
+        }
+
+        #[test]
+        fn test_import_metadata_from_comic_info() {
+            use std::io::Write;
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_comicinfo_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let write_cbz = |name: &str, comic_info: Option<&str>| {
+                let path = dir.join(name);
+                let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+                let options = zip::write::SimpleFileOptions::default();
+                for page in ["001.jpg", "002.jpg", "003.jpg"] {
+                    zip.start_file(page, options).unwrap();
+                    zip.write_all(b"page").unwrap();
+                }
+                if let Some(xml) = comic_info {
+                    zip.start_file("ComicInfo.xml", options).unwrap();
+                    zip.write_all(xml.as_bytes()).unwrap();
+                }
+                zip.finish().unwrap();
+                path
+            };
+
+            let tagged = write_cbz(
+                "naruto_v01_scan.cbz",
+                Some(
+                    r#"<ComicInfo>
+                        <Title>Uzumaki Naruto</Title>
+                        <Series>Naruto</Series>
+                        <Number>1</Number>
+                        <Pages><Page Image="0" Type="FrontCover" /></Pages>
+                    </ComicInfo>"#,
+                ),
+            );
+            let plain = write_cbz("Plain Book.cbz", None);
+            let broken = write_cbz("Broken Book.cbz", Some("<ComicInfo><Title>Unclosed"));
+
+            let read = |path: &std::path::Path| {
+                read_archive_metadata(path, detect_archive_type(path).unwrap(), 3)
+            };
+
+            let (metadata, story_pages) = read(&tagged);
+            assert_eq!(import_title(&metadata, "naruto_v01_scan.cbz"), "Uzumaki Naruto");
+            assert_eq!(metadata.series.as_deref(), Some("Naruto"));
+            assert_eq!(metadata.number, Some(1.0));
+            assert_eq!(story_pages, Some(2));
+
+            // Without usable metadata the title comes from the filename
+            for (path, filename) in [(&plain, "Plain Book.cbz"), (&broken, "Broken Book.cbz")] {
+                let (metadata, story_pages) = read(path);
+                assert_eq!(import_title(&metadata, filename), filename.trim_end_matches(".cbz"));
+                assert_eq!((metadata.series, metadata.number, story_pages), (None, None, None));
+            }
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn test_series_volumes_and_summary() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let volumes = [
+                ("Vol 2", Some("Naruto"), Some(2.0), 9, "reading"),
+                ("Extra", Some("Naruto"), None, 0, "unread"),
+                ("Side story", Some("Naruto"), Some(1.5), 0, "unread"),
+                ("Vol 1", Some("Naruto"), Some(1.0), 9, "completed"),
+                ("Standalone", None, None, 5, "reading"),
+            ];
+            for (title, series_name, volume_number, current_page, status) in volumes {
+                create_test_book_with(&mut conn, title, |b| {
+                    b.current_page = current_page;
+                    b.total_pages = 20;
+                    b.series_name = series_name.map(str::to_string);
+                    b.volume_number = volume_number;
+                });
+                diesel::update(books::table.filter(books::title.eq(title)))
+                    .set(books::reading_status.eq(status))
+                    .execute(&mut conn)
+                    .unwrap();
+            }
+
+            // Numbered volumes in order, fractional ones in between, unnumbered last
+            let titles: Vec<String> = load_series_volumes(&mut conn, "Naruto")
+                .unwrap()
+                .into_iter()
+                .map(|b| b.title)
+                .collect();
+            assert_eq!(titles, vec!["Vol 1", "Side story", "Vol 2", "Extra"]);
+
+            let summaries = load_series_summaries(&mut conn).unwrap();
+            assert_eq!(summaries.len(), 1);
+            let naruto = &summaries[0];
+            assert_eq!((naruto.volume_count, naruto.completed_volumes), (4, 1));
+            // Completed volume counts in full, the one in progress up to its current page
+            assert_eq!((naruto.pages_read, naruto.total_pages), (30, 80));
+            assert!((naruto.progress - 37.5).abs() < 0.01);
+        }
 
         fn create_test_book(conn: &mut SqliteConnection, title: &str) -> Book {
             let new_book = NewBook {
@@ -213,6 +863,8 @@ mod database_tests {
                 title: title.to_string(),
                 current_page: 0,
                 total_pages: 100,
+                series_name: None,
+                volume_number: None,
             };
 
             diesel::insert_into(books::table)
@@ -222,6 +874,41 @@ mod database_tests {
                 .unwrap()
         }
 
+        #[test]
+        fn test_books_page() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let collection = create_test_collection(&mut conn, "Shelf");
+
+            for i in 1..=50 {
+                let book = create_test_book(&mut conn, &format!("Volume {:02}", i));
+                diesel::update(books::table.find(book.id))
+                    .set(books::sort_key.eq(format!("volume {:02}", i)))
+                    .execute(&mut conn)
+                    .unwrap();
+                link_test_book(&mut conn, book.id, collection.id);
+            }
+
+            let page = books_page(&mut conn, None, None, false, Some("sort_key"), Some(20), Some(20)).unwrap();
+            assert_eq!(page.total_count, 50);
+            let titles: Vec<&str> = page.books.iter().map(|b| b.book.title.as_str()).collect();
+            assert_eq!(titles.len(), 20);
+            assert_eq!((titles[0], titles[19]), ("Volume 21", "Volume 40"));
+            assert!(page.books.iter().all(|b| b.collection_names == vec!["Shelf".to_string()]));
+
+            // The last page is short, past the end is empty, and no limit returns the rest
+            let last = books_page(&mut conn, Some(collection.id), None, false, Some("sort_key"), Some(20), Some(40)).unwrap();
+            assert_eq!((last.books.len(), last.total_count), (10, 50));
+            let beyond = books_page(&mut conn, None, None, false, None, Some(20), Some(60)).unwrap();
+            assert_eq!((beyond.books.len(), beyond.total_count), (0, 50));
+            let rest = books_page(&mut conn, None, None, false, None, None, Some(45)).unwrap();
+            assert_eq!(rest.books.len(), 5);
+
+            let favorites = books_page(&mut conn, None, None, true, None, Some(20), None).unwrap();
+            assert_eq!((favorites.books.len(), favorites.total_count), (0, 0));
+        }
+
         #[test]
         fn test_create_book() {
             let pool = setup_test_db();
@@ -250,6 +937,8 @@ mod database_tests {
                 title: "Manga 1".to_string(),
                 current_page: 0,
                 total_pages: 50,
+                series_name: None,
+                volume_number: None,
             };
 
             let book2 = NewBook {
@@ -261,6 +950,8 @@ mod database_tests {
                 title: "Manga 2".to_string(),
                 current_page: 0,
                 total_pages: 60,
+                series_name: None,
+                volume_number: None,
             };
 
             diesel::insert_into(books::table)
@@ -302,6 +993,212 @@ mod database_tests {
             assert!(updated.last_read_at.is_some());
         }
 
+        #[test]
+        fn test_store_rescan_clamps_position_and_replaces_hash() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let book = create_test_book(&mut conn, "Replaced Archive");
+            diesel::update(books::table.find(book.id))
+                .set((
+                    books::current_page.eq(80),
+                    books::page_fraction.eq(Some(0.5)),
+                    books::hash_scheme.eq(HashScheme::File.as_str()),
+                ))
+                .execute(&mut conn)
+                .unwrap();
+
+            let rescanned = |total_pages, story_page_count, file_hash: &str| RescannedFile {
+                total_pages,
+                story_page_count,
+                file_hash: file_hash.to_string(),
+                archive_type: "zip",
+            };
+
+            // More pages: the position stays where it was
+            let grown = store_rescan(&mut conn, book.id, &rescanned(120, None, "grownhash")).unwrap();
+            assert_eq!((grown.total_pages, grown.current_page, grown.page_fraction), (120, 80, Some(0.5)));
+            assert_eq!(grown.file_hash.as_deref(), Some("grownhash"));
+            assert_eq!(grown.hash_scheme(), HashScheme::Content);
+
+            // Fewer pages: the position moves to the new last page, at its top
+            let shrunk = store_rescan(&mut conn, book.id, &rescanned(40, Some(38), "shrunkhash")).unwrap();
+            assert_eq!((shrunk.total_pages, shrunk.current_page), (40, 39));
+            assert_eq!(shrunk.story_page_count, Some(38));
+            assert_eq!(shrunk.page_fraction, None);
+            assert_eq!(shrunk.file_hash.as_deref(), Some("shrunkhash"));
+        }
+
+        #[test]
+        fn test_rescan_file_replaced_by_another_format() {
+            use std::io::Write;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let path = std::env::temp_dir().join(format!("yomiyougu_rescan_{}.cbz", uuid::Uuid::new_v4()));
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            for page in ["001.jpg", "002.jpg"] {
+                zip.start_file(page, zip::write::SimpleFileOptions::default()).unwrap();
+                zip.write_all(page.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+
+            let book = create_test_book(&mut conn, "Reformatted");
+            let book: Book = diesel::update(books::table.find(book.id))
+                .set((
+                    books::file_path.eq(path.to_string_lossy().to_string()),
+                    books::archive_type.eq(Some("zip")),
+                ))
+                .returning(Book::as_returning())
+                .get_result(&mut conn)
+                .unwrap();
+
+            // The same path now holds a 7z archive with a credits page
+            let mut sevenz = sevenz_rust::SevenZWriter::create(&path).unwrap();
+            for page in ["001.jpg", "002.jpg", "003.jpg", "credits.jpg"] {
+                let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+                entry.name = page.to_string();
+                sevenz.push_archive_entry(entry, Some(page.as_bytes())).unwrap();
+            }
+            sevenz.finish().unwrap();
+
+            let rescanned = scan_book_file(&book, &PageFilter::parse("*credits*"));
+            std::fs::remove_file(&path).unwrap();
+            let rescanned = rescanned.unwrap();
+            assert_eq!((rescanned.total_pages, rescanned.archive_type), (3, "7z"));
+
+            // Pages are read as 7z from now on instead of failing as a broken ZIP
+            let stored = store_rescan(&mut conn, book.id, &rescanned).unwrap();
+            assert_eq!(stored.total_pages, 3);
+            assert_eq!(stored.archive_type.as_deref(), Some("7z"));
+        }
+
+        #[test]
+        fn test_remap_pages_for_new_exclusions() {
+            use crate::database::operations::remap_book_pages;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let pages: Vec<String> = ["001.jpg", "002.jpg", "credits.jpg", "003.jpg"]
+                .iter()
+                .map(|page| page.to_string())
+                .collect();
+            let none = PageFilter::default();
+            let credits = PageFilter::parse("*credits*");
+
+            // Reading 003.jpg, with a bookmark on the credits page
+            let book = create_test_book(&mut conn, "Remapped");
+            diesel::update(books::table.find(book.id))
+                .set((
+                    books::total_pages.eq(4),
+                    books::current_page.eq(3),
+                    books::page_fraction.eq(Some(0.5)),
+                ))
+                .execute(&mut conn)
+                .unwrap();
+            diesel::insert_into(bookmarks::table)
+                .values(&NewBookmark {
+                    uuid: test_uuid(),
+                    book_id: book.id,
+                    name: "Credits".to_string(),
+                    description: None,
+                    page: 2,
+                })
+                .execute(&mut conn)
+                .unwrap();
+            let bookmark_page = |conn: &mut SqliteConnection| -> i32 {
+                bookmarks::table.select(bookmarks::page).first(conn).unwrap()
+            };
+
+            // Hiding the credits keeps the position on 003.jpg; the bookmark moves to the next page shown
+            assert!(remap_book_pages(&mut conn, book.id, &pages, &none, &credits).unwrap());
+            let hidden: Book = books::table.find(book.id).first(&mut conn).unwrap();
+            assert_eq!((hidden.total_pages, hidden.current_page), (3, 2));
+            assert_eq!(hidden.page_fraction, None);
+            assert_eq!(hidden.updated_at, book.updated_at);
+            assert_eq!(bookmark_page(&mut conn), 2);
+
+            // Showing them again puts 003.jpg back at its old index
+            assert!(remap_book_pages(&mut conn, book.id, &pages, &credits, &none).unwrap());
+            let shown: Book = books::table.find(book.id).first(&mut conn).unwrap();
+            assert_eq!((shown.total_pages, shown.current_page), (4, 3));
+
+            // Patterns that change nothing for this book, or hide every page, leave it alone
+            assert!(!remap_book_pages(&mut conn, book.id, &pages, &none, &PageFilter::parse("*.png")).unwrap());
+            assert!(!remap_book_pages(&mut conn, book.id, &pages, &none, &PageFilter::parse("*")).unwrap());
+            let kept: Book = books::table.find(book.id).first(&mut conn).unwrap();
+            assert_eq!((kept.total_pages, kept.current_page), (4, 3));
+        }
+
+        #[test]
+        fn test_buffered_page_turn_clears_fractions() {
+            use crate::database::progress::save_positions;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let book = create_test_book(&mut conn, "Buffered Position");
+            diesel::update(books::table.find(book.id))
+                .set((
+                    books::current_page.eq(10),
+                    books::page_fraction.eq(Some(0.5)),
+                    books::scroll_fraction.eq(Some(0.25)),
+                ))
+                .execute(&mut conn)
+                .unwrap();
+            let read_at = chrono::Utc::now().naive_utc();
+            let load = |conn: &mut SqliteConnection| -> Book {
+                books::table.find(book.id).select(Book::as_select()).first(conn).unwrap()
+            };
+
+            // Same page: the fractions still apply
+            save_positions(&mut conn, &[(book.id, 10, read_at)]).unwrap();
+            let same = load(&mut conn);
+            assert_eq!((same.page_fraction, same.scroll_fraction), (Some(0.5), Some(0.25)));
+
+            // Another page (clamped into the book) starts at its top
+            save_positions(&mut conn, &[(book.id, 500, read_at)]).unwrap();
+            let turned = load(&mut conn);
+            assert_eq!(turned.current_page, 99);
+            assert_eq!(turned.last_read_at, Some(read_at));
+            assert_eq!((turned.page_fraction, turned.scroll_fraction), (None, None));
+
+            // Unknown books are skipped
+            save_positions(&mut conn, &[(book.id + 1000, 3, read_at)]).unwrap();
+        }
+
+        #[test]
+        fn test_book_notes() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let book = create_test_book(&mut conn, "Notes Test");
+            assert!(book.notes.is_none());
+
+            let update = UpdateBook {
+                notes: Some(Some("Official translation is better".to_string())),
+                ..Default::default()
+            };
+            let updated: Book = diesel::update(books::table.find(book.id))
+                .set(&update)
+                .returning(Book::as_returning())
+                .get_result(&mut conn)
+                .unwrap();
+            assert_eq!(updated.notes.as_deref(), Some("Official translation is better"));
+
+            // Clearing the note stores NULL
+            let clear = UpdateBook {
+                notes: Some(None),
+                ..Default::default()
+            };
+            let cleared: Book = diesel::update(books::table.find(book.id))
+                .set(&clear)
+                .returning(Book::as_returning())
+                .get_result(&mut conn)
+                .unwrap();
+            assert!(cleared.notes.is_none());
+        }
+
         #[test]
         fn test_book_progress_calculation() {
             let pool = setup_test_db();
@@ -322,6 +1219,62 @@ mod database_tests {
             assert_eq!(book.progress(), 0.0); // Avoid division by zero
         }
 
+        #[test]
+        fn test_book_story_progress() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let mut book = create_test_book(&mut conn, "Story Progress Test");
+            assert!(book.story_page_count.is_none());
+            book.current_page = 39;
+            book.total_pages = 50;
+
+            // Without ComicInfo metadata the raw count is used either way
+            assert_eq!(book.progress_with(true), 80.0);
+
+            // Ten trailing ad pages: the story is done before the archive is
+            book.story_page_count = Some(40);
+            assert_eq!(book.progress_with(false), 80.0);
+            assert_eq!(book.progress_with(true), 100.0);
+
+            book.current_page = 49;
+            assert_eq!(book.progress_with(true), 100.0);
+        }
+
+        #[test]
+        fn test_page_navigation_single() {
+            let nav = PageNavigation::new(0, 10, "ltr", "single");
+            assert_eq!(nav.visible_pages, vec![0]);
+            assert_eq!(nav.next_page, Some(1));
+            assert_eq!(nav.previous_page, None);
+
+            let nav = PageNavigation::new(9, 10, "rtl", "single");
+            assert_eq!(nav.next_page, None);
+            assert_eq!(nav.previous_page, Some(8));
+
+            // Out-of-range progress is clamped to the last page
+            let nav = PageNavigation::new(42, 10, "ltr", "single");
+            assert_eq!(nav.current_page, 9);
+        }
+
+        #[test]
+        fn test_page_navigation_double_rtl() {
+            // Odd page snaps back to the start of its spread
+            let nav = PageNavigation::new(3, 7, "rtl", "double");
+            assert_eq!(nav.current_page, 2);
+            assert_eq!(nav.visible_pages, vec![3, 2]); // Right-to-left pair order
+            assert_eq!(nav.next_page, Some(4));
+            assert_eq!(nav.previous_page, Some(0));
+
+            // Last spread has a single page
+            let nav = PageNavigation::new(6, 7, "rtl", "double");
+            assert_eq!(nav.visible_pages, vec![6]);
+            assert_eq!(nav.next_page, None);
+
+            let nav = PageNavigation::new(2, 7, "ltr", "double");
+            assert_eq!(nav.visible_pages, vec![2, 3]);
+        }
+
         #[test]
         fn test_book_status_enum() {
             assert_eq!(ReadingStatus::Unread.as_str(), "unread");
@@ -381,6 +1334,8 @@ mod database_tests {
                 title: "Naruto".to_string(),
                 current_page: 0,
                 total_pages: 200,
+                series_name: None,
+                volume_number: None,
             };
 
             let book: Book = diesel::insert_into(books::table)
@@ -437,6 +1392,8 @@ mod database_tests {
                     title: "Test".to_string(),
                     current_page: 0,
                     total_pages: 50,
+                    series_name: None,
+                    volume_number: None,
                 })
                 .returning(Book::as_returning())
                 .get_result(&mut conn)
@@ -484,6 +1441,114 @@ mod database_tests {
             );
         }
 
+        #[test]
+        fn test_restored_book_regains_collections() {
+            use crate::database::operations::{restore_book, soft_delete_book};
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let book = create_test_book(&mut conn, "Restored");
+            let mut collection_ids = Vec::new();
+            for name in ["Kept", "Removed Earlier"] {
+                let collection = create_test_collection(&mut conn, name);
+                link_test_book(&mut conn, book.id, collection.id);
+                collection_ids.push(collection.id);
+            }
+
+            // A membership removed before the book was deleted must stay removed
+            let earlier = chrono::NaiveDateTime::parse_from_str("2026-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+            diesel::update(
+                book_collections::table.filter(book_collections::collection_id.eq(collection_ids[1])),
+            )
+            .set(book_collections::deleted_at.eq(Some(earlier)))
+            .execute(&mut conn)
+            .unwrap();
+
+            let live_memberships = |conn: &mut SqliteConnection| -> Vec<i32> {
+                book_collections::table
+                    .filter(book_collections::book_id.eq(book.id))
+                    .filter(book_collections::deleted_at.is_null())
+                    .select(book_collections::collection_id)
+                    .load(conn)
+                    .unwrap()
+            };
+
+            // The old file's archive type doesn't describe the new one
+            diesel::update(books::table.find(book.id))
+                .set(books::archive_type.eq(Some("rar")))
+                .execute(&mut conn)
+                .unwrap();
+
+            soft_delete_book(&mut conn, book.id).unwrap();
+            assert!(live_memberships(&mut conn).is_empty());
+
+            let restored = restore_book(&mut conn, book.id, "/manga/restored.cbz", "restored.cbz").unwrap();
+            assert!(restored.deleted_at.is_none());
+            assert!(restored.archive_type.is_none());
+            assert_eq!(live_memberships(&mut conn), vec![collection_ids[0]]);
+        }
+
+        #[test]
+        fn test_import_into_missing_collection_writes_nothing() {
+            use crate::database::operations::{insert_imported_book, soft_delete_book};
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let [target, deleted] = ["Target", "Deleted"].map(|name| create_test_collection(&mut conn, name).id);
+            diesel::update(collections::table.find(deleted))
+                .set(collections::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+
+            let new_book = |title: &str| NewBook {
+                uuid: test_uuid(),
+                file_path: format!("/manga/{}.cbz", title),
+                filename: format!("{}.cbz", title),
+                file_size: None,
+                file_hash: Some("abc123".to_string()),
+                title: title.to_string(),
+                current_page: 0,
+                total_pages: 10,
+                series_name: None,
+                volume_number: None,
+            };
+
+            assert!(
+                insert_imported_book(&mut conn, &new_book("Lost"), None, "zip", None, HashScheme::Content, Some(deleted))
+                    .is_err()
+            );
+            let count: i64 = books::table.count().get_result(&mut conn).unwrap();
+            assert_eq!(count, 0);
+
+            let book = insert_imported_book(&mut conn, &new_book("Kept"), None, "zip", Some(8), HashScheme::File, Some(target))
+                .unwrap();
+            assert_eq!(book.archive_type.as_deref(), Some("zip"));
+            assert_eq!(book.story_page_count, Some(8));
+            assert_eq!(book.hash_scheme, "file");
+
+            // Re-importing a deleted book relinks its old membership instead of adding a second one
+            soft_delete_book(&mut conn, book.id).unwrap();
+            let restored = insert_imported_book(
+                &mut conn,
+                &new_book("Kept again"),
+                Some(book.id),
+                "zip",
+                None,
+                HashScheme::File,
+                Some(target),
+            )
+            .unwrap();
+            assert_eq!(restored.file_path, "/manga/Kept again.cbz");
+            let links: Vec<Option<chrono::NaiveDateTime>> = book_collections::table
+                .filter(book_collections::book_id.eq(book.id))
+                .select(book_collections::deleted_at)
+                .load(&mut conn)
+                .unwrap();
+            assert_eq!(links, vec![None]);
+        }
+
         #[test]
         fn test_filter_by_reading_status() {
             let pool = setup_test_db();
@@ -503,6 +1568,8 @@ mod database_tests {
                     title: format!("Book {}", i),
                     current_page: 0,
                     total_pages: 100,
+                    series_name: None,
+                    volume_number: None,
                 };
 
                 let inserted: Book = diesel::insert_into(books::table)
@@ -586,6 +1653,129 @@ mod database_tests {
 
             assert!(found_active.is_none());
         }
+
+        #[test]
+        fn test_lookup_drive_file() {
+            use crate::database::operations::lookup_drive_file;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let book = create_test_book(&mut conn, "Drive File Test");
+
+            let info = lookup_drive_file(&mut conn, "book_ABC123.cbz").unwrap();
+            assert_eq!(info.status, DriveFileStatus::Local);
+            assert_eq!(info.book_id, Some(book.id));
+            assert_eq!(info.title.as_deref(), Some("Drive File Test"));
+
+            diesel::update(books::table.find(book.id))
+                .set(books::file_path.eq("cloud://abc123"))
+                .execute(&mut conn)
+                .unwrap();
+            let info = lookup_drive_file(&mut conn, "abc123").unwrap();
+            assert_eq!(info.status, DriveFileStatus::CloudOnly);
+            assert!(!info.file_exists);
+
+            diesel::update(books::table.find(book.id))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+            assert_eq!(lookup_drive_file(&mut conn, "abc123").unwrap().status, DriveFileStatus::Deleted);
+
+            let info = lookup_drive_file(&mut conn, "book_def456.cbz").unwrap();
+            assert_eq!(info.status, DriveFileStatus::Orphaned);
+            assert!(info.book_id.is_none());
+            assert_eq!(info.file_hash, lookup_drive_file(&mut conn, "personal_book_def456.cbz").unwrap().file_hash);
+
+            assert!(lookup_drive_file(&mut conn, "not a hash").is_err());
+        }
+
+        #[test]
+        fn test_books_by_hash_prefix() {
+            use crate::database::operations::books_by_hash_prefix;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            for (title, hash) in [("First", "abcdef0123"), ("Second", "abcdef9999"), ("Other", "123456abcd")] {
+                create_test_book_with(&mut conn, title, |b| b.file_hash = Some(hash.to_string()));
+            }
+
+            let titles = |books: Vec<Book>| books.into_iter().map(|b| b.title).collect::<Vec<_>>();
+            assert_eq!(titles(books_by_hash_prefix(&mut conn, " ABCDEF").unwrap()), vec!["First", "Second"]);
+            assert_eq!(titles(books_by_hash_prefix(&mut conn, "abcdef01").unwrap()), vec!["First"]);
+            assert!(books_by_hash_prefix(&mut conn, "fedcba").unwrap().is_empty());
+
+            // Too short to be useful, or a LIKE wildcard
+            for prefix in ["abcde", "abcde%", "abc_ef"] {
+                let err = books_by_hash_prefix(&mut conn, prefix).unwrap_err();
+                assert!(matches!(err.code, crate::error::ErrorCode::InvalidInput), "{}", prefix);
+            }
+        }
+
+        #[test]
+        fn test_link_local_files() {
+            use crate::database::operations::link_local_files;
+            use std::io::Write;
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_reconcile_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let write_cbz = |name: &str, page: &str| {
+                let path = dir.join(name);
+                let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+                zip.start_file(page, zip::write::SimpleFileOptions::default()).unwrap();
+                zip.write_all(page.as_bytes()).unwrap();
+                zip.finish().unwrap();
+                path
+            };
+            let sideloaded = write_cbz("sideloaded.cbz", "a.jpg");
+            let imported = write_cbz("imported.cbz", "b.jpg");
+            write_cbz("unrelated.cbz", "c.jpg");
+            std::fs::write(dir.join("notes.txt"), "a.jpg").unwrap();
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            // Cloud-only copies of the sideloaded file, the imported one and a file that isn't here
+            let mut cloud_books = Vec::new();
+            for (title, path) in [("Sideloaded", &sideloaded), ("Imported", &imported)] {
+                let hash = calculate_archive_hash(path).unwrap();
+                let book = create_test_book_with(&mut conn, title, |b| {
+                    b.file_path = format!("cloud://{}", hash);
+                    b.file_hash = Some(hash);
+                });
+                diesel::update(books::table.find(book.id))
+                    .set(books::archive_type.eq(Some("rar")))
+                    .execute(&mut conn)
+                    .unwrap();
+                cloud_books.push(book);
+            }
+            create_test_book_with(&mut conn, "Elsewhere", |b| {
+                b.file_path = "cloud://0123456789".to_string();
+                b.file_hash = Some("0123456789".to_string());
+            });
+            // Already in the library under its own path
+            create_test_book_with(&mut conn, "Local copy", |b| {
+                b.file_path = imported.to_string_lossy().to_string();
+            });
+
+            let linked = link_local_files(&mut conn, &dir).unwrap();
+            assert_eq!(linked.len(), 1);
+            assert_eq!(linked[0].id, cloud_books[0].id);
+            assert_eq!(linked[0].file_path, sideloaded.to_string_lossy());
+            assert!(linked[0].archive_type.is_none());
+
+            let still_cloud: Vec<String> = books::table
+                .filter(books::file_path.like("cloud://%"))
+                .select(books::title)
+                .order(books::title)
+                .load(&mut conn)
+                .unwrap();
+            assert_eq!(still_cloud, vec!["Elsewhere", "Imported"]);
+
+            // Nothing left to link
+            assert!(link_local_files(&mut conn, &dir).unwrap().is_empty());
+            let err = link_local_files(&mut conn, &dir.join("missing")).unwrap_err();
+            assert!(matches!(err.code, crate::error::ErrorCode::IoError));
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
     }
 
     // ========================================================================
@@ -605,6 +1795,8 @@ mod database_tests {
                 title: "Test Book".to_string(),
                 current_page: 0,
                 total_pages: 100,
+                series_name: None,
+                volume_number: None,
             };
 
             diesel::insert_into(books::table)
@@ -708,6 +1900,102 @@ mod database_tests {
 
             assert_eq!(remaining, 0, "Bookmarks should be cascade deleted");
         }
+
+        #[test]
+        fn test_bookmark_export_import_round_trip() {
+            use crate::database::operations::{import_bookmark_file, write_bookmark_export};
+
+            let path = std::env::temp_dir().join(format!("bookmarks_{}.json", uuid::Uuid::new_v4()));
+            let add_hashed_book = |conn: &mut SqliteConnection| {
+                let book = create_test_book(conn);
+                diesel::update(books::table.find(book.id))
+                    .set(books::file_hash.eq("bookmarkhash"))
+                    .execute(conn)
+                    .unwrap();
+                book
+            };
+
+            // Export from one library...
+            let source_pool = setup_test_db();
+            let mut source = source_pool.get().unwrap();
+            let source_book = add_hashed_book(&mut source);
+            for (name, page) in [("Opening", 3), ("Finale", 90)] {
+                diesel::insert_into(bookmarks::table)
+                    .values(&NewBookmark {
+                        uuid: test_uuid(),
+                        book_id: source_book.id,
+                        name: name.to_string(),
+                        description: None,
+                        page,
+                    })
+                    .execute(&mut source)
+                    .unwrap();
+            }
+            assert_eq!(write_bookmark_export(&mut source, source_book.id, &path).unwrap(), 2);
+
+            // ...into another that has the same book under a different ID
+            let target_pool = setup_test_db();
+            let mut target = target_pool.get().unwrap();
+            let other_book = create_test_book(&mut target);
+            diesel::update(books::table.find(other_book.id))
+                .set(books::file_path.eq("/path/to/other.cbz"))
+                .execute(&mut target)
+                .unwrap();
+            let target_book = add_hashed_book(&mut target);
+            assert_ne!(target_book.id, source_book.id);
+
+            let result = import_bookmark_file(&mut target, &path).unwrap();
+            assert_eq!(result.book_id, target_book.id);
+            assert_eq!(result.imported.len(), 2);
+            assert_eq!(result.skipped, 0);
+
+            // Importing the same file again only finds duplicates
+            let again = import_bookmark_file(&mut target, &path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert!(again.imported.is_empty());
+            assert_eq!(again.skipped, 2);
+        }
+
+        #[test]
+        fn test_bookmark_import_unknown_book() {
+            use crate::database::operations::import_bookmark_file;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            create_test_book(&mut conn);
+
+            let path = std::env::temp_dir().join(format!("bookmarks_{}.json", uuid::Uuid::new_v4()));
+            let export = crate::database::models::BookmarkExport {
+                version: crate::database::models::BookmarkExport::CURRENT_VERSION,
+                file_hash: "unknownhash".to_string(),
+                title: "Elsewhere".to_string(),
+                bookmarks: Vec::new(),
+            };
+            std::fs::write(&path, serde_json::to_string(&export).unwrap()).unwrap();
+
+            let err = import_bookmark_file(&mut conn, &path).unwrap_err();
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(err.code, crate::error::ErrorCode::InvalidInput));
+            assert!(err.message.contains("Elsewhere"));
+        }
+
+        #[test]
+        fn test_bookmark_export_rejects_unknown_or_unhashed_book() {
+            use crate::database::operations::write_bookmark_export;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let book = create_test_book(&mut conn);
+            let path = std::env::temp_dir().join(format!("bookmarks_{}.json", uuid::Uuid::new_v4()));
+
+            let err = write_bookmark_export(&mut conn, book.id + 1, &path).unwrap_err();
+            assert!(matches!(err.code, crate::error::ErrorCode::InvalidInput));
+
+            // A book without a content hash can't be matched on another install
+            let err = write_bookmark_export(&mut conn, book.id, &path).unwrap_err();
+            assert!(matches!(err.code, crate::error::ErrorCode::InvalidInput));
+            assert!(!path.exists());
+        }
     }
 
     // ========================================================================
@@ -727,6 +2015,8 @@ mod database_tests {
                 title: "Settings Test Book".to_string(),
                 current_page: 0,
                 total_pages: 100,
+                series_name: None,
+                volume_number: None,
             };
 
             diesel::insert_into(books::table)
@@ -750,6 +2040,7 @@ mod database_tests {
                 page_display_mode: Some("double".to_string()),
                 image_fit_mode: None,
                 sync_progress: Some(true),
+                reader_background: None,
             };
 
             let settings: BookSettings = diesel::insert_into(book_settings::table)
@@ -779,6 +2070,7 @@ mod database_tests {
                 page_display_mode: None,
                 image_fit_mode: None,
                 sync_progress: None,
+                reader_background: None,
             };
 
             diesel::insert_into(book_settings::table)
@@ -793,6 +2085,7 @@ mod database_tests {
                 page_display_mode: None,
                 image_fit_mode: None,
                 sync_progress: None,
+                reader_background: None,
             };
 
             let result = diesel::insert_into(book_settings::table)
@@ -817,6 +2110,7 @@ mod database_tests {
                     page_display_mode: None,
                     image_fit_mode: None,
                     sync_progress: None,
+                    reader_background: None,
                 })
                 .execute(&mut conn)
                 .unwrap();
@@ -860,6 +2154,7 @@ mod database_tests {
                     page_display_mode: None,
                     image_fit_mode: None,
                     sync_progress: None,
+                    reader_background: None,
                 })
                 .returning(BookSettings::as_returning())
                 .get_result(&mut conn)
@@ -885,14 +2180,168 @@ mod database_tests {
             assert_eq!(updated.reading_direction, Some("rtl".to_string()));
             assert_eq!(updated.sync_progress, Some(false));
         }
+
+        #[test]
+        fn test_reader_config_scroll_fraction() {
+            use crate::database::operations::reader_config;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let book = create_test_book(&mut conn);
+            diesel::update(books::table.find(book.id))
+                .set((
+                    books::current_page.eq(40),
+                    books::page_fraction.eq(Some(0.5)),
+                    books::scroll_fraction.eq(Some(0.42)),
+                ))
+                .execute(&mut conn)
+                .unwrap();
+
+            // Paged modes resume at the page only
+            let paged = reader_config(&mut conn, book.id, "rtl", "single", "fit_width", "black").unwrap();
+            assert_eq!((paged.current_page, paged.total_pages), (40, 100));
+            assert_eq!(paged.reading_direction, "rtl");
+            assert_eq!((paged.page_fraction, paged.scroll_fraction), (None, None));
+            assert_eq!(paged.reader_background, "black");
+
+            // A vertical override restores the exact scroll position
+            diesel::insert_into(book_settings::table)
+                .values(&NewBookSettings {
+                    uuid: test_uuid(),
+                    book_id: book.id,
+                    reading_direction: Some("vertical".to_string()),
+                    page_display_mode: None,
+                    image_fit_mode: Some("fit_screen".to_string()),
+                    sync_progress: None,
+                    reader_background: Some("sepia".to_string()),
+                })
+                .execute(&mut conn)
+                .unwrap();
+            let vertical = reader_config(&mut conn, book.id, "rtl", "single", "fit_width", "black").unwrap();
+            assert_eq!(vertical.reading_direction, "vertical");
+            assert_eq!(vertical.page_display_mode, "single");
+            assert_eq!(vertical.image_fit_mode, "fit_screen");
+            assert_eq!(vertical.reader_background, "sepia");
+            assert_eq!(vertical.page_fraction, Some(0.5));
+            assert_eq!(vertical.scroll_fraction, Some(0.42));
+
+            assert!(reader_config(&mut conn, book.id + 1, "rtl", "single", "fit_width", "black").is_err());
+        }
+
+        #[test]
+        fn test_set_collection_reading_direction() {
+            use crate::database::operations::apply_collection_reading_direction;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let collection = create_test_collection(&mut conn, "Manga");
+
+            let mut book_ids = Vec::new();
+            for name in ["with_settings", "without_settings", "deleted_settings", "deleted_book"] {
+                let book = create_test_book_with(&mut conn, name, |_| {});
+                link_test_book(&mut conn, book.id, collection.id);
+                book_ids.push(book.id);
+            }
+
+            for (book_id, deleted) in [(book_ids[0], false), (book_ids[2], true)] {
+                diesel::insert_into(book_settings::table)
+                    .values(&NewBookSettings {
+                        book_id,
+                        reading_direction: Some("ltr".to_string()),
+                        page_display_mode: Some("double".to_string()),
+                        image_fit_mode: None,
+                        sync_progress: None,
+                        reader_background: None,
+                        uuid: test_uuid(),
+                    })
+                    .execute(&mut conn)
+                    .unwrap();
+                if deleted {
+                    diesel::update(book_settings::table.filter(book_settings::book_id.eq(book_id)))
+                        .set(book_settings::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                        .execute(&mut conn)
+                        .unwrap();
+                }
+            }
+            diesel::update(books::table.find(book_ids[3]))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+
+            assert!(apply_collection_reading_direction(&mut conn, collection.id, "sideways").is_err());
+            let updated = apply_collection_reading_direction(&mut conn, collection.id, "rtl").unwrap();
+            assert_eq!(updated, 3);
+
+            let settings: Vec<BookSettings> = book_settings::table
+                .filter(book_settings::deleted_at.is_null())
+                .order(book_settings::book_id.asc())
+                .load(&mut conn)
+                .unwrap();
+            assert_eq!(settings.len(), 3);
+            assert!(settings.iter().all(|s| s.reading_direction.as_deref() == Some("rtl")));
+            // Other overrides survive on live rows but not on revived ones
+            assert_eq!(settings[0].page_display_mode.as_deref(), Some("double"));
+            assert_eq!(settings[2].page_display_mode, None);
+            assert!(settings.iter().all(|s| s.book_id != book_ids[3]));
+        }
     }
 
     // ========================================================================
     // QUERY TESTS
     // ========================================================================
 
-    mod query_tests {
-        use super::*;
+    mod query_tests {
+        use super::*;
+
+        #[test]
+        fn test_favorites_without_collection() {
+            use crate::database::operations::favorites_without_collection;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let [shelf, old_shelf] = ["Shelf", "Old shelf"].map(|name| create_test_collection(&mut conn, name).id);
+            diesel::update(collections::table.find(old_shelf))
+                .set(collections::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+
+            // (title, favorite, book deleted, collection, link deleted)
+            let layout = [
+                ("Filed", true, false, Some(shelf), false),
+                ("Loose", true, false, None, false),
+                ("Unlinked", true, false, Some(shelf), true),
+                ("Orphaned", true, false, Some(old_shelf), false),
+                ("Plain", false, false, None, false),
+                ("Trashed", true, true, None, false),
+            ];
+            for (title, favorite, deleted, collection_id, unlinked) in layout {
+                let book = create_test_book_with(&mut conn, title, |_| {});
+                diesel::update(books::table.find(book.id))
+                    .set((
+                        books::is_favorite.eq(favorite),
+                        books::deleted_at.eq(deleted.then(|| chrono::Utc::now().naive_utc())),
+                    ))
+                    .execute(&mut conn)
+                    .unwrap();
+                if let Some(collection_id) = collection_id {
+                    link_test_book(&mut conn, book.id, collection_id);
+                    diesel::update(book_collections::table.filter(book_collections::book_id.eq(book.id)))
+                        .set(book_collections::deleted_at.eq(unlinked.then(|| chrono::Utc::now().naive_utc())))
+                        .execute(&mut conn)
+                        .unwrap();
+                }
+            }
+
+            let titles: Vec<String> = favorites_without_collection(&mut conn)
+                .unwrap()
+                .into_iter()
+                .map(|b| b.book.title)
+                .collect();
+            assert_eq!(titles, vec!["Loose", "Orphaned", "Unlinked"]);
+        }
 
         #[test]
         fn test_books_ordered_by_last_read() {
@@ -912,6 +2361,8 @@ mod database_tests {
                         title: format!("Book {}", i),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -963,6 +2414,8 @@ mod database_tests {
                         title: format!("Collection Book {}", i),
                         current_page: 0,
                         total_pages: 50,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -1005,6 +2458,8 @@ mod database_tests {
                         title: title.to_string(),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .execute(&mut conn)
                     .unwrap();
@@ -1018,6 +2473,53 @@ mod database_tests {
             assert_eq!(results.len(), 2);
         }
 
+        #[test]
+        fn test_search_books_ranking() {
+            use crate::database::operations::find_books_matching;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let titles = ["One Punch Man", "Someone's Diary", "One Piece", "100% Orange", "100 Orange", "Naruto"];
+            for (i, title) in titles.iter().enumerate() {
+                // Neutral file names, so only titles decide the ranking
+                create_test_book_with(&mut conn, title, |b| {
+                    b.file_path = format!("/manga/rank{}.cbz", i);
+                    b.filename = format!("rank{}.cbz", i);
+                    b.total_pages = 100;
+                });
+            }
+
+            let titles_for = |conn: &mut SqliteConnection, query: &str| -> Vec<String> {
+                find_books_matching(conn, query, 50)
+                    .unwrap()
+                    .into_iter()
+                    .map(|b| b.book.title)
+                    .collect()
+            };
+
+            // Both series match, prefixes rank above the substring match
+            assert_eq!(
+                titles_for(&mut conn, "one"),
+                vec!["One Piece", "One Punch Man", "Someone's Diary"]
+            );
+
+            // The exact title ranks first
+            assert_eq!(titles_for(&mut conn, "ONE PIECE"), vec!["One Piece"]);
+            assert_eq!(titles_for(&mut conn, "one p")[0], "One Piece");
+
+            // Wildcards in the query are literal
+            assert_eq!(titles_for(&mut conn, "100%"), vec!["100% Orange"]);
+            assert!(titles_for(&mut conn, "_").is_empty());
+
+            // Filename matches count too
+            assert_eq!(titles_for(&mut conn, "rank5"), vec!["Naruto"]);
+
+            let limited = find_books_matching(&mut conn, "one", 1).unwrap();
+            assert_eq!(limited.len(), 1);
+            assert!(find_books_matching(&mut conn, "  ", 10).is_err());
+        }
+
         #[test]
         fn test_favorites_query() {
             let pool = setup_test_db();
@@ -1034,6 +2536,8 @@ mod database_tests {
                         title: format!("Book {}", i),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -1073,6 +2577,8 @@ mod database_tests {
                         title: title.to_string(),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .execute(&mut conn)
                     .unwrap();
@@ -1115,6 +2621,8 @@ mod database_tests {
                         title: format!("Added Book {}", i),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -1156,6 +2664,8 @@ mod database_tests {
                         title: format!("Last Read Test {}", i),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -1180,6 +2690,43 @@ mod database_tests {
             assert_eq!(last_read.unwrap().title, "Last Read Test 0");
         }
 
+        #[test]
+        fn test_last_read_book_skips_completed() {
+            use crate::database::operations::last_read_book;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let now = chrono::Utc::now().naive_utc();
+
+            // Most recent book is completed, the older one is still in progress
+            for (i, status) in ["completed", "reading"].iter().enumerate() {
+                let book = create_test_book_with(&mut conn, &format!("Resume Test {}", i), |b| {
+                    b.current_page = 20;
+                    b.total_pages = 100;
+                });
+
+                diesel::update(books::table.find(book.id))
+                    .set((
+                        books::last_read_at.eq(Some(now - chrono::Duration::hours(i as i64))),
+                        books::reading_status.eq(*status),
+                    ))
+                    .execute(&mut conn)
+                    .unwrap();
+            }
+
+            let resume = last_read_book(&mut conn).unwrap().unwrap();
+            assert_eq!(resume.book.book.title, "Resume Test 1");
+            assert_eq!(resume.resume_page, 20);
+
+            // Progress past the end of the archive resumes on its last page
+            diesel::update(books::table.find(resume.book.book.id))
+                .set(books::current_page.eq(150))
+                .execute(&mut conn)
+                .unwrap();
+            assert_eq!(last_read_book(&mut conn).unwrap().unwrap().resume_page, 99);
+        }
+
         #[test]
         fn test_get_recently_read_books() {
             let pool = setup_test_db();
@@ -1198,6 +2745,8 @@ mod database_tests {
                         title: format!("Recent Book {}", i),
                         current_page: 0,
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -1242,6 +2791,8 @@ mod database_tests {
                         title: format!("Stale Book {}", i),
                         current_page: 10, // Started reading
                         total_pages: 100,
+                        series_name: None,
+                        volume_number: None,
                     })
                     .returning(Book::as_returning())
                     .get_result(&mut conn)
@@ -1274,6 +2825,44 @@ mod database_tests {
             assert_eq!(stale.len(), 2);
         }
 
+        #[test]
+        fn test_stale_in_progress() {
+            use crate::database::operations::stale_in_progress;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+            let now = chrono::Utc::now().naive_utc();
+
+            // (title, status, days since last read, deleted)
+            let layout = [
+                ("Recent", "reading", Some(2), false),
+                ("Oldest", "reading", Some(60), false),
+                ("Older", "reading", Some(30), false),
+                ("Never opened", "reading", None, false),
+                ("Finished", "completed", Some(90), false),
+                ("Removed", "reading", Some(90), true),
+            ];
+            for (title, status, days_ago, deleted) in layout {
+                let book = create_test_book_with(&mut conn, title, |b| b.current_page = 3);
+                diesel::update(books::table.find(book.id))
+                    .set((
+                        books::reading_status.eq(status),
+                        books::last_read_at.eq(days_ago.map(|days| now - chrono::Duration::days(days))),
+                        books::deleted_at.eq(deleted.then_some(now)),
+                    ))
+                    .execute(&mut conn)
+                    .unwrap();
+            }
+
+            let titles = |books: Vec<BookWithDetails>| books.into_iter().map(|b| b.book.title).collect::<Vec<_>>();
+            assert_eq!(titles(stale_in_progress(&mut conn, now, 14, 10).unwrap()), vec!["Oldest", "Older"]);
+            assert_eq!(titles(stale_in_progress(&mut conn, now, 14, 1).unwrap()), vec!["Oldest"]);
+            assert_eq!(titles(stale_in_progress(&mut conn, now, 0, 10).unwrap()), vec!["Oldest", "Older", "Recent"]);
+
+            assert!(stale_in_progress(&mut conn, now, -1, 10).is_err());
+            assert!(stale_in_progress(&mut conn, now, 14, 0).is_err());
+        }
+
         #[test]
         fn test_add_remove_book_from_collection() {
             let pool = setup_test_db();
@@ -1299,6 +2888,8 @@ mod database_tests {
                     title: "Add Remove Test".to_string(),
                     current_page: 0,
                     total_pages: 100,
+                    series_name: None,
+                    volume_number: None,
                 })
                 .returning(Book::as_returning())
                 .get_result(&mut conn)
@@ -1340,4 +2931,598 @@ mod database_tests {
             assert_eq!(count_after, 0);
         }
     }
+
+    // ========================================================================
+    // READING ACTIVITY TESTS
+    // ========================================================================
+
+    mod activity_tests {
+        use super::*;
+        use crate::database::operations::{activity_by_day, insert_reading_session, pages_read, reading_stats};
+
+        #[test]
+        fn test_reading_stats_per_local_day() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let book = create_test_book_with(&mut conn, "Stats", |b| b.total_pages = 100);
+
+            let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+            let mut record = |page_from: i32, page_to: i32, start: &str, end: &str| {
+                insert_reading_session(
+                    &mut conn,
+                    NewReadingSession {
+                        book_id: book.id,
+                        page_from,
+                        page_to,
+                        started_at: at(start),
+                        ended_at: at(end),
+                    },
+                )
+            };
+            record(0, 10, "2026-03-10 10:00", "2026-03-10 10:30").unwrap();
+            record(10, 25, "2026-03-10 20:00", "2026-03-10 20:15").unwrap();
+            // Paging back counts no pages but still counts the time
+            record(25, 20, "2026-03-11 07:00", "2026-03-11 07:06").unwrap();
+            // Too old for the 30-day window
+            record(0, 5, "2026-01-01 10:00", "2026-01-01 10:10").unwrap();
+            assert!(record(0, 5, "2026-03-11 07:00", "2026-03-11 06:00").is_err());
+
+            let stats = reading_stats(&mut conn, at("2026-03-11 08:00"), 0).unwrap();
+            assert_eq!(stats.len(), 30);
+            let last_two: Vec<_> = stats[28..].iter().map(|d| (d.pages_read, d.minutes_read)).collect();
+            assert_eq!(last_two, vec![(25, 45.0), (0, 6.0)]);
+            assert_eq!(stats.iter().map(|d| d.pages_read).sum::<i64>(), 25);
+
+            // At UTC+5 the evening session falls on the next day
+            let stats = reading_stats(&mut conn, at("2026-03-11 08:00"), 300).unwrap();
+            assert_eq!((stats[28].pages_read, stats[29].pages_read), (10, 15));
+        }
+
+        #[test]
+        fn test_pages_read_counts_overlapping_sessions_once() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let mut book_ids = Vec::new();
+            for (title, status) in [("Current", "reading"), ("Finished", "completed")] {
+                let book = create_test_book_with(&mut conn, title, |b| b.total_pages = 200);
+                diesel::update(books::table.find(book.id))
+                    .set(books::reading_status.eq(status))
+                    .execute(&mut conn)
+                    .unwrap();
+                book_ids.push(book.id);
+            }
+            let (current, finished) = (book_ids[0], book_ids[1]);
+
+            let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+            let mut record = |book_id: i32, page_from: i32, page_to: i32, start: &str, end: &str| {
+                insert_reading_session(
+                    &mut conn,
+                    NewReadingSession { book_id, page_from, page_to, started_at: at(start), ended_at: at(end) },
+                )
+                .unwrap();
+            };
+            // Two devices open at the same time, covering pages 0-30 between them
+            record(current, 0, 20, "2026-03-10 10:00", "2026-03-10 10:30");
+            record(current, 10, 30, "2026-03-10 10:10", "2026-03-10 10:40");
+            // A later re-read of the same pages counts again
+            record(current, 0, 10, "2026-03-11 10:00", "2026-03-11 10:10");
+            // Paging back counts nothing
+            record(finished, 50, 40, "2026-03-10 12:00", "2026-03-10 12:05");
+            record(finished, 40, 100, "2026-03-10 12:05", "2026-03-10 13:00");
+            // Before the window
+            record(finished, 0, 40, "2026-02-01 12:00", "2026-02-01 13:00");
+
+            let read = pages_read(&mut conn, at("2026-03-01 00:00")).unwrap();
+            assert_eq!(read.total, 100);
+            assert_eq!(read.by_status.get("reading"), Some(&40));
+            assert_eq!(read.by_status.get("completed"), Some(&60));
+            assert_eq!(read.by_status.get("unread"), None);
+
+            // The daily stats merge the same sessions, overlapping time included
+            let stats = reading_stats(&mut conn, at("2026-03-11 12:00"), 0).unwrap();
+            assert_eq!(stats.iter().map(|d| d.pages_read).sum::<i64>(), read.total);
+            let day = stats.iter().find(|d| d.date == at("2026-03-10 00:00").date()).unwrap();
+            assert_eq!((day.pages_read, day.minutes_read), (90, 100.0));
+
+            assert_eq!(pages_read(&mut conn, at("2026-04-01 00:00")).unwrap(), PagesRead::default());
+        }
+
+        #[test]
+        fn test_activity_by_day_uses_local_days() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+            let reads = [
+                ("a", Some(at("2026-03-10 12:00"))),
+                ("b", Some(at("2026-03-10 23:30"))), // Already March 11 at UTC+2
+                ("c", Some(at("2026-03-08 09:00"))),
+                ("d", Some(at("2026-02-01 09:00"))), // Outside the range
+                ("e", None),
+            ];
+            for (title, last_read_at) in reads {
+                create_test_book_with(&mut conn, title, |_| {});
+                diesel::update(books::table.filter(books::title.eq(title)))
+                    .set(books::last_read_at.eq(last_read_at))
+                    .execute(&mut conn)
+                    .unwrap();
+            }
+
+            let now = at("2026-03-11 08:00");
+            let mut counts = |offset: i32| {
+                activity_by_day(&mut conn, now, 5, offset)
+                    .unwrap()
+                    .into_iter()
+                    .map(|day| (day.date.format("%m-%d").to_string(), day.count))
+                    .collect::<Vec<_>>()
+            };
+
+            let utc = counts(0);
+            assert_eq!(
+                utc,
+                vec![
+                    ("03-07".to_string(), 0),
+                    ("03-08".to_string(), 1),
+                    ("03-09".to_string(), 0),
+                    ("03-10".to_string(), 2),
+                    ("03-11".to_string(), 0),
+                ]
+            );
+            let east = counts(120);
+            assert_eq!((east[3].1, east[4].1), (1, 1));
+
+            assert!(activity_by_day(&mut conn, now, 0, 0).is_err());
+            assert!(activity_by_day(&mut conn, now, 30, 15 * 60).is_err());
+        }
+    }
+
+    // ========================================================================
+    // MAINTENANCE TESTS
+    // ========================================================================
+
+    mod maintenance_tests {
+        use super::*;
+        use crate::database::operations::{
+            backfill_sort_keys, book_sort_key, check_library_consistency, clamp_progress,
+            backfill_hashes, calculate_archive_hash, calculate_file_hash, find_archives_in_dir,
+            relocate_library_files, verify_archive,
+        };
+        use diesel::connection::SimpleConnection;
+
+        #[test]
+        fn test_library_consistency_cleanup() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let collection = create_test_collection(&mut conn, "Consistency");
+
+            let mut book_ids = Vec::new();
+            for i in 0..3 {
+                let book = create_test_book_with(&mut conn, &format!("Consistency {}", i), |b| b.total_pages = 100);
+
+                link_test_book(&mut conn, book.id, collection.id);
+
+                diesel::insert_into(bookmarks::table)
+                    .values(&NewBookmark {
+                        uuid: test_uuid(),
+                        book_id: book.id,
+                        name: "Mark".to_string(),
+                        description: None,
+                        page: 1,
+                    })
+                    .execute(&mut conn)
+                    .unwrap();
+
+                book_ids.push(book.id);
+            }
+
+            // Book 0 is hard-deleted behind the app's back (no cascade), book 1 is soft-deleted
+            conn.batch_execute("PRAGMA foreign_keys = OFF;").unwrap();
+            diesel::delete(books::table.find(book_ids[0]))
+                .execute(&mut conn)
+                .unwrap();
+            diesel::update(books::table.find(book_ids[1]))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+
+            let report = check_library_consistency(&mut conn, false).unwrap();
+            assert_eq!(report.orphaned_book_collections.len(), 2);
+            assert_eq!(report.orphaned_bookmarks.len(), 1);
+            assert!(!report.cleaned_up);
+
+            let report = check_library_consistency(&mut conn, true).unwrap();
+            assert!(report.cleaned_up);
+
+            // Hard-deleted book's rows are gone, soft-deleted book's link is soft-deleted
+            let links: Vec<BookCollection> = book_collections::table.load(&mut conn).unwrap();
+            assert_eq!(links.len(), 2);
+            assert!(links.iter().any(|l| l.book_id == book_ids[1] && l.deleted_at.is_some()));
+            assert!(links.iter().any(|l| l.book_id == book_ids[2] && l.deleted_at.is_none()));
+
+            let bookmark_count: i64 = bookmarks::table.count().get_result(&mut conn).unwrap();
+            assert_eq!(bookmark_count, 2);
+
+            let report = check_library_consistency(&mut conn, false).unwrap();
+            assert!(report.is_consistent());
+        }
+
+        #[test]
+        fn test_move_library_files() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let root = std::env::temp_dir().join(format!("yomiyougu_move_{}", uuid::Uuid::new_v4()));
+            let old_dir = root.join("old");
+            let new_dir = root.join("new");
+            std::fs::create_dir_all(&old_dir).unwrap();
+
+            let managed_path = old_dir.join("managed.cbz");
+            std::fs::write(&managed_path, b"archive").unwrap();
+
+            for path in [managed_path.to_string_lossy().to_string(), "/elsewhere/external.cbz".to_string()] {
+                create_test_book_with(&mut conn, "Movable", |b| {
+                    b.filename = "book.cbz".to_string();
+                    b.file_path = path;
+                });
+            }
+
+            let moved = relocate_library_files(&mut conn, &old_dir, &new_dir).unwrap();
+            assert_eq!(moved, 1);
+            assert!(!managed_path.exists());
+            assert_eq!(std::fs::read(new_dir.join("managed.cbz")).unwrap(), b"archive");
+
+            let paths: Vec<String> = books::table
+                .select(books::file_path)
+                .order(books::id.asc())
+                .load(&mut conn)
+                .unwrap();
+            assert_eq!(paths[0], new_dir.join("managed.cbz").to_string_lossy());
+            assert_eq!(paths[1], "/elsewhere/external.cbz");
+
+            // Moving into a nested directory is refused
+            assert!(relocate_library_files(&mut conn, &new_dir, &new_dir.join("sub")).is_err());
+
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[test]
+        fn test_clamp_invalid_progress() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            for (i, (current_page, total_pages)) in [(150, 100), (-3, 100), (42, 100), (5, 0)].into_iter().enumerate() {
+                create_test_book_with(&mut conn, &format!("Clamp {}", i), |b| {
+                    b.current_page = current_page;
+                    b.total_pages = total_pages;
+                });
+            }
+
+            assert_eq!(clamp_progress(&mut conn).unwrap(), 2);
+
+            let pages: Vec<i32> = books::table
+                .select(books::current_page)
+                .order(books::id.asc())
+                .load(&mut conn)
+                .unwrap();
+            // Books with an unknown page count are left alone
+            assert_eq!(pages, vec![99, 0, 42, 5]);
+
+            assert_eq!(clamp_progress(&mut conn).unwrap(), 0);
+        }
+
+        #[test]
+        fn test_backfill_sort_keys() {
+            assert_eq!(book_sort_key("The  Wandering Inn Vol 2"), "wandering inn vol 00000002");
+            assert_eq!(book_sort_key("A"), "a");
+            assert!(book_sort_key("Berserk 2") < book_sort_key("berserk 010"));
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            for title in ["Vol 10", "Vol 2", "An Apple"] {
+                create_test_book_with(&mut conn, title, |_| {});
+            }
+
+            assert_eq!(backfill_sort_keys(&mut conn).unwrap(), 3);
+            assert_eq!(backfill_sort_keys(&mut conn).unwrap(), 0);
+
+            let titles: Vec<String> = books::table
+                .order(books::sort_key.asc())
+                .select(books::title)
+                .load(&mut conn)
+                .unwrap();
+            assert_eq!(titles, vec!["An Apple", "Vol 2", "Vol 10"]);
+        }
+
+        #[test]
+        fn test_find_archives_in_dir() {
+            let root = std::env::temp_dir().join(format!("yomiyougu_scan_{}", uuid::Uuid::new_v4()));
+            let nested = root.join("Series").join("Extras");
+            std::fs::create_dir_all(&nested).unwrap();
+
+            for path in [
+                root.join("Vol 10.cbz"),
+                root.join("Vol 2.CBR"),
+                root.join("cover.jpg"),
+                root.join("notes.txt"),
+                root.join("Series").join("Vol 1.7z"),
+                nested.join("Omake.zip"),
+                nested.join("info.nfo"),
+            ] {
+                std::fs::write(&path, b"data").unwrap();
+            }
+            // A directory named like an archive is not picked up as one
+            std::fs::create_dir_all(root.join("folder.cbz")).unwrap();
+            // Nor is a symlink back up the tree followed
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&root, nested.join("loop")).unwrap();
+
+            let names = |paths: Vec<std::path::PathBuf>| -> Vec<String> {
+                paths
+                    .iter()
+                    .map(|p| p.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+                    .collect()
+            };
+
+            let top_level = find_archives_in_dir(&root, false).unwrap();
+            assert_eq!(names(top_level), vec!["Vol 2.CBR", "Vol 10.cbz"]);
+
+            let all = find_archives_in_dir(&root, true).unwrap();
+            assert_eq!(
+                names(all),
+                vec!["Series/Extras/Omake.zip", "Series/Vol 1.7z", "Vol 2.CBR", "Vol 10.cbz"]
+            );
+
+            assert!(find_archives_in_dir(&root.join("missing"), true).is_err());
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[test]
+        fn test_backfill_hashes() {
+            use std::io::Write;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_backfill_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+
+            let write_zip = |name: &str, method: zip::CompressionMethod, pages: &[&[u8]]| {
+                let path = dir.join(name);
+                let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+                let options = zip::write::SimpleFileOptions::default().compression_method(method);
+                for (i, data) in pages.iter().enumerate() {
+                    zip.start_file(format!("{:03}.jpg", i), options).unwrap();
+                    zip.write_all(data).unwrap();
+                }
+                zip.finish().unwrap();
+                path
+            };
+            // The same pages packed two ways, plus a different book
+            let original = write_zip("original.cbz", zip::CompressionMethod::Stored, &[b"page one", b"page two"]);
+            let repacked = write_zip("repacked.cbz", zip::CompressionMethod::Deflated, &[b"page one", b"page two"]);
+            let other = write_zip("other.cbz", zip::CompressionMethod::Stored, &[b"another page"]);
+
+            assert_ne!(calculate_file_hash(&original).unwrap(), calculate_file_hash(&repacked).unwrap());
+            assert_eq!(calculate_archive_hash(&original).unwrap(), calculate_archive_hash(&repacked).unwrap());
+
+            let mut insert = |path: &std::path::Path, scheme: HashScheme| -> Book {
+                let hash = match scheme {
+                    HashScheme::Content => calculate_archive_hash(path).unwrap(),
+                    HashScheme::File => calculate_file_hash(path).unwrap(),
+                };
+                let book = create_test_book_with(&mut conn, &path.file_stem().unwrap().to_string_lossy(), |b| {
+                    b.file_path = path.to_string_lossy().to_string();
+                    b.filename = path.file_name().unwrap().to_string_lossy().to_string();
+                    b.file_hash = Some(hash);
+                    b.total_pages = 1;
+                });
+                diesel::update(books::table.find(book.id))
+                    .set(books::hash_scheme.eq(scheme.as_str()))
+                    .returning(Book::as_returning())
+                    .get_result(&mut conn)
+                    .unwrap()
+            };
+            let original_book = insert(&original, HashScheme::Content);
+            let repacked_book = insert(&repacked, HashScheme::File);
+            let other_book = insert(&other, HashScheme::File);
+            assert_eq!(repacked_book.hash_scheme(), HashScheme::File);
+
+            let result = backfill_hashes(&mut conn).unwrap();
+            assert_eq!((result.hashed, result.failed), (1, 0));
+            assert_eq!(result.duplicates.len(), 1);
+            assert_eq!(result.duplicates[0].book_id, repacked_book.id);
+            assert_eq!(result.duplicates[0].existing_book_id, original_book.id);
+
+            let reload = |conn: &mut SqliteConnection, id: i32| -> Book {
+                books::table.find(id).select(Book::as_select()).first(conn).unwrap()
+            };
+            let other_book = reload(&mut conn, other_book.id);
+            assert_eq!(other_book.hash_scheme(), HashScheme::Content);
+            assert_eq!(other_book.file_hash, Some(calculate_archive_hash(&other).unwrap()));
+
+            // The duplicate keeps its file hash
+            let repacked_book = reload(&mut conn, repacked_book.id);
+            assert_eq!(repacked_book.hash_scheme(), HashScheme::File);
+            assert_eq!(repacked_book.file_hash, Some(calculate_file_hash(&repacked).unwrap()));
+            assert_eq!(repacked_book.content_duplicate_of, Some(original_book.id));
+
+            // It isn't hashed or reported again while the original is in the library
+            let result = backfill_hashes(&mut conn).unwrap();
+            assert_eq!((result.hashed, result.failed, result.duplicates.len()), (0, 0, 0));
+
+            // Once the original is deleted, the duplicate takes over its content hash
+            diesel::update(books::table.find(original_book.id))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+            let result = backfill_hashes(&mut conn).unwrap();
+            assert_eq!((result.hashed, result.duplicates.len()), (1, 0));
+            let repacked_book = reload(&mut conn, repacked_book.id);
+            assert_eq!(repacked_book.hash_scheme(), HashScheme::Content);
+            assert_eq!(repacked_book.content_duplicate_of, None);
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn test_verify_archive() {
+            use std::io::Write;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_verify_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let archive_path = dir.join("verify.cbz");
+
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            for (name, data) in [("001.jpg", b"first page"), ("002.jpg", b"other page")] {
+                zip.start_file(name, options).unwrap();
+                zip.write_all(data).unwrap();
+            }
+            zip.finish().unwrap();
+
+            let book = create_test_book_with(&mut conn, "Verify", |b| {
+                b.file_path = archive_path.to_string_lossy().to_string();
+                b.filename = "verify.cbz".to_string();
+                b.total_pages = 2;
+            });
+
+            assert!(verify_archive(&book).is_ok());
+
+            let mismatched = Book { total_pages: 5, ..book.clone() };
+            assert_eq!(
+                verify_archive(&mismatched).problems,
+                vec![ArchiveProblem::PageCountMismatch { stored: 5, actual: 2 }]
+            );
+
+            // Flip a byte of the second page's stored data so its CRC no longer matches
+            let mut bytes = std::fs::read(&archive_path).unwrap();
+            let offset = bytes.windows(10).position(|w| w == b"other page").unwrap();
+            bytes[offset] ^= 0xFF;
+            std::fs::write(&archive_path, &bytes).unwrap();
+
+            let problems = verify_archive(&book).problems;
+            assert!(matches!(
+                problems.as_slice(),
+                [ArchiveProblem::CorruptEntry { entry, .. }] if entry == "002.jpg"
+            ));
+
+            std::fs::remove_dir_all(&dir).unwrap();
+            assert_eq!(verify_archive(&book).problems, vec![ArchiveProblem::MissingFile]);
+
+            // Cloud-only books have no local file to check
+            let cloud = Book { file_path: "cloud://abc".to_string(), ..book };
+            assert!(verify_archive(&cloud).is_ok());
+        }
+
+        #[test]
+        fn test_audit_pages() {
+            use crate::database::operations::audit_pages;
+            use std::io::Write;
+
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let dir = std::env::temp_dir().join(format!("yomiyougu_audit_{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let archive_path = dir.join("audit.cbz");
+
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for name in ["001.jpg", "002.jpg", "003.jpg"] {
+                zip.start_file(name, options).unwrap();
+                zip.write_all(b"page").unwrap();
+            }
+            zip.finish().unwrap();
+
+            let book = create_test_book_with(&mut conn, "Audit", |b| {
+                b.file_path = archive_path.to_string_lossy().to_string();
+                b.filename = "audit.cbz".to_string();
+                b.total_pages = 3;
+            });
+
+            let inflated = Book { id: book.id + 1, total_pages: 7, ..book.clone() };
+            let missing = Book {
+                id: book.id + 2,
+                file_path: dir.join("gone.cbz").to_string_lossy().to_string(),
+                ..book.clone()
+            };
+            let cloud = Book { id: book.id + 3, file_path: "cloud://abc".to_string(), ..book.clone() };
+
+            let audit = audit_pages(&[book.clone(), inflated.clone(), missing.clone(), cloud]);
+            assert_eq!(audit.checked, 2);
+            assert_eq!(
+                audit.mismatches,
+                vec![PageCountMismatch {
+                    book_id: inflated.id,
+                    title: "Audit".to_string(),
+                    stored_pages: 7,
+                    actual_pages: 3,
+                }]
+            );
+            assert_eq!(audit.unreadable.len(), 1);
+            assert_eq!(audit.unreadable[0].book_id, missing.id);
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    mod queue_tests {
+        use super::*;
+        use crate::database::operations::{dequeue, enqueue, load_queue, reorder};
+
+        fn queued_ids(conn: &mut SqliteConnection) -> Vec<i32> {
+            load_queue(conn).unwrap().into_iter().map(|q| q.book.id).collect()
+        }
+
+        #[test]
+        fn test_enqueue_reorder_dequeue() {
+            let pool = setup_test_db();
+            let mut conn = pool.get().unwrap();
+
+            let mut ids = Vec::new();
+            for i in 0..3 {
+                let book = create_test_book_with(&mut conn, &format!("Queue {}", i), |_| {});
+                enqueue(&mut conn, book.id).unwrap();
+                ids.push(book.id);
+            }
+            assert_eq!(queued_ids(&mut conn), ids);
+
+            // Enqueuing again keeps the existing place
+            let again = enqueue(&mut conn, ids[0]).unwrap();
+            assert_eq!(again.position, 0);
+
+            // Unlisted books follow the listed ones in their previous order
+            reorder(&mut conn, &[ids[2]]).unwrap();
+            assert_eq!(queued_ids(&mut conn), vec![ids[2], ids[0], ids[1]]);
+            assert!(reorder(&mut conn, &[9999]).is_err());
+
+            // Listing a book twice is rejected and leaves the order alone
+            let err = reorder(&mut conn, &[ids[1], ids[0], ids[1]]).unwrap_err();
+            assert!(matches!(err.code, crate::error::ErrorCode::InvalidInput));
+            assert_eq!(queued_ids(&mut conn), vec![ids[2], ids[0], ids[1]]);
+
+            // A removed book comes back at the end, reusing its entry
+            let original = enqueue(&mut conn, ids[2]).unwrap();
+            dequeue(&mut conn, ids[2]).unwrap();
+            assert_eq!(queued_ids(&mut conn), vec![ids[0], ids[1]]);
+            let revived = enqueue(&mut conn, ids[2]).unwrap();
+            assert_eq!(revived.uuid, original.uuid);
+            assert_eq!(queued_ids(&mut conn), vec![ids[0], ids[1], ids[2]]);
+
+            // Deleted books drop out of the listing
+            diesel::update(books::table.find(ids[0]))
+                .set(books::deleted_at.eq(Some(chrono::Utc::now().naive_utc())))
+                .execute(&mut conn)
+                .unwrap();
+            assert_eq!(queued_ids(&mut conn), vec![ids[1], ids[2]]);
+        }
+    }
 }
@@ -6,6 +6,9 @@ use std::fmt;
 pub struct AppError {
     pub code: ErrorCode,
     pub message: String,
+    /// Individual problems when one error reports several (e.g. a settings form)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Clone, Copy)]
@@ -28,6 +31,14 @@ pub enum ErrorCode {
     DuplicateEntry,
     NotAuthenticated,
     SyncFailed,
+    /// The sync snapshot is encrypted and the passphrase is missing or wrong
+    SyncPassphraseRequired,
+    /// A cloud-only book's file is not stored on Drive
+    CloudFileMissing,
+    /// The archive is encrypted and the password is missing or wrong
+    ArchiveEncrypted,
+    InvalidInput,
+    CredentialsUnreadable,
 }
 
 impl AppError {
@@ -35,6 +46,7 @@ impl AppError {
         Self {
             code,
             message: message.into(),
+            details: Vec::new(),
         }
     }
 
@@ -84,10 +96,33 @@ impl AppError {
         )
     }
 
+    /// Combine several setting errors into one, keeping each message as a detail
+    pub fn invalid_settings(errors: Vec<AppError>) -> Self {
+        let all_keys = errors
+            .iter()
+            .all(|e| matches!(e.code, ErrorCode::InvalidSettingKey));
+        Self {
+            code: if all_keys {
+                ErrorCode::InvalidSettingKey
+            } else {
+                ErrorCode::InvalidSettingValue
+            },
+            message: format!("{} invalid setting(s)", errors.len()),
+            details: errors.into_iter().map(|e| e.message).collect(),
+        }
+    }
+
     pub fn not_authenticated() -> Self {
         Self::new(ErrorCode::NotAuthenticated, "Not authenticated with Google")
     }
 
+    pub fn credentials_unreadable(err: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorCode::CredentialsUnreadable,
+            format!("Stored credentials can't be decrypted, please sign in again: {}", err),
+        )
+    }
+
     pub fn sync_failed(err: impl fmt::Display) -> Self {
         Self::new(
             ErrorCode::SyncFailed,
@@ -95,6 +130,34 @@ impl AppError {
         )
     }
 
+    pub fn sync_passphrase_required(reason: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::SyncPassphraseRequired, reason.to_string())
+    }
+
+    pub fn archive_encrypted(reason: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::ArchiveEncrypted, reason.to_string())
+    }
+
+    pub fn cloud_file_missing(file_hash: &str) -> Self {
+        Self::new(
+            ErrorCode::CloudFileMissing,
+            format!("Book file not found in Drive: {}", file_hash),
+        )
+    }
+
+    /// An archive that is already in the library, keeping the existing title as the only detail
+    pub fn duplicate_book(existing_title: &str) -> Self {
+        Self {
+            code: ErrorCode::DuplicateEntry,
+            message: format!("Duplicate of existing book '{}'", existing_title),
+            details: vec![existing_title.to_string()],
+        }
+    }
+
+    pub fn invalid_input(reason: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::InvalidInput, format!("Invalid input: {}", reason))
+    }
+
     pub fn database_error(err: impl fmt::Display) -> Self {
         Self::new(
             ErrorCode::DatabaseError,
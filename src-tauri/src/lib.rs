@@ -1,27 +1,82 @@
 //! yomiyougu - A cross-platform manga/comic reader
 //!
 //! ## Module Structure
+//! - `archive_password` - Session passwords of encrypted ZIP/CBZ archives
+//! - `archive_pages` - Which archive entries are pages, and their reading order
 //! - `auth/` - Google OAuth token management
+//! - `comic_info` - Page metadata from ComicInfo.xml in archives
 //! - `commands/` - Tauri commands exposed to frontend
 //! - `database/` - Diesel ORM models and connection management
+//! - `manifest` - Books stored as loose files ordered by a `pages.json` manifest
+//! - `page_cache` - In-memory LRU cache of decompressed pages, filled ahead of the reader
+//! - `page_filter` - User-configured exclusion of archive entries from pages
 //! - `protocol` - Custom comic:// protocol for serving images from archives
 //! - `settings/` - Configuration management with UI schema generation
 //! - `sync/` - Google Drive synchronization
+//! - `thumbnail` - Downscaled, disk-cached page thumbnails for the comic:// protocol
 //! - `error` - Application-wide error types
 //! - `schema` - Auto-generated Diesel schema
 
 pub mod auth;
+mod archive_pages;
+mod archive_password;
+mod comic_info;
 mod commands;
 mod database;
 mod error;
+mod manifest;
+mod page_cache;
+mod page_filter;
 mod protocol;
 mod schema;
 mod settings;
 mod sync;
+mod thumbnail;
 
 pub use database::{establish_connection, DbPool};
 pub use error::AppError;
 
+use tauri::{Listener, Manager};
+
+/// Apply a setting to the backend subsystem that reads it at runtime
+fn apply_live_setting(key: &str, value: &settings::SettingValue) {
+    match key {
+        "advanced.prefetch_concurrency" => {
+            if let Some(concurrency) = value.as_number() {
+                protocol::set_prefetch_concurrency(concurrency.max(1) as usize);
+            }
+        }
+        "advanced.image_cache_size" => {
+            if let Some(size) = value.as_number() {
+                protocol::set_image_cache_capacity(size.max(1) as usize);
+            }
+        }
+        "advanced.max_rar_page_mb" => {
+            if let Some(megabytes) = value.as_number() {
+                protocol::set_max_rar_page_size(megabytes.max(1) as u64);
+            }
+        }
+        "advanced.disable_image_cache" => {
+            if let Some(disabled) = value.as_bool() {
+                protocol::set_image_cache_disabled(disabled);
+            }
+        }
+        page_filter::EXCLUDE_GLOBS_KEY => {
+            if let Some(globs) = value.as_string() {
+                let filter = page_filter::PageFilter::parse(globs);
+                // Nothing to redo when the setting is first applied at startup
+                let previous = page_filter::replace(filter.clone());
+                if let Some(previous) = previous.filter(|previous| *previous != filter) {
+                    // Cached page lists and stored page numbers were built with the old patterns
+                    protocol::clear_image_cache();
+                    commands::schedule_page_remap(previous);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize logger - respect RUST_LOG env var, default to Info
@@ -45,7 +100,28 @@ pub fn run() {
         .setup(|app| {
             database::connection::init_pool(app.handle())?;
             log::info!("Database connection pool initialized");
+            // Finish content hashes a previous session's fast import left pending
+            commands::schedule_hash_backfill();
+            match app.path().app_cache_dir() {
+                Ok(cache_dir) => thumbnail::set_thumbnail_dir(cache_dir.join("thumbnails")),
+                Err(e) => log::warn!("No cache directory, thumbnails won't be cached: {}", e),
+            }
             log::info!("Stronghold secure storage available for credential management");
+
+            // Apply live settings at startup, then follow changes
+            if let Ok(settings) = settings::load_settings(app.handle()) {
+                for category in &settings.categories {
+                    for setting in &category.settings {
+                        apply_live_setting(&setting.key, &setting.value);
+                    }
+                }
+            }
+            app.listen(settings::SETTING_CHANGED_EVENT, |event| {
+                match serde_json::from_str::<settings::SettingChangedEvent>(event.payload()) {
+                    Ok(change) => apply_live_setting(&change.key, &change.value),
+                    Err(e) => log::warn!("Invalid setting-changed payload: {}", e),
+                }
+            });
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -53,7 +129,11 @@ pub fn run() {
             commands::get_auth_status,
             commands::google_sign_in,
             commands::refresh_google_token,
+            commands::validate_token,
             commands::google_logout,
+            commands::rotate_credential_key,
+            commands::list_accounts,
+            commands::switch_account,
             commands::set_auth_token,
             commands::save_google_auth_token,
             // Device commands
@@ -68,35 +148,125 @@ pub fn run() {
             commands::complete_setup,
             commands::reset_all_settings,
             commands::reset_setting,
+            commands::get_modified_settings,
+            commands::export_settings_schema,
+            commands::export_settings,
+            commands::import_settings,
+            commands::list_settings_presets,
+            commands::apply_settings_preset,
             // Library commands - collections
             commands::create_collection,
             commands::get_collections,
+            commands::get_unread_counts,
             commands::get_collection,
             commands::update_collection,
             commands::delete_collection,
+            commands::diff_collections,
+            commands::move_collection,
+            commands::get_child_collections,
+            commands::get_collection_tree,
             // Library commands - books
             commands::get_books,
+            commands::search_books,
             commands::get_book,
+            commands::get_last_read_book,
+            commands::get_stale_in_progress,
+            commands::get_unfiled_favorites,
+            commands::get_page_navigation,
+            commands::get_reader_config,
+            commands::get_page_names,
+            commands::rescan_book,
+            commands::set_archive_password,
+            commands::archive_password_required,
+            commands::find_by_hash_prefix,
+            commands::resolve_drive_file,
             commands::update_book,
+            commands::save_reading_position,
+            commands::flush_reading_position,
+            commands::mark_book_opened,
+            commands::set_book_note,
             commands::delete_book,
             commands::import_book_from_archive,
+            commands::import_books_from_archives,
+            commands::import_directory,
+            commands::import_book_from_manifest,
+            commands::convert_to_cbz,
             // Library commands - book-collection management
             commands::set_book_collections,
             commands::add_book_to_collection,
             commands::remove_book_from_collection,
+            // Library commands - tags
+            commands::create_tag,
+            commands::get_tags,
+            commands::delete_tag,
+            commands::add_tag_to_book,
+            commands::remove_tag_from_book,
+            commands::get_books_by_tag,
+            // Library commands - series
+            commands::get_series,
+            commands::get_all_series,
             // Library commands - book settings
             commands::get_book_settings,
             commands::update_book_settings,
+            commands::set_collection_reading_direction,
             // Library commands - bookmarks
             commands::create_bookmark,
             commands::get_bookmarks,
             commands::update_bookmark,
             commands::delete_bookmark,
+            commands::export_bookmarks,
+            commands::import_bookmarks,
+            // Library commands - reading queue
+            commands::add_to_queue,
+            commands::get_queue,
+            commands::remove_from_queue,
+            commands::reorder_queue,
+            // Library commands - reading activity
+            commands::get_reading_activity,
+            commands::record_reading_session,
+            commands::get_reading_history,
+            commands::get_pages_read,
+            commands::get_reading_stats,
+            // Library commands - maintenance
+            commands::verify_library_consistency,
+            commands::verify_archive,
+            commands::verify_all_archives,
+            commands::cancel_verify_archives,
+            commands::audit_collection_pages,
+            commands::backfill_content_hashes,
+            commands::set_library_directory,
+            commands::reconcile_local_files,
+            commands::fix_invalid_progress,
             // Sync commands
             commands::get_sync_status,
             commands::sync_now,
-            commands::download_cloud_book,
+            commands::initial_sync,
+            commands::sync_with_strategy,
+            commands::preview_sync,
+            commands::set_sync_passphrase,
+            commands::has_sync_passphrase,
+            commands::get_last_sync_errors,
+            commands::get_recent_sync_changes,
+            commands::estimate_sync_upload_size,
+            commands::download_book,
+            commands::get_book_conflict,
+            commands::download_all_books,
+            commands::cancel_download_all,
+            commands::export_snapshot,
         ])
-        .run(tauri::generate_context!())
-        .expect("Critical error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("Critical error while running tauri application")
+        .run(|_app, event| match event {
+            // Persist buffered reading positions before the app exits or goes to the background
+            tauri::RunEvent::Exit
+            | tauri::RunEvent::WindowEvent {
+                event: tauri::WindowEvent::Focused(false),
+                ..
+            } => {
+                if let Err(e) = database::progress::flush_reading_positions(None) {
+                    log::error!("Failed to flush reading positions: {}", e);
+                }
+            }
+            _ => {}
+        });
 }
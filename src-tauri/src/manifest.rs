@@ -0,0 +1,152 @@
+//! Books stored as loose image files with a page manifest
+//!
+//! Some readers export a book as a folder of images plus a `pages.json` manifest
+//! listing the pages in reading order. Such books are stored with a
+//! `manifest://{path to pages.json}` file path; pages are served in manifest order
+//! rather than by sorting file names.
+//!
+//! The manifest is either a JSON array of file names or an object with a `pages`
+//! array and an optional `title`. Entries are paths relative to the manifest's folder.
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Component, Path};
+
+use crate::error::{AppError, ErrorCode};
+
+/// File path prefix for manifest books
+pub const MANIFEST_SCHEME: &str = "manifest://";
+
+/// Value stored in `books.archive_type` for manifest books
+pub const MANIFEST_ARCHIVE_TYPE: &str = "manifest";
+
+/// Ordered page list read from a `pages.json` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageManifest {
+    pub title: Option<String>,
+    pub pages: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManifestFile {
+    Pages(Vec<String>),
+    Object {
+        #[serde(default)]
+        title: Option<String>,
+        pages: Vec<String>,
+    },
+}
+
+/// The manifest path of a `manifest://` book, or `None` for any other book
+pub fn manifest_path(file_path: &str) -> Option<&Path> {
+    file_path.strip_prefix(MANIFEST_SCHEME).map(Path::new)
+}
+
+/// `books.file_path` value for a manifest
+pub fn manifest_file_path(manifest_path: &Path) -> String {
+    format!("{}{}", MANIFEST_SCHEME, manifest_path.to_string_lossy())
+}
+
+/// Folder holding a manifest's page files
+pub fn manifest_dir(manifest_path: &Path) -> &Path {
+    manifest_path.parent().unwrap_or(Path::new(""))
+}
+
+/// Read and check a `pages.json` manifest
+///
+/// Entries must be relative paths that stay inside the manifest's folder.
+pub fn read_manifest(manifest_path: &Path) -> Result<PageManifest, AppError> {
+    let content = fs::read_to_string(manifest_path).map_err(|e| {
+        AppError::new(
+            ErrorCode::IoError,
+            format!("Failed to read page manifest: {}", e),
+        )
+    })?;
+
+    let (title, pages) = match serde_json::from_str(&content) {
+        Ok(ManifestFile::Pages(pages)) => (None, pages),
+        Ok(ManifestFile::Object { title, pages }) => (title, pages),
+        Err(e) => {
+            return Err(AppError::invalid_input(format!(
+                "Malformed page manifest: {}",
+                e
+            )))
+        }
+    };
+
+    if pages.is_empty() {
+        return Err(AppError::invalid_input("Page manifest lists no pages"));
+    }
+
+    if let Some(bad) = pages.iter().find(|page| !is_contained(page)) {
+        return Err(AppError::invalid_input(format!(
+            "Page manifest entry '{}' points outside the book folder",
+            bad
+        )));
+    }
+
+    Ok(PageManifest { title, pages })
+}
+
+/// Manifest entries with no matching file in the folder
+pub fn missing_pages(dir: &Path, manifest: &PageManifest) -> Vec<String> {
+    manifest
+        .pages
+        .iter()
+        .filter(|page| !dir.join(page).is_file())
+        .cloned()
+        .collect()
+}
+
+/// Content hash of the pages in manifest order
+pub fn hash_pages(dir: &Path, manifest: &PageManifest) -> Result<String, AppError> {
+    let mut hasher = Sha256::new();
+    for page in &manifest.pages {
+        let data = fs::read(dir.join(page)).map_err(|e| {
+            AppError::new(
+                ErrorCode::IoError,
+                format!("Failed to read page '{}': {}", page, e),
+            )
+        })?;
+        hasher.update(&data);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn is_contained(page: &str) -> bool {
+    !page.is_empty()
+        && Path::new(page)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_manifest_formats_and_missing_pages() {
+        let dir = std::env::temp_dir().join(format!("manifest_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b.jpg"), b"b").unwrap();
+        fs::write(dir.join("a.jpg"), b"a").unwrap();
+
+        let manifest_path = dir.join("pages.json");
+        fs::write(&manifest_path, r#"["b.jpg", "a.jpg", "gone.jpg"]"#).unwrap();
+        let manifest = read_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.pages, vec!["b.jpg", "a.jpg", "gone.jpg"]);
+        assert_eq!(missing_pages(&dir, &manifest), vec!["gone.jpg".to_string()]);
+
+        fs::write(&manifest_path, r#"{"title": "Vol 1", "pages": ["b.jpg", "a.jpg"]}"#).unwrap();
+        let manifest = read_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.title.as_deref(), Some("Vol 1"));
+        assert!(missing_pages(&dir, &manifest).is_empty());
+
+        fs::write(&manifest_path, r#"["../secret.jpg"]"#).unwrap();
+        assert!(read_manifest(&manifest_path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
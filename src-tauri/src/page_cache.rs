@@ -0,0 +1,240 @@
+//! In-memory cache of decompressed page bytes for the comic:// protocol
+//!
+//! Pages are keyed by (book_id, page_number) and evicted least-recently-used once
+//! the cache holds more than `DEFAULT_PAGE_CACHE_BYTES`. When a page is served, the
+//! following pages are decompressed in the background so turning the page doesn't
+//! have to wait for the archive.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+/// Total size of cached page data
+pub const DEFAULT_PAGE_CACHE_BYTES: usize = 50 * 1024 * 1024;
+
+/// How many pages after the requested one are prefetched
+pub const PREFETCH_AHEAD: usize = 2;
+
+type PageKey = (i32, usize);
+
+struct CachedPage {
+    data: Vec<u8>,
+    mime_type: String,
+    /// Value of `PageCache::clock` when the page was last read or written
+    last_used: u64,
+}
+
+struct PageCache {
+    pages: HashMap<PageKey, CachedPage>,
+    total_bytes: usize,
+    capacity: usize,
+    clock: u64,
+}
+
+impl PageCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            pages: HashMap::new(),
+            total_bytes: 0,
+            capacity,
+            clock: 0,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn get(&mut self, key: PageKey) -> Option<(Vec<u8>, String)> {
+        let now = self.tick();
+        let page = self.pages.get_mut(&key)?;
+        page.last_used = now;
+        Some((page.data.clone(), page.mime_type.clone()))
+    }
+
+    fn insert(&mut self, key: PageKey, data: Vec<u8>, mime_type: String) {
+        // A page larger than the whole cache would only evict everything else
+        if data.len() > self.capacity {
+            return;
+        }
+
+        self.remove(key);
+        let needed = data.len();
+        while self.total_bytes + needed > self.capacity {
+            let Some(oldest) = self
+                .pages
+                .iter()
+                .min_by_key(|(_, page)| page.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            self.remove(oldest);
+        }
+
+        let last_used = self.tick();
+        self.total_bytes += needed;
+        self.pages.insert(key, CachedPage { data, mime_type, last_used });
+    }
+
+    fn remove(&mut self, key: PageKey) {
+        if let Some(page) = self.pages.remove(&key) {
+            self.total_bytes -= page.data.len();
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&PageKey) -> bool) {
+        let removed: Vec<PageKey> = self.pages.keys().filter(|key| !keep(key)).copied().collect();
+        for key in removed {
+            self.remove(key);
+        }
+    }
+}
+
+/// Decompressed pages (created on first use)
+static PAGE_CACHE: RwLock<Option<PageCache>> = RwLock::new(None);
+
+/// Pages currently being prefetched, so repeated requests don't decompress them twice
+static IN_FLIGHT: RwLock<Option<HashSet<PageKey>>> = RwLock::new(None);
+
+/// How often the cache was invalidated, for every book and per book
+struct Generations {
+    all: u64,
+    books: Option<HashMap<i32, u64>>,
+}
+
+static GENERATIONS: RwLock<Generations> = RwLock::new(Generations { all: 0, books: None });
+
+/// Point in a book's invalidation history, taken before reading a page
+///
+/// A page read from before the book was last invalidated may come from a replaced
+/// file, so `insert` drops it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generation {
+    all: u64,
+    book: u64,
+}
+
+/// The book's current generation
+pub fn generation(book_id: i32) -> Generation {
+    let generations = GENERATIONS.read().unwrap_or_else(|e| e.into_inner());
+    Generation {
+        all: generations.all,
+        book: generations
+            .books
+            .as_ref()
+            .and_then(|books| books.get(&book_id).copied())
+            .unwrap_or(0),
+    }
+}
+
+/// Get a cached page's bytes and MIME type, marking it as recently used
+pub fn get(book_id: i32, page: usize) -> Option<(Vec<u8>, String)> {
+    // Reads update recency, so they need the write lock as well
+    let mut cache = PAGE_CACHE.write().unwrap_or_else(|e| e.into_inner());
+    cache.as_mut()?.get((book_id, page))
+}
+
+/// Whether a page is cached, without affecting its recency
+pub fn contains(book_id: i32, page: usize) -> bool {
+    let cache = PAGE_CACHE.read().unwrap_or_else(|e| e.into_inner());
+    cache
+        .as_ref()
+        .is_some_and(|cache| cache.pages.contains_key(&(book_id, page)))
+}
+
+/// Cache a page's bytes, evicting least recently used pages to stay within the byte limit
+///
+/// Nothing is cached if the book was invalidated since `read_at` was taken.
+pub fn insert(book_id: i32, page: usize, read_at: Generation, data: Vec<u8>, mime_type: String) {
+    // Checked under the cache lock, which `invalidate` holds while bumping the generation
+    let mut cache = PAGE_CACHE.write().unwrap_or_else(|e| e.into_inner());
+    if generation(book_id) != read_at {
+        log::debug!("Not caching page {} of book {}: invalidated while reading", page, book_id);
+        return;
+    }
+    cache
+        .get_or_insert_with(|| PageCache::new(DEFAULT_PAGE_CACHE_BYTES))
+        .insert((book_id, page), data, mime_type);
+}
+
+/// Drop cached pages of one book, or of every book when `book_id` is `None`
+pub fn invalidate(book_id: Option<i32>) {
+    let mut cache = PAGE_CACHE.write().unwrap_or_else(|e| e.into_inner());
+    {
+        let mut generations = GENERATIONS.write().unwrap_or_else(|e| e.into_inner());
+        match book_id {
+            Some(book_id) => *generations.books.get_or_insert_with(HashMap::new).entry(book_id).or_default() += 1,
+            None => generations.all += 1,
+        }
+    }
+    match (cache.as_mut(), book_id) {
+        (Some(cache), Some(book_id)) => cache.retain(|(id, _)| *id != book_id),
+        (Some(_), None) => *cache = None,
+        (None, _) => {}
+    }
+}
+
+/// Claim a page for prefetching
+///
+/// Returns false if the page is already cached or another prefetch is reading it.
+/// Every successful claim must be followed by `finish_prefetch`.
+pub fn begin_prefetch(book_id: i32, page: usize) -> bool {
+    if contains(book_id, page) {
+        return false;
+    }
+    let mut in_flight = IN_FLIGHT.write().unwrap_or_else(|e| e.into_inner());
+    in_flight.get_or_insert_with(HashSet::new).insert((book_id, page))
+}
+
+/// Release a page claimed by `begin_prefetch`
+pub fn finish_prefetch(book_id: i32, page: usize) {
+    let mut in_flight = IN_FLIGHT.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(set) = in_flight.as_mut() {
+        set.remove(&(book_id, page));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used_by_bytes() {
+        let mut cache = PageCache::new(100);
+        cache.insert((1, 0), vec![0; 40], "image/png".to_string());
+        cache.insert((1, 1), vec![0; 40], "image/png".to_string());
+
+        // Touch page 0 so page 1 becomes the eviction candidate
+        assert!(cache.get((1, 0)).is_some());
+        cache.insert((1, 2), vec![0; 40], "image/png".to_string());
+
+        assert!(cache.get((1, 0)).is_some());
+        assert!(cache.get((1, 1)).is_none());
+        assert!(cache.get((1, 2)).is_some());
+        assert_eq!(cache.total_bytes, 80);
+
+        // Replacing a page doesn't count its old size twice
+        cache.insert((1, 2), vec![0; 60], "image/png".to_string());
+        assert_eq!(cache.total_bytes, 100);
+
+        // Oversized pages are not cached at all
+        cache.insert((2, 0), vec![0; 101], "image/png".to_string());
+        assert!(cache.get((2, 0)).is_none());
+        assert_eq!(cache.total_bytes, 100);
+
+        cache.retain(|(book_id, _)| *book_id != 1);
+        assert_eq!((cache.pages.len(), cache.total_bytes), (0, 0));
+    }
+
+    #[test]
+    fn test_page_read_before_invalidation_not_cached() {
+        let book_id = -21;
+        let before = generation(book_id);
+        invalidate(Some(book_id));
+        assert_ne!(generation(book_id), before);
+
+        insert(book_id, 0, before, vec![1, 2, 3], "image/png".to_string());
+        assert!(!contains(book_id, 0));
+    }
+}
@@ -0,0 +1,141 @@
+//! User-configurable page exclusion
+//!
+//! Glob patterns from `advanced.page_exclude_globs` (e.g. `*credits*`) hide matching
+//! archive entries from page counts and from the reader. Content hashes ignore them,
+//! so changing the patterns never changes a book's identity.
+//!
+//! Patterns are case-insensitive; `*` matches any run of characters and `?` a single
+//! one. A pattern matches if it fits either the full entry path or its file name.
+//!
+//! Page numbers stored for a book (its page count, reading position and bookmarks)
+//! index the page list under the active patterns. When the patterns change they are
+//! remapped to the new list (`operations::remap_pages_for_filter`).
+
+use std::sync::RwLock;
+
+/// Setting key holding the exclusion patterns (comma or newline separated)
+pub const EXCLUDE_GLOBS_KEY: &str = "advanced.page_exclude_globs";
+
+/// A set of exclusion patterns, lowercased once when parsed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageFilter {
+    globs: Vec<String>,
+}
+
+impl PageFilter {
+    /// Parse the setting value into individual patterns
+    pub fn parse(value: &str) -> Self {
+        let globs = value
+            .split([',', '\n'])
+            .map(|pattern| pattern.trim().to_lowercase())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+        Self { globs }
+    }
+
+    /// Whether an archive entry is hidden by these patterns
+    pub fn is_excluded(&self, entry_name: &str) -> bool {
+        if self.globs.is_empty() {
+            return false;
+        }
+
+        let path = entry_name.to_lowercase();
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+
+        self.globs
+            .iter()
+            .any(|glob| glob_match(glob.as_bytes(), path.as_bytes()) || glob_match(glob.as_bytes(), file_name.as_bytes()))
+    }
+}
+
+/// Patterns applied from the setting; `None` until it is first applied at startup
+static ACTIVE: RwLock<Option<PageFilter>> = RwLock::new(None);
+
+/// The active exclusion patterns
+pub fn current() -> PageFilter {
+    ACTIVE.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// Make `filter` the active patterns, returning the ones it replaced
+///
+/// Returns `None` when the setting is applied for the first time.
+pub fn replace(filter: PageFilter) -> Option<PageFilter> {
+    log::debug!("Page exclusion patterns: {:?}", filter.globs);
+    ACTIVE.write().unwrap_or_else(|e| e.into_inner()).replace(filter)
+}
+
+/// Match `text` against a glob with `*` and `?` wildcards
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*credits*", b"099_credits.jpg"));
+        assert!(glob_match(b"page?.png", b"page1.png"));
+        assert!(glob_match(b"*", b""));
+        assert!(!glob_match(b"page?.png", b"page10.png"));
+        assert!(!glob_match(b"*.jpg", b"cover.png"));
+    }
+
+    #[test]
+    fn test_parse_page_filter() {
+        let filter = PageFilter::parse(" *Credits*,\nthumbs.db ,, ");
+        assert_eq!(filter.globs, vec!["*credits*".to_string(), "thumbs.db".to_string()]);
+        assert!(filter.is_excluded("Extras/THUMBS.DB"));
+        assert!(!PageFilter::default().is_excluded("credits.jpg"));
+    }
+
+    #[test]
+    fn test_excluded_pages_dropped_from_count_and_list() {
+        let path = std::env::temp_dir().join(format!("exclude_{}.cbz", uuid::Uuid::new_v4()));
+        {
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            for name in ["001.jpg", "002.jpg", "extras/Scan_Credits.jpg"] {
+                zip.start_file(name, options).unwrap();
+                zip.write_all(b"image").unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        let filter = PageFilter::parse("*credits*");
+        let count = crate::database::operations::count_zip_images(&path, &filter);
+        let list = crate::protocol::get_zip_image_list(&path, &filter);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(count.unwrap(), 2);
+        assert_eq!(list.unwrap(), vec!["001.jpg".to_string(), "002.jpg".to_string()]);
+    }
+}
@@ -1,25 +1,237 @@
 //! Comic image protocol handler
 //!
-//! Serves images from comic archives (CBZ/ZIP, CBR/RAR) via a custom protocol.
+//! Serves images from comic archives (CBZ/ZIP, CBR/RAR, CB7/7Z) and manifest folders via a custom protocol.
 //! URL format: comic://book/{book_id}/page/{page_number}
 //! - page 0 is the cover (first image in sorted order)
+//! - `thumb` instead of `page` serves a downscaled JPEG, see [`crate::thumbnail`]
+//!
+//! RAR archives can't be read in chunks or seeked into: unrar decompresses a whole entry
+//! into memory in one go, after walking every header before it. Entries larger than
+//! `advanced.max_rar_page_mb` are therefore refused based on the size in their header,
+//! before anything is allocated.
+//!
+//! 7z archives are usually solid, so reaching a page means decoding every entry stored
+//! before it. Entries are decoded as a stream and only the requested page is kept.
+//!
+//! Decompressed pages are kept in [`crate::page_cache`], and serving a page prefetches
+//! the next few into it in the background. ZIP archives of the last few books read stay
+//! open, so a page turn doesn't re-parse the central directory.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
 
 use tauri::http::{Request, Response};
 use zip::ZipArchive;
 
-use crate::database::operations::get_book_by_id;
+use crate::database::models::Book;
+use crate::database::operations::{get_book_by_id, set_book_archive_type, sevenz_error, SEVEN_ZIP_MAGIC};
+use crate::archive_pages;
+use crate::archive_password;
+use crate::manifest;
+use crate::page_cache;
+use crate::page_filter::{self, PageFilter};
+use crate::thumbnail;
+
+/// Image lists of recently opened books, least recently used first
+#[derive(Default)]
+struct ImageListCache {
+    entries: VecDeque<(i32, Vec<String>)>,
+}
+
+impl ImageListCache {
+    /// Look up a book's list, marking it as most recently used
+    fn get(&mut self, book_id: i32) -> Option<Vec<String>> {
+        let index = self.entries.iter().position(|(id, _)| *id == book_id)?;
+        let entry = self.entries.remove(index)?;
+        let list = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(list)
+    }
+
+    /// Store a book's list as most recently used, evicting the least recently used
+    /// books beyond `capacity`
+    fn insert(&mut self, book_id: i32, list: Vec<String>, capacity: usize) {
+        self.remove(book_id);
+        self.entries.push_back((book_id, list));
+        self.evict_to_capacity(capacity);
+    }
+
+    fn remove(&mut self, book_id: i32) {
+        self.entries.retain(|(id, _)| *id != book_id);
+    }
+
+    /// Remove least recently used entries until at most `capacity` remain
+    fn evict_to_capacity(&mut self, capacity: usize) {
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+}
 
 /// Cache for image lists (book_id -> sorted image names)
-static IMAGE_LIST_CACHE: RwLock<Option<HashMap<i32, Vec<String>>>> = RwLock::new(None);
+static IMAGE_LIST_CACHE: RwLock<Option<ImageListCache>> = RwLock::new(None);
+
+/// Default cache size (number of books to cache)
+pub const DEFAULT_IMAGE_CACHE_SIZE: usize = 10;
+
+/// Maximum cache size, adjustable at runtime via `advanced.image_cache_size`
+static MAX_CACHE_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_IMAGE_CACHE_SIZE);
+
+/// Default size limit for a single RAR page, in MiB
+pub const DEFAULT_MAX_RAR_PAGE_MB: u64 = 64;
+
+/// Largest RAR entry read into memory, adjustable at runtime via `advanced.max_rar_page_mb`
+#[cfg_attr(target_os = "android", allow(dead_code))]
+static MAX_RAR_PAGE_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_MAX_RAR_PAGE_MB * 1024 * 1024);
+
+/// Whether served images tell the webview not to cache them, set via `advanced.disable_image_cache`
+static DISABLE_IMAGE_CACHE: AtomicBool = AtomicBool::new(false);
 
-/// Maximum cache size (number of books to cache)
-const MAX_CACHE_SIZE: usize = 10;
+/// Cache-Control for served pages: revalidated against their ETag unless caching is disabled for debugging
+///
+/// Page URLs don't change when a book's pages do (reordered, excluded, file replaced),
+/// so a cached page can't be trusted without asking.
+fn image_cache_control() -> &'static str {
+    if DISABLE_IMAGE_CACHE.load(Ordering::Relaxed) {
+        "no-store"
+    } else {
+        "no-cache"
+    }
+}
+
+/// ETag of a served page: its entry name plus the size and modification time of
+/// the file it is read from
+///
+/// The name changes when the page list does, and the file stamp when the archive
+/// (or a manifest page) is replaced. `None` if the file can't be checked.
+fn page_etag(archive_path: &Path, image_name: &str, archive_type: ArchiveType) -> Option<String> {
+    use std::hash::{Hash, Hasher};
+
+    let source = match archive_type {
+        ArchiveType::Manifest => manifest::manifest_dir(archive_path).join(image_name),
+        _ => archive_path.to_path_buf(),
+    };
+    let stamp = file_stamp(&source).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (image_name, stamp).hash(&mut hasher);
+    Some(format!("\"{:016x}\"", hasher.finish()))
+}
+
+/// Upper bound for parallel page reads during prefetch
+const MAX_PREFETCH_CONCURRENCY: usize = 8;
+
+/// Parallel page reads during prefetch, adjustable at runtime via `advanced.prefetch_concurrency`
+static PREFETCH_CONCURRENCY: AtomicUsize = AtomicUsize::new(2);
+
+/// Number of open ZIP archives kept around
+const MAX_ZIP_HANDLES: usize = 4;
+
+/// A parsed ZIP archive with the index of each entry by full path
+struct ZipHandle {
+    archive: ZipArchive<BufReader<File>>,
+    /// First entry with each name, so pages are found by their exact path even
+    /// when an archive repeats a name
+    entries: HashMap<String, usize>,
+}
+
+impl ZipHandle {
+    fn open(archive_path: &Path) -> Result<Self, String> {
+        let file = File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+        let reader = BufReader::with_capacity(64 * 1024, file); // 64KB buffer
+        let mut archive = ZipArchive::new(reader).map_err(|e| format!("Failed to read zip archive: {}", e))?;
+
+        let mut entries = HashMap::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let entry = archive
+                .by_index_raw(i)
+                .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+            entries.entry(entry.name().to_string()).or_insert(i);
+        }
+        Ok(Self { archive, entries })
+    }
+}
+
+/// An open ZIP archive with the file state it was parsed from
+struct OpenZip {
+    book_id: i32,
+    path: PathBuf,
+    /// Size and modification time, so a replaced file is opened again
+    stamp: (u64, Option<SystemTime>),
+    archive: ZipHandle,
+}
+
+/// Open ZIP archives, least recently used first
+///
+/// A reader takes the book's handle out while using it and puts it back afterwards,
+/// so parallel reads of one book never wait on each other; the ones that find no
+/// handle simply open the archive themselves.
+struct ZipHandles {
+    open: Mutex<VecDeque<OpenZip>>,
+    capacity: usize,
+}
+
+impl ZipHandles {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            open: Mutex::new(VecDeque::new()),
+            capacity,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<OpenZip>> {
+        self.open.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take a book's open archive out of the cache, if it is still current
+    fn take(&self, book_id: i32, path: &Path, stamp: (u64, Option<SystemTime>)) -> Option<ZipHandle> {
+        let open = {
+            let mut handles = self.lock();
+            let index = handles.iter().position(|open| open.book_id == book_id)?;
+            handles.remove(index)?
+        };
+        (open.path == path && open.stamp == stamp).then_some(open.archive)
+    }
+
+    /// Put an archive back as most recently used, closing the least recently used beyond the capacity
+    fn put(&self, open: OpenZip) {
+        let mut handles = self.lock();
+        // Another reader of the same book may have returned its handle first
+        if handles.iter().any(|other| other.book_id == open.book_id) {
+            return;
+        }
+        handles.push_back(open);
+        while handles.len() > self.capacity {
+            handles.pop_front();
+        }
+    }
+
+    fn close(&self, book_id: Option<i32>) {
+        let mut handles = self.lock();
+        match book_id {
+            Some(book_id) => handles.retain(|open| open.book_id != book_id),
+            None => handles.clear(),
+        }
+    }
+}
+
+static ZIP_HANDLES: ZipHandles = ZipHandles::new(MAX_ZIP_HANDLES);
+
+fn file_stamp(path: &Path) -> Result<(u64, Option<SystemTime>), String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+/// Close the open archive of one book, or of every book when `book_id` is `None`
+///
+/// Open files can't be moved or deleted on Windows.
+pub fn close_zip_handles(book_id: Option<i32>) {
+    ZIP_HANDLES.close(book_id);
+}
 
 /// Get cached image list or compute and cache it
 fn get_cached_image_list(
@@ -27,65 +239,135 @@ fn get_cached_image_list(
     archive_path: &Path,
     archive_type: ArchiveType,
 ) -> Result<Vec<String>, String> {
-    // Try to read from cache first
+    // Try to read from cache first (a hit updates recency, so it needs the write lock)
     {
-        let cache = IMAGE_LIST_CACHE.read().unwrap();
-        if let Some(ref map) = *cache {
-            if let Some(list) = map.get(&book_id) {
-                return Ok(list.clone());
-            }
+        let mut cache = IMAGE_LIST_CACHE.write().unwrap();
+        if let Some(list) = cache.as_mut().and_then(|cache| cache.get(book_id)) {
+            return Ok(list);
         }
     }
 
-    let list = get_image_list(archive_path, archive_type)?;
+    let list = get_image_list(archive_path, archive_type, &page_filter::current())?;
 
-    // Store in cache
+    // Store in cache, evicting the least recently used books if it is full
     {
         let mut cache = IMAGE_LIST_CACHE.write().unwrap();
-        let map = cache.get_or_insert_with(HashMap::new);
-        
-        // Evict oldest entries if cache is too large
-        if map.len() >= MAX_CACHE_SIZE {
-            // Remove first entry
-            if let Some(key) = map.keys().next().cloned() {
-                map.remove(&key);
-            }
+        cache.get_or_insert_with(ImageListCache::default).insert(
+            book_id,
+            list.clone(),
+            MAX_CACHE_SIZE.load(Ordering::Relaxed),
+        );
+    }
+
+    Ok(list)
+}
+
+/// Change how many books' image lists are cached, shrinking the cache if needed
+pub fn set_image_cache_capacity(capacity: usize) {
+    let capacity = capacity.max(1);
+    MAX_CACHE_SIZE.store(capacity, Ordering::Relaxed);
+
+    let mut cache = IMAGE_LIST_CACHE.write().unwrap();
+    if let Some(ref mut cache) = *cache {
+        cache.evict_to_capacity(capacity);
+    }
+    log::debug!("Image list cache capacity set to {}", capacity);
+}
+
+/// Change the largest RAR page that will be read into memory
+pub fn set_max_rar_page_size(megabytes: u64) {
+    let megabytes = megabytes.max(1);
+    MAX_RAR_PAGE_BYTES.store(megabytes * 1024 * 1024, Ordering::Relaxed);
+    log::debug!("Maximum RAR page size set to {} MiB", megabytes);
+}
+
+/// Set how many pages are decompressed in parallel ahead of the one being read
+pub fn set_prefetch_concurrency(concurrency: usize) {
+    let concurrency = concurrency.clamp(1, MAX_PREFETCH_CONCURRENCY);
+    PREFETCH_CONCURRENCY.store(concurrency, Ordering::Relaxed);
+    log::debug!("Prefetch concurrency set to {}", concurrency);
+}
+
+/// Make served images uncacheable (`no-store`) so every request reaches the handler
+pub fn set_image_cache_disabled(disabled: bool) {
+    DISABLE_IMAGE_CACHE.store(disabled, Ordering::Relaxed);
+    log::debug!("Image caching in the webview {}", if disabled { "disabled" } else { "enabled" });
+}
+
+/// Recompute a book's image list and replace the cached copy
+///
+/// Use after anything that changes which pages a book has or their order, so the
+/// reader doesn't keep serving the old list until it is evicted.
+pub fn rebuild_image_cache(book_id: i32) -> Result<Vec<String>, String> {
+    let book = get_book_by_id(book_id).map_err(|e| e.to_string())?;
+    if book.file_path.starts_with("cloud://") {
+        invalidate_image_cache(book_id);
+        return Err("Book is stored in cloud. Please download first.".to_string());
+    }
+
+    let archive_path = book_source_path(&book);
+    let archive_type = resolve_archive_type(&book, &archive_path)?;
+    replace_cached_image_list(book_id, &archive_path, archive_type)
+}
+
+/// Compute the image list and store it in place of any cached one
+fn replace_cached_image_list(
+    book_id: i32,
+    archive_path: &Path,
+    archive_type: ArchiveType,
+) -> Result<Vec<String>, String> {
+    let list = match get_image_list(archive_path, archive_type, &page_filter::current()) {
+        Ok(list) => list,
+        Err(e) => {
+            invalidate_image_cache(book_id);
+            return Err(e);
         }
-        
-        map.insert(book_id, list.clone());
+    };
+
+    {
+        let mut cache = IMAGE_LIST_CACHE.write().unwrap();
+        cache.get_or_insert_with(ImageListCache::default).insert(
+            book_id,
+            list.clone(),
+            MAX_CACHE_SIZE.load(Ordering::Relaxed),
+        );
     }
+    close_zip_handles(Some(book_id));
+    page_cache::invalidate(Some(book_id));
+    thumbnail::invalidate(Some(book_id));
 
     Ok(list)
 }
 
 /// Invalidate cache for a specific book
-#[allow(dead_code)]
+///
+/// Also closes the book's archive, so call it before deleting or moving the file.
 pub fn invalidate_image_cache(book_id: i32) {
-    let mut cache = IMAGE_LIST_CACHE.write().unwrap();
-    if let Some(ref mut map) = *cache {
-        map.remove(&book_id);
+    {
+        let mut cache = IMAGE_LIST_CACHE.write().unwrap();
+        if let Some(ref mut cache) = *cache {
+            cache.remove(book_id);
+        }
     }
+    close_zip_handles(Some(book_id));
+    // Cached pages and thumbnails are keyed by page number, which may now point at another image
+    page_cache::invalidate(Some(book_id));
+    thumbnail::invalidate(Some(book_id));
 }
 
 /// Clear entire image cache
-#[allow(dead_code)]
 pub fn clear_image_cache() {
-    let mut cache = IMAGE_LIST_CACHE.write().unwrap();
-    *cache = None;
-}
-
-/// Check if a file is an image based on extension
-fn is_image_file(name: &str) -> bool {
-    let lower = name.to_lowercase();
-    lower.ends_with(".jpg")
-        || lower.ends_with(".jpeg")
-        || lower.ends_with(".png")
-        || lower.ends_with(".gif")
-        || lower.ends_with(".webp")
+    {
+        let mut cache = IMAGE_LIST_CACHE.write().unwrap();
+        *cache = None;
+    }
+    close_zip_handles(None);
+    page_cache::invalidate(None);
+    thumbnail::invalidate(None);
 }
 
 /// Get sorted list of image files from a ZIP/CBZ archive
-fn get_zip_image_list(archive_path: &Path) -> Result<Vec<String>, String> {
+pub(crate) fn get_zip_image_list(archive_path: &Path, filter: &PageFilter) -> Result<Vec<String>, String> {
     let file = File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
     let reader = BufReader::with_capacity(64 * 1024, file); // 64KB buffer for faster reads
 
@@ -101,24 +383,22 @@ fn get_zip_image_list(archive_path: &Path) -> Result<Vec<String>, String> {
 
         let file_name = file.name().to_string();
         if !file.is_dir()
-            && is_image_file(&file_name)
-            && !file_name.starts_with('.')
-            && !file_name.contains("/.")
-            && !file_name.contains("__MACOSX")
+            && archive_pages::is_page_entry(&file_name)
+            && !filter.is_excluded(&file_name)
         {
             image_files.push(file_name);
         }
     }
 
     // Sort naturally (handles "page1", "page2", "page10" correctly)
-    image_files.sort_by(|a, b| natord::compare(a, b));
+    image_files.sort_by(|a, b| archive_pages::compare_pages(a, b));
 
     Ok(image_files)
 }
 
 /// Get sorted list of image files from a RAR/CBR archive (desktop only)
 #[cfg(not(target_os = "android"))]
-fn get_rar_image_list(archive_path: &Path) -> Result<Vec<String>, String> {
+fn get_rar_image_list(archive_path: &Path, filter: &PageFilter) -> Result<Vec<String>, String> {
     let archive = unrar::Archive::new(archive_path)
         .open_for_listing()
         .map_err(|e| format!("Failed to open RAR archive: {}", e))?;
@@ -130,38 +410,82 @@ fn get_rar_image_list(archive_path: &Path) -> Result<Vec<String>, String> {
         let file_name = entry.filename.to_string_lossy().to_string();
 
         if !entry.is_directory()
-            && is_image_file(&file_name)
-            && !file_name.starts_with('.')
-            && !file_name.contains("/.")
+            && archive_pages::is_page_entry(&file_name)
+            && !filter.is_excluded(&file_name)
         {
             image_files.push(file_name);
         }
     }
 
     // Sort naturally
-    image_files.sort_by(|a, b| natord::compare(a, b));
+    image_files.sort_by(|a, b| archive_pages::compare_pages(a, b));
 
     Ok(image_files)
 }
 
-/// Read a specific image from a ZIP/CBZ archive
-fn read_zip_image(archive_path: &Path, image_name: &str) -> Result<(Vec<u8>, String), String> {
-    let file = File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
-    let reader = BufReader::with_capacity(64 * 1024, file); // 64KB buffer
+/// Get sorted list of image files from a 7z/CB7 archive
+fn get_7z_image_list(archive_path: &Path, filter: &PageFilter) -> Result<Vec<String>, String> {
+    let archive = sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())
+        .map_err(|e| sevenz_error("open 7z archive", e).message)?;
+
+    let mut image_files: Vec<String> = archive
+        .archive()
+        .files
+        .iter()
+        .filter(|entry| {
+            let file_name = entry.name();
+            !entry.is_directory()
+                && archive_pages::is_page_entry(file_name)
+                && !filter.is_excluded(file_name)
+        })
+        .map(|entry| entry.name().to_string())
+        .collect();
+
+    // Sort naturally, matching the order pages are hashed in on import
+    image_files.sort_by(|a, b| archive_pages::compare_pages(a, b));
 
-    let mut archive =
-        ZipArchive::new(reader).map_err(|e| format!("Failed to read zip archive: {}", e))?;
+    Ok(image_files)
+}
 
-    let mut entry = archive
-        .by_name(image_name)
-        .map_err(|e| format!("Failed to find image '{}': {}", image_name, e))?;
+/// Read a specific image from a ZIP/CBZ archive, reusing the book's open archive in `handles`
+fn read_zip_image(
+    handles: &ZipHandles,
+    book_id: i32,
+    archive_path: &Path,
+    image_name: &str,
+) -> Result<(Vec<u8>, String), String> {
+    let stamp = file_stamp(archive_path)?;
+    let mut archive = match handles.take(book_id, archive_path, stamp) {
+        Some(archive) => archive,
+        None => ZipHandle::open(archive_path)?,
+    };
 
-    // Pre-allocate buffer based on uncompressed size for efficiency
-    let size_hint = entry.size() as usize;
-    let mut buffer = Vec::with_capacity(size_hint.max(1024));
-    entry
-        .read_to_end(&mut buffer)
-        .map_err(|e| format!("Failed to read image data: {}", e))?;
+    let password = archive_password::get(book_id);
+    let buffer = {
+        let index = *archive
+            .entries
+            .get(image_name)
+            .ok_or_else(|| format!("Failed to find image '{}' in archive", image_name))?;
+        // Password errors keep their exact message so the handler can tell them apart
+        let mut entry = archive_password::zip_entry(&mut archive.archive, index, password.as_deref())
+            .map_err(|e| e.message)?;
+
+        // Pre-allocate buffer based on uncompressed size for efficiency
+        let size_hint = entry.size() as usize;
+        let mut buffer = Vec::with_capacity(size_hint.max(1024));
+        entry
+            .read_to_end(&mut buffer)
+            .map_err(|e| format!("Failed to read image data: {}", e))?;
+        buffer
+    };
+
+    // Only handles that read cleanly go back; a failed read may leave the reader mid-entry
+    handles.put(OpenZip {
+        book_id,
+        path: archive_path.to_path_buf(),
+        stamp,
+        archive,
+    });
 
     let mime_type = get_mime_type(image_name);
 
@@ -169,6 +493,8 @@ fn read_zip_image(archive_path: &Path, image_name: &str) -> Result<(Vec<u8>, Str
 }
 
 /// Read a specific image from a RAR/CBR archive (desktop only)
+///
+/// The entry is extracted whole, so it is checked against the configured size limit first.
 #[cfg(not(target_os = "android"))]
 fn read_rar_image(archive_path: &Path, image_name: &str) -> Result<(Vec<u8>, String), String> {
     let archive = unrar::Archive::new(archive_path)
@@ -183,6 +509,17 @@ fn read_rar_image(archive_path: &Path, image_name: &str) -> Result<(Vec<u8>, Str
                 let file_name = header.entry().filename.to_string_lossy().to_string();
 
                 if file_name == image_name {
+                    let size = header.entry().unpacked_size;
+                    let limit = MAX_RAR_PAGE_BYTES.load(Ordering::Relaxed);
+                    if size > limit {
+                        return Err(format!(
+                            "Page '{}' is too large to load ({} MiB, limit {} MiB). Raise \"Maximum RAR Page Size\" or convert the book to CBZ.",
+                            image_name,
+                            size.div_ceil(1024 * 1024),
+                            limit / (1024 * 1024)
+                        ));
+                    }
+
                     let (data, _) = header
                         .read()
                         .map_err(|e| format!("Failed to read RAR entry: {}", e))?;
@@ -202,6 +539,55 @@ fn read_rar_image(archive_path: &Path, image_name: &str) -> Result<(Vec<u8>, Str
     Err(format!("Image '{}' not found in archive", image_name))
 }
 
+/// Read a specific image from a 7z/CB7 archive
+///
+/// Entries before the requested one are decoded and discarded; decoding stops once it is found.
+fn read_7z_image(archive_path: &Path, image_name: &str) -> Result<(Vec<u8>, String), String> {
+    let mut archive =
+        sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())
+            .map_err(|e| sevenz_error("open 7z archive", e).message)?;
+
+    let mut found: Option<Vec<u8>> = None;
+    archive
+        .for_each_entries(|entry, reader| {
+            if entry.name() != image_name {
+                // Solid blocks must still be consumed to reach later entries
+                std::io::copy(reader, &mut std::io::sink())?;
+                return Ok(true);
+            }
+            let mut buffer = Vec::with_capacity((entry.size() as usize).max(1024));
+            reader.read_to_end(&mut buffer)?;
+            found = Some(buffer);
+            Ok(false)
+        })
+        .map_err(|e| sevenz_error("read 7z entry", e).message)?;
+
+    match found {
+        Some(data) => Ok((data, get_mime_type(image_name))),
+        None => Err(format!("Image '{}' not found in archive", image_name)),
+    }
+}
+
+/// Get the page list of a manifest book, in manifest order
+fn get_manifest_image_list(manifest_path: &Path, filter: &PageFilter) -> Result<Vec<String>, String> {
+    let page_manifest = manifest::read_manifest(manifest_path).map_err(|e| e.message)?;
+
+    Ok(page_manifest
+        .pages
+        .into_iter()
+        .filter(|page| !filter.is_excluded(page))
+        .collect())
+}
+
+/// Read a page file listed in a manifest
+fn read_manifest_image(manifest_path: &Path, image_name: &str) -> Result<(Vec<u8>, String), String> {
+    let image_path = manifest::manifest_dir(manifest_path).join(image_name);
+    let data = std::fs::read(&image_path)
+        .map_err(|e| format!("Failed to read page '{}': {}", image_name, e))?;
+
+    Ok((data, get_mime_type(image_name)))
+}
+
 /// Determine MIME type from file extension
 fn get_mime_type(filename: &str) -> String {
     let lower = filename.to_lowercase();
@@ -211,6 +597,8 @@ fn get_mime_type(filename: &str) -> String {
         "image/gif".to_string()
     } else if lower.ends_with(".webp") {
         "image/webp".to_string()
+    } else if lower.ends_with(".avif") {
+        "image/avif".to_string()
     } else {
         "image/jpeg".to_string()
     }
@@ -240,6 +628,11 @@ fn detect_archive_type(path: &Path) -> Result<ArchiveType, String> {
         return Err("RAR archives not supported on Android".to_string());
     }
 
+    // 7z: starts with "7z\xBC\xAF\x27\x1C"
+    if magic[..6] == SEVEN_ZIP_MAGIC {
+        return Ok(ArchiveType::SevenZip);
+    }
+
     // Fallback to extension
     let ext = path
         .extension()
@@ -250,6 +643,7 @@ fn detect_archive_type(path: &Path) -> Result<ArchiveType, String> {
         Some("zip") | Some("cbz") => Ok(ArchiveType::Zip),
         #[cfg(not(target_os = "android"))]
         Some("rar") | Some("cbr") => Ok(ArchiveType::Rar),
+        Some("7z") | Some("cb7") => Ok(ArchiveType::SevenZip),
         _ => Err("Unsupported archive format".to_string()),
     }
 }
@@ -259,44 +653,264 @@ enum ArchiveType {
     Zip,
     #[cfg(not(target_os = "android"))]
     Rar,
+    SevenZip,
+    /// Loose files in a folder, ordered by a `pages.json` manifest
+    Manifest,
+}
+
+impl ArchiveType {
+    /// Parse the value stored in `books.archive_type`
+    fn from_stored(value: &str) -> Option<Self> {
+        match value {
+            "zip" => Some(ArchiveType::Zip),
+            #[cfg(not(target_os = "android"))]
+            "rar" => Some(ArchiveType::Rar),
+            "7z" => Some(ArchiveType::SevenZip),
+            manifest::MANIFEST_ARCHIVE_TYPE => Some(ArchiveType::Manifest),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveType::Zip => "zip",
+            #[cfg(not(target_os = "android"))]
+            ArchiveType::Rar => "rar",
+            ArchiveType::SevenZip => "7z",
+            ArchiveType::Manifest => manifest::MANIFEST_ARCHIVE_TYPE,
+        }
+    }
 }
 
-/// Get image list based on archive type
-fn get_image_list(archive_path: &Path, archive_type: ArchiveType) -> Result<Vec<String>, String> {
+/// Path the book's pages are read from: the archive, or the manifest of a `manifest://` book
+fn book_source_path(book: &Book) -> PathBuf {
+    manifest::manifest_path(&book.file_path)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(&book.file_path))
+}
+
+/// Use the archive type stored on the book, detecting (and storing) it when unknown
+fn resolve_archive_type(book: &Book, archive_path: &Path) -> Result<ArchiveType, String> {
+    if let Some(archive_type) = book.archive_type.as_deref().and_then(ArchiveType::from_stored) {
+        return Ok(archive_type);
+    }
+    if manifest::manifest_path(&book.file_path).is_some() {
+        return Ok(ArchiveType::Manifest);
+    }
+
+    let archive_type = detect_archive_type(archive_path)?;
+    if let Err(e) = set_book_archive_type(book.id, Some(archive_type.as_str())) {
+        log::warn!("Failed to store archive type for book {}: {}", book.id, e);
+    }
+    Ok(archive_type)
+}
+
+/// Get image list based on archive type, leaving out entries hidden by `filter`
+fn get_image_list(archive_path: &Path, archive_type: ArchiveType, filter: &PageFilter) -> Result<Vec<String>, String> {
     match archive_type {
-        ArchiveType::Zip => get_zip_image_list(archive_path),
+        ArchiveType::Zip => get_zip_image_list(archive_path, filter),
         #[cfg(not(target_os = "android"))]
-        ArchiveType::Rar => get_rar_image_list(archive_path),
+        ArchiveType::Rar => get_rar_image_list(archive_path, filter),
+        ArchiveType::SevenZip => get_7z_image_list(archive_path, filter),
+        ArchiveType::Manifest => get_manifest_image_list(archive_path, filter),
     }
 }
 
 /// Read image based on archive type
 fn read_image(
+    book_id: i32,
     archive_path: &Path,
     image_name: &str,
     archive_type: ArchiveType,
 ) -> Result<(Vec<u8>, String), String> {
     match archive_type {
-        ArchiveType::Zip => read_zip_image(archive_path, image_name),
+        ArchiveType::Zip => read_zip_image(&ZIP_HANDLES, book_id, archive_path, image_name),
         #[cfg(not(target_os = "android"))]
         ArchiveType::Rar => read_rar_image(archive_path, image_name),
+        ArchiveType::SevenZip => read_7z_image(archive_path, image_name),
+        ArchiveType::Manifest => read_manifest_image(archive_path, image_name),
+    }
+}
+
+/// Get the ordered image entry names of a book, as served by the protocol
+pub fn get_page_names(book_id: i32) -> Result<Vec<String>, String> {
+    let book = get_book_by_id(book_id).map_err(|e| e.to_string())?;
+    page_names(&book)
+}
+
+fn page_names(book: &Book) -> Result<Vec<String>, String> {
+    if book.file_path.starts_with("cloud://") {
+        return Err("Book is stored in cloud. Please download first.".to_string());
+    }
+
+    let archive_path = book_source_path(&book);
+    if !archive_path.exists() {
+        return Err(format!("Archive file not found: {}", book.file_path));
+    }
+
+    let archive_type = resolve_archive_type(book, &archive_path)?;
+    get_cached_image_list(book.id, &archive_path, archive_type)
+}
+
+/// Every page of a book in reading order, including those the exclusion patterns hide
+///
+/// Not cached; used to remap stored page numbers when the patterns change.
+pub(crate) fn unfiltered_page_names(book: &Book) -> Result<Vec<String>, String> {
+    let archive_path = book_source_path(book);
+    let archive_type = resolve_archive_type(book, &archive_path)?;
+    get_image_list(&archive_path, archive_type, &PageFilter::default())
+}
+
+/// Read a page through the page cache, caching it on a miss
+fn read_page_cached(
+    book_id: i32,
+    page: usize,
+    archive_path: &Path,
+    image_name: &str,
+    archive_type: ArchiveType,
+) -> Result<(Vec<u8>, String), String> {
+    if let Some(cached) = page_cache::get(book_id, page) {
+        return Ok(cached);
+    }
+
+    let read_at = page_cache::generation(book_id);
+    let (data, mime_type) = read_image(book_id, archive_path, image_name, archive_type)?;
+    page_cache::insert(book_id, page, read_at, data.clone(), mime_type.clone());
+    Ok((data, mime_type))
+}
+
+/// Background threads decompressing pages after the one being served
+static PREFETCH_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Pages waiting for a prefetch worker; further requests are dropped while it is full
+const PREFETCH_QUEUE_LEN: usize = 16;
+
+/// A page to decompress into the page cache
+struct PrefetchJob {
+    book_id: i32,
+    page: usize,
+    archive_path: PathBuf,
+    image_name: String,
+    archive_type: ArchiveType,
+    queued_at: page_cache::Generation,
+}
+
+impl PrefetchJob {
+    fn run(self) {
+        match read_image(self.book_id, &self.archive_path, &self.image_name, self.archive_type) {
+            Ok((data, mime_type)) => page_cache::insert(self.book_id, self.page, self.queued_at, data, mime_type),
+            Err(e) => log::debug!("Prefetch of page {} of book {} failed: {}", self.page, self.book_id, e),
+        }
+        page_cache::finish_prefetch(self.book_id, self.page);
+    }
+}
+
+type PrefetchReceiver = Arc<Mutex<std::sync::mpsc::Receiver<PrefetchJob>>>;
+
+/// Queue of the prefetch workers, with as many workers as `advanced.prefetch_concurrency` allows
+fn prefetch_queue() -> &'static std::sync::mpsc::SyncSender<PrefetchJob> {
+    static QUEUE: std::sync::OnceLock<(std::sync::mpsc::SyncSender<PrefetchJob>, PrefetchReceiver)> =
+        std::sync::OnceLock::new();
+    let (sender, receiver) = QUEUE.get_or_init(|| {
+        let (sender, receiver) = std::sync::mpsc::sync_channel::<PrefetchJob>(PREFETCH_QUEUE_LEN);
+        (sender, Arc::new(Mutex::new(receiver)))
+    });
+
+    // Start workers up to the setting; lowering it retires the extra ones after their current job
+    loop {
+        let running = PREFETCH_WORKERS.load(Ordering::Relaxed);
+        if running >= PREFETCH_CONCURRENCY.load(Ordering::Relaxed) {
+            break;
+        }
+        if PREFETCH_WORKERS
+            .compare_exchange(running, running + 1, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            let receiver = Arc::clone(receiver);
+            std::thread::spawn(move || prefetch_worker(&receiver));
+        }
+    }
+    sender
+}
+
+fn prefetch_worker(receiver: &Mutex<std::sync::mpsc::Receiver<PrefetchJob>>) {
+    loop {
+        let running = PREFETCH_WORKERS.load(Ordering::Relaxed);
+        if running > PREFETCH_CONCURRENCY.load(Ordering::Relaxed)
+            && PREFETCH_WORKERS
+                .compare_exchange(running, running - 1, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            return;
+        }
+
+        let job = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+        match job {
+            Ok(job) => job.run(),
+            Err(_) => {
+                PREFETCH_WORKERS.fetch_sub(1, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+}
+
+/// Queue the pages after `page` for decompression into the page cache
+fn prefetch_following_pages(
+    book_id: i32,
+    page: usize,
+    archive_path: PathBuf,
+    archive_type: ArchiveType,
+    image_list: &[String],
+) {
+    let queued_at = page_cache::generation(book_id);
+    for next in page + 1..=page + page_cache::PREFETCH_AHEAD {
+        let Some(image_name) = image_list.get(next) else {
+            break;
+        };
+        if !page_cache::begin_prefetch(book_id, next) {
+            continue;
+        }
+        let job = PrefetchJob {
+            book_id,
+            page: next,
+            archive_path: archive_path.clone(),
+            image_name: image_name.clone(),
+            archive_type,
+            queued_at,
+        };
+        if prefetch_queue().try_send(job).is_err() {
+            // Busy reading ahead elsewhere; the page is read on demand instead
+            log::debug!("Skipping prefetch of page {} of book {}: queue full", next, book_id);
+            page_cache::finish_prefetch(book_id, next);
+        }
     }
 }
 
 /// Handle comic:// protocol requests
 /// URL format: comic://localhost/book/{book_id}/page/{page_number}
+/// or comic://localhost/book/{book_id}/thumb/{page_number}?w={width}
 pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
     let uri = request.uri().to_string();
     log::debug!("Comic protocol request: {}", uri);
+    let range = request
+        .headers()
+        .get("Range")
+        .and_then(|value| value.to_str().ok());
+    let if_none_match = request
+        .headers()
+        .get("If-None-Match")
+        .and_then(|value| value.to_str().ok());
 
     let path = uri
         .strip_prefix("comic://localhost")
         .or_else(|| uri.strip_prefix("comic://"))
         .unwrap_or(&uri);
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
 
     let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
 
-    if parts.len() < 4 || parts[0] != "book" || parts[2] != "page" {
+    if parts.len() < 4 || parts[0] != "book" || (parts[2] != "page" && parts[2] != "thumb") {
         log::warn!("Invalid comic URL format: {}", uri);
         return Response::builder()
             .status(400)
@@ -305,6 +919,8 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
             .unwrap();
     }
 
+    let thumb_width = (parts[2] == "thumb").then(|| thumbnail::parse_width(query));
+
     let book_id: i32 = match parts[1].parse() {
         Ok(id) => id,
         Err(_) => {
@@ -327,6 +943,12 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
         }
     };
 
+    if let Some(width) = thumb_width {
+        if let Some(data) = thumbnail::get_cached(book_id, page_number, width) {
+            return image_response(data, thumbnail::THUMBNAIL_MIME_TYPE, None, range);
+        }
+    }
+
     // Get the book from database
     let book = match get_book_by_id(book_id) {
         Ok(b) => b,
@@ -349,7 +971,8 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
             .unwrap();
     }
 
-    let archive_path = Path::new(&book.file_path);
+    let archive_path = book_source_path(&book);
+    let archive_path = archive_path.as_path();
 
     // Check if file exists
     if !archive_path.exists() {
@@ -361,8 +984,8 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
             .unwrap();
     }
 
-    // Detect archive type
-    let archive_type = match detect_archive_type(archive_path) {
+    // Stored archive type, detected on first read for older rows
+    let archive_type = match resolve_archive_type(&book, archive_path) {
         Ok(t) => t,
         Err(e) => {
             log::error!("Failed to detect archive type: {}", e);
@@ -405,10 +1028,34 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
     }
 
     let image_name = &image_list[page_number];
+    let etag = page_etag(archive_path, image_name, archive_type);
+
+    // Warm the cache for the next page turn (not for thumbnail grids)
+    if thumb_width.is_none() {
+        prefetch_following_pages(book_id, page_number, archive_path.to_path_buf(), archive_type, &image_list);
+    }
+
+    // The webview's copy is still current
+    if let Some(etag) = etag.as_deref().filter(|etag| if_none_match.is_some_and(|tags| etag_matches(tags, etag))) {
+        return Response::builder()
+            .status(304)
+            .header("ETag", etag)
+            .header("Cache-Control", image_cache_control())
+            .body(Vec::new())
+            .unwrap();
+    }
 
     // Read the image
-    let (image_data, mime_type) = match read_image(archive_path, image_name, archive_type) {
+    let (image_data, mime_type) = match read_page_cached(book_id, page_number, archive_path, image_name, archive_type) {
         Ok((data, mime)) => (data, mime),
+        Err(e) if archive_password::is_password_error(&e) => {
+            log::info!("Book {} needs its archive password: {}", book_id, e);
+            return Response::builder()
+                .status(401)
+                .header("Content-Type", "text/plain")
+                .body(e.as_bytes().to_vec())
+                .unwrap();
+        }
         Err(e) => {
             log::error!("Failed to read image: {}", e);
             return Response::builder()
@@ -419,6 +1066,14 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
         }
     };
 
+    // Fall back to the original page if it can't be decoded
+    let (image_data, mime_type) = match thumb_width
+        .and_then(|width| thumbnail::render(book_id, page_number, width, &image_data))
+    {
+        Some(data) => (data, thumbnail::THUMBNAIL_MIME_TYPE.to_string()),
+        None => (image_data, mime_type),
+    };
+
     log::debug!(
         "Serving page {} ({}) from book {}, {} bytes",
         page_number,
@@ -427,10 +1082,419 @@ pub fn handle_comic_protocol(request: Request<Vec<u8>>) -> Response<Vec<u8>> {
         image_data.len()
     );
 
-    Response::builder()
-        .status(200)
-        .header("Content-Type", &mime_type)
-        .header("Cache-Control", "max-age=31536000, immutable")
-        .body(image_data)
-        .unwrap()
+    image_response(image_data, &mime_type, etag.as_deref(), range)
+}
+
+/// Whether an `If-None-Match` header lists `etag` (or is `*`)
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// Build the response for an image, honoring a `Range: bytes=...` request header
+///
+/// A satisfiable range gets 206 with the slice, an unsatisfiable one 416, and a
+/// missing or unsupported (e.g. multi-range) header the whole image.
+fn image_response(data: Vec<u8>, mime_type: &str, etag: Option<&str>, range: Option<&str>) -> Response<Vec<u8>> {
+    let total = data.len();
+    let mut builder = Response::builder()
+        .header("Content-Type", mime_type)
+        .header("Cache-Control", image_cache_control())
+        .header("Accept-Ranges", "bytes");
+    if let Some(etag) = etag {
+        builder = builder.header("ETag", etag);
+    }
+
+    match range.map(|header| parse_byte_range(header, total)) {
+        Some(ByteRange::Satisfiable(start, end)) => builder
+            .status(206)
+            .header("Content-Range", format!("bytes {}-{}/{}", start, end, total))
+            .header("Content-Length", end - start + 1)
+            .body(data[start..=end].to_vec())
+            .unwrap(),
+        Some(ByteRange::Unsatisfiable) => builder
+            .status(416)
+            .header("Content-Range", format!("bytes */{}", total))
+            .body(Vec::new())
+            .unwrap(),
+        Some(ByteRange::Ignored) | None => builder
+            .status(200)
+            .header("Content-Length", total)
+            .body(data)
+            .unwrap(),
+    }
+}
+
+/// Outcome of parsing a `Range` header against a body of known length
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    /// Inclusive start and end offsets
+    Satisfiable(usize, usize),
+    Unsatisfiable,
+    /// Malformed or multi-range header; the whole body is served
+    Ignored,
+}
+
+/// Parse a single `bytes=start-end`, `bytes=start-` or `bytes=-suffix` range
+fn parse_byte_range(header: &str, total: usize) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Ignored;
+    };
+    if spec.contains(',') {
+        return ByteRange::Ignored;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Ignored;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let (start, end) = if start.is_empty() {
+        // Suffix range: the last `end` bytes
+        match end.parse::<usize>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => (total.saturating_sub(suffix), total.saturating_sub(1)),
+            Err(_) => return ByteRange::Ignored,
+        }
+    } else {
+        let Ok(start) = start.parse::<usize>() else {
+            return ByteRange::Ignored;
+        };
+        let end = if end.is_empty() {
+            total.saturating_sub(1)
+        } else {
+            match end.parse::<usize>() {
+                Ok(end) if end >= start => end.min(total.saturating_sub(1)),
+                Ok(_) => return ByteRange::Ignored,
+                Err(_) => return ByteRange::Ignored,
+            }
+        };
+        (start, end)
+    };
+
+    if total == 0 || start >= total {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Satisfiable(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, names: &[&str]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for name in names {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_7z_pages_listed_and_read() {
+        let path = std::env::temp_dir().join(format!("yomiyougu_protocol_{}.cb7", uuid::Uuid::new_v4()));
+        let mut sevenz = sevenz_rust::SevenZWriter::create(&path).unwrap();
+        for name in ["page10.png", "ComicInfo.xml", "page2.jpg", "page1.jpg"] {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            sevenz.push_archive_entry(entry, Some(name.as_bytes())).unwrap();
+        }
+        sevenz.finish().unwrap();
+
+        assert!(matches!(detect_archive_type(&path), Ok(ArchiveType::SevenZip)));
+        assert_eq!(
+            get_7z_image_list(&path, &PageFilter::default()).unwrap(),
+            vec!["page1.jpg", "page2.jpg", "page10.png"]
+        );
+
+        let (data, mime) = read_7z_image(&path, "page10.png").unwrap();
+        assert_eq!(data, b"page10.png");
+        assert_eq!(mime, "image/png");
+        assert!(read_7z_image(&path, "page3.jpg").unwrap_err().contains("not found"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// A zip book stored at `file_path`, with the given ID
+    fn test_book(id: i32, file_path: &str) -> Book {
+        use diesel::prelude::*;
+        use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+        let mut conn = diesel::SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        let book: Book = diesel::insert_into(crate::schema::books::table)
+            .values(&crate::database::models::NewBook {
+                uuid: None,
+                file_path: file_path.to_string(),
+                filename: "book.cbz".to_string(),
+                file_size: None,
+                file_hash: None,
+                title: "Book".to_string(),
+                current_page: 0,
+                total_pages: 0,
+                series_name: None,
+                volume_number: None,
+            })
+            .returning(Book::as_returning())
+            .get_result(&mut conn)
+            .unwrap();
+        Book { id, archive_type: Some("zip".to_string()), ..book }
+    }
+
+    #[test]
+    fn test_page_names_in_reading_order() {
+        let path = std::env::temp_dir().join(format!("yomiyougu_names_{}.cbz", uuid::Uuid::new_v4()));
+        write_zip(&path, &["ch1/page10.jpg", "ComicInfo.xml", "ch1/page2.jpg", "ch1/page1.jpg"]);
+
+        let book = test_book(-5, &path.to_string_lossy());
+        assert_eq!(
+            page_names(&book).unwrap(),
+            vec!["ch1/page1.jpg", "ch1/page2.jpg", "ch1/page10.jpg"]
+        );
+        invalidate_image_cache(book.id);
+        std::fs::remove_file(&path).unwrap();
+
+        // Missing and cloud-only files have no pages to list
+        assert!(page_names(&book).unwrap_err().contains("not found"));
+        let cloud = test_book(-5, "cloud://abc123");
+        assert!(page_names(&cloud).unwrap_err().contains("download"));
+    }
+
+    #[test]
+    fn test_stored_archive_type_skips_detection() {
+        // The file doesn't exist, so any detection would fail
+        let mut book = test_book(-6, "/nonexistent/book.cbz");
+        for archive_type in [ArchiveType::Zip, ArchiveType::SevenZip, ArchiveType::Manifest] {
+            book.archive_type = Some(archive_type.as_str().to_string());
+            let resolved = resolve_archive_type(&book, Path::new(&book.file_path)).unwrap();
+            assert_eq!(resolved.as_str(), archive_type.as_str());
+        }
+
+        // An unknown stored value falls back to detection
+        book.archive_type = Some("tar".to_string());
+        assert!(resolve_archive_type(&book, Path::new(&book.file_path)).is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range("bytes=0-99", 1000), ByteRange::Satisfiable(0, 99));
+        assert_eq!(parse_byte_range("bytes=900-", 1000), ByteRange::Satisfiable(900, 999));
+        assert_eq!(parse_byte_range("bytes=-100", 1000), ByteRange::Satisfiable(900, 999));
+        assert_eq!(parse_byte_range("bytes=-5000", 1000), ByteRange::Satisfiable(0, 999));
+        // An end past the body is clamped
+        assert_eq!(parse_byte_range("bytes=500-5000", 1000), ByteRange::Satisfiable(500, 999));
+
+        assert_eq!(parse_byte_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_byte_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_byte_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+
+        assert_eq!(parse_byte_range("bytes=0-1,5-9", 1000), ByteRange::Ignored);
+        assert_eq!(parse_byte_range("items=0-1", 1000), ByteRange::Ignored);
+        assert_eq!(parse_byte_range("bytes=9-1", 1000), ByteRange::Ignored);
+    }
+
+    #[test]
+    fn test_page_etag_follows_page_and_file() {
+        let path = std::env::temp_dir().join(format!("etag_{}.cbz", uuid::Uuid::new_v4()));
+        write_zip(&path, &["001.jpg", "002.jpg"]);
+        let first = page_etag(&path, "001.jpg", ArchiveType::Zip).unwrap();
+        assert_eq!(page_etag(&path, "001.jpg", ArchiveType::Zip), Some(first.clone()));
+
+        // Another page at the same URL after a reorder, or the same page in a replaced file
+        let reordered = page_etag(&path, "002.jpg", ArchiveType::Zip).unwrap();
+        write_zip(&path, &["001.jpg", "002.jpg", "003.jpg"]);
+        let replaced = page_etag(&path, "001.jpg", ArchiveType::Zip).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_ne!(reordered, first);
+        assert_ne!(replaced, first);
+        assert_eq!(page_etag(&path, "001.jpg", ArchiveType::Zip), None);
+
+        assert!(etag_matches(&first, &first));
+        assert!(etag_matches(&format!("\"other\", W/{}", first), &first));
+        assert!(etag_matches("*", &first));
+        assert!(!etag_matches(&replaced, &first));
+    }
+
+    #[test]
+    fn test_mime_types() {
+        assert_eq!(get_mime_type("001.PNG"), "image/png");
+        assert_eq!(get_mime_type("001.webp"), "image/webp");
+        assert_eq!(get_mime_type("a/001.avif"), "image/avif");
+        assert_eq!(get_mime_type("001.jpeg"), "image/jpeg");
+    }
+
+    #[test]
+    fn test_image_response_serves_ranges() {
+        let data: Vec<u8> = (0..10).collect();
+
+        let full = image_response(data.clone(), "image/png", Some("\"abc\""), None);
+        assert_eq!(full.status(), 200);
+        assert_eq!(full.body().len(), 10);
+        assert_eq!(full.headers()["ETag"], "\"abc\"");
+
+        let partial = image_response(data.clone(), "image/png", None, Some("bytes=2-4"));
+        assert_eq!(partial.status(), 206);
+        assert_eq!(partial.body(), &vec![2, 3, 4]);
+        assert_eq!(partial.headers()["Content-Range"], "bytes 2-4/10");
+        assert_eq!(partial.headers()["Content-Length"], "3");
+        assert_eq!(partial.headers()["Cache-Control"], "no-cache");
+
+        let unsatisfiable = image_response(data, "image/png", None, Some("bytes=20-"));
+        assert_eq!(unsatisfiable.status(), 416);
+        assert_eq!(unsatisfiable.headers()["Content-Range"], "bytes */10");
+    }
+
+    #[test]
+    fn test_image_list_cache_evicts_least_recently_used() {
+        let mut cache = ImageListCache::default();
+        let list = |book_id: i32| vec![format!("{}.jpg", book_id)];
+
+        for book_id in 1..=3 {
+            cache.insert(book_id, list(book_id), 3);
+        }
+
+        // Reading book 1 again makes book 2 the least recently used
+        assert_eq!(cache.get(1), Some(list(1)));
+        cache.insert(4, list(4), 3);
+
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(3), Some(list(3)));
+        assert_eq!(cache.get(1), Some(list(1)));
+        assert_eq!(cache.get(4), Some(list(4)));
+
+        // Order is now 3, 1, 4: shrinking keeps the most recent ones
+        cache.evict_to_capacity(2);
+        assert_eq!(cache.get(3), None);
+        assert!(cache.get(1).is_some() && cache.get(4).is_some());
+
+        cache.remove(1);
+        assert_eq!(cache.get(1), None);
+    }
+
+    #[test]
+    fn test_zip_handles_reused_until_file_changes() {
+        let handles = ZipHandles::new(1);
+        let book_id = -2;
+        let path = std::env::temp_dir().join(format!("handles_{}.cbz", uuid::Uuid::new_v4()));
+        let other_path = std::env::temp_dir().join(format!("handles_{}.cbz", uuid::Uuid::new_v4()));
+        let is_open = |book_id| handles.lock().iter().any(|open| open.book_id == book_id);
+        write_zip(&path, &["001.jpg", "002.jpg"]);
+        write_zip(&other_path, &["001.jpg"]);
+
+        assert_eq!(read_zip_image(&handles, book_id, &path, "001.jpg").unwrap().0, b"001.jpg");
+        assert!(is_open(book_id));
+        assert_eq!(read_zip_image(&handles, book_id, &path, "002.jpg").unwrap().0, b"002.jpg");
+
+        // A replaced file is opened again instead of read through the stale directory
+        write_zip(&path, &["001.jpg", "002.jpg", "extra_page.jpg"]);
+        assert_eq!(read_zip_image(&handles, book_id, &path, "extra_page.jpg").unwrap().0, b"extra_page.jpg");
+
+        // A failed read doesn't put the handle back
+        assert!(read_zip_image(&handles, book_id, &path, "missing.jpg").is_err());
+        assert!(!is_open(book_id));
+
+        // Opening another book beyond the capacity closes the least recently used one
+        read_zip_image(&handles, book_id, &path, "001.jpg").unwrap();
+        read_zip_image(&handles, book_id - 1, &other_path, "001.jpg").unwrap();
+        assert!(!is_open(book_id) && is_open(book_id - 1));
+
+        handles.close(Some(book_id - 1));
+        assert!(!is_open(book_id - 1));
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&other_path).unwrap();
+    }
+
+    #[test]
+    fn test_serving_page_prefetches_following_pages() {
+        let book_id = -5;
+        let path = std::env::temp_dir().join(format!("prefetch_{}.cbz", uuid::Uuid::new_v4()));
+        let list: Vec<String> = ["001.jpg", "002.jpg", "003.jpg", "004.jpg"].map(String::from).into();
+        write_zip(&path, &["001.jpg", "002.jpg", "003.jpg", "004.jpg"]);
+
+        prefetch_following_pages(book_id, 0, path.clone(), ArchiveType::Zip, &list);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !(page_cache::contains(book_id, 1) && page_cache::contains(book_id, 2)) {
+            assert!(std::time::Instant::now() < deadline, "following pages were not prefetched");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        // Only the next `PREFETCH_AHEAD` pages are read, and turning the page serves them from the cache
+        assert!(!page_cache::contains(book_id, 0) && !page_cache::contains(book_id, 3));
+        close_zip_handles(Some(book_id));
+        std::fs::remove_file(&path).unwrap();
+        let (data, _) = read_page_cached(book_id, 1, &path, "002.jpg", ArchiveType::Zip).unwrap();
+        assert_eq!(data, b"002.jpg");
+        page_cache::invalidate(Some(book_id));
+    }
+
+    #[test]
+    fn test_same_page_name_in_different_folders() {
+        let handles = ZipHandles::new(MAX_ZIP_HANDLES);
+        let book_id = -4;
+        let path = std::env::temp_dir().join(format!("folders_{}.cbz", uuid::Uuid::new_v4()));
+        write_zip(&path, &["b/001.jpg", "a/001.jpg", "a/002.jpg"]);
+
+        let list = get_zip_image_list(&path, &PageFilter::default()).unwrap();
+        let pages: Vec<Vec<u8>> = list
+            .iter()
+            .map(|name| read_zip_image(&handles, book_id, &path, name).unwrap().0)
+            .collect();
+        handles.close(None);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(list, vec!["a/001.jpg", "a/002.jpg", "b/001.jpg"]);
+        assert_eq!(pages, vec![b"a/001.jpg".to_vec(), b"a/002.jpg".to_vec(), b"b/001.jpg".to_vec()]);
+    }
+
+    #[test]
+    fn test_rebuild_replaces_cached_page_order() {
+        let book_id = -1;
+        let path = std::env::temp_dir().join(format!("rebuild_{}.cbz", uuid::Uuid::new_v4()));
+        write_zip(&path, &["002.jpg", "003.jpg"]);
+
+        let list = get_cached_image_list(book_id, &path, ArchiveType::Zip).unwrap();
+        assert_eq!(list[0], "002.jpg");
+
+        // A new first page isn't picked up while the old list is cached
+        write_zip(&path, &["001.jpg", "002.jpg", "003.jpg"]);
+        let list = get_cached_image_list(book_id, &path, ArchiveType::Zip).unwrap();
+        assert_eq!(list[0], "002.jpg");
+
+        replace_cached_image_list(book_id, &path, ArchiveType::Zip).unwrap();
+        let list = get_cached_image_list(book_id, &path, ArchiveType::Zip).unwrap();
+        let (page_0, _) = read_image(book_id, &path, &list[0], ArchiveType::Zip).unwrap();
+        invalidate_image_cache(book_id);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(page_0, b"001.jpg");
+    }
+
+    #[test]
+    fn test_path_change_drops_cached_image_list() {
+        let book_id = -3;
+        let dir = std::env::temp_dir();
+        let old_path = dir.join(format!("old_path_{}.cbz", uuid::Uuid::new_v4()));
+        let new_path = dir.join(format!("new_path_{}.cbz", uuid::Uuid::new_v4()));
+        write_zip(&old_path, &["old_01.jpg"]);
+        write_zip(&new_path, &["new_01.jpg", "new_02.jpg"]);
+
+        get_cached_image_list(book_id, &old_path, ArchiveType::Zip).unwrap();
+        read_image(book_id, &old_path, "old_01.jpg", ArchiveType::Zip).unwrap();
+
+        // What restore_deleted_book and download_book do after moving the book to new_path
+        invalidate_image_cache(book_id);
+        let list = get_cached_image_list(book_id, &new_path, ArchiveType::Zip).unwrap();
+        invalidate_image_cache(book_id);
+        std::fs::remove_file(&old_path).unwrap();
+        std::fs::remove_file(&new_path).unwrap();
+
+        assert_eq!(list, vec!["new_01.jpg", "new_02.jpg"]);
+    }
 }
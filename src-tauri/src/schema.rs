@@ -23,6 +23,19 @@ diesel::table! {
         updated_at -> Timestamp,
         uuid -> Nullable<Text>,
         deleted_at -> Nullable<Timestamp>,
+        reader_background -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    book_tags (id) {
+        id -> Integer,
+        book_id -> Integer,
+        tag_id -> Integer,
+        added_at -> Timestamp,
+        uuid -> Nullable<Text>,
+        updated_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -57,6 +70,18 @@ diesel::table! {
         reading_status -> Text,
         uuid -> Nullable<Text>,
         deleted_at -> Nullable<Timestamp>,
+        notes -> Nullable<Text>,
+        archive_type -> Nullable<Text>,
+        open_count -> Integer,
+        completed_count -> Integer,
+        sort_key -> Nullable<Text>,
+        page_fraction -> Nullable<Double>,
+        story_page_count -> Nullable<Integer>,
+        hash_scheme -> Text,
+        series_name -> Nullable<Text>,
+        volume_number -> Nullable<Double>,
+        scroll_fraction -> Nullable<Double>,
+        content_duplicate_of -> Nullable<Integer>,
     }
 }
 
@@ -69,6 +94,30 @@ diesel::table! {
         updated_at -> Timestamp,
         uuid -> Nullable<Text>,
         deleted_at -> Nullable<Timestamp>,
+        parent_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    reading_history (id) {
+        id -> Integer,
+        book_id -> Integer,
+        page_from -> Integer,
+        page_to -> Integer,
+        started_at -> Timestamp,
+        ended_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reading_queue (id) {
+        id -> Integer,
+        book_id -> Integer,
+        position -> Integer,
+        added_at -> Timestamp,
+        updated_at -> Timestamp,
+        uuid -> Nullable<Text>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -78,19 +127,41 @@ diesel::table! {
         last_sync_at -> Nullable<Timestamp>,
         last_sync_device -> Nullable<Text>,
         sync_file_id -> Nullable<Text>,
+        last_attempt_at -> Nullable<Timestamp>,
+        last_errors -> Nullable<Text>,
+        last_changes -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        uuid -> Nullable<Text>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
 diesel::joinable!(book_collections -> books (book_id));
 diesel::joinable!(book_collections -> collections (collection_id));
 diesel::joinable!(book_settings -> books (book_id));
+diesel::joinable!(book_tags -> books (book_id));
+diesel::joinable!(book_tags -> tags (tag_id));
 diesel::joinable!(bookmarks -> books (book_id));
+diesel::joinable!(reading_history -> books (book_id));
+diesel::joinable!(reading_queue -> books (book_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     book_collections,
     book_settings,
+    book_tags,
     bookmarks,
     books,
     collections,
+    reading_history,
+    reading_queue,
     sync_state,
+    tags,
 );
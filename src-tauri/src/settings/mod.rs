@@ -9,5 +9,6 @@ mod schema;
 pub mod storage;
 mod types;
 
+pub use schema::{create_default_settings, settings_presets};
 pub use storage::*;
 pub use types::*;
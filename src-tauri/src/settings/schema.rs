@@ -1,10 +1,49 @@
 //! Default settings schema with manga/comic-appropriate defaults
 
+use std::collections::HashMap;
+
 use super::types::*;
 
 /// Current settings schema version (increment when making breaking changes)
 pub const SETTINGS_VERSION: u32 = 1;
 
+/// Built-in presets: curated reading settings for a type of content, by preset name
+pub fn settings_presets() -> HashMap<String, Vec<(String, SettingValue)>> {
+    let preset = |values: &[(&str, &str)]| {
+        values
+            .iter()
+            .map(|(key, value)| (key.to_string(), SettingValue::String(value.to_string())))
+            .collect::<Vec<_>>()
+    };
+
+    HashMap::from([
+        (
+            "manga".to_string(),
+            preset(&[
+                ("reading.direction", "rtl"),
+                ("reading.page_display_mode", "double"),
+                ("reading.image_fit_mode", "fit_height"),
+            ]),
+        ),
+        (
+            "webtoon".to_string(),
+            preset(&[
+                ("reading.direction", "vertical"),
+                ("reading.page_display_mode", "single"),
+                ("reading.image_fit_mode", "fit_width"),
+            ]),
+        ),
+        (
+            "comic".to_string(),
+            preset(&[
+                ("reading.direction", "ltr"),
+                ("reading.page_display_mode", "single"),
+                ("reading.image_fit_mode", "fit_screen"),
+            ]),
+        ),
+    ])
+}
+
 /// Create the default settings schema with all categories and settings
 pub fn create_default_settings() -> AppSettings {
     AppSettings {
@@ -17,6 +56,7 @@ pub fn create_default_settings() -> AppSettings {
             create_reading_category(),
             create_library_category(),
             create_sync_category(),
+            create_advanced_category(),
         ],
     }
 }
@@ -95,6 +135,27 @@ fn create_reading_category() -> SettingCategory {
                 },
                 SettingValue::String("fit_width".to_string()),
             ),
+            SettingItem::new(
+                "reading.background",
+                "Reader Background",
+                "Color shown around pages in the reader",
+                WidgetType::Select {
+                    options: vec![
+                        SelectOption::new("black", "Black"),
+                        SelectOption::new("gray", "Gray"),
+                        SelectOption::new("white", "White"),
+                        SelectOption::new("sepia", "Sepia"),
+                    ],
+                },
+                SettingValue::String("black".to_string()),
+            ),
+            SettingItem::new(
+                "reading.progress_story_pages_only",
+                "Progress Ignores Ads and Covers",
+                "Base reading progress on story pages only, for books whose ComicInfo.xml marks pages as ads, deleted or the front cover. Those pages are still shown.",
+                WidgetType::Toggle,
+                SettingValue::Bool(false),
+            ),
         ])
 }
 
@@ -109,6 +170,27 @@ fn create_library_category() -> SettingCategory {
                 WidgetType::Toggle,
                 SettingValue::Bool(false),
             ),
+            SettingItem::new(
+                "library.directory",
+                "Library Location",
+                "Folder where imported files are stored. Leave empty to use app storage. Editing this only affects new imports; files already in the library are not moved.",
+                WidgetType::Input,
+                SettingValue::String(String::new()),
+            ),
+            SettingItem::new(
+                "library.default_collection_id",
+                "Default Collection",
+                "Collection new imports are added to when none is chosen. Choose None to import without a collection.",
+                WidgetType::Input,
+                SettingValue::String(String::new()),
+            ),
+            SettingItem::new(
+                "library.fast_import",
+                "Fast Import",
+                "Detect duplicates by comparing archive files instead of their pages, so large imports finish sooner. Page contents are hashed in the background afterwards; until then, the same book packed differently isn't recognized as a duplicate.",
+                WidgetType::Toggle,
+                SettingValue::Bool(false),
+            ),
         ])
 }
 
@@ -141,9 +223,112 @@ fn create_sync_category() -> SettingCategory {
             WidgetType::Toggle,
             SettingValue::Bool(true),
         ),
+        SettingItem::new(
+            "sync.backend",
+            "Sync Service",
+            "Where your library is synced to",
+            WidgetType::Select {
+                options: vec![SelectOption::new("google_drive", "Google Drive")],
+            },
+            SettingValue::String("google_drive".to_string()),
+        ),
+        SettingItem::new(
+            "sync.namespace",
+            "Sync Namespace",
+            "Name of the library this device syncs with. Devices with different namespaces keep separate libraries on the same Google account. Letters, digits, - and _ only; leave empty for the default library.",
+            WidgetType::Input,
+            SettingValue::String(String::new()),
+        ),
+        SettingItem::new(
+            "sync.encrypt",
+            "Encrypt Sync Data",
+            "Encrypt your library data on Google Drive with a passphrase. The passphrase is asked for once per session and never stored; without it, synced data can't be read. Book files are not encrypted.",
+            WidgetType::Toggle,
+            SettingValue::Bool(false),
+        ),
+        SettingItem::new(
+            "sync.conflict_strategy",
+            "Conflict Resolution",
+            "Which version is kept when the same item was changed on this device and another one since the last sync",
+            WidgetType::Select {
+                options: vec![
+                    SelectOption::with_description(
+                        "last_write_wins",
+                        "Most Recent",
+                        "Keep whichever change was made last",
+                    ),
+                    SelectOption::with_description("local_wins", "This Device", "Always keep this device's version"),
+                    SelectOption::with_description("remote_wins", "Cloud", "Always keep the version on Google Drive"),
+                ],
+            },
+            SettingValue::String("last_write_wins".to_string()),
+        ),
     ])
 }
 
+/// Default number of pages prefetched in parallel (lower on mobile to save memory)
+fn default_prefetch_concurrency() -> i64 {
+    if cfg!(any(target_os = "android", target_os = "ios")) {
+        2
+    } else {
+        4
+    }
+}
+
+fn create_advanced_category() -> SettingCategory {
+    SettingCategory::new("advanced", "Advanced", "Performance tuning and troubleshooting")
+        .with_icon("cog")
+        .add_settings(vec![
+            SettingItem::new(
+                "advanced.prefetch_concurrency",
+                "Prefetch Concurrency",
+                "How many upcoming pages are loaded in parallel. Higher is faster on powerful devices but uses more memory.",
+                WidgetType::Slider {
+                    min: 1.0,
+                    max: 8.0,
+                    step: 1.0,
+                },
+                SettingValue::Number(default_prefetch_concurrency()),
+            ),
+            SettingItem::new(
+                "advanced.image_cache_size",
+                "Page List Cache Size",
+                "How many books keep their page list in memory for faster reopening.",
+                WidgetType::Slider {
+                    min: 1.0,
+                    max: 50.0,
+                    step: 1.0,
+                },
+                SettingValue::Number(10),
+            ),
+            SettingItem::new(
+                "advanced.max_rar_page_mb",
+                "Maximum RAR Page Size",
+                "Largest single page (in MB) loaded from a CBR/RAR book. RAR pages are decompressed whole into memory, so oversized pages are refused instead of risking a crash; converting the book to CBZ avoids the limit.",
+                WidgetType::Slider {
+                    min: 8.0,
+                    max: 512.0,
+                    step: 8.0,
+                },
+                SettingValue::Number(crate::protocol::DEFAULT_MAX_RAR_PAGE_MB as i64),
+            ),
+            SettingItem::new(
+                "advanced.page_exclude_globs",
+                "Excluded Pages",
+                "Comma-separated patterns for archive entries to hide from page counts and the reader, e.g. *credits*, *scan_ad*. Applies to books imported or opened after the change; stored page counts of existing books are not recounted.",
+                WidgetType::Input,
+                SettingValue::String(String::new()),
+            ),
+            SettingItem::new(
+                "advanced.disable_image_cache",
+                "Disable Image Caching",
+                "Debugging aid: pages and thumbnails are sent with no-store so the app re-requests them every time instead of reusing cached copies. Slower; leave off normally.",
+                WidgetType::Toggle,
+                SettingValue::Bool(false),
+            ),
+        ])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,4 +359,34 @@ mod tests {
             Some("dark")
         );
     }
+
+    #[test]
+    fn test_export_settings_schema() {
+        let path = std::env::temp_dir().join(format!("settings_schema_{}.json", uuid::Uuid::new_v4()));
+        let count = crate::settings::export_settings_schema(&path).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let categories: Vec<SettingCategory> = serde_json::from_str(&json).unwrap();
+        let settings: Vec<_> = categories.iter().flat_map(|c| &c.settings).collect();
+        assert_eq!(settings.len(), count);
+        assert!(settings.iter().all(|s| s.value == s.default_value));
+        assert!(settings.iter().any(|s| s.key == "appearance.theme"));
+    }
+
+    #[test]
+    fn test_modified_settings() {
+        let mut settings = create_default_settings();
+        assert!(settings.modified().is_empty());
+
+        settings.set("appearance.theme", SettingValue::String("dark".to_string()));
+        // Same number stored as a float is not a change
+        settings.set("advanced.image_cache_size", SettingValue::Float(10.0));
+
+        let modified = settings.modified();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].key, "appearance.theme");
+        assert_eq!(modified[0].value, SettingValue::String("dark".to_string()));
+        assert_eq!(modified[0].default_value, SettingValue::String("system".to_string()));
+    }
 }
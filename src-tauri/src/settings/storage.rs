@@ -1,15 +1,27 @@
 //! Settings storage - file I/O operations for persisting settings
 
 use std::fs;
-use std::path::PathBuf;
-use tauri::Manager;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
 
-use super::schema::create_default_settings;
-use super::types::AppSettings;
-use crate::error::AppError;
+use super::schema::{create_default_settings, settings_presets, SETTINGS_VERSION};
+use super::types::{AppSettings, ModifiedSetting, SettingChangedEvent};
+use crate::error::{AppError, ErrorCode};
 
 const SETTINGS_FILENAME: &str = "settings.json";
 
+/// Event emitted once per setting whose value changed
+pub const SETTING_CHANGED_EVENT: &str = "setting-changed";
+
+/// Setting key holding a custom library directory (empty = app storage)
+pub const LIBRARY_DIR_KEY: &str = "library.directory";
+
+/// Setting key naming the library this device syncs with (empty = default)
+pub const SYNC_NAMESPACE_KEY: &str = "sync.namespace";
+
+/// Setting key holding the collection imports go to by default (empty = none)
+pub const DEFAULT_COLLECTION_KEY: &str = "library.default_collection_id";
+
 /// Get the path to the settings file
 pub fn get_settings_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
     app.path()
@@ -18,6 +30,79 @@ pub fn get_settings_path(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
         .map_err(AppError::config_read_failed)
 }
 
+/// Resolve the directory imported files are stored in
+///
+/// Uses the `library.directory` setting when set, otherwise `<app data>/library`.
+pub fn get_library_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let settings = load_settings(app)?;
+    let custom_dir = settings
+        .get(LIBRARY_DIR_KEY)
+        .and_then(|v| v.as_string())
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty());
+
+    match custom_dir {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => app
+            .path()
+            .app_data_dir()
+            .map(|path| path.join("library"))
+            .map_err(AppError::config_read_failed),
+    }
+}
+
+/// Resolve a writable directory for temporary files, such as imports copied out of
+/// Android content URIs
+///
+/// Tries the app cache directory, then a `tmp` directory in app data, then the OS
+/// temp directory. The cache directory is skipped when it is the app data directory
+/// itself, which some Android configurations report.
+pub fn get_temp_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let cache_dir = app.path().app_cache_dir().ok();
+    let data_dir = app.path().app_data_dir().ok();
+
+    let mut candidates = Vec::new();
+    if let Some(cache_dir) = cache_dir.filter(|dir| Some(dir) != data_dir.as_ref()) {
+        candidates.push(cache_dir);
+    }
+    if let Some(data_dir) = data_dir {
+        candidates.push(data_dir.join("tmp"));
+    }
+    candidates.push(std::env::temp_dir());
+
+    first_writable_dir(&candidates)
+}
+
+/// First directory that can be created and written to
+///
+/// The error lists every path tried and why it was rejected.
+fn first_writable_dir(candidates: &[PathBuf]) -> Result<PathBuf, AppError> {
+    let mut failures = Vec::new();
+
+    for dir in candidates {
+        match check_writable(dir) {
+            Ok(()) => return Ok(dir.clone()),
+            Err(e) => {
+                log::warn!("Temporary directory {:?} is unusable: {}", dir, e);
+                failures.push(format!("{} ({})", dir.display(), e));
+            }
+        }
+    }
+
+    Err(AppError::new(
+        ErrorCode::IoError,
+        format!("No writable temporary directory found. Tried: {}", failures.join("; ")),
+    ))
+}
+
+/// Create the directory if needed and check a file can be written in it
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".write_test_{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
 /// Check if settings file exists
 pub fn settings_exist(app: &tauri::AppHandle) -> Result<bool, AppError> {
     let path = get_settings_path(app)?;
@@ -25,6 +110,9 @@ pub fn settings_exist(app: &tauri::AppHandle) -> Result<bool, AppError> {
 }
 
 /// Load settings from disk, returning defaults if not found
+///
+/// Categories and settings added to the schema since the file was written are filled
+/// in with their defaults and saved right away, so they show up after an upgrade.
 pub fn load_settings(app: &tauri::AppHandle) -> Result<AppSettings, AppError> {
     let path = get_settings_path(app)?;
 
@@ -37,14 +125,47 @@ pub fn load_settings(app: &tauri::AppHandle) -> Result<AppSettings, AppError> {
     let mut settings: AppSettings =
         serde_json::from_str(&json).map_err(AppError::config_parse_failed)?;
 
-    let defaults = create_default_settings();
-    merge_with_defaults(&mut settings, &defaults);
+    if reconcile_schema(&mut settings, &create_default_settings()) {
+        // Not a user change, so other devices' settings still win on the next sync
+        if let Err(e) = save_settings_no_timestamp(app, &settings) {
+            log::warn!("Failed to save reconciled settings: {}", e);
+        }
+    }
 
     Ok(settings)
 }
 
+/// Add whatever `defaults` has that the loaded settings lack, without dropping anything
+///
+/// Also brings older files up to `SETTINGS_VERSION`; settings added without a version
+/// bump are filled in as well. Returns whether the settings changed.
+fn reconcile_schema(settings: &mut AppSettings, defaults: &AppSettings) -> bool {
+    let migrated = settings.version < SETTINGS_VERSION;
+    if migrated {
+        log::info!("Migrating settings from version {} to {}", settings.version, SETTINGS_VERSION);
+        settings.version = SETTINGS_VERSION;
+    }
+    let added = merge_with_defaults(settings, defaults);
+    if added > 0 {
+        log::info!("Added {} new settings with their defaults", added);
+    }
+    migrated || added > 0
+}
+
+/// Bring settings from an older schema version up to `SETTINGS_VERSION`
+///
+/// Categories and settings missing from the file are added with their defaults;
+/// values the user already has are kept.
+pub fn migrate_settings(mut settings: AppSettings) -> AppSettings {
+    reconcile_schema(&mut settings, &create_default_settings());
+    settings
+}
+
 /// Merge loaded settings with defaults to fill in any missing categories/settings
-fn merge_with_defaults(settings: &mut AppSettings, defaults: &AppSettings) {
+///
+/// Returns the number of settings added.
+fn merge_with_defaults(settings: &mut AppSettings, defaults: &AppSettings) -> usize {
+    let mut added = 0;
     for default_category in &defaults.categories {
         if let Some(existing_category) = settings.categories.iter_mut()
             .find(|c| c.id == default_category.id)
@@ -52,12 +173,15 @@ fn merge_with_defaults(settings: &mut AppSettings, defaults: &AppSettings) {
             for default_setting in &default_category.settings {
                 if !existing_category.settings.iter().any(|s| s.key == default_setting.key) {
                     existing_category.settings.push(default_setting.clone());
+                    added += 1;
                 }
             }
         } else {
             settings.categories.push(default_category.clone());
+            added += default_category.settings.len();
         }
     }
+    added
 }
 
 /// Save settings to disk
@@ -100,17 +224,14 @@ pub fn update_settings_from_map(
     updates: std::collections::HashMap<String, serde_json::Value>,
 ) -> Result<AppSettings, AppError> {
     let mut settings = load_settings(app)?;
+    let before = settings.clone();
     
     // Check if only sync settings are being changed (shouldn't update timestamp)
     let only_sync_keys = updates.keys().all(|k| k.starts_with("sync."));
 
-    for (key, value) in updates {
-        let setting_value = json_to_setting_value(value)
-            .ok_or_else(|| AppError::invalid_setting_value(&key, "unsupported type"))?;
-
-        if !settings.set(&key, setting_value) {
-            return Err(AppError::invalid_setting_key(&key));
-        }
+    // Nothing is applied unless every key is valid
+    for (key, value) in validate_updates(&settings, updates)? {
+        settings.set(&key, value);
     }
 
     if only_sync_keys {
@@ -118,9 +239,123 @@ pub fn update_settings_from_map(
     } else {
         save_settings(app, &settings)?;
     }
+    emit_setting_changes(app, &before, &settings);
     Ok(settings)
 }
 
+/// Names of the built-in settings presets, sorted
+pub fn list_settings_presets() -> Vec<String> {
+    let mut names: Vec<String> = settings_presets().into_keys().collect();
+    names.sort();
+    names
+}
+
+/// Apply a built-in preset's values through the validated update path
+pub fn apply_settings_preset(app: &tauri::AppHandle, name: &str) -> Result<AppSettings, AppError> {
+    update_settings_from_map(app, preset_updates(name)?)
+}
+
+/// A preset's values as an update map
+fn preset_updates(name: &str) -> Result<std::collections::HashMap<String, serde_json::Value>, AppError> {
+    let Some(values) = settings_presets().remove(name) else {
+        return Err(AppError::invalid_input(format!(
+            "unknown settings preset '{}' (available: {})",
+            name,
+            list_settings_presets().join(", ")
+        )));
+    };
+
+    values
+        .into_iter()
+        .map(|(key, value)| {
+            serde_json::to_value(value)
+                .map(|value| (key, value))
+                .map_err(AppError::serialization_failed)
+        })
+        .collect()
+}
+
+/// Validate every update against the settings schema
+///
+/// Returns the converted values, or one error listing every invalid key.
+fn validate_updates(
+    settings: &AppSettings,
+    updates: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<Vec<(String, super::types::SettingValue)>, AppError> {
+    let mut updates: Vec<_> = updates.into_iter().collect();
+    updates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut valid = Vec::with_capacity(updates.len());
+    let mut errors = Vec::new();
+
+    for (key, value) in updates {
+        let Some(item) = settings.item(&key) else {
+            errors.push(AppError::invalid_setting_key(&key));
+            continue;
+        };
+
+        let checked = json_to_setting_value(value)
+            .ok_or_else(|| "unsupported type".to_string())
+            .and_then(|value| item.validate(&value).map(|_| value))
+            .and_then(|value| check_setting_format(&key, &value).map(|_| value));
+
+        match checked {
+            Ok(value) => valid.push((key, value)),
+            Err(reason) => errors.push(AppError::invalid_setting_value(&key, &reason)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(valid)
+    } else {
+        Err(AppError::invalid_settings(errors))
+    }
+}
+
+/// Checks beyond what the widget allows, for settings that store a specific format
+fn check_setting_format(key: &str, value: &super::types::SettingValue) -> Result<(), String> {
+    match key {
+        DEFAULT_COLLECTION_KEY => {
+            let id = value.as_string().unwrap_or_default().trim();
+            if id.is_empty() || id.parse::<i32>().is_ok_and(|id| id > 0) {
+                Ok(())
+            } else {
+                Err(format!("'{}' is not a collection ID", id))
+            }
+        }
+        // Checked here too, or a bad value would only surface when every sync fails
+        SYNC_NAMESPACE_KEY => match crate::sync::drive::namespace_error(value.as_string().unwrap_or_default()) {
+            Some(reason) => Err(reason),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Settings whose value differs between two snapshots
+fn changed_settings(before: &AppSettings, after: &AppSettings) -> Vec<SettingChangedEvent> {
+    after
+        .categories
+        .iter()
+        .flat_map(|category| &category.settings)
+        .filter(|setting| before.get(&setting.key) != Some(&setting.value))
+        .map(|setting| SettingChangedEvent {
+            key: setting.key.clone(),
+            value: setting.value.clone(),
+        })
+        .collect()
+}
+
+/// Notify the frontend and backend listeners about changed settings
+fn emit_setting_changes(app: &tauri::AppHandle, before: &AppSettings, after: &AppSettings) {
+    for change in changed_settings(before, after) {
+        log::debug!("Setting changed: {} = {:?}", change.key, change.value);
+        if let Err(e) = app.emit(SETTING_CHANGED_EVENT, change) {
+            log::warn!("Failed to emit {}: {}", SETTING_CHANGED_EVENT, e);
+        }
+    }
+}
+
 /// Convert JSON value to SettingValue
 fn json_to_setting_value(value: serde_json::Value) -> Option<super::types::SettingValue> {
     use super::types::SettingValue;
@@ -148,21 +383,95 @@ pub fn complete_setup(app: &tauri::AppHandle) -> Result<(), AppError> {
     save_settings(app, &settings)
 }
 
+/// Settings the user changed from their defaults
+pub fn get_modified_settings(app: &tauri::AppHandle) -> Result<Vec<ModifiedSetting>, AppError> {
+    Ok(load_settings(app)?.modified())
+}
+
+/// Write the settings schema (every category and setting, at its default) to a JSON file
+///
+/// The output never depends on the user's values, so external tools can rely on it as a
+/// description of the available settings. Returns the number of settings written.
+pub fn export_settings_schema(path: &Path) -> Result<usize, AppError> {
+    let mut settings = create_default_settings();
+    settings.reset_all();
+    let count = settings.categories.iter().map(|category| category.settings.len()).sum();
+
+    let json = serde_json::to_string_pretty(&settings.categories)
+        .map_err(AppError::serialization_failed)?;
+    fs::write(path, json).map_err(AppError::config_write_failed)?;
+
+    Ok(count)
+}
+
+/// Serialize the current settings as a portable JSON document
+pub fn export_settings(app: &tauri::AppHandle) -> Result<String, AppError> {
+    let settings = load_settings(app)?;
+    serde_json::to_string_pretty(&settings).map_err(AppError::serialization_failed)
+}
+
+/// Replace the current setting values with those of an exported settings document
+///
+/// Nothing is written unless the whole document is valid.
+pub fn import_settings(app: &tauri::AppHandle, json: &str) -> Result<AppSettings, AppError> {
+    let before = load_settings(app)?;
+    let settings = apply_imported_settings(&before, json)?;
+    save_settings(app, &settings)?;
+    emit_setting_changes(app, &before, &settings);
+    Ok(settings)
+}
+
+/// Apply the values of an exported settings document on top of `current`
+///
+/// Documents from a newer app version are rejected; older ones are migrated first.
+/// Every value goes through the same validation as `update_settings_from_map`.
+/// Setup and license state stay those of the current install, and so does the
+/// default collection: its ID names a different collection in another library.
+fn apply_imported_settings(current: &AppSettings, json: &str) -> Result<AppSettings, AppError> {
+    let imported: AppSettings = serde_json::from_str(json).map_err(AppError::config_parse_failed)?;
+    if imported.version > SETTINGS_VERSION {
+        return Err(AppError::invalid_input(format!(
+            "settings version {} is newer than this app supports ({})",
+            imported.version, SETTINGS_VERSION
+        )));
+    }
+    let imported = migrate_settings(imported);
+
+    let mut updates = std::collections::HashMap::new();
+    for setting in imported.categories.iter().flat_map(|category| &category.settings) {
+        if setting.key == DEFAULT_COLLECTION_KEY {
+            continue;
+        }
+        let value = serde_json::to_value(&setting.value).map_err(AppError::serialization_failed)?;
+        updates.insert(setting.key.clone(), value);
+    }
+
+    let mut settings = current.clone();
+    for (key, value) in validate_updates(&settings, updates)? {
+        settings.set(&key, value);
+    }
+    Ok(settings)
+}
+
 /// Reset all settings to defaults
 pub fn reset_settings(app: &tauri::AppHandle) -> Result<AppSettings, AppError> {
     let mut settings = load_settings(app)?;
+    let before = settings.clone();
     settings.reset_all();
     save_settings(app, &settings)?;
+    emit_setting_changes(app, &before, &settings);
     Ok(settings)
 }
 
 /// Reset a specific setting to its default
 pub fn reset_setting(app: &tauri::AppHandle, key: &str) -> Result<AppSettings, AppError> {
     let mut settings = load_settings(app)?;
+    let before = settings.clone();
     if !settings.reset(key) {
         return Err(AppError::invalid_setting_key(key));
     }
     save_settings(app, &settings)?;
+    emit_setting_changes(app, &before, &settings);
     Ok(settings)
 }
 
@@ -170,6 +479,145 @@ pub fn reset_setting(app: &tauri::AppHandle, key: &str) -> Result<AppSettings, A
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_migrate_settings_from_v0() {
+        use super::super::types::SettingValue;
+
+        // A version 0 file from before the sync category existed, with one customized value
+        let mut json = serde_json::to_value(create_default_settings()).unwrap();
+        json["version"] = 0.into();
+        let categories = json["categories"].as_array_mut().unwrap();
+        categories.retain(|category| category["id"] != "sync");
+        let mut v0: AppSettings = serde_json::from_value(json).unwrap();
+        v0.set("reading.direction", SettingValue::String("ltr".to_string()));
+        assert!(v0.get("sync.books").is_none());
+
+        let migrated = migrate_settings(v0);
+        assert_eq!(migrated.version, SETTINGS_VERSION);
+        assert_eq!(migrated.get("sync.books"), create_default_settings().get("sync.books"));
+        assert_eq!(
+            migrated.get("reading.direction"),
+            Some(&SettingValue::String("ltr".to_string()))
+        );
+        assert_eq!(migrated.categories.len(), create_default_settings().categories.len());
+    }
+
+    #[test]
+    fn test_reconcile_schema_adds_new_defaults() {
+        use super::super::types::{SettingCategory, SettingItem, SettingValue, WidgetType};
+
+        let mut loaded = create_default_settings();
+        loaded.set("reading.direction", SettingValue::String("ltr".to_string()));
+        loaded.categories[0].settings.push(SettingItem::new(
+            "legacy.flag",
+            "Legacy",
+            "A setting the schema no longer has",
+            WidgetType::Toggle,
+            SettingValue::Bool(true),
+        ));
+        assert!(!reconcile_schema(&mut loaded, &create_default_settings()));
+
+        // A newer schema with one more setting and a whole new category
+        let mut defaults = create_default_settings();
+        defaults.categories[0].settings.push(SettingItem::new(
+            "reading.new_option",
+            "New Option",
+            "Added in an update",
+            WidgetType::Toggle,
+            SettingValue::Bool(true),
+        ));
+        defaults.categories.push(
+            SettingCategory::new("labs", "Labs", "Experimental features").add_settings(vec![SettingItem::new(
+                "labs.preview",
+                "Preview",
+                "Try new features early",
+                WidgetType::Toggle,
+                SettingValue::Bool(false),
+            )]),
+        );
+
+        assert!(reconcile_schema(&mut loaded, &defaults));
+        assert_eq!(loaded.get("reading.new_option"), Some(&SettingValue::Bool(true)));
+        assert_eq!(loaded.get("labs.preview"), Some(&SettingValue::Bool(false)));
+        // User values and settings unknown to the schema are kept
+        assert_eq!(
+            loaded.get("reading.direction"),
+            Some(&SettingValue::String("ltr".to_string()))
+        );
+        assert_eq!(loaded.get("legacy.flag"), Some(&SettingValue::Bool(true)));
+
+        // Reconciling again is a no-op, so the file isn't rewritten on every load
+        assert!(!reconcile_schema(&mut loaded, &defaults));
+    }
+
+    #[test]
+    fn test_apply_imported_settings() {
+        use super::super::types::SettingValue;
+
+        let current = create_default_settings();
+        let mut exported = create_default_settings();
+        exported.set("reading.direction", SettingValue::String("ltr".to_string()));
+        exported.set(DEFAULT_COLLECTION_KEY, SettingValue::String("7".to_string()));
+        exported.setup_completed = true;
+        let json = serde_json::to_string(&exported).unwrap();
+
+        let imported = apply_imported_settings(&current, &json).unwrap();
+        assert_eq!(
+            imported.get("reading.direction"),
+            Some(&SettingValue::String("ltr".to_string()))
+        );
+        assert_eq!(imported.setup_completed, current.setup_completed);
+        assert_eq!(imported.get(DEFAULT_COLLECTION_KEY), current.get(DEFAULT_COLLECTION_KEY));
+
+        // Newer versions, invalid values and malformed JSON are rejected
+        let mut newer = exported.clone();
+        newer.version = SETTINGS_VERSION + 1;
+        let error = apply_imported_settings(&current, &serde_json::to_string(&newer).unwrap()).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::InvalidInput));
+
+        let mut invalid = exported.clone();
+        invalid.set("reading.direction", SettingValue::String("sideways".to_string()));
+        let error = apply_imported_settings(&current, &serde_json::to_string(&invalid).unwrap()).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::InvalidSettingValue));
+
+        assert!(apply_imported_settings(&current, "{\"version\": 1").is_err());
+    }
+
+    #[test]
+    fn test_settings_presets_are_valid() {
+        let defaults = create_default_settings();
+        for name in list_settings_presets() {
+            let updates = preset_updates(&name).unwrap();
+            assert!(!updates.is_empty());
+            assert!(validate_updates(&defaults, updates).is_ok(), "preset '{}' is invalid", name);
+        }
+
+        let error = preset_updates("novel").unwrap_err();
+        assert!(error.message.contains("manga"));
+    }
+
+    #[test]
+    fn test_first_writable_dir_falls_back() {
+        let root = std::env::temp_dir().join(format!("temp_dir_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        // A directory can't be created below a regular file
+        let blocker = root.join("not_a_dir");
+        fs::write(&blocker, b"").unwrap();
+        let unusable = blocker.join("cache");
+        let usable = root.join("data").join("tmp");
+
+        let dir = first_writable_dir(&[unusable.clone(), usable.clone()]).unwrap();
+        assert_eq!(dir, usable);
+        assert!(usable.is_dir());
+        assert_eq!(fs::read_dir(&usable).unwrap().count(), 0, "probe file is removed");
+
+        let error = first_writable_dir(&[unusable.clone(), blocker.join("other")]).unwrap_err();
+        assert!(error.message.contains(&unusable.display().to_string()));
+        assert!(error.message.contains("other"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn test_json_to_setting_value() {
         use serde_json::json;
@@ -189,4 +637,58 @@ mod tests {
             Some(super::super::types::SettingValue::Number(42))
         ));
     }
+
+    #[test]
+    fn test_changed_settings_only_reports_differences() {
+        use super::super::types::SettingValue;
+
+        let before = create_default_settings();
+        let mut after = before.clone();
+        after.set("reading.direction", SettingValue::String("ltr".to_string()));
+        after.set("sync.books", SettingValue::Bool(false)); // unchanged default
+
+        let changes = changed_settings(&before, &after);
+        assert_eq!(
+            changes,
+            vec![SettingChangedEvent {
+                key: "reading.direction".to_string(),
+                value: SettingValue::String("ltr".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_updates_reports_every_error() {
+        use serde_json::json;
+
+        let settings = create_default_settings();
+        let updates = std::collections::HashMap::from([
+            ("reading.direction".to_string(), json!("ltr")),
+            ("reading.nope".to_string(), json!(true)),
+            ("appearance.theme".to_string(), json!("purple")),
+            ("advanced.image_cache_size".to_string(), json!(500)),
+            (DEFAULT_COLLECTION_KEY.to_string(), json!("Manga")),
+            (SYNC_NAMESPACE_KEY.to_string(), json!("my library")),
+        ]);
+
+        let err = validate_updates(&settings, updates).unwrap_err();
+        assert!(matches!(err.code, crate::error::ErrorCode::InvalidSettingValue));
+        assert_eq!(err.details.len(), 5);
+        assert!(err.details.iter().any(|d| d.contains(SYNC_NAMESPACE_KEY)));
+        assert!(err.details.iter().any(|d| d.contains(DEFAULT_COLLECTION_KEY)));
+        assert!(err.details.iter().any(|d| d.contains("reading.nope")));
+        assert!(err.details.iter().any(|d| d.contains("appearance.theme")));
+        assert!(err.details.iter().any(|d| d.contains("advanced.image_cache_size")));
+
+        let valid = validate_updates(
+            &settings,
+            std::collections::HashMap::from([
+                ("reading.direction".to_string(), json!("ltr")),
+                (DEFAULT_COLLECTION_KEY.to_string(), json!("12")),
+                (SYNC_NAMESPACE_KEY.to_string(), json!("work-laptop")),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(valid.len(), 3);
+    }
 }
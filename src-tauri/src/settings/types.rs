@@ -87,6 +87,22 @@ impl From<SettingValue> for Value {
     }
 }
 
+/// Payload of the `setting-changed` event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SettingChangedEvent {
+    pub key: String,
+    pub value: SettingValue,
+}
+
+/// A setting whose value differs from its default
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifiedSetting {
+    pub key: String,
+    pub value: SettingValue,
+    pub default_value: SettingValue,
+}
+
 /// Widget type for UI rendering
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
@@ -178,6 +194,30 @@ impl SettingItem {
             platforms: Vec::new(),
         }
     }
+
+    /// Check that a value fits this setting's widget
+    pub fn validate(&self, value: &SettingValue) -> Result<(), String> {
+        match (&self.widget, value) {
+            (WidgetType::Toggle, SettingValue::Bool(_)) => Ok(()),
+            (WidgetType::Input | WidgetType::Color, SettingValue::String(_)) => Ok(()),
+            (WidgetType::Select { options }, SettingValue::String(s)) => {
+                if options.iter().any(|option| &option.value == s) {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not one of the available options", s))
+                }
+            }
+            (WidgetType::Slider { min, max, .. }, SettingValue::Number(_) | SettingValue::Float(_)) => {
+                let n = value.as_float().unwrap_or_default();
+                if n >= *min && n <= *max {
+                    Ok(())
+                } else {
+                    Err(format!("{} is outside the range {}-{}", n, min, max))
+                }
+            }
+            _ => Err("wrong value type".to_string()),
+        }
+    }
 }
 
 /// Category grouping related settings
@@ -253,6 +293,14 @@ impl AppSettings {
         None
     }
 
+    /// Get a setting definition by key
+    pub fn item(&self, key: &str) -> Option<&SettingItem> {
+        self.categories
+            .iter()
+            .flat_map(|category| &category.settings)
+            .find(|setting| setting.key == key)
+    }
+
     /// Set a setting value by key
     pub fn set(&mut self, key: &str, value: SettingValue) -> bool {
         for category in &mut self.categories {
@@ -279,6 +327,25 @@ impl AppSettings {
         false
     }
 
+    /// Settings whose value differs from their default, in schema order
+    ///
+    /// Numbers compare by value, so a slider stored as `3` matches a `3.0` default.
+    pub fn modified(&self) -> Vec<ModifiedSetting> {
+        self.categories
+            .iter()
+            .flat_map(|category| &category.settings)
+            .filter(|setting| match (setting.value.as_float(), setting.default_value.as_float()) {
+                (Some(value), Some(default)) => value != default,
+                _ => setting.value != setting.default_value,
+            })
+            .map(|setting| ModifiedSetting {
+                key: setting.key.clone(),
+                value: setting.value.clone(),
+                default_value: setting.default_value.clone(),
+            })
+            .collect()
+    }
+
     /// Reset all settings to defaults
     pub fn reset_all(&mut self) {
         for category in &mut self.categories {
@@ -0,0 +1,108 @@
+//! Storage backend a sync runs against
+//!
+//! A backend stores the sync snapshot and the book files (named by content hash).
+//! Sync commands only talk to `SyncBackend`, so another storage service can be added
+//! without touching the merge and upload logic. Which backend is used comes from the
+//! `sync.backend` setting; Google Drive is currently the only one.
+
+use async_trait::async_trait;
+
+use super::drive::DriveSync;
+use super::types::SyncSnapshot;
+use crate::error::AppError;
+
+/// `sync.backend` value for Google Drive
+pub const GOOGLE_DRIVE_BACKEND: &str = "google_drive";
+
+/// A book file stored by a backend
+#[derive(Debug, Clone)]
+pub struct RemoteBookFile {
+    /// Content hash the file is stored under
+    pub file_hash: String,
+}
+
+#[async_trait]
+pub trait SyncBackend: Send + Sync {
+    /// Name of the storage service, for messages shown to the user
+    fn name(&self) -> &'static str;
+
+    /// Fail early when the snapshot can't be read or written with the current settings
+    fn check_snapshot_key(&self) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    /// ID of the stored snapshot, if there is one
+    ///
+    /// `cached_file_id` is the ID remembered from the last sync.
+    async fn find_sync_file(&self, cached_file_id: Option<&str>) -> Result<Option<String>, AppError>;
+
+    /// Download the stored snapshot, if there is one
+    async fn download_snapshot(&self, cached_file_id: Option<&str>) -> Result<Option<SyncSnapshot>, AppError>;
+
+    /// Store a snapshot, replacing `existing_file_id` if given; returns the snapshot's ID
+    async fn upload_snapshot(&self, snapshot: &SyncSnapshot, existing_file_id: Option<&str>) -> Result<String, AppError>;
+
+    /// All stored book files
+    async fn list_book_files(&self) -> Result<Vec<RemoteBookFile>, AppError>;
+
+    /// Store a book file under its content hash; returns the file's ID
+    async fn upload_book_file(&self, file_path: &str, file_hash: &str) -> Result<String, AppError>;
+
+    /// Download the book file with `file_hash` to `target_path`
+    ///
+    /// `on_progress` is called with the bytes downloaded so far and the total size,
+    /// if known. Fails with `CloudFileMissing` when there is no such file.
+    async fn download_book_file(
+        &self,
+        file_hash: &str,
+        target_path: &str,
+        on_progress: &(dyn Fn(u64, Option<u64>) + Send + Sync),
+    ) -> Result<(), AppError>;
+
+    /// Delete the book file with `file_hash`; returns false if there was none
+    async fn delete_book_file(&self, file_hash: &str) -> Result<bool, AppError>;
+}
+
+#[async_trait]
+impl SyncBackend for DriveSync {
+    fn name(&self) -> &'static str {
+        "Google Drive"
+    }
+
+    fn check_snapshot_key(&self) -> Result<(), AppError> {
+        DriveSync::check_snapshot_key(self)
+    }
+
+    async fn find_sync_file(&self, cached_file_id: Option<&str>) -> Result<Option<String>, AppError> {
+        DriveSync::find_sync_file(self, cached_file_id).await
+    }
+
+    async fn download_snapshot(&self, cached_file_id: Option<&str>) -> Result<Option<SyncSnapshot>, AppError> {
+        DriveSync::download_snapshot(self, cached_file_id).await
+    }
+
+    async fn upload_snapshot(&self, snapshot: &SyncSnapshot, existing_file_id: Option<&str>) -> Result<String, AppError> {
+        DriveSync::upload_snapshot(self, snapshot, existing_file_id).await
+    }
+
+    async fn list_book_files(&self) -> Result<Vec<RemoteBookFile>, AppError> {
+        DriveSync::list_book_files(self).await
+    }
+
+    async fn upload_book_file(&self, file_path: &str, file_hash: &str) -> Result<String, AppError> {
+        DriveSync::upload_book_file(self, file_path, file_hash).await
+    }
+
+    async fn download_book_file(
+        &self,
+        file_hash: &str,
+        target_path: &str,
+        on_progress: &(dyn Fn(u64, Option<u64>) + Send + Sync),
+    ) -> Result<(), AppError> {
+        DriveSync::download_book_file(self, file_hash, target_path, on_progress).await
+    }
+
+    async fn delete_book_file(&self, file_hash: &str) -> Result<bool, AppError> {
+        DriveSync::delete_book_file(self, file_hash).await
+    }
+}
@@ -1,23 +1,261 @@
 //! Google Drive integration for sync
 //!
 //! Handles reading/writing the sync snapshot to Google Drive's appData folder.
+//!
+//! Several independent libraries can share one Drive account: with a namespace
+//! (the `sync.namespace` setting) the snapshot is `sync_snapshot_{namespace}.json`
+//! and book files are `{namespace}_book_{hash}.cbz`. Without one the original
+//! names are used.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use crate::error::AppError;
+use super::backend::RemoteBookFile;
+use super::encryption;
 use super::types::SyncSnapshot;
 
 const SYNC_FILENAME: &str = "sync_snapshot.json";
+const MAX_NAMESPACE_LEN: usize = 32;
 const DRIVE_API_BASE: &str = "https://www.googleapis.com/drive/v3";
 const DRIVE_UPLOAD_BASE: &str = "https://www.googleapis.com/upload/drive/v3";
 
+/// Time allowed to establish a connection to Drive
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Longest wait for data on an open connection; not a limit on the whole transfer,
+/// so large book uploads and downloads can take as long as they need
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long an idle connection is kept for the next request
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Book files from this size on are sent with a resumable upload instead of one
+/// in-memory multipart request
+const RESUMABLE_UPLOAD_THRESHOLD: u64 = 5 * 1024 * 1024;
+/// Bytes sent per resumable upload request; Drive requires a multiple of 256 KiB
+const UPLOAD_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+/// Attempts per chunk before a resumable upload gives up
+const MAX_CHUNK_ATTEMPTS: u32 = 4;
+
+/// Attempts per request before a transient Drive error is returned to the caller
+const MAX_REQUEST_ATTEMPTS: u32 = 4;
+/// Wait before the first retry, doubled after each failed attempt (plus jitter)
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between attempts, also for a `Retry-After` header
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Bytes between download progress reports
+const DOWNLOAD_PROGRESS_STEP: u64 = 512 * 1024;
+
 /// Google Drive sync operations
+///
+/// All requests share one HTTP client, so consecutive calls (e.g. uploading many
+/// book files) reuse the same TLS connections.
 pub struct DriveSync {
     access_token: String,
+    namespace: Option<String>,
+    /// Whether uploaded snapshots are encrypted with the session passphrase
+    encrypt: bool,
+    /// Whether the last downloaded snapshot was encrypted; such a snapshot is
+    /// uploaded encrypted again even with `encrypt` off
+    remote_encrypted: AtomicBool,
+    client: reqwest::Client,
+}
+
+fn build_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .build()
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to configure Drive HTTP client, using defaults: {}", e);
+            reqwest::Client::new()
+        })
+}
+
+/// Check a sync namespace, returning `None` for the default (blank) one
+///
+/// Namespaces end up in Drive file names and queries, so only ASCII letters,
+/// digits, `-` and `_` are accepted.
+pub fn normalize_namespace(namespace: &str) -> Result<Option<String>, AppError> {
+    if let Some(reason) = namespace_error(namespace) {
+        return Err(AppError::invalid_input(reason));
+    }
+    let namespace = namespace.trim();
+    Ok((!namespace.is_empty()).then(|| namespace.to_string()))
+}
+
+/// Why `normalize_namespace` would reject a namespace, if it would
+pub fn namespace_error(namespace: &str) -> Option<String> {
+    let namespace = namespace.trim();
+    let valid = namespace.len() <= MAX_NAMESPACE_LEN
+        && namespace.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    (!valid).then(|| {
+        format!(
+            "sync namespace '{}' must be at most {} letters, digits, '-' or '_'",
+            namespace, MAX_NAMESPACE_LEN
+        )
+    })
 }
 
 impl DriveSync {
     /// Create with a specific access token
     pub fn with_token(access_token: String) -> Self {
-        Self { access_token }
+        Self {
+            access_token,
+            namespace: None,
+            encrypt: false,
+            remote_encrypted: AtomicBool::new(false),
+            client: build_client(),
+        }
+    }
+
+    /// Encrypt uploaded snapshots with the session passphrase (see [`encryption`])
+    ///
+    /// Encrypted snapshots are decrypted on download either way, and stay
+    /// encrypted when uploaded again.
+    pub fn with_encryption(mut self, encrypt: bool) -> Self {
+        self.encrypt = encrypt;
+        self
+    }
+
+    /// Fail early when snapshots are to be encrypted but no passphrase was entered
+    pub fn check_snapshot_key(&self) -> Result<(), AppError> {
+        if self.encrypt && encryption::passphrase().is_none() {
+            return Err(AppError::sync_passphrase_required(
+                "Sync encryption is on. Enter the sync passphrase to continue.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get the serialized snapshot out of a downloaded payload, noting whether it was encrypted
+    fn open_snapshot(&self, payload: String, passphrase: Option<&str>) -> Result<String, AppError> {
+        self.remote_encrypted
+            .store(encryption::is_encrypted(&payload), Ordering::Relaxed);
+        encryption::open(payload, passphrase)
+    }
+
+    /// Serialized snapshot ready for upload, with its content type
+    ///
+    /// Encrypted when `encrypt` is on or the downloaded snapshot was encrypted, so a
+    /// device with the setting off can't replace an encrypted snapshot with plain JSON.
+    fn seal_snapshot(&self, json: String, passphrase: Option<String>) -> Result<(String, &'static str), AppError> {
+        if !self.encrypt && !self.remote_encrypted.load(Ordering::Relaxed) {
+            return Ok((json, "application/json"));
+        }
+        let Some(passphrase) = passphrase else {
+            return Err(AppError::sync_passphrase_required(
+                "Sync encryption is on. Enter the sync passphrase to continue.",
+            ));
+        };
+        Ok((encryption::seal(&json, &passphrase)?, "text/plain"))
+    }
+
+    /// Use the snapshot and book files of the library named `namespace`
+    ///
+    /// A blank namespace keeps the default file names.
+    pub fn with_namespace(mut self, namespace: &str) -> Result<Self, AppError> {
+        self.namespace = normalize_namespace(namespace)?;
+        Ok(self)
+    }
+
+    /// Name of this library's snapshot file
+    fn snapshot_filename(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("sync_snapshot_{}.json", namespace),
+            None => SYNC_FILENAME.to_string(),
+        }
+    }
+
+    /// Prefix of this library's book file names, followed by the file hash
+    fn book_file_prefix(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}_book_", namespace),
+            None => "book_".to_string(),
+        }
+    }
+
+    fn book_filename(&self, file_hash: &str) -> String {
+        format!("{}{}.cbz", self.book_file_prefix(), file_hash)
+    }
+
+    /// Send a request, retrying transient failures
+    ///
+    /// `build` creates the request for each attempt. 429 and 5xx responses, connection
+    /// errors and timeouts are retried with exponential backoff, waiting as long as a
+    /// `Retry-After` header asks when there is one. Any other response is returned for
+    /// the caller to check, as is the last one once `MAX_REQUEST_ATTEMPTS` is reached.
+    ///
+    /// Only for requests that are safe to repeat; files are created with
+    /// `create_with_retry`.
+    async fn send_with_retry(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> reqwest::Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            let outcome = build().send().await;
+            let Some((reason, requested_delay)) = transient_failure(&outcome) else {
+                return outcome;
+            };
+            if attempt >= MAX_REQUEST_ATTEMPTS {
+                return outcome;
+            }
+
+            wait_before_retry(&reason, requested_delay, attempt).await;
+            attempt += 1;
+        }
+    }
+
+    /// Create the file `name` with a multipart upload, returning its ID
+    ///
+    /// A create that timed out or failed with a 5xx may still have gone through, so
+    /// before each retry Drive is searched for `name`. A file found there is the
+    /// result, rather than a second copy being created. `action` describes the
+    /// request in error messages.
+    async fn create_with_retry(
+        &self,
+        name: &str,
+        action: &str,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<String, AppError> {
+        let mut attempt = 1;
+        let response = loop {
+            let outcome = build().send().await;
+            let Some((reason, requested_delay)) = transient_failure(&outcome) else {
+                break outcome;
+            };
+            if attempt >= MAX_REQUEST_ATTEMPTS {
+                break outcome;
+            }
+
+            wait_before_retry(&reason, requested_delay, attempt).await;
+            if let Some(id) = self.find_file_by_name(name).await? {
+                log::info!("{} was created by the failed request, not creating it again", name);
+                return Ok(id);
+            }
+            attempt += 1;
+        }
+        .map_err(|e| AppError::sync_failed(format!("Failed to {}: {}", action, e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::sync_failed(format!(
+                "Failed to {}: Drive error {}: {}",
+                action, status, body
+            )));
+        }
+
+        #[derive(serde::Deserialize)]
+        struct CreateResponse {
+            id: String,
+        }
+
+        let create_response: CreateResponse = response.json().await
+            .map_err(|e| AppError::sync_failed(format!("Failed to parse create response: {}", e)))?;
+
+        Ok(create_response.id)
     }
 
     /// Find the sync file in appData folder, returns file ID if found
@@ -31,14 +269,12 @@ impl DriveSync {
             log::info!("Cached sync file ID {} no longer valid, searching...", id);
         }
         
-        let client = reqwest::Client::new();
-        
-        let response = client
+        let response = self.client
             .get(format!("{}/files", DRIVE_API_BASE))
             .bearer_auth(&self.access_token)
             .query(&[
                 ("spaces", "appDataFolder"),
-                ("q", &format!("name = '{}'", SYNC_FILENAME)),
+                ("q", &format!("name = '{}'", self.snapshot_filename())),
                 ("fields", "files(id, name, modifiedTime)"),
             ])
             .send()
@@ -70,19 +306,31 @@ impl DriveSync {
         Ok(file_list.files.into_iter().next().map(|f| f.id))
     }
 
-    /// Verify a file ID still exists on Drive
+    /// Verify a file ID still exists on Drive and is this library's snapshot
+    ///
+    /// A cached ID can belong to another namespace's snapshot after the namespace changed.
     async fn verify_file_exists(&self, file_id: &str) -> Result<bool, AppError> {
-        let client = reqwest::Client::new();
-        
-        let response = client
+        let response = self.client
             .get(format!("{}/files/{}", DRIVE_API_BASE, file_id))
             .bearer_auth(&self.access_token)
-            .query(&[("fields", "id")])
+            .query(&[("fields", "id, name")])
             .send()
             .await
             .map_err(|e| AppError::sync_failed(format!("Failed to verify file: {}", e)))?;
 
-        Ok(response.status().is_success())
+        if !response.status().is_success() {
+            return Ok(false);
+        }
+
+        #[derive(serde::Deserialize)]
+        struct FileInfo {
+            name: String,
+        }
+
+        let file: FileInfo = response.json().await
+            .map_err(|e| AppError::sync_failed(format!("Failed to parse file info: {}", e)))?;
+
+        Ok(file.name == self.snapshot_filename())
     }
 
     /// Download the sync snapshot from Google Drive
@@ -92,13 +340,13 @@ impl DriveSync {
             None => return Ok(None),
         };
 
-        let client = reqwest::Client::new();
-        
-        let response = client
-            .get(format!("{}/files/{}", DRIVE_API_BASE, file_id))
-            .bearer_auth(&self.access_token)
-            .query(&[("alt", "media")])
-            .send()
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .get(format!("{}/files/{}", DRIVE_API_BASE, file_id))
+                    .bearer_auth(&self.access_token)
+                    .query(&[("alt", "media")])
+            })
             .await
             .map_err(|e| AppError::sync_failed(format!("Failed to download snapshot: {}", e)))?;
 
@@ -114,7 +362,10 @@ impl DriveSync {
             )));
         }
 
-        let snapshot: SyncSnapshot = response.json().await
+        let payload = response.text().await
+            .map_err(|e| AppError::sync_failed(format!("Failed to download snapshot: {}", e)))?;
+        let json = self.open_snapshot(payload, encryption::passphrase().as_deref())?;
+        let snapshot: SyncSnapshot = serde_json::from_str(&json)
             .map_err(|e| AppError::sync_failed(format!("Failed to parse snapshot: {}", e)))?;
 
         log::info!("Downloaded sync snapshot with {} books, {} bookmarks, {} collections",
@@ -128,19 +379,21 @@ impl DriveSync {
 
     /// Upload the sync snapshot to Google Drive
     pub async fn upload_snapshot(&self, snapshot: &SyncSnapshot, existing_file_id: Option<&str>) -> Result<String, AppError> {
-        let client = reqwest::Client::new();
         let json_content = serde_json::to_string(snapshot)
             .map_err(|e| AppError::sync_failed(format!("Failed to serialize snapshot: {}", e)))?;
+        let (json_content, content_type) = self.seal_snapshot(json_content, encryption::passphrase())?;
 
         let file_id = if let Some(id) = existing_file_id {
             // Update existing file
-            let response = client
-                .patch(format!("{}/files/{}", DRIVE_UPLOAD_BASE, id))
-                .bearer_auth(&self.access_token)
-                .query(&[("uploadType", "media")])
-                .header("Content-Type", "application/json")
-                .body(json_content)
-                .send()
+            let response = self
+                .send_with_retry(|| {
+                    self.client
+                        .patch(format!("{}/files/{}", DRIVE_UPLOAD_BASE, id))
+                        .bearer_auth(&self.access_token)
+                        .query(&[("uploadType", "media")])
+                        .header("Content-Type", content_type)
+                        .body(json_content.clone())
+                })
                 .await
                 .map_err(|e| AppError::sync_failed(format!("Failed to update snapshot: {}", e)))?;
 
@@ -163,7 +416,7 @@ impl DriveSync {
             }
 
             let metadata = FileMetadata {
-                name: SYNC_FILENAME.to_string(),
+                name: self.snapshot_filename(),
                 parents: vec!["appDataFolder".to_string()],
             };
 
@@ -177,39 +430,20 @@ impl DriveSync {
                 Content-Type: application/json; charset=UTF-8\r\n\r\n\
                 {metadata_json}\r\n\
                 --{boundary}\r\n\
-                Content-Type: application/json\r\n\r\n\
+                Content-Type: {content_type}\r\n\r\n\
                 {json_content}\r\n\
                 --{boundary}--"
             );
 
-            let response = client
-                .post(format!("{}/files", DRIVE_UPLOAD_BASE))
-                .bearer_auth(&self.access_token)
-                .query(&[("uploadType", "multipart")])
-                .header("Content-Type", format!("multipart/related; boundary={}", boundary))
-                .body(body)
-                .send()
-                .await
-                .map_err(|e| AppError::sync_failed(format!("Failed to create snapshot: {}", e)))?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(AppError::sync_failed(format!(
-                    "Drive create error {}: {}",
-                    status, body
-                )));
-            }
-
-            #[derive(serde::Deserialize)]
-            struct CreateResponse {
-                id: String,
-            }
-
-            let create_response: CreateResponse = response.json().await
-                .map_err(|e| AppError::sync_failed(format!("Failed to parse create response: {}", e)))?;
-
-            create_response.id
+            self.create_with_retry(&metadata.name, "create snapshot", || {
+                self.client
+                    .post(format!("{}/files", DRIVE_UPLOAD_BASE))
+                    .bearer_auth(&self.access_token)
+                    .query(&[("uploadType", "multipart")])
+                    .header("Content-Type", format!("multipart/related; boundary={}", boundary))
+                    .body(body.clone())
+            })
+            .await?
         };
 
         log::info!("Uploaded sync snapshot with {} books, {} bookmarks, {} collections",
@@ -233,9 +467,7 @@ impl DriveSync {
 
         log::info!("Deleting book file {} (Drive ID: {})...", file_hash, file_id);
 
-        let client = reqwest::Client::new();
-        
-        let response = client
+        let response = self.client
             .delete(format!("{}/files/{}", DRIVE_API_BASE, file_id))
             .bearer_auth(&self.access_token)
             .send()
@@ -257,20 +489,22 @@ impl DriveSync {
 
     /// Find a comic book file in appData folder by its hash
     pub async fn find_book_file(&self, file_hash: &str) -> Result<Option<String>, AppError> {
-        let client = reqwest::Client::new();
-        let filename = format!("book_{}.cbz", file_hash);
-        
-        let response = client
+        self.find_file_by_name(&self.book_filename(file_hash)).await
+    }
+
+    /// Find a file in appData folder by its exact name, returns file ID if found
+    async fn find_file_by_name(&self, name: &str) -> Result<Option<String>, AppError> {
+        let response = self.client
             .get(format!("{}/files", DRIVE_API_BASE))
             .bearer_auth(&self.access_token)
             .query(&[
                 ("spaces", "appDataFolder"),
-                ("q", &format!("name = '{}'", filename)),
+                ("q", &format!("name = '{}'", name)),
                 ("fields", "files(id, name, modifiedTime, size)"),
             ])
             .send()
             .await
-            .map_err(|e| AppError::sync_failed(format!("Failed to search for book file: {}", e)))?;
+            .map_err(|e| AppError::sync_failed(format!("Failed to search Drive for {}: {}", name, e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -298,23 +532,25 @@ impl DriveSync {
     }
 
     /// Upload a comic book file to Google Drive appData folder
+    ///
+    /// Files of `RESUMABLE_UPLOAD_THRESHOLD` and more are streamed from disk in
+    /// chunks, so memory use doesn't grow with the archive size.
     pub async fn upload_book_file(&self, file_path: &str, file_hash: &str) -> Result<String, AppError> {
         use std::fs;
         
-        let client = reqwest::Client::new();
-        let filename = format!("book_{}.cbz", file_hash);
-        
-        // Read file content
-        let file_content = fs::read(file_path)
-            .map_err(|e| AppError::sync_failed(format!("Failed to read book file: {}", e)))?;
+        let filename = self.book_filename(file_hash);
         
         // Check if file already exists
         if let Some(existing_id) = self.find_book_file(file_hash).await? {
             log::info!("Book file {} already exists in Drive, skipping upload", file_hash);
             return Ok(existing_id);
         }
-        
-        log::info!("Uploading book file {} ({} bytes)...", filename, file_content.len());
+
+        let file_size = fs::metadata(file_path)
+            .map_err(|e| AppError::sync_failed(format!("Failed to read book file: {}", e)))?
+            .len();
+
+        log::info!("Uploading book file {} ({} bytes)...", filename, file_size);
 
         // Create file metadata
         #[derive(serde::Serialize)]
@@ -331,7 +567,16 @@ impl DriveSync {
         let metadata_json = serde_json::to_string(&metadata)
             .map_err(|e| AppError::sync_failed(format!("Failed to serialize metadata: {}", e)))?;
 
-        // Use resumable upload for larger files
+        if file_size >= RESUMABLE_UPLOAD_THRESHOLD {
+            let file_id = self.upload_resumable(file_path, file_size, metadata_json).await?;
+            log::info!("Uploaded book file {} with ID {}", filename, file_id);
+            return Ok(file_id);
+        }
+
+        // Small files go in a single multipart request
+        let file_content = fs::read(file_path)
+            .map_err(|e| AppError::sync_failed(format!("Failed to read book file: {}", e)))?;
+
         let boundary = format!("book_boundary_{}", uuid::Uuid::new_v4());
         
         // Build multipart body
@@ -344,51 +589,199 @@ impl DriveSync {
         body.extend_from_slice(&file_content);
         body.extend_from_slice(format!("\r\n--{}--", boundary).as_bytes());
 
-        let response = client
-            .post(format!("{}/files", DRIVE_UPLOAD_BASE))
-            .bearer_auth(&self.access_token)
-            .query(&[("uploadType", "multipart")])
-            .header("Content-Type", format!("multipart/related; boundary={}", boundary))
-            .body(body)
-            .send()
+        let file_id = self
+            .create_with_retry(&filename, "upload book file", || {
+                self.client
+                    .post(format!("{}/files", DRIVE_UPLOAD_BASE))
+                    .bearer_auth(&self.access_token)
+                    .query(&[("uploadType", "multipart")])
+                    .header("Content-Type", format!("multipart/related; boundary={}", boundary))
+                    .body(body.clone())
+            })
+            .await?;
+
+        log::info!("Uploaded book file {} with ID {}", filename, file_id);
+
+        Ok(file_id)
+    }
+
+    /// Upload a file with Drive's resumable upload protocol
+    ///
+    /// Starts an upload session, then sends the file in `UPLOAD_CHUNK_SIZE` pieces.
+    /// A chunk that fails with a network error, 429 or 5xx is retried with backoff
+    /// after asking Drive how many bytes it already has. Returns the new file ID.
+    async fn upload_resumable(&self, file_path: &str, file_size: u64, metadata_json: String) -> Result<String, AppError> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        // Starting a session doesn't create the file yet, so it's safe to retry
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .post(format!("{}/files", DRIVE_UPLOAD_BASE))
+                    .bearer_auth(&self.access_token)
+                    .query(&[("uploadType", "resumable")])
+                    .header("Content-Type", "application/json; charset=UTF-8")
+                    .header("X-Upload-Content-Type", "application/zip")
+                    .header("X-Upload-Content-Length", file_size.to_string())
+                    .body(metadata_json.clone())
+            })
             .await
-            .map_err(|e| AppError::sync_failed(format!("Failed to upload book file: {}", e)))?;
+            .map_err(|e| AppError::sync_failed(format!("Failed to start upload session: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             return Err(AppError::sync_failed(format!(
-                "Drive upload error {}: {}",
+                "Drive upload session error {}: {}",
                 status, body
             )));
         }
 
+        let session_url = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| AppError::sync_failed("Drive didn't return an upload session URL"))?;
+
+        let mut file = std::fs::File::open(file_path)
+            .map_err(|e| AppError::sync_failed(format!("Failed to read book file: {}", e)))?;
+        let mut offset = 0u64;
+        let mut attempt = 0u32;
+
+        loop {
+            if offset >= file_size {
+                // Everything arrived but the final response was lost
+                return match self.upload_status(&session_url, file_size).await? {
+                    UploadStatus::Complete(file_id) => Ok(file_id),
+                    UploadStatus::Incomplete(received) => Err(AppError::sync_failed(format!(
+                        "Drive reports {} of {} bytes received after the last chunk",
+                        received, file_size
+                    ))),
+                };
+            }
+
+            let chunk_len = UPLOAD_CHUNK_SIZE.min(file_size - offset);
+            let mut chunk = vec![0u8; chunk_len as usize];
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read_exact(&mut chunk))
+                .map_err(|e| AppError::sync_failed(format!("Failed to read book file: {}", e)))?;
+
+            let sent = self.client
+                .put(&session_url)
+                .header(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", offset, offset + chunk_len - 1, file_size),
+                )
+                .body(chunk)
+                .send()
+                .await;
+
+            let failure = match sent {
+                Ok(response) if response.status().is_success() => {
+                    #[derive(serde::Deserialize)]
+                    struct CreateResponse {
+                        id: String,
+                    }
+
+                    let created: CreateResponse = response.json().await
+                        .map_err(|e| AppError::sync_failed(format!("Failed to parse upload response: {}", e)))?;
+                    return Ok(created.id);
+                }
+                // 308 Resume Incomplete: the chunk arrived, Drive wants the next one
+                Ok(response) if response.status().as_u16() == 308 => {
+                    offset = next_upload_offset(response.headers());
+                    attempt = 0;
+                    continue;
+                }
+                Ok(response) if is_transient_status(response.status()) => {
+                    format!("Drive returned {}", response.status())
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(AppError::sync_failed(format!(
+                        "Drive upload error {}: {}",
+                        status, body
+                    )));
+                }
+                Err(e) => e.to_string(),
+            };
+
+            attempt += 1;
+            if attempt >= MAX_CHUNK_ATTEMPTS {
+                return Err(AppError::sync_failed(format!(
+                    "Upload failed at byte {} of {}: {}",
+                    offset, file_size, failure
+                )));
+            }
+            log::warn!(
+                "Upload chunk at byte {} failed ({}), retrying (attempt {} of {})",
+                offset, failure, attempt + 1, MAX_CHUNK_ATTEMPTS
+            );
+            tokio::time::sleep(backoff_delay(attempt)).await;
+
+            // Part of the chunk may have arrived; continue from what Drive confirms
+            match self.upload_status(&session_url, file_size).await {
+                Ok(UploadStatus::Complete(file_id)) => return Ok(file_id),
+                Ok(UploadStatus::Incomplete(received)) => offset = received,
+                Err(e) => log::warn!("Failed to query upload status, resending chunk: {}", e),
+            }
+        }
+    }
+
+    /// Ask Drive how much of a resumable upload it has received
+    async fn upload_status(&self, session_url: &str, file_size: u64) -> Result<UploadStatus, AppError> {
+        let response = self.client
+            .put(session_url)
+            .header("Content-Range", format!("bytes */{}", file_size))
+            .body(Vec::new())
+            .send()
+            .await
+            .map_err(|e| AppError::sync_failed(format!("Failed to query upload status: {}", e)))?;
+
+        if response.status().as_u16() == 308 {
+            return Ok(UploadStatus::Incomplete(next_upload_offset(response.headers())));
+        }
+        if !response.status().is_success() {
+            return Err(AppError::sync_failed(format!(
+                "Drive upload status error {}",
+                response.status()
+            )));
+        }
+
         #[derive(serde::Deserialize)]
         struct CreateResponse {
             id: String,
         }
 
-        let create_response: CreateResponse = response.json().await
+        let created: CreateResponse = response.json().await
             .map_err(|e| AppError::sync_failed(format!("Failed to parse upload response: {}", e)))?;
-
-        log::info!("Uploaded book file {} with ID {}", filename, create_response.id);
-
-        Ok(create_response.id)
+        Ok(UploadStatus::Complete(created.id))
     }
 
     /// Download a comic book file from Google Drive
-    pub async fn download_book_file(&self, file_hash: &str, target_path: &str) -> Result<(), AppError> {
+    ///
+    /// The file is streamed to `target_path`; `on_progress` gets the bytes written so
+    /// far and the total size (if Drive reports it) about every `DOWNLOAD_PROGRESS_STEP`
+    /// bytes and once at the end. A partially written file is removed on failure.
+    /// Fails with `CloudFileMissing` if there is no file with this hash on Drive.
+    pub async fn download_book_file(
+        &self,
+        file_hash: &str,
+        target_path: &str,
+        on_progress: &(dyn Fn(u64, Option<u64>) + Send + Sync),
+    ) -> Result<(), AppError> {
         use std::fs;
+        use std::io::Write;
         use std::path::Path;
         
         let file_id = self.find_book_file(file_hash).await?
-            .ok_or_else(|| AppError::sync_failed(format!("Book file not found in Drive: {}", file_hash)))?;
+            .ok_or_else(|| AppError::cloud_file_missing(file_hash))?;
         
         log::info!("Downloading book file {} from Drive...", file_hash);
         
-        let client = reqwest::Client::new();
-        
-        let response = client
+        let mut response = self.client
             .get(format!("{}/files/{}", DRIVE_API_BASE, file_id))
             .bearer_auth(&self.access_token)
             .query(&[("alt", "media")])
@@ -405,17 +798,44 @@ impl DriveSync {
             )));
         }
 
-        let bytes = response.bytes().await
-            .map_err(|e| AppError::sync_failed(format!("Failed to read book file bytes: {}", e)))?;
-
         // Ensure target directory exists
         if let Some(parent) = Path::new(target_path).parent() {
             fs::create_dir_all(parent)
                 .map_err(|e| AppError::sync_failed(format!("Failed to create target directory: {}", e)))?;
         }
 
-        fs::write(target_path, &bytes)
+        let total = response.content_length();
+        let mut file = fs::File::create(target_path)
             .map_err(|e| AppError::sync_failed(format!("Failed to write book file: {}", e)))?;
+        let streamed: Result<(), AppError> = async {
+            let mut downloaded = 0u64;
+            let mut reported = 0u64;
+            on_progress(0, total);
+            while let Some(chunk) = response.chunk().await
+                .map_err(|e| AppError::sync_failed(format!("Failed to read book file bytes: {}", e)))?
+            {
+                file.write_all(&chunk)
+                    .map_err(|e| AppError::sync_failed(format!("Failed to write book file: {}", e)))?;
+                downloaded += chunk.len() as u64;
+                if downloaded - reported >= DOWNLOAD_PROGRESS_STEP {
+                    reported = downloaded;
+                    on_progress(downloaded, total);
+                }
+            }
+            file.flush()
+                .map_err(|e| AppError::sync_failed(format!("Failed to write book file: {}", e)))?;
+            if reported != downloaded {
+                on_progress(downloaded, total);
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = streamed {
+            drop(file);
+            let _ = fs::remove_file(target_path);
+            return Err(e);
+        }
 
         log::info!("Downloaded book file {} to {}", file_hash, target_path);
 
@@ -423,19 +843,21 @@ impl DriveSync {
     }
 
     /// List all book files in appData folder
-    pub async fn list_book_files(&self) -> Result<Vec<DriveBookFile>, AppError> {
-        let client = reqwest::Client::new();
+    pub async fn list_book_files(&self) -> Result<Vec<RemoteBookFile>, AppError> {
+        let prefix = self.book_file_prefix();
         
-        let response = client
-            .get(format!("{}/files", DRIVE_API_BASE))
-            .bearer_auth(&self.access_token)
-            .query(&[
-                ("spaces", "appDataFolder"),
-                ("q", "name contains 'book_' and name contains '.cbz'"),
-                ("fields", "files(id, name, size, modifiedTime)"),
-                ("pageSize", "1000"),
-            ])
-            .send()
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .get(format!("{}/files", DRIVE_API_BASE))
+                    .bearer_auth(&self.access_token)
+                    .query(&[
+                        ("spaces", "appDataFolder"),
+                        ("q", &format!("name contains '{}' and name contains '.cbz'", prefix)),
+                        ("fields", "files(id, name, size, modifiedTime)"),
+                        ("pageSize", "1000"),
+                    ])
+            })
             .await
             .map_err(|e| AppError::sync_failed(format!("Failed to list book files: {}", e)))?;
 
@@ -467,9 +889,9 @@ impl DriveSync {
 
         let book_files = file_list.files.into_iter()
             .filter_map(|f| {
-                // Extract hash from filename like "book_abc123.cbz"
-                let hash = f.name.strip_prefix("book_")?.strip_suffix(".cbz")?.to_string();
-                Some(DriveBookFile {
+                // Extract hash from filename like "book_abc123.cbz"; other libraries' files don't match
+                let hash = f.name.strip_prefix(prefix.as_str())?.strip_suffix(".cbz")?.to_string();
+                Some(RemoteBookFile {
                     file_hash: hash,
                 })
             })
@@ -479,8 +901,138 @@ impl DriveSync {
     }
 }
 
-/// Info about a book file stored in Google Drive
-#[derive(Debug, Clone)]
-pub struct DriveBookFile {
-    pub file_hash: String,
+/// State of a resumable upload session
+enum UploadStatus {
+    /// Bytes received so far, i.e. the offset of the next chunk
+    Incomplete(u64),
+    /// All bytes received; holds the new file's ID
+    Complete(String),
+}
+
+/// Offset to continue a resumable upload from, read from a 308 response's
+/// `Range: bytes=0-{last}` header (no header means nothing was received)
+fn next_upload_offset(headers: &reqwest::header::HeaderMap) -> u64 {
+    headers
+        .get(reqwest::header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_received_range)
+        .unwrap_or(0)
+}
+
+fn parse_received_range(range: &str) -> Option<u64> {
+    let (_, last) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    last.parse::<u64>().ok().map(|last| last + 1)
+}
+
+/// Whether a failed request is worth retrying
+/// Why a request is worth retrying, and how long Drive asked to wait, if it is
+fn transient_failure(outcome: &reqwest::Result<reqwest::Response>) -> Option<(String, Option<Duration>)> {
+    match outcome {
+        Ok(response) if is_transient_status(response.status()) => {
+            Some((response.status().to_string(), retry_after(response.headers())))
+        }
+        Err(e) if e.is_connect() || e.is_timeout() => Some((e.to_string(), None)),
+        _ => None,
+    }
+}
+
+/// Sleep before retry number `attempt` + 1, logging why
+async fn wait_before_retry(reason: &str, requested_delay: Option<Duration>, attempt: u32) {
+    let delay = requested_delay.unwrap_or_else(|| backoff_delay(attempt)).min(MAX_RETRY_DELAY);
+    log::warn!(
+        "Drive request failed ({}), retrying in {:?} (attempt {} of {})",
+        reason, delay, attempt + 1, MAX_REQUEST_ATTEMPTS
+    );
+    tokio::time::sleep(delay).await;
+}
+
+fn is_transient_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Wait before retry number `attempt` (starting at 1): the base delay doubled per
+/// attempt, plus up to half of that again so clients don't retry in lockstep
+fn backoff_delay(attempt: u32) -> Duration {
+    use rand::Rng;
+
+    let delay = RETRY_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1).min(8));
+    let jitter_ms = rand::thread_rng().gen_range(0..=delay.as_millis() as u64 / 2);
+    (delay + Duration::from_millis(jitter_ms)).min(MAX_RETRY_DELAY)
+}
+
+/// Delay requested by a `Retry-After` header given in seconds
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaced_file_names() {
+        let drive = DriveSync::with_token("token".to_string());
+        assert_eq!(drive.snapshot_filename(), "sync_snapshot.json");
+        assert_eq!(drive.book_filename("abc123"), "book_abc123.cbz");
+
+        let drive = drive.with_namespace(" work ").unwrap();
+        assert_eq!(drive.snapshot_filename(), "sync_snapshot_work.json");
+        assert_eq!(drive.book_filename("abc123"), "work_book_abc123.cbz");
+
+        assert_eq!(normalize_namespace("   ").unwrap(), None);
+        assert!(normalize_namespace("it's").is_err());
+        assert!(normalize_namespace(&"a".repeat(MAX_NAMESPACE_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_retry_delays() {
+        for attempt in 1..=MAX_REQUEST_ATTEMPTS {
+            let base = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+            let delay = backoff_delay(attempt);
+            assert!(delay >= base.min(MAX_RETRY_DELAY) && delay <= (base * 3 / 2).min(MAX_RETRY_DELAY));
+        }
+        assert_eq!(backoff_delay(40), MAX_RETRY_DELAY);
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert(reqwest::header::RETRY_AFTER, "Wed, 21 Oct 2026 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_parse_received_range() {
+        assert_eq!(parse_received_range("bytes=0-8388607"), Some(8388608));
+        assert_eq!(parse_received_range("bytes=0-0"), Some(1));
+        assert_eq!(parse_received_range("0-100"), None);
+        assert_eq!(parse_received_range("bytes=0-"), None);
+        assert_eq!(UPLOAD_CHUNK_SIZE % (256 * 1024), 0);
+    }
+
+    #[test]
+    fn test_encrypted_snapshot_stays_encrypted() {
+        // This device has encryption off but knows the passphrase
+        let drive = DriveSync::with_token("token".to_string());
+        let sealed = encryption::seal("{}", "secret").unwrap();
+        assert_eq!(drive.open_snapshot(sealed, Some("secret")).unwrap(), "{}");
+
+        let (payload, content_type) = drive.seal_snapshot("{}".to_string(), Some("secret".to_string())).unwrap();
+        assert!(encryption::is_encrypted(&payload));
+        assert_eq!(content_type, "text/plain");
+        let err = drive.seal_snapshot("{}".to_string(), None).unwrap_err();
+        assert!(matches!(err.code, crate::error::ErrorCode::SyncPassphraseRequired));
+
+        // A plain snapshot stays plain
+        drive.open_snapshot("{}".to_string(), Some("secret")).unwrap();
+        let (payload, content_type) = drive.seal_snapshot("{}".to_string(), Some("secret".to_string())).unwrap();
+        assert_eq!((payload.as_str(), content_type), ("{}", "application/json"));
+    }
 }
@@ -0,0 +1,131 @@
+//! Optional encryption of the sync snapshot stored on Drive
+//!
+//! With `sync.encrypt` on, the serialized snapshot is sealed with XChaCha20-Poly1305
+//! under a key derived from the user's passphrase with Argon2id. Every upload uses a
+//! fresh salt and nonce. The stored payload stays text: `ENCRYPTED_HEADER` followed by
+//! base64 of salt, nonce and ciphertext. Payloads without the header are plain JSON,
+//! so snapshots uploaded before encryption was turned on still load.
+//!
+//! The passphrase is only held in memory for the session; the frontend asks for it
+//! and hands it over with `set_sync_passphrase`.
+
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+
+use crate::error::AppError;
+
+/// First line of an encrypted snapshot
+pub const ENCRYPTED_HEADER: &str = "yomiyougu-encrypted-snapshot-v1\n";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Passphrase for this session, never written to disk
+static PASSPHRASE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set the session passphrase; `None` or a blank one forgets it
+pub fn set_passphrase(passphrase: Option<String>) {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    *PASSPHRASE.write().unwrap_or_else(|e| e.into_inner()) = passphrase;
+}
+
+/// The session passphrase, if one was set
+pub fn passphrase() -> Option<String> {
+    PASSPHRASE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Whether a payload downloaded from Drive is an encrypted snapshot
+pub fn is_encrypted(payload: &str) -> bool {
+    payload.starts_with(ENCRYPTED_HEADER)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN], AppError> {
+    let mut key = [0u8; KEY_LEN];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::sync_failed(format!("Failed to derive snapshot key: {}", e)))?;
+    Ok(key)
+}
+
+/// Encrypt a serialized snapshot with `passphrase`
+pub fn seal(plaintext: &str, passphrase: &str) -> Result<String, AppError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?.into());
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| AppError::sync_failed("Failed to encrypt snapshot"))?;
+
+    let mut sealed = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_HEADER, BASE64.encode(sealed)))
+}
+
+/// Get the serialized snapshot out of a downloaded payload
+///
+/// Plain JSON is returned unchanged. Encrypted payloads need the passphrase; a
+/// missing or wrong one is reported as `SyncPassphraseRequired`.
+pub fn open(payload: String, passphrase: Option<&str>) -> Result<String, AppError> {
+    let Some(encoded) = payload.strip_prefix(ENCRYPTED_HEADER) else {
+        return Ok(payload);
+    };
+    let Some(passphrase) = passphrase else {
+        return Err(AppError::sync_passphrase_required(
+            "The sync snapshot is encrypted. Enter the sync passphrase to continue.",
+        ));
+    };
+
+    let sealed = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| AppError::sync_failed(format!("Encrypted snapshot is damaged: {}", e)))?;
+    if sealed.len() < SALT_LEN + NONCE_LEN {
+        return Err(AppError::sync_failed("Encrypted snapshot is damaged: too short"));
+    }
+    let (salt, rest) = sealed.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt)?.into());
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::sync_passphrase_required("Can't decrypt the sync snapshot. Check the sync passphrase."))?;
+    String::from_utf8(plaintext)
+        .map_err(|e| AppError::sync_failed(format!("Decrypted snapshot is not text: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_seal_and_open() {
+        let json = r#"{"books":{},"last_modified_by":"laptop"}"#;
+
+        let sealed = seal(json, "correct horse").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("laptop"));
+        // Fresh salt and nonce every time
+        assert_ne!(sealed, seal(json, "correct horse").unwrap());
+
+        assert_eq!(open(sealed.clone(), Some("correct horse")).unwrap(), json);
+        let wrong = open(sealed.clone(), Some("battery staple")).unwrap_err();
+        assert!(matches!(wrong.code, ErrorCode::SyncPassphraseRequired));
+        let missing = open(sealed, None).unwrap_err();
+        assert!(matches!(missing.code, ErrorCode::SyncPassphraseRequired));
+
+        // Plaintext snapshots load with or without a passphrase
+        assert_eq!(open(json.to_string(), None).unwrap(), json);
+        assert_eq!(open(json.to_string(), Some("correct horse")).unwrap(), json);
+    }
+}
@@ -3,29 +3,132 @@
 //! Implements the pull-merge-push algorithm for conflict resolution.
 
 use diesel::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tauri::AppHandle;
 
 use crate::database::{get_connection, models::*};
+use crate::database::operations::creates_collection_cycle;
 use crate::error::AppError;
-use crate::schema::{books, bookmarks, collections, book_collections, book_settings, sync_state};
-use crate::settings::{load_settings, save_settings};
+use crate::schema::{books, bookmarks, collections, book_collections, book_settings, book_tags, reading_queue, sync_state, tags};
+use crate::settings::{create_default_settings, load_settings, save_settings, AppSettings, SettingValue};
 
 use super::types::*;
 
+/// Setting key prefixes that stay device-local and never go into the snapshot
+/// (the library directory is a filesystem path and the default collection a local
+/// row ID, both meaningless on other devices)
+const EXCLUDED_SETTING_PREFIXES: &[&str] = &["sync.", "library.directory", "library.default_collection_id"];
+
+/// Most changes kept for the recent changes feed; a first sync can touch the whole library
+const MAX_RECENT_SYNC_CHANGES: usize = 500;
+
+/// Add a sync's changes to the front of the recent changes feed
+///
+/// Call once the snapshot is uploaded. A sync that changed nothing leaves the feed
+/// as it was; older entries beyond `MAX_RECENT_SYNC_CHANGES` are dropped.
+pub(crate) fn save_recent_changes(
+    conn: &mut diesel::SqliteConnection,
+    changes: &[SyncChange],
+) -> Result<(), AppError> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let stored: Option<Option<String>> = sync_state::table
+        .find(1)
+        .select(sync_state::last_changes)
+        .first(conn)
+        .optional()
+        .map_err(|e| AppError::database_error(e.to_string()))?;
+    let older: Vec<SyncChange> = stored
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let feed: Vec<&SyncChange> = changes
+        .iter()
+        .chain(older.iter())
+        .take(MAX_RECENT_SYNC_CHANGES)
+        .collect();
+    let last_changes = serde_json::to_string(&feed)
+        .map_err(|e| AppError::database_error(e.to_string()))?;
+    diesel::update(sync_state::table.find(1))
+        .set(sync_state::last_changes.eq(Some(last_changes)))
+        .execute(conn)
+        .map_err(|e| AppError::database_error(e.to_string()))?;
+    Ok(())
+}
+
+/// Collect every setting key defined by the current settings schema
+fn known_setting_keys() -> HashSet<String> {
+    create_default_settings()
+        .categories
+        .into_iter()
+        .flat_map(|category| category.settings.into_iter().map(|s| s.key))
+        .collect()
+}
+
+/// Whether a setting key belongs in the snapshot's app settings
+fn is_syncable_setting_key(key: &str, known_keys: &HashSet<String>) -> bool {
+    known_keys.contains(key)
+        && !EXCLUDED_SETTING_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+/// Drop app settings that are no longer part of the schema (or are device-local)
+/// so removed settings don't linger in the snapshot and resurrect on other devices
+fn prune_stale_app_settings(app_settings: &mut HashMap<String, serde_json::Value>) {
+    let known_keys = known_setting_keys();
+    let before = app_settings.len();
+    app_settings.retain(|key, _| is_syncable_setting_key(key, &known_keys));
+
+    let pruned = before - app_settings.len();
+    if pruned > 0 {
+        log::info!("Pruned {} stale app settings from snapshot", pruned);
+    }
+}
+
+/// Whether this device has never completed a sync
+///
+/// True when there is no sync state row yet or it has no `last_sync_at`.
+pub fn is_first_sync(conn: &mut diesel::SqliteConnection) -> Result<bool, AppError> {
+    let state: Option<SyncState> = sync_state::table
+        .find(1)
+        .first(conn)
+        .optional()
+        .map_err(|e| AppError::database_error(e.to_string()))?;
+
+    Ok(state.and_then(|s| s.last_sync_at).is_none())
+}
+
 /// Merge engine for syncing local DB with remote snapshot
 pub struct MergeEngine {
     device_id: String,
     strategy: ConflictStrategy,
     options: SyncOptions,
+    pull_dominant: bool,
 }
 
 impl MergeEngine {
     pub fn new(device_id: String, strategy: ConflictStrategy, options: SyncOptions) -> Self {
-        Self { device_id, strategy, options }
+        Self { device_id, strategy, options, pull_dominant: false }
+    }
+
+    /// Make the merge pull-dominant, as used for a device's first sync
+    ///
+    /// Remote data wins every conflict and existing remote entries are never
+    /// overwritten, so a fresh (or freshly reset) local database can't delete or
+    /// roll back what other devices uploaded. Local-only entries are still added.
+    pub fn pull_dominant(mut self, enabled: bool) -> Self {
+        self.pull_dominant = enabled;
+        self
     }
 
     /// Execute a full sync: pull remote, merge, push updates
+    ///
+    /// The local merge and the sync state update share one transaction, so a failure
+    /// part way leaves the database as it was. With `SyncOptions::dry_run` that
+    /// transaction is always rolled back; the result and snapshot show what a real
+    /// sync would do.
     pub fn sync(
         &self,
         app_handle: &AppHandle,
@@ -50,54 +153,144 @@ impl MergeEngine {
             .map(|dt| to_timestamp(&dt))
             .unwrap_or(0);
 
-        // Merge each entity type based on options
-        // sync_books: Full book metadata sync (creates new books, syncs all fields)
-        // sync_progress: Only syncs progress fields for books that already exist locally
+        // Downloaded changes come from whichever device uploaded the snapshot last
+        let remote_device = snapshot.last_modified_by.clone();
+        self.merge_transaction(&mut conn, |conn| {
+            self.merge_all(conn, app_handle, &mut snapshot, last_sync_at, &mut result)?;
+            for change in result.changes.iter_mut().filter(|c| c.direction == SyncDirection::Down) {
+                change.device = remote_device.clone();
+            }
+            if self.options.dry_run {
+                return Ok(());
+            }
+
+            // Update local sync state; the change feed is saved once the snapshot is uploaded
+            let now = chrono::Utc::now().naive_utc();
+            diesel::update(sync_state::table.find(1))
+                .set((
+                    sync_state::last_sync_at.eq(Some(now)),
+                    sync_state::last_sync_device.eq(Some(&self.device_id)),
+                ))
+                .execute(conn)
+                .map_err(|e| AppError::database_error(e.to_string()))?;
+            Ok(())
+        })?;
+        self.stamp(&mut snapshot);
+
+        result.success = result.errors.is_empty();
+        result.completed_at = chrono::Utc::now().timestamp_millis();
+
+        Ok((snapshot, result))
+    }
+
+    /// Run `merge` in a transaction that commits only if it succeeds and this isn't a dry run
+    ///
+    /// App settings live in a file and aren't rolled back; they are merged last, so a
+    /// database failure never leaves them merged on their own.
+    fn merge_transaction(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        merge: impl FnOnce(&mut diesel::SqliteConnection) -> Result<(), AppError>,
+    ) -> Result<(), AppError> {
+        let mut outcome = Ok(());
+        let committed = conn.transaction::<(), diesel::result::Error, _>(|conn| {
+            outcome = merge(conn);
+            if outcome.is_err() || self.options.dry_run {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+            Ok(())
+        });
+        outcome?;
+
+        match committed {
+            Ok(()) | Err(diesel::result::Error::RollbackTransaction) => Ok(()),
+            Err(e) => Err(AppError::database_error(format!("Failed to commit sync: {}", e))),
+        }
+    }
+
+    /// Build the snapshot this device would upload to an empty Drive, without changing anything
+    ///
+    /// Runs the regular merge against an empty snapshot in a transaction that is
+    /// rolled back, so the result holds exactly what a sync would write for local data.
+    pub fn local_snapshot(&self, app_handle: &AppHandle) -> Result<SyncSnapshot, AppError> {
+        let mut conn = get_connection()?;
+        let mut snapshot = SyncSnapshot::new();
+        let mut result = SyncResult::empty();
+
+        let mut outcome = Ok(());
+        let _ = conn.transaction::<(), diesel::result::Error, _>(|conn| {
+            outcome = self.merge_all(conn, app_handle, &mut snapshot, 0, &mut result);
+            Err(diesel::result::Error::RollbackTransaction)
+        });
+        outcome?;
+
+        self.stamp(&mut snapshot);
+        Ok(snapshot)
+    }
+
+    /// Merge each entity type based on options
+    ///
+    /// - sync_books: Full book metadata sync (creates new books, syncs all fields)
+    /// - sync_progress: Only syncs progress fields for books that already exist locally
+    fn merge_all(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        app_handle: &AppHandle,
+        snapshot: &mut SyncSnapshot,
+        last_sync_at: i64,
+        result: &mut SyncResult,
+    ) -> Result<(), AppError> {
+        self.merge_library(conn, snapshot, last_sync_at, result)?;
+
+        // App settings sync (separate from book settings)
+        if self.options.sync_settings {
+            self.merge_app_settings(app_handle, snapshot, last_sync_at, result)?;
+        }
+
+        Ok(())
+    }
+
+    /// Merge everything stored in the database, as enabled by the sync options
+    fn merge_library(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        snapshot: &mut SyncSnapshot,
+        last_sync_at: i64,
+        result: &mut SyncResult,
+    ) -> Result<(), AppError> {
         if self.options.sync_books {
-            self.merge_books(&mut conn, &mut snapshot, last_sync_at, &mut result, true)?;
-            self.merge_collections(&mut conn, &mut snapshot, last_sync_at, &mut result)?;
-            self.merge_book_collections(&mut conn, &mut snapshot, last_sync_at, &mut result)?;
+            self.merge_books(conn, snapshot, last_sync_at, result, true)?;
+            self.merge_collections(conn, snapshot, last_sync_at, result)?;
+            self.merge_book_collections(conn, snapshot, last_sync_at, result)?;
+            self.merge_tags(conn, snapshot, last_sync_at, result)?;
+            self.merge_book_tags(conn, snapshot)?;
+            self.merge_reading_queue(conn, snapshot, result)?;
         } else if self.options.sync_progress {
             // Only sync progress for existing books
-            self.merge_books(&mut conn, &mut snapshot, last_sync_at, &mut result, false)?;
+            self.merge_books(conn, snapshot, last_sync_at, result, false)?;
         }
-        
+
         // Bookmarks are part of reading progress
         if self.options.sync_progress {
-            self.merge_bookmarks(&mut conn, &mut snapshot, last_sync_at, &mut result)?;
-            self.merge_book_settings(&mut conn, &mut snapshot, last_sync_at, &mut result)?;
+            self.merge_bookmarks(conn, snapshot, last_sync_at, result)?;
+            self.merge_book_settings(conn, snapshot, last_sync_at, result)?;
         }
 
-        // App settings sync (separate from book settings)
-        if self.options.sync_settings {
-            self.merge_app_settings(app_handle, &mut snapshot, last_sync_at)?;
-        }
+        Ok(())
+    }
 
-        // Update snapshot metadata
+    /// Update snapshot metadata
+    fn stamp(&self, snapshot: &mut SyncSnapshot) {
         snapshot.last_modified_by = Some(self.device_id.clone());
         snapshot.last_modified_at = chrono::Utc::now().timestamp_millis();
-
-        // Update local sync state
-        let now = chrono::Utc::now().naive_utc();
-        diesel::update(sync_state::table.find(1))
-            .set((
-                sync_state::last_sync_at.eq(Some(now)),
-                sync_state::last_sync_device.eq(Some(&self.device_id)),
-            ))
-            .execute(&mut conn)
-            .map_err(|e| AppError::database_error(e.to_string()))?;
-
-        result.success = result.errors.is_empty();
-        result.completed_at = chrono::Utc::now().timestamp_millis();
-
-        Ok((snapshot, result))
     }
 
     /// Merge books between local DB and remote snapshot
     /// 
     /// - `full_sync`: If true, creates new books from remote and syncs all fields.
-    ///                If false, only syncs progress fields (current_page, reading_status, last_read_at)
-    ///                for books that already exist locally.
+    ///                If false, only syncs progress fields (current_page, page_fraction, reading_status,
+    ///                last_read_at)
+    ///                for books that already exist locally, matched by UUID or file hash.
     fn merge_books(
         &self,
         conn: &mut diesel::SqliteConnection,
@@ -131,11 +324,31 @@ impl MergeEngine {
             .filter_map(|b| b.uuid.as_ref().map(|uuid| (uuid.clone(), b)))
             .collect();
         
-        // Build file_hash -> Book map for matching by content
-        let local_by_hash: HashMap<String, &Book> = local_books
-            .iter()
-            .filter_map(|b| b.file_hash.as_ref().map(|hash| (hash.clone(), b)))
-            .collect();
+        // Build file_hash -> Book map for matching by content, preferring live books
+        let mut local_by_hash: HashMap<String, &Book> = HashMap::new();
+        for book in &local_books {
+            // Other devices only share content hashes; fast-import file hashes never match them
+            if book.hash_scheme() != HashScheme::Content {
+                continue;
+            }
+            if let Some(hash) = &book.file_hash {
+                let replace = local_by_hash
+                    .get(hash)
+                    .is_none_or(|existing| existing.deleted_at.is_some());
+                if replace {
+                    local_by_hash.insert(hash.clone(), book);
+                }
+            }
+        }
+
+        // Books that took over a remote UUID through a hash match, with the UUID they took;
+        // their loaded UUID is stale
+        let mut rekeyed: HashMap<i32, String> = HashMap::new();
+        // Remote entries resolved into a local book already synced under another UUID,
+        // as (duplicate UUID, UUID the book is kept under)
+        let mut duplicates: Vec<(String, String)> = Vec::new();
+        // Hash-matched books whose local progress is newer than the remote copy's
+        let mut progress_uploads: Vec<(String, &Book)> = Vec::new();
 
         // Process remote books
         for (uuid, remote_book) in snapshot.books.iter() {
@@ -158,6 +371,7 @@ impl MergeEngine {
                     let remote_ts = remote_book.updated_at;
 
                     let action = self.resolve_conflict(
+                        result,
                         local_ts,
                         remote_ts,
                         last_sync_at,
@@ -167,6 +381,17 @@ impl MergeEngine {
 
                     match action {
                         ConflictAction::UseRemote => {
+                            let (kind, fields) = if full_sync {
+                                let kind = change_kind(
+                                    remote_book.deleted_at.is_some(),
+                                    local_book.deleted_at.is_some(),
+                                    book_details_differ(local_book, remote_book),
+                                );
+                                (kind, book_changed_fields(local_book, remote_book))
+                            } else {
+                                (SyncChangeKind::Progress, book_progress_fields(local_book, remote_book))
+                            };
+                            result.record(SyncEntity::Book, &remote_book.title, kind, SyncDirection::Down, &fields);
                             if full_sync {
                                 // Full sync - update all fields
                                 self.update_local_book(conn, local_book.id, remote_book)?;
@@ -190,16 +415,36 @@ impl MergeEngine {
                             .and_then(|hash| local_by_hash.get(hash).copied());
 
                         if let Some(existing) = existing_by_hash {
+                            // The book already answers to a live synced UUID (possibly one it
+                            // took over earlier in this loop), so this entry is a second copy
+                            let kept_uuid = rekeyed.get(&existing.id).cloned().or_else(|| {
+                                existing.uuid.clone().filter(|u| {
+                                    snapshot.books.get(u).is_some_and(|b| b.deleted_at.is_none())
+                                })
+                            });
+                            if let Some(kept_uuid) = kept_uuid {
+                                log::info!(
+                                    "Remote book {} duplicates book {} (synced as {}), dropping it",
+                                    uuid, existing.id, kept_uuid
+                                );
+                                duplicates.push((uuid.clone(), kept_uuid));
+                                continue;
+                            }
+
                             // Book with same hash exists - update its UUID and merge progress
                             log::info!("Found existing book by hash, updating UUID: {} -> {}", 
                                 existing.uuid.as_deref().unwrap_or("none"), uuid);
-                            
+                            rekeyed.insert(existing.id, uuid.clone());
+
                             if full_sync {
                                 diesel::update(books::table.find(existing.id))
                                     .set((
                                         books::uuid.eq(Some(uuid)),
                                         books::title.eq(&remote_book.title),
+                                        books::sort_key.eq(remote_sort_key(remote_book)),
                                         books::current_page.eq(remote_book.current_page),
+                                        books::page_fraction.eq(remote_book.page_fraction),
+                                        books::scroll_fraction.eq(remote_book.scroll_fraction),
                                         books::is_favorite.eq(remote_book.is_favorite),
                                         books::reading_status.eq(&remote_book.reading_status),
                                         books::last_read_at.eq(from_opt_timestamp(remote_book.last_read_at)),
@@ -207,23 +452,60 @@ impl MergeEngine {
                                     ))
                                     .execute(conn)
                                     .map_err(|e| AppError::database_error(e.to_string()))?;
+                                let kind = change_kind(false, false, book_details_differ(existing, remote_book));
+                                let fields = book_changed_fields(existing, remote_book);
+                                result.record(SyncEntity::Book, &remote_book.title, kind, SyncDirection::Down, &fields);
+                                result.books_downloaded += 1;
                             } else {
-                                // Progress only - just update UUID and progress fields
-                                diesel::update(books::table.find(existing.id))
-                                    .set((
-                                        books::uuid.eq(Some(uuid)),
-                                        books::current_page.eq(remote_book.current_page),
-                                        books::reading_status.eq(&remote_book.reading_status),
-                                        books::last_read_at.eq(from_opt_timestamp(remote_book.last_read_at)),
-                                    ))
-                                    .execute(conn)
-                                    .map_err(|e| AppError::database_error(e.to_string()))?;
+                                // Progress only - e.g. a book that arrived here as metadata and was
+                                // imported again locally. Adopt the remote UUID so later syncs match
+                                // directly, then keep whichever side read the book last.
+                                let book_sync_enabled = sync_progress_by_book
+                                    .get(&existing.id)
+                                    .copied()
+                                    .unwrap_or(true);
+                                let remote_is_newer =
+                                    remote_book.last_read_at > to_opt_timestamp(&existing.last_read_at);
+
+                                if book_sync_enabled && remote_is_newer {
+                                    diesel::update(books::table.find(existing.id))
+                                        .set((
+                                            books::uuid.eq(Some(uuid)),
+                                            books::current_page.eq(remote_book.current_page),
+                                            books::page_fraction.eq(remote_book.page_fraction),
+                                            books::scroll_fraction.eq(remote_book.scroll_fraction),
+                                            books::reading_status.eq(&remote_book.reading_status),
+                                            books::last_read_at.eq(from_opt_timestamp(remote_book.last_read_at)),
+                                        ))
+                                        .execute(conn)
+                                        .map_err(|e| AppError::database_error(e.to_string()))?;
+                                    result.record(
+                                        SyncEntity::Book,
+                                        &remote_book.title,
+                                        SyncChangeKind::Progress,
+                                        SyncDirection::Down,
+                                        &book_progress_fields(existing, remote_book),
+                                    );
+                                    result.books_downloaded += 1;
+                                } else {
+                                    diesel::update(books::table.find(existing.id))
+                                        .set(books::uuid.eq(Some(uuid)))
+                                        .execute(conn)
+                                        .map_err(|e| AppError::database_error(e.to_string()))?;
+                                    if book_sync_enabled && existing.last_read_at.is_some() {
+                                        progress_uploads.push((uuid.clone(), existing));
+                                    }
+                                }
                             }
-                            result.books_downloaded += 1;
                         } else if full_sync {
                             // Truly new book - only insert if full_sync
-                            self.insert_local_book(conn, remote_book)?;
-                            result.books_downloaded += 1;
+                            match self.insert_local_book(conn, remote_book)? {
+                                Some(kept_uuid) => duplicates.push((uuid.clone(), kept_uuid)),
+                                None => {
+                                    result.record(SyncEntity::Book, &remote_book.title, SyncChangeKind::Added, SyncDirection::Down, &[]);
+                                    result.books_downloaded += 1;
+                                }
+                            }
                         }
                         // If not full_sync and book doesn't exist locally, skip it
                     }
@@ -231,12 +513,40 @@ impl MergeEngine {
             }
         }
 
+        // Point everything at the kept entry so later syncs don't resolve the duplicate again
+        for (duplicate, kept_uuid) in duplicates {
+            if duplicate == kept_uuid {
+                continue;
+            }
+            snapshot.books.remove(&duplicate);
+            self.repoint_book_references(snapshot, &duplicate, &kept_uuid);
+        }
+
+        for (uuid, local_book) in progress_uploads {
+            if let Some(remote) = snapshot.books.get_mut(&uuid) {
+                let local_ts = to_timestamp(&local_book.updated_at);
+                let fields = book_progress_fields(local_book, remote);
+                remote.current_page = local_book.current_page;
+                remote.page_fraction = local_book.page_fraction;
+                remote.scroll_fraction = local_book.scroll_fraction;
+                remote.reading_status = local_book.reading_status.clone();
+                remote.last_read_at = to_opt_timestamp(&local_book.last_read_at);
+                remote.updated_at = remote.updated_at.max(local_ts);
+                result.record(SyncEntity::Book, &local_book.title, SyncChangeKind::Progress, SyncDirection::Up, &fields);
+                result.books_uploaded += 1;
+            }
+        }
+
         // Process local books that might be new or updated
         for local_book in &local_books {
             let uuid = match &local_book.uuid {
                 Some(u) => u.clone(),
                 None => continue, // Skip books without UUID (shouldn't happen after migration)
             };
+            if rekeyed.contains_key(&local_book.id) {
+                // Now stored under the remote UUID, which was merged above
+                continue;
+            }
 
             // Check if this book has sync disabled
             let book_sync_enabled = sync_progress_by_book
@@ -257,14 +567,39 @@ impl MergeEngine {
                     // Already processed above, but check if local is newer
                     let remote_ts = remote_book.updated_at;
                     
-                    if local_ts > remote_ts && local_ts > last_sync_at {
+                    if self.local_supersedes(
+                        local_ts,
+                        remote_ts,
+                        last_sync_at,
+                        local_book.deleted_at.is_some(),
+                        remote_book.deleted_at.is_some(),
+                    ) {
+                        let (kind, fields) = if full_sync {
+                            let kind = change_kind(
+                                local_book.deleted_at.is_some(),
+                                remote_book.deleted_at.is_some(),
+                                book_details_differ(local_book, remote_book),
+                            );
+                            (kind, book_changed_fields(local_book, remote_book))
+                        } else {
+                            (SyncChangeKind::Progress, book_progress_fields(local_book, remote_book))
+                        };
+                        result.record(SyncEntity::Book, &local_book.title, kind, SyncDirection::Up, &fields);
+
                         // Local is newer - update remote
                         if full_sync {
-                            snapshot.books.insert(uuid, self.book_to_remote(local_book));
+                            let mut remote = self.book_to_remote(local_book);
+                            // Keep the content hash other devices know the book by
+                            if remote.file_hash.is_none() {
+                                remote.file_hash = remote_book.file_hash.clone();
+                            }
+                            snapshot.books.insert(uuid, remote);
                         } else {
                             // Progress only - only upload progress fields
                             let mut remote = remote_book.clone();
                             remote.current_page = local_book.current_page;
+                            remote.page_fraction = local_book.page_fraction;
+                            remote.scroll_fraction = local_book.scroll_fraction;
                             remote.reading_status = local_book.reading_status.clone();
                             remote.last_read_at = local_book.last_read_at.as_ref().map(|dt| to_timestamp(dt));
                             remote.updated_at = local_ts;
@@ -277,6 +612,9 @@ impl MergeEngine {
                     if full_sync {
                         // New local book - add to remote (only if full_sync)
                         snapshot.books.insert(uuid, self.book_to_remote(local_book));
+                        if local_book.deleted_at.is_none() {
+                            result.record(SyncEntity::Book, &local_book.title, SyncChangeKind::Added, SyncDirection::Up, &[]);
+                        }
                         result.books_uploaded += 1;
                     }
                     // If not full_sync, don't add new books to remote
@@ -284,6 +622,46 @@ impl MergeEngine {
             }
         }
 
+        self.merge_read_counts(conn, snapshot)
+    }
+
+    /// Merge open/completed counters of books present on both sides
+    ///
+    /// Counters only grow, so both sides take the larger value regardless of which
+    /// side won the last-write-wins merge. Local rows are raised without bumping
+    /// `updated_at`, so this alone never triggers another upload.
+    fn merge_read_counts(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        snapshot: &mut SyncSnapshot,
+    ) -> Result<(), AppError> {
+        let local_counts: Vec<(i32, Option<String>, i32, i32)> = books::table
+            .filter(books::uuid.is_not_null())
+            .select((books::id, books::uuid, books::open_count, books::completed_count))
+            .load(conn)
+            .map_err(|e| AppError::database_error(e.to_string()))?;
+
+        for (book_id, uuid, open_count, completed_count) in local_counts {
+            let Some(remote) = uuid.and_then(|uuid| snapshot.books.get_mut(&uuid)) else {
+                continue;
+            };
+
+            let merged_open = open_count.max(remote.open_count);
+            let merged_completed = completed_count.max(remote.completed_count);
+
+            if merged_open != open_count || merged_completed != completed_count {
+                diesel::update(books::table.find(book_id))
+                    .set((
+                        books::open_count.eq(merged_open),
+                        books::completed_count.eq(merged_completed),
+                    ))
+                    .execute(conn)
+                    .map_err(|e| AppError::database_error(e.to_string()))?;
+            }
+            remote.open_count = merged_open;
+            remote.completed_count = merged_completed;
+        }
+
         Ok(())
     }
     
@@ -297,6 +675,8 @@ impl MergeEngine {
         diesel::update(books::table.find(book_id))
             .set((
                 books::current_page.eq(remote.current_page),
+                books::page_fraction.eq(remote.page_fraction),
+                books::scroll_fraction.eq(remote.scroll_fraction),
                 books::reading_status.eq(&remote.reading_status),
                 books::last_read_at.eq(from_opt_timestamp(remote.last_read_at)),
                 books::updated_at.eq(from_timestamp(remote.updated_at)),
@@ -322,6 +702,13 @@ impl MergeEngine {
             .iter()
             .filter_map(|c| c.uuid.as_ref().map(|uuid| (uuid.clone(), c)))
             .collect();
+        let uuid_by_id: HashMap<i32, String> = local_collections
+            .iter()
+            .filter_map(|c| c.uuid.as_ref().map(|uuid| (c.id, uuid.clone())))
+            .collect();
+
+        // Remote collections written locally, whose parents are applied once all exist
+        let mut downloaded: Vec<String> = Vec::new();
 
         // Process remote collections
         for (uuid, remote_coll) in snapshot.collections.iter() {
@@ -331,6 +718,7 @@ impl MergeEngine {
                     let remote_ts = remote_coll.updated_at;
 
                     let action = self.resolve_conflict(
+                        result,
                         local_ts,
                         remote_ts,
                         last_sync_at,
@@ -339,19 +727,27 @@ impl MergeEngine {
                     );
 
                     if matches!(action, ConflictAction::UseRemote) {
+                        let kind = change_kind(remote_coll.deleted_at.is_some(), local_coll.deleted_at.is_some(), true);
+                        let fields = collection_changed_fields(local_coll, remote_coll, &uuid_by_id);
+                        result.record(SyncEntity::Collection, &remote_coll.name, kind, SyncDirection::Down, &fields);
                         self.update_local_collection(conn, local_coll.id, remote_coll)?;
+                        downloaded.push(uuid.clone());
                         result.collections_downloaded += 1;
                     }
                 }
                 None => {
                     if remote_coll.deleted_at.is_none() {
                         self.insert_local_collection(conn, remote_coll)?;
+                        result.record(SyncEntity::Collection, &remote_coll.name, SyncChangeKind::Added, SyncDirection::Down, &[]);
+                        downloaded.push(uuid.clone());
                         result.collections_downloaded += 1;
                     }
                 }
             }
         }
 
+        self.apply_collection_parents(conn, snapshot, &downloaded)?;
+
         // Process local collections
         for local_coll in &local_collections {
             let uuid = match &local_coll.uuid {
@@ -363,13 +759,25 @@ impl MergeEngine {
 
             match snapshot.collections.get(&uuid) {
                 Some(remote_coll) => {
-                    if local_ts > remote_coll.updated_at && local_ts > last_sync_at {
-                        snapshot.collections.insert(uuid, self.collection_to_remote(local_coll));
+                    if self.local_supersedes(
+                        local_ts,
+                        remote_coll.updated_at,
+                        last_sync_at,
+                        local_coll.deleted_at.is_some(),
+                        remote_coll.deleted_at.is_some(),
+                    ) {
+                        let kind = change_kind(local_coll.deleted_at.is_some(), remote_coll.deleted_at.is_some(), true);
+                        let fields = collection_changed_fields(local_coll, remote_coll, &uuid_by_id);
+                        result.record(SyncEntity::Collection, &local_coll.name, kind, SyncDirection::Up, &fields);
+                        snapshot.collections.insert(uuid, self.collection_to_remote(local_coll, &uuid_by_id));
                         result.collections_uploaded += 1;
                     }
                 }
                 None => {
-                    snapshot.collections.insert(uuid, self.collection_to_remote(local_coll));
+                    snapshot.collections.insert(uuid, self.collection_to_remote(local_coll, &uuid_by_id));
+                    if local_coll.deleted_at.is_none() {
+                        result.record(SyncEntity::Collection, &local_coll.name, SyncChangeKind::Added, SyncDirection::Up, &[]);
+                    }
                     result.collections_uploaded += 1;
                 }
             }
@@ -378,6 +786,50 @@ impl MergeEngine {
         Ok(())
     }
 
+    /// Nest downloaded collections under their remote parents
+    ///
+    /// Runs after the remote collections are written, since a parent may be inserted
+    /// after its child. A parent that would form a cycle with local nesting is
+    /// dropped, leaving the collection at the top level.
+    fn apply_collection_parents(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        snapshot: &SyncSnapshot,
+        uuids: &[String],
+    ) -> Result<(), AppError> {
+        let id_by_uuid: HashMap<String, i32> = collections::table
+            .select((collections::id, collections::uuid))
+            .load::<(i32, Option<String>)>(conn)
+            .map_err(|e| AppError::database_error(e.to_string()))?
+            .into_iter()
+            .filter_map(|(id, uuid)| uuid.map(|u| (u, id)))
+            .collect();
+
+        for uuid in uuids {
+            let (Some(&collection_id), Some(remote)) = (id_by_uuid.get(uuid), snapshot.collections.get(uuid)) else {
+                continue;
+            };
+
+            let mut parent_id = remote.parent_uuid.as_ref().and_then(|p| id_by_uuid.get(p).copied());
+            if let Some(pid) = parent_id {
+                let cycle = creates_collection_cycle(conn, collection_id, pid)
+                    .map_err(|e| AppError::database_error(e.to_string()))?;
+                if cycle {
+                    log::warn!("Remote parent of collection {} would create a cycle, moving it to the top level", uuid);
+                    parent_id = None;
+                }
+            }
+
+            // Part of the remote state just applied, so updated_at stays as downloaded
+            diesel::update(collections::table.find(collection_id))
+                .set(collections::parent_id.eq(parent_id))
+                .execute(conn)
+                .map_err(|e| AppError::database_error(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
     /// Merge bookmarks
     fn merge_bookmarks(
         &self,
@@ -412,6 +864,7 @@ impl MergeEngine {
                     let remote_ts = remote_bm.updated_at;
 
                     let action = self.resolve_conflict(
+                        result,
                         local_ts,
                         remote_ts,
                         last_sync_at,
@@ -420,6 +873,9 @@ impl MergeEngine {
                     );
 
                     if matches!(action, ConflictAction::UseRemote) {
+                        let kind = change_kind(remote_bm.deleted_at.is_some(), local_bm.deleted_at.is_some(), true);
+                        let fields = bookmark_changed_fields(local_bm, remote_bm);
+                        result.record(SyncEntity::Bookmark, &remote_bm.name, kind, SyncDirection::Down, &fields);
                         self.update_local_bookmark(conn, local_bm.id, remote_bm)?;
                         result.bookmarks_downloaded += 1;
                     }
@@ -429,6 +885,7 @@ impl MergeEngine {
                         // Find local book_id for this bookmark's book_uuid
                         if let Some(book_id) = self.find_book_id_by_uuid(conn, &remote_bm.book_uuid)? {
                             self.insert_local_bookmark(conn, remote_bm, book_id)?;
+                            result.record(SyncEntity::Bookmark, &remote_bm.name, SyncChangeKind::Added, SyncDirection::Down, &[]);
                             result.bookmarks_downloaded += 1;
                         }
                     }
@@ -452,13 +909,25 @@ impl MergeEngine {
 
             match snapshot.bookmarks.get(&uuid) {
                 Some(remote_bm) => {
-                    if local_ts > remote_bm.updated_at && local_ts > last_sync_at {
+                    if self.local_supersedes(
+                        local_ts,
+                        remote_bm.updated_at,
+                        last_sync_at,
+                        local_bm.deleted_at.is_some(),
+                        remote_bm.deleted_at.is_some(),
+                    ) {
+                        let kind = change_kind(local_bm.deleted_at.is_some(), remote_bm.deleted_at.is_some(), true);
+                        let fields = bookmark_changed_fields(local_bm, remote_bm);
+                        result.record(SyncEntity::Bookmark, &local_bm.name, kind, SyncDirection::Up, &fields);
                         snapshot.bookmarks.insert(uuid, self.bookmark_to_remote(local_bm, &book_uuid));
                         result.bookmarks_uploaded += 1;
                     }
                 }
                 None => {
                     snapshot.bookmarks.insert(uuid, self.bookmark_to_remote(local_bm, &book_uuid));
+                    if local_bm.deleted_at.is_none() {
+                        result.record(SyncEntity::Bookmark, &local_bm.name, SyncChangeKind::Added, SyncDirection::Up, &[]);
+                    }
                     result.bookmarks_uploaded += 1;
                 }
             }
@@ -504,21 +973,32 @@ impl MergeEngine {
             .map(|(id, uuid)| (uuid.clone(), *id))
             .collect();
 
-        // Build set of local book-collection UUIDs
-        let local_bc_uuids: std::collections::HashSet<String> = local_bcs
+        let local_by_uuid: HashMap<String, &BookCollection> = local_bcs
             .iter()
-            .filter_map(|bc| bc.uuid.clone())
+            .filter_map(|bc| bc.uuid.as_ref().map(|uuid| (uuid.clone(), bc)))
             .collect();
 
         // Download: Insert remote book_collections that don't exist locally
         for (uuid, remote_bc) in snapshot.book_collections.iter() {
+            if let Some(local_bc) = local_by_uuid.get(uuid) {
+                // Already exists locally: carry over a newer removal or restore
+                if remote_bc.updated_at > bc_timestamp(local_bc)
+                    && remote_bc.deleted_at.is_some() != local_bc.deleted_at.is_some()
+                {
+                    diesel::update(book_collections::table.find(local_bc.id))
+                        .set((
+                            book_collections::deleted_at.eq(from_opt_timestamp(remote_bc.deleted_at)),
+                            book_collections::updated_at.eq(Some(from_timestamp(remote_bc.updated_at))),
+                        ))
+                        .execute(conn)
+                        .map_err(|e| AppError::database_error(e.to_string()))?;
+                }
+                continue;
+            }
+
             if remote_bc.deleted_at.is_some() {
                 continue; // Skip deleted
             }
-            
-            if local_bc_uuids.contains(uuid) {
-                continue; // Already exists locally
-            }
 
             // Resolve UUIDs to local IDs
             let book_id = match book_id_map.get(&remote_bc.book_uuid) {
@@ -575,13 +1055,18 @@ impl MergeEngine {
                 None => continue,
             };
 
-            if !snapshot.book_collections.contains_key(&uuid) {
+            let local_ts = bc_timestamp(local_bc);
+            let is_newer = snapshot
+                .book_collections
+                .get(&uuid)
+                .is_none_or(|remote_bc| !self.pull_dominant && local_ts > remote_bc.updated_at);
+            if is_newer {
                 snapshot.book_collections.insert(uuid.clone(), RemoteBookCollectionState {
                     uuid,
                     book_uuid,
                     collection_uuid: coll_uuid,
                     added_at: to_timestamp(&local_bc.added_at),
-                    updated_at: local_bc.updated_at.map(|dt| to_timestamp(&dt)).unwrap_or_else(|| to_timestamp(&local_bc.added_at)),
+                    updated_at: local_ts,
                     deleted_at: local_bc.deleted_at.map(|dt| to_timestamp(&dt)),
                 });
             }
@@ -590,15 +1075,127 @@ impl MergeEngine {
         Ok(())
     }
 
-    /// Merge book settings
-    fn merge_book_settings(
+    /// Merge tags
+    ///
+    /// Tags are matched by UUID like collections. A remote tag whose name is already
+    /// taken by a different local tag isn't inserted; `merge_book_tags` attaches its
+    /// books to the local tag of that name instead.
+    fn merge_tags(
         &self,
         conn: &mut diesel::SqliteConnection,
         snapshot: &mut SyncSnapshot,
-        _last_sync_at: i64,
-        _result: &mut SyncResult,
+        last_sync_at: i64,
+        result: &mut SyncResult,
     ) -> Result<(), AppError> {
-        let local_settings: Vec<BookSettings> = book_settings::table
+        let local_tags: Vec<Tag> = tags::table
+            .select(Tag::as_select())
+            .load(conn)
+            .map_err(|e| AppError::database_error(e.to_string()))?;
+
+        let local_by_uuid: HashMap<String, &Tag> = local_tags
+            .iter()
+            .filter_map(|t| t.uuid.as_ref().map(|uuid| (uuid.clone(), t)))
+            .collect();
+        let mut local_names: HashSet<String> = local_tags.iter().map(|t| t.name.clone()).collect();
+
+        // Process remote tags
+        for (uuid, remote_tag) in snapshot.tags.iter() {
+            match local_by_uuid.get(uuid) {
+                Some(local_tag) => {
+                    let action = self.resolve_conflict(
+                        result,
+                        to_timestamp(&local_tag.updated_at),
+                        remote_tag.updated_at,
+                        last_sync_at,
+                        remote_tag.deleted_at.is_some(),
+                        local_tag.deleted_at.is_some(),
+                    );
+
+                    if matches!(action, ConflictAction::UseRemote) {
+                        let fields = differing(&[("name", local_tag.name != remote_tag.name)]);
+                        let kind = change_kind(remote_tag.deleted_at.is_some(), local_tag.deleted_at.is_some(), !fields.is_empty());
+                        result.record(SyncEntity::Tag, &remote_tag.name, kind, SyncDirection::Down, &fields);
+                        diesel::update(tags::table.find(local_tag.id))
+                            .set((
+                                tags::name.eq(&remote_tag.name),
+                                tags::updated_at.eq(from_timestamp(remote_tag.updated_at)),
+                                tags::deleted_at.eq(from_opt_timestamp(remote_tag.deleted_at)),
+                            ))
+                            .execute(conn)
+                            .map_err(|e| AppError::database_error(e.to_string()))?;
+                    }
+                }
+                None => {
+                    if remote_tag.deleted_at.is_none() && local_names.insert(remote_tag.name.clone()) {
+                        log::info!("Inserting tag {} ({})", remote_tag.name, uuid);
+                        diesel::insert_into(tags::table)
+                            .values((
+                                tags::uuid.eq(uuid),
+                                tags::name.eq(&remote_tag.name),
+                                tags::created_at.eq(from_timestamp(remote_tag.created_at)),
+                                tags::updated_at.eq(from_timestamp(remote_tag.updated_at)),
+                            ))
+                            .execute(conn)
+                            .map_err(|e| AppError::database_error(e.to_string()))?;
+                        result.record(SyncEntity::Tag, &remote_tag.name, SyncChangeKind::Added, SyncDirection::Down, &[]);
+                    }
+                }
+            }
+        }
+
+        // Process local tags
+        for local_tag in &local_tags {
+            let uuid = match &local_tag.uuid {
+                Some(u) => u.clone(),
+                None => continue,
+            };
+
+            let local_ts = to_timestamp(&local_tag.updated_at);
+            let upload = match snapshot.tags.get(&uuid) {
+                Some(remote_tag) => {
+                    let upload = self.local_supersedes(
+                        local_ts,
+                        remote_tag.updated_at,
+                        last_sync_at,
+                        local_tag.deleted_at.is_some(),
+                        remote_tag.deleted_at.is_some(),
+                    );
+                    if upload {
+                        let fields = differing(&[("name", local_tag.name != remote_tag.name)]);
+                        let kind = change_kind(local_tag.deleted_at.is_some(), remote_tag.deleted_at.is_some(), !fields.is_empty());
+                        result.record(SyncEntity::Tag, &local_tag.name, kind, SyncDirection::Up, &fields);
+                    }
+                    upload
+                }
+                None => {
+                    if local_tag.deleted_at.is_none() {
+                        result.record(SyncEntity::Tag, &local_tag.name, SyncChangeKind::Added, SyncDirection::Up, &[]);
+                    }
+                    true
+                }
+            };
+            if upload {
+                snapshot.tags.insert(uuid.clone(), RemoteTagState {
+                    uuid,
+                    name: local_tag.name.clone(),
+                    created_at: to_timestamp(&local_tag.created_at),
+                    updated_at: local_ts,
+                    deleted_at: to_opt_timestamp(&local_tag.deleted_at),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge book-tag relationships (same rules as book-collection links)
+    fn merge_book_tags(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        snapshot: &mut SyncSnapshot,
+    ) -> Result<(), AppError> {
+        let local_bts: Vec<BookTag> = book_tags::table
+            .select(BookTag::as_select())
             .load(conn)
             .map_err(|e| AppError::database_error(e.to_string()))?;
 
@@ -609,30 +1206,168 @@ impl MergeEngine {
             .into_iter()
             .filter_map(|(id, uuid)| uuid.map(|u| (id, u)))
             .collect();
-
-        // Build reverse mapping: UUID -> ID
         let book_id_map: HashMap<String, i32> = book_uuid_map.iter()
             .map(|(id, uuid)| (uuid.clone(), *id))
             .collect();
 
-        // Build set of local book_settings UUIDs
-        let local_bs_uuids: std::collections::HashSet<String> = local_settings
+        let local_tags: Vec<Tag> = tags::table
+            .select(Tag::as_select())
+            .load(conn)
+            .map_err(|e| AppError::database_error(e.to_string()))?;
+        let tag_uuid_map: HashMap<i32, String> = local_tags
             .iter()
-            .filter_map(|bs| bs.uuid.clone())
+            .filter_map(|t| t.uuid.clone().map(|u| (t.id, u)))
             .collect();
 
-        // Download: Insert remote book_settings that don't exist locally
-        for (uuid, remote_bs) in snapshot.book_settings.iter() {
-            if remote_bs.deleted_at.is_some() {
+        // Remote tag UUID -> local tag ID, falling back to a live local tag of the same name
+        let local_by_name: HashMap<&str, i32> = local_tags
+            .iter()
+            .filter(|t| t.deleted_at.is_none())
+            .map(|t| (t.name.as_str(), t.id))
+            .collect();
+        let resolve_tag = |tag_uuid: &str| -> Option<i32> {
+            local_tags
+                .iter()
+                .find(|t| t.uuid.as_deref() == Some(tag_uuid))
+                .map(|t| t.id)
+                .or_else(|| {
+                    let remote_tag = snapshot.tags.get(tag_uuid)?;
+                    local_by_name.get(remote_tag.name.as_str()).copied()
+                })
+        };
+
+        let local_by_uuid: HashMap<String, &BookTag> = local_bts
+            .iter()
+            .filter_map(|bt| bt.uuid.as_ref().map(|uuid| (uuid.clone(), bt)))
+            .collect();
+
+        // Download: apply newer remote links and insert ones missing locally
+        let mut inserts = Vec::new();
+        for (uuid, remote_bt) in snapshot.book_tags.iter() {
+            if let Some(local_bt) = local_by_uuid.get(uuid) {
+                if remote_bt.updated_at > bt_timestamp(local_bt)
+                    && remote_bt.deleted_at.is_some() != local_bt.deleted_at.is_some()
+                {
+                    diesel::update(book_tags::table.find(local_bt.id))
+                        .set((
+                            book_tags::deleted_at.eq(from_opt_timestamp(remote_bt.deleted_at)),
+                            book_tags::updated_at.eq(Some(from_timestamp(remote_bt.updated_at))),
+                        ))
+                        .execute(conn)
+                        .map_err(|e| AppError::database_error(e.to_string()))?;
+                }
                 continue;
             }
 
-            if local_bs_uuids.contains(uuid) {
+            if remote_bt.deleted_at.is_some() {
                 continue;
             }
 
-            let book_id = match book_id_map.get(&remote_bs.book_uuid) {
-                Some(id) => *id,
+            let Some(book_id) = book_id_map.get(&remote_bt.book_uuid).copied() else {
+                log::debug!("Skipping book_tag {}: book {} not found locally", uuid, remote_bt.book_uuid);
+                continue;
+            };
+            let Some(tag_id) = resolve_tag(&remote_bt.tag_uuid) else {
+                log::debug!("Skipping book_tag {}: tag {} not found locally", uuid, remote_bt.tag_uuid);
+                continue;
+            };
+            inserts.push((uuid.clone(), book_id, tag_id, remote_bt.added_at));
+        }
+
+        for (uuid, book_id, tag_id, added_at) in inserts {
+            // The same link may already exist under another UUID
+            let existing: Option<i32> = book_tags::table
+                .filter(book_tags::book_id.eq(book_id))
+                .filter(book_tags::tag_id.eq(tag_id))
+                .select(book_tags::id)
+                .first(conn)
+                .optional()
+                .map_err(|e| AppError::database_error(e.to_string()))?;
+
+            if existing.is_none() {
+                log::info!("Inserting book_tag {} (book {} -> tag {})", uuid, book_id, tag_id);
+                diesel::insert_into(book_tags::table)
+                    .values((
+                        book_tags::uuid.eq(uuid),
+                        book_tags::book_id.eq(book_id),
+                        book_tags::tag_id.eq(tag_id),
+                        book_tags::added_at.eq(from_timestamp(added_at)),
+                    ))
+                    .execute(conn)
+                    .map_err(|e| AppError::database_error(e.to_string()))?;
+            }
+        }
+
+        // Upload: add newer local links to the snapshot
+        for local_bt in &local_bts {
+            let uuid = match &local_bt.uuid {
+                Some(u) => u.clone(),
+                None => continue,
+            };
+            let (Some(book_uuid), Some(tag_uuid)) = (
+                book_uuid_map.get(&local_bt.book_id),
+                tag_uuid_map.get(&local_bt.tag_id),
+            ) else {
+                continue;
+            };
+
+            let local_ts = bt_timestamp(local_bt);
+            let is_newer = snapshot
+                .book_tags
+                .get(&uuid)
+                .is_none_or(|remote_bt| !self.pull_dominant && local_ts > remote_bt.updated_at);
+            if is_newer {
+                snapshot.book_tags.insert(uuid.clone(), RemoteBookTagState {
+                    uuid,
+                    book_uuid: book_uuid.clone(),
+                    tag_uuid: tag_uuid.clone(),
+                    added_at: to_timestamp(&local_bt.added_at),
+                    updated_at: local_ts,
+                    deleted_at: to_opt_timestamp(&local_bt.deleted_at),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge book settings
+    fn merge_book_settings(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        snapshot: &mut SyncSnapshot,
+        _last_sync_at: i64,
+        result: &mut SyncResult,
+    ) -> Result<(), AppError> {
+        let local_settings: Vec<BookSettings> = book_settings::table
+            .load(conn)
+            .map_err(|e| AppError::database_error(e.to_string()))?;
+
+        let (book_uuid_map, title_by_id) = load_book_uuids_and_titles(conn)?;
+
+        // Build reverse mapping: UUID -> ID
+        let book_id_map: HashMap<String, i32> = book_uuid_map.iter()
+            .map(|(id, uuid)| (uuid.clone(), *id))
+            .collect();
+
+        // Build set of local book_settings UUIDs
+        let local_bs_uuids: std::collections::HashSet<String> = local_settings
+            .iter()
+            .filter_map(|bs| bs.uuid.clone())
+            .collect();
+
+        // Download: Insert remote book_settings that don't exist locally
+        for (uuid, remote_bs) in snapshot.book_settings.iter() {
+            if remote_bs.deleted_at.is_some() {
+                continue;
+            }
+
+            if local_bs_uuids.contains(uuid) {
+                continue;
+            }
+
+            let book_id = match book_id_map.get(&remote_bs.book_uuid) {
+                Some(id) => *id,
                 None => {
                     log::debug!("Skipping book_settings {}: book {} not found locally", uuid, remote_bs.book_uuid);
                     continue;
@@ -648,6 +1383,8 @@ impl MergeEngine {
 
             if existing.is_none() {
                 log::info!("Inserting book_settings {} for book {}", uuid, book_id);
+                let title = title_by_id.get(&book_id).map(String::as_str).unwrap_or_default();
+                result.record(SyncEntity::BookSettings, title, SyncChangeKind::Added, SyncDirection::Down, &[]);
                 diesel::insert_into(book_settings::table)
                     .values((
                         book_settings::uuid.eq(uuid),
@@ -656,6 +1393,7 @@ impl MergeEngine {
                         book_settings::page_display_mode.eq(&remote_bs.page_display_mode),
                         book_settings::image_fit_mode.eq(&remote_bs.image_fit_mode),
                         book_settings::sync_progress.eq(remote_bs.sync_progress),
+                        book_settings::reader_background.eq(&remote_bs.reader_background),
                     ))
                     .execute(conn)
                     .map_err(|e| AppError::database_error(e.to_string()))?;
@@ -675,6 +1413,10 @@ impl MergeEngine {
             };
 
             if !snapshot.book_settings.contains_key(&uuid) {
+                if local_bs.deleted_at.is_none() {
+                    let title = title_by_id.get(&local_bs.book_id).map(String::as_str).unwrap_or_default();
+                    result.record(SyncEntity::BookSettings, title, SyncChangeKind::Added, SyncDirection::Up, &[]);
+                }
                 snapshot.book_settings.insert(uuid.clone(), RemoteBookSettingsState {
                     uuid,
                     book_uuid,
@@ -682,6 +1424,7 @@ impl MergeEngine {
                     page_display_mode: local_bs.page_display_mode.clone(),
                     image_fit_mode: local_bs.image_fit_mode.clone(),
                     sync_progress: local_bs.sync_progress,
+                    reader_background: local_bs.reader_background.clone(),
                     updated_at: to_timestamp(&local_bs.updated_at),
                     deleted_at: local_bs.deleted_at.map(|dt| to_timestamp(&dt)),
                 });
@@ -691,24 +1434,205 @@ impl MergeEngine {
         Ok(())
     }
 
+    /// Merge the reading queue
+    ///
+    /// Entries are matched by UUID and the newer `updated_at` wins, so moving a
+    /// book or removing it from the queue carries over as a whole. Unlike other
+    /// entities a removal does not always win: re-adding a book revives its entry
+    /// with a newer timestamp. A remote entry is only inserted or revived when its
+    /// book exists here and isn't already queued under another UUID; such second
+    /// entries are removed from the snapshot so every device keeps one per book.
+    fn merge_reading_queue(
+        &self,
+        conn: &mut diesel::SqliteConnection,
+        snapshot: &mut SyncSnapshot,
+        result: &mut SyncResult,
+    ) -> Result<(), AppError> {
+        let local_entries: Vec<QueueEntry> = reading_queue::table
+            .load(conn)
+            .map_err(|e| AppError::database_error(e.to_string()))?;
+
+        let (book_uuid_map, title_by_id) = load_book_uuids_and_titles(conn)?;
+        let title_of = |book_id: i32| title_by_id.get(&book_id).map(String::as_str).unwrap_or_default();
+        let book_id_map: HashMap<String, i32> = book_uuid_map.iter()
+            .map(|(id, uuid)| (uuid.clone(), *id))
+            .collect();
+
+        let local_by_uuid: HashMap<String, &QueueEntry> = local_entries
+            .iter()
+            .filter_map(|e| e.uuid.as_ref().map(|uuid| (uuid.clone(), e)))
+            .collect();
+        let mut queued_books: HashSet<i32> = local_entries
+            .iter()
+            .filter(|e| e.deleted_at.is_none())
+            .map(|e| e.book_id)
+            .collect();
+
+        // Remote entries that would queue an already-queued book a second time
+        let mut superseded: Vec<String> = Vec::new();
+
+        // Download: apply newer remote entries, insert missing ones. Known entries go
+        // first so removals free their book before another entry claims it.
+        let mut remote_entries: Vec<(&String, &RemoteQueueEntryState)> = snapshot.reading_queue.iter().collect();
+        remote_entries.sort_by_key(|(uuid, _)| !local_by_uuid.contains_key(*uuid));
+        for (uuid, remote) in remote_entries {
+            match local_by_uuid.get(uuid) {
+                Some(local) => {
+                    if remote.updated_at > to_timestamp(&local.updated_at) {
+                        match (local.deleted_at.is_some(), remote.deleted_at.is_some()) {
+                            (false, true) => {
+                                queued_books.remove(&local.book_id);
+                            }
+                            (true, false) if !queued_books.insert(local.book_id) => {
+                                superseded.push(uuid.clone());
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        let fields = differing(&[("position", local.position != remote.position)]);
+                        let removed_differs = remote.deleted_at.is_some() != local.deleted_at.is_some();
+                        if removed_differs || !fields.is_empty() {
+                            let kind = change_kind(remote.deleted_at.is_some(), local.deleted_at.is_some(), true);
+                            result.record(SyncEntity::QueueEntry, title_of(local.book_id), kind, SyncDirection::Down, &fields);
+                        }
+                        diesel::update(reading_queue::table.find(local.id))
+                            .set((
+                                reading_queue::position.eq(remote.position),
+                                reading_queue::updated_at.eq(from_timestamp(remote.updated_at)),
+                                reading_queue::deleted_at.eq(from_opt_timestamp(remote.deleted_at)),
+                            ))
+                            .execute(conn)
+                            .map_err(|e| AppError::database_error(e.to_string()))?;
+                    }
+                }
+                None => {
+                    if remote.deleted_at.is_some() {
+                        continue;
+                    }
+                    let book_id = match book_id_map.get(&remote.book_uuid) {
+                        Some(id) => *id,
+                        None => {
+                            log::debug!("Skipping queue entry {}: book {} not found locally", uuid, remote.book_uuid);
+                            continue;
+                        }
+                    };
+                    if !queued_books.insert(book_id) {
+                        superseded.push(uuid.clone());
+                        continue;
+                    }
+
+                    log::info!("Inserting queue entry {} for book {}", uuid, book_id);
+                    diesel::insert_into(reading_queue::table)
+                        .values((
+                            reading_queue::uuid.eq(uuid),
+                            reading_queue::book_id.eq(book_id),
+                            reading_queue::position.eq(remote.position),
+                            reading_queue::added_at.eq(from_timestamp(remote.added_at)),
+                            reading_queue::updated_at.eq(from_timestamp(remote.updated_at)),
+                        ))
+                        .execute(conn)
+                        .map_err(|e| AppError::database_error(e.to_string()))?;
+                    result.record(SyncEntity::QueueEntry, title_of(book_id), SyncChangeKind::Added, SyncDirection::Down, &[]);
+                }
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+        for uuid in superseded {
+            if let Some(remote) = snapshot.reading_queue.get_mut(&uuid) {
+                log::info!("Removing queue entry {}: its book is already queued", uuid);
+                remote.deleted_at = Some(now);
+                remote.updated_at = now;
+            }
+        }
+
+        // Upload: add local entries that are missing or newer than the snapshot's
+        for local in &local_entries {
+            let uuid = match &local.uuid {
+                Some(u) => u.clone(),
+                None => continue,
+            };
+            let book_uuid = match book_uuid_map.get(&local.book_id) {
+                Some(u) => u.clone(),
+                None => continue,
+            };
+
+            let local_ts = to_timestamp(&local.updated_at);
+            let is_newer = match snapshot.reading_queue.get(&uuid) {
+                Some(remote) => {
+                    let is_newer = !self.pull_dominant && local_ts > remote.updated_at;
+                    let fields = differing(&[("position", local.position != remote.position)]);
+                    let removed_differs = local.deleted_at.is_some() != remote.deleted_at.is_some();
+                    if is_newer && (removed_differs || !fields.is_empty()) {
+                        let kind = change_kind(local.deleted_at.is_some(), remote.deleted_at.is_some(), true);
+                        result.record(SyncEntity::QueueEntry, title_of(local.book_id), kind, SyncDirection::Up, &fields);
+                    }
+                    is_newer
+                }
+                None => {
+                    if local.deleted_at.is_none() {
+                        result.record(SyncEntity::QueueEntry, title_of(local.book_id), SyncChangeKind::Added, SyncDirection::Up, &[]);
+                    }
+                    true
+                }
+            };
+            if is_newer {
+                snapshot.reading_queue.insert(uuid.clone(), RemoteQueueEntryState {
+                    uuid,
+                    book_uuid,
+                    position: local.position,
+                    added_at: to_timestamp(&local.added_at),
+                    updated_at: local_ts,
+                    deleted_at: to_opt_timestamp(&local.deleted_at),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     /// Merge app settings (the settings.json file)
     fn merge_app_settings(
         &self,
         app_handle: &AppHandle,
         snapshot: &mut SyncSnapshot,
         _last_sync_at: i64,
+        result: &mut SyncResult,
     ) -> Result<(), AppError> {
-        use crate::settings::SettingValue;
-
         // Load local settings
         let local_settings = load_settings(app_handle)?;
-        
+
+        if let Some(settings) = self.merge_app_settings_values(local_settings, snapshot, result) {
+            // The settings file isn't covered by the dry run's rollback
+            if !self.options.dry_run {
+                save_settings(app_handle, &settings)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge local settings with the snapshot's app settings.
+    ///
+    /// Returns the updated local settings when the remote copy is newer and
+    /// must be saved, or `None` when local settings were written to the snapshot.
+    /// The keys whose values change are recorded as one `AppSettings` change.
+    fn merge_app_settings_values(
+        &self,
+        local_settings: AppSettings,
+        snapshot: &mut SyncSnapshot,
+        result: &mut SyncResult,
+    ) -> Option<AppSettings> {
+        // Drop keys left behind by older schema versions before comparing
+        prune_stale_app_settings(&mut snapshot.app_settings);
+        let known_keys = known_setting_keys();
+
         // Convert local settings to JSON map
         let mut local_map: HashMap<String, serde_json::Value> = HashMap::new();
         for category in &local_settings.categories {
             for setting in &category.settings {
-                // Skip sync settings themselves to avoid circular issues
-                if setting.key.starts_with("sync.") {
+                // Skip sync settings themselves and keys no longer in the schema
+                if !is_syncable_setting_key(&setting.key, &known_keys) {
                     continue;
                 }
                 let value = match &setting.value {
@@ -725,14 +1649,34 @@ impl MergeEngine {
         let local_updated_at = local_settings.updated_at;
         let remote_updated_at = snapshot.app_settings_updated_at;
 
-        if snapshot.app_settings.is_empty() || local_updated_at > remote_updated_at {
+        let mut changed_keys: Vec<&str> = local_map
+            .keys()
+            .chain(snapshot.app_settings.keys())
+            .filter(|key| local_map.get(*key) != snapshot.app_settings.get(*key))
+            .map(String::as_str)
+            .collect();
+        changed_keys.sort_unstable();
+        changed_keys.dedup();
+
+        if snapshot.app_settings.is_empty()
+            || (!self.pull_dominant && local_updated_at > remote_updated_at)
+        {
             // Local is newer or remote is empty - upload local settings
             log::info!("Uploading local app settings to remote");
+            if !changed_keys.is_empty() {
+                result.record(SyncEntity::AppSettings, "App settings", SyncChangeKind::Updated, SyncDirection::Up, &changed_keys);
+            }
             snapshot.app_settings = local_map;
             snapshot.app_settings_updated_at = local_updated_at;
-        } else if remote_updated_at > 0 {
-            // Remote is newer - download remote settings
+            None
+        } else if remote_updated_at > 0 || self.pull_dominant {
+            // Remote is newer (or this is a first sync) - download remote settings
             log::info!("Downloading remote app settings to local");
+            // Keys only set locally keep their value
+            changed_keys.retain(|key| snapshot.app_settings.contains_key(*key));
+            if !changed_keys.is_empty() {
+                result.record(SyncEntity::AppSettings, "App settings", SyncChangeKind::Updated, SyncDirection::Down, &changed_keys);
+            }
             let mut settings = local_settings;
             
             for (key, value) in &snapshot.app_settings {
@@ -752,29 +1696,41 @@ impl MergeEngine {
                 };
                 settings.set(key, setting_value);
             }
-            
-            save_settings(app_handle, &settings)?;
-        }
 
-        Ok(())
+            Some(settings)
+        } else {
+            None
+        }
     }
 
     // ========================================================================
     // CONFLICT RESOLUTION
     // ========================================================================
 
+    /// Decide which side of an entry present locally and remotely wins
+    ///
+    /// Entries changed on both sides since the last sync count as a resolved conflict.
     fn resolve_conflict(
         &self,
+        result: &mut SyncResult,
         local_ts: i64,
         remote_ts: i64,
-        _last_sync_at: i64,
+        last_sync_at: i64,
         remote_deleted: bool,
         local_deleted: bool,
     ) -> ConflictAction {
+        if local_ts != remote_ts && local_ts > last_sync_at && remote_ts > last_sync_at {
+            result.conflicts_resolved += 1;
+        }
+
         // Deletion always wins (if either side deleted, it stays deleted)
         if remote_deleted && !local_deleted {
             return ConflictAction::UseRemote;
         }
+        if self.pull_dominant {
+            // Never push a local state (including a deletion) over existing remote data
+            return ConflictAction::UseRemote;
+        }
         if local_deleted && !remote_deleted {
             return ConflictAction::UseLocal;
         }
@@ -794,6 +1750,31 @@ impl MergeEngine {
         }
     }
 
+    /// Whether a local entry should replace the remote copy in the snapshot
+    ///
+    /// Mirrors `resolve_conflict`, so the snapshot ends up with the same side that
+    /// was kept locally.
+    fn local_supersedes(
+        &self,
+        local_ts: i64,
+        remote_ts: i64,
+        last_sync_at: i64,
+        local_deleted: bool,
+        remote_deleted: bool,
+    ) -> bool {
+        if self.pull_dominant || (remote_deleted && !local_deleted) {
+            return false;
+        }
+        match self.strategy {
+            ConflictStrategy::LastWriteWins => local_ts > remote_ts && local_ts > last_sync_at,
+            ConflictStrategy::LocalWins => local_ts != remote_ts,
+            // Only changes the remote side hasn't touched since the last sync
+            ConflictStrategy::RemoteWins => {
+                local_ts > remote_ts && local_ts > last_sync_at && remote_ts <= last_sync_at
+            }
+        }
+    }
+
     // ========================================================================
     // LOCAL DB UPDATE HELPERS
     // ========================================================================
@@ -807,24 +1788,57 @@ impl MergeEngine {
         diesel::update(books::table.find(book_id))
             .set((
                 books::title.eq(&remote.title),
+                books::sort_key.eq(remote_sort_key(remote)),
                 books::current_page.eq(remote.current_page),
+                books::page_fraction.eq(remote.page_fraction),
+                books::scroll_fraction.eq(remote.scroll_fraction),
                 books::total_pages.eq(remote.total_pages),
                 books::is_favorite.eq(remote.is_favorite),
                 books::reading_status.eq(&remote.reading_status),
                 books::last_read_at.eq(from_opt_timestamp(remote.last_read_at)),
                 books::updated_at.eq(from_timestamp(remote.updated_at)),
                 books::deleted_at.eq(from_opt_timestamp(remote.deleted_at)),
+                books::notes.eq(&remote.notes),
+                books::series_name.eq(&remote.series_name),
+                books::volume_number.eq(remote.volume_number),
             ))
             .execute(conn)
             .map_err(|e| AppError::database_error(e.to_string()))?;
         Ok(())
     }
 
+    /// Insert a remote book, or merge it into an existing local book with the same file hash
+    ///
+    /// The hash is checked against the database rather than the pre-loaded book map, so
+    /// books inserted earlier in the same merge (e.g. two remote UUIDs for one file)
+    /// are caught too. Returns the UUID of the book it was merged into, or None if a
+    /// new row was inserted.
     fn insert_local_book(
         &self,
         conn: &mut diesel::SqliteConnection,
         remote: &RemoteBookState,
-    ) -> Result<(), AppError> {
+    ) -> Result<Option<String>, AppError> {
+        if let Some(ref file_hash) = remote.file_hash {
+            let existing: Option<Book> = books::table
+                .filter(books::file_hash.eq(file_hash))
+                .filter(books::deleted_at.is_null())
+                .first(conn)
+                .optional()
+                .map_err(|e| AppError::database_error(e.to_string()))?;
+
+            if let Some(existing) = existing {
+                log::info!(
+                    "Remote book {} has the same hash as local book {}, merging instead of inserting",
+                    remote.uuid,
+                    existing.id
+                );
+                if remote.updated_at > to_timestamp(&existing.updated_at) {
+                    self.update_local_book(conn, existing.id, remote)?;
+                }
+                return Ok(Some(existing.uuid.unwrap_or_else(|| remote.uuid.clone())));
+            }
+        }
+
         // Generate a unique placeholder path for cloud-only books
         // This will be replaced with the actual path when the file is downloaded
         let placeholder_path = format!("cloud://{}", remote.uuid);
@@ -836,17 +1850,25 @@ impl MergeEngine {
                 books::filename.eq(&remote.filename),
                 books::file_hash.eq(&remote.file_hash),
                 books::title.eq(&remote.title),
+                books::sort_key.eq(remote_sort_key(remote)),
                 books::current_page.eq(remote.current_page),
+                books::page_fraction.eq(remote.page_fraction),
+                books::scroll_fraction.eq(remote.scroll_fraction),
                 books::total_pages.eq(remote.total_pages),
                 books::is_favorite.eq(remote.is_favorite),
                 books::reading_status.eq(&remote.reading_status),
                 books::last_read_at.eq(from_opt_timestamp(remote.last_read_at)),
                 books::added_at.eq(from_timestamp(remote.added_at)),
                 books::updated_at.eq(from_timestamp(remote.updated_at)),
+                books::notes.eq(&remote.notes),
+                books::series_name.eq(&remote.series_name),
+                books::volume_number.eq(remote.volume_number),
+                books::open_count.eq(remote.open_count),
+                books::completed_count.eq(remote.completed_count),
             ))
             .execute(conn)
             .map_err(|e| AppError::database_error(e.to_string()))?;
-        Ok(())
+        Ok(None)
     }
 
     fn update_local_collection(
@@ -943,13 +1965,36 @@ impl MergeEngine {
     // LOCAL -> REMOTE CONVERSION HELPERS
     // ========================================================================
 
+    /// Move a dropped duplicate book's bookmarks, links, settings and queue entries to
+    /// the entry it was merged into
+    fn repoint_book_references(&self, snapshot: &mut SyncSnapshot, from: &str, to: &str) {
+        let book_uuids = snapshot
+            .bookmarks
+            .values_mut()
+            .map(|b| &mut b.book_uuid)
+            .chain(snapshot.book_collections.values_mut().map(|bc| &mut bc.book_uuid))
+            .chain(snapshot.book_tags.values_mut().map(|bt| &mut bt.book_uuid))
+            .chain(snapshot.book_settings.values_mut().map(|bs| &mut bs.book_uuid))
+            .chain(snapshot.reading_queue.values_mut().map(|q| &mut q.book_uuid));
+        for book_uuid in book_uuids {
+            if book_uuid == from {
+                *book_uuid = to.to_string();
+            }
+        }
+    }
+
+    /// Only content hashes are shared: Drive files are named by them and other devices
+    /// match and verify downloads against them. A fast-imported book's file hash stays
+    /// local until the backfill replaces it.
     fn book_to_remote(&self, book: &Book) -> RemoteBookState {
         RemoteBookState {
             uuid: book.uuid.clone().unwrap_or_default(),
-            file_hash: book.file_hash.clone(),
+            file_hash: book.file_hash.clone().filter(|_| book.hash_scheme() == HashScheme::Content),
             title: book.title.clone(),
             filename: book.filename.clone(),
             current_page: book.current_page,
+            page_fraction: book.page_fraction,
+            scroll_fraction: book.scroll_fraction,
             total_pages: book.total_pages,
             is_favorite: book.is_favorite,
             reading_status: book.reading_status.clone(),
@@ -957,10 +2002,20 @@ impl MergeEngine {
             added_at: to_timestamp(&book.added_at),
             updated_at: to_timestamp(&book.updated_at),
             deleted_at: to_opt_timestamp(&book.deleted_at),
+            notes: book.notes.clone(),
+            open_count: book.open_count,
+            completed_count: book.completed_count,
+            sort_key: book.sort_key.clone(),
+            series_name: book.series_name.clone(),
+            volume_number: book.volume_number,
         }
     }
 
-    fn collection_to_remote(&self, collection: &Collection) -> RemoteCollectionState {
+    fn collection_to_remote(
+        &self,
+        collection: &Collection,
+        uuid_by_id: &HashMap<i32, String>,
+    ) -> RemoteCollectionState {
         RemoteCollectionState {
             uuid: collection.uuid.clone().unwrap_or_default(),
             name: collection.name.clone(),
@@ -968,6 +2023,7 @@ impl MergeEngine {
             created_at: to_timestamp(&collection.created_at),
             updated_at: to_timestamp(&collection.updated_at),
             deleted_at: to_opt_timestamp(&collection.deleted_at),
+            parent_uuid: collection.parent_id.and_then(|id| uuid_by_id.get(&id).cloned()),
         }
     }
 
@@ -985,6 +2041,113 @@ impl MergeEngine {
     }
 }
 
+/// Map local book IDs to their sync UUIDs and to their titles (for the change feed)
+fn load_book_uuids_and_titles(
+    conn: &mut diesel::SqliteConnection,
+) -> Result<(HashMap<i32, String>, HashMap<i32, String>), AppError> {
+    let rows: Vec<(i32, Option<String>, String)> = books::table
+        .select((books::id, books::uuid, books::title))
+        .load(conn)
+        .map_err(|e| AppError::database_error(e.to_string()))?;
+
+    let title_by_id = rows.iter().map(|(id, _, title)| (*id, title.clone())).collect();
+    let uuid_by_id = rows
+        .into_iter()
+        .filter_map(|(id, uuid, _)| uuid.map(|u| (id, u)))
+        .collect();
+    Ok((uuid_by_id, title_by_id))
+}
+
+/// Sort key for a remote book, derived from its title when the snapshot has none
+fn remote_sort_key(remote: &RemoteBookState) -> String {
+    remote
+        .sort_key
+        .clone()
+        .unwrap_or_else(|| crate::database::operations::book_sort_key(&remote.title))
+}
+
+/// Whether a book's details, rather than only its reading progress, differ between the sides
+fn book_details_differ(local: &Book, remote: &RemoteBookState) -> bool {
+    !book_detail_fields(local, remote).is_empty()
+}
+
+/// Names of the fields in `fields` whose sides differ, for the change feed
+fn differing(fields: &[(&'static str, bool)]) -> Vec<&'static str> {
+    fields.iter().filter(|(_, differs)| *differs).map(|(name, _)| *name).collect()
+}
+
+/// Book details (not reading progress) that differ between the sides
+fn book_detail_fields(local: &Book, remote: &RemoteBookState) -> Vec<&'static str> {
+    differing(&[
+        ("title", local.title != remote.title),
+        ("is_favorite", local.is_favorite != remote.is_favorite),
+        ("notes", local.notes != remote.notes),
+        ("series_name", local.series_name != remote.series_name),
+        ("volume_number", local.volume_number != remote.volume_number),
+    ])
+}
+
+/// Reading progress fields that differ between the sides
+fn book_progress_fields(local: &Book, remote: &RemoteBookState) -> Vec<&'static str> {
+    differing(&[
+        ("current_page", local.current_page != remote.current_page),
+        ("reading_status", local.reading_status != remote.reading_status),
+        ("last_read_at", to_opt_timestamp(&local.last_read_at) != remote.last_read_at),
+    ])
+}
+
+/// Every synced book field that differs between the sides
+fn book_changed_fields(local: &Book, remote: &RemoteBookState) -> Vec<&'static str> {
+    let mut fields = book_detail_fields(local, remote);
+    fields.extend(book_progress_fields(local, remote));
+    fields
+}
+
+fn collection_changed_fields(
+    local: &Collection,
+    remote: &RemoteCollectionState,
+    uuid_by_id: &HashMap<i32, String>,
+) -> Vec<&'static str> {
+    let local_parent = local.parent_id.and_then(|id| uuid_by_id.get(&id));
+    differing(&[
+        ("name", local.name != remote.name),
+        ("description", local.description != remote.description),
+        ("parent", local_parent != remote.parent_uuid.as_ref()),
+    ])
+}
+
+fn bookmark_changed_fields(local: &Bookmark, remote: &RemoteBookmarkState) -> Vec<&'static str> {
+    differing(&[
+        ("name", local.name != remote.name),
+        ("description", local.description != remote.description),
+        ("page", local.page != remote.page),
+    ])
+}
+
+/// Classify a change copied onto an entry that exists on both sides, for the change feed
+fn change_kind(source_deleted: bool, target_deleted: bool, details_changed: bool) -> SyncChangeKind {
+    match (source_deleted, target_deleted) {
+        (true, false) => SyncChangeKind::Deleted,
+        (false, true) => SyncChangeKind::Added,
+        _ if details_changed => SyncChangeKind::Updated,
+        _ => SyncChangeKind::Progress,
+    }
+}
+
+/// Last change of a book-collection row (rows from before sync columns have no `updated_at`)
+fn bc_timestamp(bc: &BookCollection) -> i64 {
+    bc.updated_at
+        .map(|dt| to_timestamp(&dt))
+        .unwrap_or_else(|| to_timestamp(&bc.added_at))
+}
+
+/// Last change of a book-tag row
+fn bt_timestamp(bt: &BookTag) -> i64 {
+    bt.updated_at
+        .map(|dt| to_timestamp(&dt))
+        .unwrap_or_else(|| to_timestamp(&bt.added_at))
+}
+
 /// Result of conflict resolution
 #[derive(Debug, Clone, Copy)]
 enum ConflictAction {
@@ -992,3 +2155,1053 @@ enum ConflictAction {
     UseLocal,
     NoOp,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{SettingItem, WidgetType};
+
+    fn test_engine() -> MergeEngine {
+        MergeEngine::new(
+            "test-device".to_string(),
+            ConflictStrategy::default(),
+            SyncOptions {
+                sync_books: true,
+                sync_books_files: false,
+                sync_settings: true,
+                sync_progress: true,
+                dry_run: false,
+            },
+        )
+    }
+
+    #[test]
+    fn test_stale_setting_dropped_on_upload() {
+        let engine = test_engine();
+
+        // Local settings still carry a key that was removed from the schema
+        let mut local = create_default_settings();
+        local.updated_at = 2_000;
+        local.categories[0].settings.push(SettingItem::new(
+            "appearance.removed_option",
+            "Removed",
+            "No longer part of the schema",
+            WidgetType::Toggle,
+            SettingValue::Bool(true),
+        ));
+
+        let mut snapshot = SyncSnapshot::new();
+        snapshot.app_settings_updated_at = 1_000;
+        snapshot.app_settings.insert("appearance.theme".to_string(), serde_json::json!("dark"));
+        snapshot.app_settings.insert("reading.legacy_mode".to_string(), serde_json::json!(true));
+
+        let mut result = SyncResult::empty();
+        let to_save = engine.merge_app_settings_values(local, &mut snapshot, &mut result);
+
+        assert!(to_save.is_none());
+        assert!(snapshot.app_settings.contains_key("appearance.theme"));
+        assert!(!snapshot.app_settings.contains_key("appearance.removed_option"));
+        assert!(!snapshot.app_settings.contains_key("reading.legacy_mode"));
+        assert!(!snapshot.app_settings.keys().any(|k| k.starts_with("sync.")));
+
+        // The theme went up with the other local settings
+        assert_eq!(result.changes.len(), 1);
+        let change = &result.changes[0];
+        assert_eq!((change.entity, change.direction), (SyncEntity::AppSettings, SyncDirection::Up));
+        assert!(change.fields.contains(&"appearance.theme".to_string()));
+        assert!(!change.fields.contains(&"reading.legacy_mode".to_string()));
+    }
+
+    #[test]
+    fn test_stale_setting_dropped_on_download() {
+        let engine = test_engine();
+
+        let local = create_default_settings();
+
+        let mut snapshot = SyncSnapshot::new();
+        snapshot.app_settings_updated_at = 5_000;
+        snapshot.app_settings.insert("appearance.theme".to_string(), serde_json::json!("dark"));
+        snapshot.app_settings.insert("appearance.removed_option".to_string(), serde_json::json!(true));
+        snapshot.app_settings.insert("sync.books".to_string(), serde_json::json!(true));
+
+        let mut result = SyncResult::empty();
+        let to_save = engine
+            .merge_app_settings_values(local, &mut snapshot, &mut result)
+            .expect("remote settings should be applied");
+
+        assert!(matches!(to_save.get("appearance.theme"), Some(SettingValue::String(s)) if s == "dark"));
+        assert_eq!(snapshot.app_settings.len(), 1);
+        let fields: Vec<&str> = result.changes.iter().flat_map(|c| c.fields.iter().map(String::as_str)).collect();
+        assert_eq!(fields, vec!["appearance.theme"]);
+        assert_eq!(result.changes[0].direction, SyncDirection::Down);
+        assert!(!snapshot.app_settings.contains_key("appearance.removed_option"));
+        assert!(!snapshot.app_settings.contains_key("sync.books"));
+    }
+
+    fn test_db() -> diesel::SqliteConnection {
+        use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+        const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+        let mut conn = diesel::SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        conn
+    }
+
+    fn remote_book(uuid: &str, file_hash: &str, updated_at: i64) -> RemoteBookState {
+        RemoteBookState {
+            uuid: uuid.to_string(),
+            file_hash: Some(file_hash.to_string()),
+            title: format!("Remote {}", uuid),
+            filename: "remote.cbz".to_string(),
+            current_page: 7,
+            total_pages: 20,
+            is_favorite: false,
+            reading_status: "reading".to_string(),
+            last_read_at: None,
+            added_at: updated_at,
+            updated_at,
+            deleted_at: None,
+            notes: None,
+            open_count: 0,
+            completed_count: 0,
+            sort_key: None,
+            series_name: None,
+            volume_number: None,
+            page_fraction: None,
+            scroll_fraction: None,
+        }
+    }
+
+    #[test]
+    fn test_remote_book_with_existing_hash_not_duplicated() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        diesel::insert_into(books::table)
+            .values(&NewBook {
+                file_path: "/manga/local.cbz".to_string(),
+                filename: "local.cbz".to_string(),
+                file_size: None,
+                file_hash: Some("samehash".to_string()),
+                title: "Local".to_string(),
+                current_page: 0,
+                total_pages: 20,
+                uuid: Some("local-uuid".to_string()),
+                series_name: None,
+                volume_number: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+
+        // Two remote UUIDs for the local file, plus two for a file that is new here
+        let far_future = chrono::Utc::now().timestamp_millis() + 60_000;
+        let mut snapshot = SyncSnapshot::default();
+        for (uuid, hash) in [
+            ("remote-a", "samehash"),
+            ("remote-b", "samehash"),
+            ("remote-c", "newhash"),
+            ("remote-d", "newhash"),
+        ] {
+            snapshot
+                .books
+                .insert(uuid.to_string(), remote_book(uuid, hash, far_future));
+        }
+        for book_uuid in ["remote-a", "remote-b", "remote-c", "remote-d"] {
+            snapshot.bookmarks.insert(
+                format!("bm-{}", book_uuid),
+                RemoteBookmarkState {
+                    uuid: format!("bm-{}", book_uuid),
+                    book_uuid: book_uuid.to_string(),
+                    name: "Mark".to_string(),
+                    description: None,
+                    page: 3,
+                    created_at: far_future,
+                    updated_at: far_future,
+                    deleted_at: None,
+                },
+            );
+        }
+
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, 0, &mut result, true)
+            .unwrap();
+
+        // The dropped duplicates leave the snapshot, and their bookmarks follow the kept entry
+        let book_uuids: Vec<Option<String>> = books::table
+            .select(books::uuid)
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(snapshot.books.len(), 2);
+        for uuid in book_uuids {
+            assert!(snapshot.books.contains_key(&uuid.unwrap()));
+        }
+        assert!(snapshot
+            .bookmarks
+            .values()
+            .all(|bm| snapshot.books.contains_key(&bm.book_uuid)));
+
+        // A later sync finds nothing left to resolve
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, 0, &mut result, true)
+            .unwrap();
+        assert_eq!(snapshot.books.len(), 2);
+        assert_eq!(result.books_downloaded, 0);
+
+        let books: Vec<Book> = books::table.load(&mut conn).unwrap();
+        assert_eq!(books.len(), 2);
+
+        let local = books.iter().find(|b| b.file_hash.as_deref() == Some("samehash")).unwrap();
+        assert_eq!(local.file_path, "/manga/local.cbz");
+        assert_eq!(local.current_page, 7);
+
+        let cloud = books.iter().find(|b| b.file_hash.as_deref() == Some("newhash")).unwrap();
+        assert!(cloud.file_path.starts_with("cloud://"));
+
+        // Inserting directly is guarded as well
+        assert!(engine
+            .insert_local_book(&mut conn, &remote_book("remote-e", "newhash", far_future))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_merge_records_change_feed() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        for (title, uuid) in [("Remote shared", "shared"), ("Local only", "local-only")] {
+            diesel::insert_into(books::table)
+                .values(&NewBook {
+                    file_path: format!("/manga/{}.cbz", uuid),
+                    filename: format!("{}.cbz", uuid),
+                    file_size: None,
+                    file_hash: Some(format!("{}hash", uuid)),
+                    title: title.to_string(),
+                    current_page: 0,
+                    total_pages: 20,
+                    uuid: Some(uuid.to_string()),
+                    series_name: None,
+                    volume_number: None,
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+
+        // The shared book only differs in progress; the other remote book is new here
+        let far_future = chrono::Utc::now().timestamp_millis() + 60_000;
+        let mut snapshot = SyncSnapshot::default();
+        for (uuid, hash) in [("shared", "sharedhash"), ("remote-new", "newhash")] {
+            snapshot.books.insert(uuid.to_string(), remote_book(uuid, hash, far_future));
+        }
+
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, 0, &mut result, true)
+            .unwrap();
+
+        let mut feed: Vec<(String, SyncChangeKind, SyncDirection)> = result
+            .changes
+            .iter()
+            .map(|c| (c.title.clone(), c.kind, c.direction))
+            .collect();
+        feed.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            feed,
+            vec![
+                ("Local only".to_string(), SyncChangeKind::Added, SyncDirection::Up),
+                ("Remote remote-new".to_string(), SyncChangeKind::Added, SyncDirection::Down),
+                ("Remote shared".to_string(), SyncChangeKind::Progress, SyncDirection::Down),
+            ]
+        );
+        assert!(result.changes.iter().all(|c| c.entity == SyncEntity::Book));
+
+        assert_eq!(change_kind(true, false, false), SyncChangeKind::Deleted);
+        assert_eq!(change_kind(false, true, false), SyncChangeKind::Added);
+        assert_eq!(change_kind(false, false, true), SyncChangeKind::Updated);
+
+        let shared = result.changes.iter().find(|c| c.title == "Remote shared").unwrap();
+        assert_eq!(shared.fields, vec!["current_page", "reading_status"]);
+    }
+
+    #[test]
+    fn test_recent_changes_feed_keeps_older_entries() {
+        let mut conn = test_db();
+        let load = |conn: &mut diesel::SqliteConnection| -> Vec<String> {
+            let json: Option<String> = sync_state::table
+                .find(1)
+                .select(sync_state::last_changes)
+                .first(conn)
+                .unwrap();
+            let feed: Vec<SyncChange> = serde_json::from_str(&json.unwrap()).unwrap();
+            feed.into_iter().map(|c| c.title).collect()
+        };
+        let sync = |titles: &[&str]| -> Vec<SyncChange> {
+            let mut result = SyncResult::empty();
+            for title in titles {
+                result.record(SyncEntity::Book, title, SyncChangeKind::Progress, SyncDirection::Down, &["current_page"]);
+            }
+            result.changes
+        };
+
+        save_recent_changes(&mut conn, &sync(&["first"])).unwrap();
+        save_recent_changes(&mut conn, &sync(&["second", "third"])).unwrap();
+        assert_eq!(load(&mut conn), vec!["second", "third", "first"]);
+
+        // A sync that changed nothing keeps the feed
+        save_recent_changes(&mut conn, &[]).unwrap();
+        assert_eq!(load(&mut conn), vec!["second", "third", "first"]);
+
+        let many: Vec<String> = (0..MAX_RECENT_SYNC_CHANGES).map(|i| format!("book {}", i)).collect();
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        save_recent_changes(&mut conn, &sync(&many)).unwrap();
+        let feed = load(&mut conn);
+        assert_eq!(feed.len(), MAX_RECENT_SYNC_CHANGES);
+        assert_eq!(feed[0], "book 0");
+    }
+
+    #[test]
+    fn test_failed_merge_leaves_no_partial_changes() {
+        let engine = test_engine();
+        let mut conn = test_db();
+        let far_future = chrono::Utc::now().timestamp_millis() + 60_000;
+        let mut snapshot = SyncSnapshot::default();
+        snapshot
+            .books
+            .insert("remote-a".to_string(), remote_book("remote-a", "hash-a", far_future));
+
+        // Books merge, then a later step fails
+        let outcome = engine.merge_transaction(&mut conn, |conn| {
+            engine.merge_books(conn, &mut snapshot, 0, &mut SyncResult::empty(), true)?;
+            assert_eq!(books::table.count().get_result::<i64>(conn).unwrap(), 1);
+            Err(AppError::database_error("injected failure"))
+        });
+        assert!(outcome.unwrap_err().message.contains("injected failure"));
+        assert_eq!(books::table.count().get_result::<i64>(&mut conn).unwrap(), 0);
+
+        engine
+            .merge_transaction(&mut conn, |conn| {
+                engine.merge_books(conn, &mut snapshot, 0, &mut SyncResult::empty(), true)
+            })
+            .unwrap();
+        assert_eq!(books::table.count().get_result::<i64>(&mut conn).unwrap(), 1);
+
+        // A dry run never commits
+        let mut dry_options = engine.options.clone();
+        dry_options.dry_run = true;
+        let dry = MergeEngine::new("test-device".to_string(), ConflictStrategy::default(), dry_options);
+        snapshot
+            .books
+            .insert("remote-b".to_string(), remote_book("remote-b", "hash-b", far_future));
+        dry.merge_transaction(&mut conn, |conn| {
+            dry.merge_books(conn, &mut snapshot, 0, &mut SyncResult::empty(), true)
+        })
+        .unwrap();
+        assert_eq!(books::table.count().get_result::<i64>(&mut conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let mut conn = test_db();
+        diesel::insert_into(books::table)
+            .values(&NewBook {
+                file_path: "/manga/a.cbz".to_string(),
+                filename: "a.cbz".to_string(),
+                file_size: None,
+                file_hash: Some("hash-a".to_string()),
+                title: "A".to_string(),
+                current_page: 0,
+                total_pages: 20,
+                uuid: Some("book-a".to_string()),
+                series_name: None,
+                volume_number: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+
+        // Progress on the local book and a new entry of every kind from another device
+        let now = chrono::Utc::now().timestamp_millis() + 60_000;
+        let mut snapshot = SyncSnapshot::default();
+        for (uuid, hash) in [("book-a", "hash-a"), ("book-b", "hash-b")] {
+            snapshot.books.insert(uuid.to_string(), remote_book(uuid, hash, now));
+        }
+        snapshot.collections.insert("shelf".into(), RemoteCollectionState {
+            uuid: "shelf".to_string(),
+            name: "Shelf".to_string(),
+            description: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            parent_uuid: None,
+        });
+        snapshot.tags.insert("inked".into(), RemoteTagState {
+            uuid: "inked".to_string(),
+            name: "inked".to_string(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        });
+        snapshot.bookmarks.insert("bm-a".into(), RemoteBookmarkState {
+            uuid: "bm-a".to_string(),
+            book_uuid: "book-a".to_string(),
+            name: "Mark".to_string(),
+            description: None,
+            page: 3,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        });
+        snapshot.reading_queue.insert("q-a".into(), RemoteQueueEntryState {
+            uuid: "q-a".to_string(),
+            book_uuid: "book-a".to_string(),
+            position: 0,
+            added_at: now,
+            updated_at: now,
+            deleted_at: None,
+        });
+
+        let row_counts = |conn: &mut diesel::SqliteConnection| {
+            (
+                books::table.count().get_result::<i64>(conn).unwrap(),
+                collections::table.count().get_result::<i64>(conn).unwrap(),
+                tags::table.count().get_result::<i64>(conn).unwrap(),
+                bookmarks::table.count().get_result::<i64>(conn).unwrap(),
+                reading_queue::table.count().get_result::<i64>(conn).unwrap(),
+            )
+        };
+        let before = row_counts(&mut conn);
+
+        let mut options = test_engine().options;
+        options.dry_run = true;
+        let dry = MergeEngine::new("test-device".to_string(), ConflictStrategy::default(), options);
+        let mut result = SyncResult::empty();
+        dry.merge_transaction(&mut conn, |conn| dry.merge_library(conn, &mut snapshot, 0, &mut result))
+            .unwrap();
+
+        // Everything is planned, nothing is written
+        for entity in [SyncEntity::Book, SyncEntity::Collection, SyncEntity::Tag, SyncEntity::Bookmark, SyncEntity::QueueEntry] {
+            assert!(result.changes.iter().any(|c| c.entity == entity), "{:?}", entity);
+        }
+        assert_eq!(row_counts(&mut conn), before);
+        let local: Book = books::table.first(&mut conn).unwrap();
+        assert_eq!(local.current_page, 0);
+    }
+
+    #[test]
+    fn test_local_wins_keeps_local_title() {
+        let options = test_engine().options;
+        let far_future = chrono::Utc::now().timestamp_millis() + 60_000;
+
+        // (strategy, expected title on both sides)
+        for (strategy, expected) in [
+            (ConflictStrategy::LocalWins, "Local title"),
+            (ConflictStrategy::LastWriteWins, "Remote shared"),
+        ] {
+            let engine = MergeEngine::new("test-device".to_string(), strategy, options.clone());
+            let mut conn = test_db();
+            diesel::insert_into(books::table)
+                .values(&NewBook {
+                    file_path: "/manga/shared.cbz".to_string(),
+                    filename: "shared.cbz".to_string(),
+                    file_size: None,
+                    file_hash: Some("sharedhash".to_string()),
+                    title: "Local title".to_string(),
+                    current_page: 0,
+                    total_pages: 20,
+                    uuid: Some("shared".to_string()),
+                    series_name: None,
+                    volume_number: None,
+                })
+                .execute(&mut conn)
+                .unwrap();
+
+            // Both sides changed since the last sync, the remote copy more recently
+            let mut snapshot = SyncSnapshot::default();
+            snapshot
+                .books
+                .insert("shared".to_string(), remote_book("shared", "sharedhash", far_future));
+
+            let mut result = SyncResult::empty();
+            engine
+                .merge_books(&mut conn, &mut snapshot, 0, &mut result, true)
+                .unwrap();
+
+            let local: Book = books::table.first(&mut conn).unwrap();
+            assert_eq!(local.title, expected, "{:?}", strategy);
+            assert_eq!(snapshot.books["shared"].title, expected, "{:?}", strategy);
+            assert_eq!(result.conflicts_resolved, 1);
+        }
+    }
+
+    #[test]
+    fn test_file_hashes_stay_local() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        // Two fast-imported books: one new, one already synced with its content hash
+        for (uuid, hash) in [("new", "newfilehash"), ("known", "knownfilehash")] {
+            diesel::insert_into(books::table)
+                .values(&NewBook {
+                    file_path: format!("/manga/{}.cbz", uuid),
+                    filename: format!("{}.cbz", uuid),
+                    file_size: None,
+                    file_hash: Some(hash.to_string()),
+                    title: uuid.to_string(),
+                    current_page: 0,
+                    total_pages: 20,
+                    uuid: Some(uuid.to_string()),
+                    series_name: None,
+                    volume_number: None,
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+        diesel::update(books::table)
+            .set(books::hash_scheme.eq(HashScheme::File.as_str()))
+            .execute(&mut conn)
+            .unwrap();
+
+        let mut snapshot = SyncSnapshot::default();
+        snapshot
+            .books
+            .insert("known".to_string(), remote_book("known", "knowncontenthash", 0));
+
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, 0, &mut result, true)
+            .unwrap();
+
+        assert_eq!(snapshot.books["new"].file_hash, None);
+        assert_eq!(snapshot.books["known"].file_hash.as_deref(), Some("knowncontenthash"));
+    }
+
+    #[test]
+    fn test_progress_only_sync_matches_by_hash() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        // Device B imported the real file; device A only knows it as metadata + progress
+        for (name, uuid) in [("fresh", "device-b-fresh"), ("reread", "device-b-reread")] {
+            diesel::insert_into(books::table)
+                .values(&NewBook {
+                    file_path: format!("/manga/{}.cbz", name),
+                    filename: format!("{}.cbz", name),
+                    file_size: None,
+                    file_hash: Some(format!("{}hash", name)),
+                    title: name.to_string(),
+                    current_page: 0,
+                    total_pages: 20,
+                    uuid: Some(uuid.to_string()),
+                    series_name: None,
+                    volume_number: None,
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+        let now = chrono::Utc::now().timestamp_millis();
+        diesel::update(books::table.filter(books::uuid.eq("device-b-reread")))
+            .set((
+                books::current_page.eq(15),
+                books::last_read_at.eq(from_opt_timestamp(Some(now))),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+
+        let mut snapshot = SyncSnapshot::default();
+        for (uuid, hash) in [("device-a-fresh", "freshhash"), ("device-a-reread", "rereadhash")] {
+            let mut remote = remote_book(uuid, hash, now - 60_000);
+            remote.last_read_at = Some(now - 60_000);
+            remote.page_fraction = Some(0.5);
+            remote.scroll_fraction = Some(0.25);
+            snapshot.books.insert(uuid.to_string(), remote);
+        }
+
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, 0, &mut result, false)
+            .unwrap();
+
+        // Remote progress lands on the locally imported copy, which takes the remote UUID
+        let fresh: Book = books::table.filter(books::file_hash.eq("freshhash")).first(&mut conn).unwrap();
+        assert_eq!(fresh.uuid.as_deref(), Some("device-a-fresh"));
+        assert_eq!(fresh.current_page, 7);
+        assert_eq!(fresh.page_fraction, Some(0.5));
+        assert_eq!(fresh.scroll_fraction, Some(0.25));
+        assert_eq!(fresh.file_path, "/manga/fresh.cbz");
+
+        // Newer local progress is kept and uploaded instead
+        let reread: Book = books::table.filter(books::file_hash.eq("rereadhash")).first(&mut conn).unwrap();
+        assert_eq!(reread.uuid.as_deref(), Some("device-a-reread"));
+        assert_eq!(reread.current_page, 15);
+        assert_eq!(snapshot.books["device-a-reread"].current_page, 15);
+
+        // No entries under the old local UUIDs
+        assert_eq!(snapshot.books.len(), 2);
+        assert_eq!((result.books_downloaded, result.books_uploaded), (1, 1));
+    }
+
+    #[test]
+    fn test_read_counts_merge_by_max() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        diesel::insert_into(books::table)
+            .values(&NewBook {
+                file_path: "/manga/counted.cbz".to_string(),
+                filename: "counted.cbz".to_string(),
+                file_size: None,
+                file_hash: Some("countedhash".to_string()),
+                title: "Counted".to_string(),
+                current_page: 0,
+                total_pages: 20,
+                uuid: Some("counted".to_string()),
+                series_name: None,
+                volume_number: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+        diesel::update(books::table)
+            .set((books::open_count.eq(5), books::completed_count.eq(1)))
+            .execute(&mut conn)
+            .unwrap();
+
+        // Remote wins last-write-wins but has fewer opens and more completions
+        let mut remote = remote_book("counted", "countedhash", chrono::Utc::now().timestamp_millis() + 60_000);
+        remote.open_count = 3;
+        remote.completed_count = 2;
+        let mut snapshot = SyncSnapshot::default();
+        snapshot.books.insert("counted".to_string(), remote);
+
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, 0, &mut result, true)
+            .unwrap();
+
+        let book: Book = books::table.first(&mut conn).unwrap();
+        assert_eq!((book.open_count, book.completed_count), (5, 2));
+        let remote = &snapshot.books["counted"];
+        assert_eq!((remote.open_count, remote.completed_count), (5, 2));
+    }
+
+    #[test]
+    fn test_opening_book_keeps_newer_remote_progress() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        let now = chrono::Utc::now();
+        let last_sync_at = (now - chrono::Duration::hours(1)).timestamp_millis();
+        diesel::insert_into(books::table)
+            .values(&NewBook {
+                file_path: "/manga/opened.cbz".to_string(),
+                filename: "opened.cbz".to_string(),
+                file_size: None,
+                file_hash: Some("openedhash".to_string()),
+                title: "Opened".to_string(),
+                current_page: 2,
+                total_pages: 20,
+                uuid: Some("opened".to_string()),
+                series_name: None,
+                volume_number: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+        // Local progress was last changed before the last sync
+        let book: Book = diesel::update(books::table)
+            .set(books::updated_at.eq((now - chrono::Duration::hours(2)).naive_utc()))
+            .get_result(&mut conn)
+            .unwrap();
+
+        // Another device read further since then, and this one opens the book
+        let mut remote = remote_book("opened", "openedhash", (now - chrono::Duration::minutes(10)).timestamp_millis());
+        remote.current_page = 15;
+        remote.open_count = 4;
+        let mut snapshot = SyncSnapshot::default();
+        snapshot.books.insert("opened".to_string(), remote);
+        crate::database::operations::record_book_opened(&mut conn, book.id).unwrap();
+
+        let mut result = SyncResult::empty();
+        engine
+            .merge_books(&mut conn, &mut snapshot, last_sync_at, &mut result, true)
+            .unwrap();
+
+        let book: Book = books::table.first(&mut conn).unwrap();
+        assert_eq!(book.current_page, 15);
+        assert_eq!(book.open_count, 4);
+        assert_eq!(snapshot.books["opened"].current_page, 15);
+    }
+
+    #[test]
+    fn test_reading_queue_merge() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        for name in ["a", "b", "c"] {
+            diesel::insert_into(books::table)
+                .values(&NewBook {
+                    file_path: format!("/manga/{}.cbz", name),
+                    filename: format!("{}.cbz", name),
+                    file_size: None,
+                    file_hash: None,
+                    title: name.to_string(),
+                    current_page: 0,
+                    total_pages: 10,
+                    uuid: Some(format!("book-{}", name)),
+                    series_name: None,
+                    volume_number: None,
+                })
+                .execute(&mut conn)
+                .unwrap();
+        }
+        let book_id = |conn: &mut diesel::SqliteConnection, name: &str| -> i32 {
+            books::table
+                .filter(books::uuid.eq(format!("book-{}", name)))
+                .select(books::id)
+                .first(conn)
+                .unwrap()
+        };
+
+        let old = chrono::NaiveDateTime::parse_from_str("2026-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let (a, b) = (book_id(&mut conn, "a"), book_id(&mut conn, "b"));
+        for (uuid, book, position, deleted_at) in [("q-a", a, 0, None), ("q-b", b, 1, None), ("q-b-old", b, 4, Some(old))] {
+            diesel::insert_into(reading_queue::table)
+                .values((
+                    reading_queue::uuid.eq(uuid),
+                    reading_queue::book_id.eq(book),
+                    reading_queue::position.eq(position),
+                    reading_queue::updated_at.eq(old),
+                    reading_queue::deleted_at.eq(deleted_at),
+                ))
+                .execute(&mut conn)
+                .unwrap();
+        }
+
+        let newer = to_timestamp(&old) + 60_000;
+        let entry = |uuid: &str, book_uuid: &str, position: i32, deleted: bool| RemoteQueueEntryState {
+            uuid: uuid.to_string(),
+            book_uuid: book_uuid.to_string(),
+            position,
+            added_at: newer,
+            updated_at: newer,
+            deleted_at: deleted.then_some(newer),
+        };
+        let mut snapshot = SyncSnapshot::default();
+        // Moved to the front elsewhere, a second entry for an already-queued book, an
+        // old entry revived for a queued book, a book that is new to the queue and one
+        // whose book doesn't exist here
+        snapshot.reading_queue.insert("q-b".into(), entry("q-b", "book-b", -1, false));
+        snapshot.reading_queue.insert("q-a2".into(), entry("q-a2", "book-a", 5, false));
+        snapshot.reading_queue.insert("q-b-old".into(), entry("q-b-old", "book-b", 4, false));
+        snapshot.reading_queue.insert("q-c".into(), entry("q-c", "book-c", 2, false));
+        snapshot.reading_queue.insert("q-x".into(), entry("q-x", "book-x", 3, false));
+
+        let mut result = SyncResult::empty();
+        engine.merge_reading_queue(&mut conn, &mut snapshot, &mut result).unwrap();
+
+        let mut feed: Vec<(String, SyncChangeKind, SyncDirection, Vec<String>)> = result
+            .changes
+            .iter()
+            .map(|c| (c.title.clone(), c.kind, c.direction, c.fields.clone()))
+            .collect();
+        feed.sort_by(|x, y| x.0.cmp(&y.0));
+        assert_eq!(
+            feed,
+            vec![
+                ("a".to_string(), SyncChangeKind::Added, SyncDirection::Up, vec![]),
+                ("b".to_string(), SyncChangeKind::Updated, SyncDirection::Down, vec!["position".to_string()]),
+                ("c".to_string(), SyncChangeKind::Added, SyncDirection::Down, vec![]),
+            ]
+        );
+        assert!(result.changes.iter().all(|c| c.entity == SyncEntity::QueueEntry));
+
+        let queued: Vec<(String, i32)> = reading_queue::table
+            .filter(reading_queue::deleted_at.is_null())
+            .order(reading_queue::position.asc())
+            .select((reading_queue::uuid.assume_not_null(), reading_queue::position))
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(
+            queued,
+            vec![("q-b".to_string(), -1), ("q-a".to_string(), 0), ("q-c".to_string(), 2)]
+        );
+        assert!(snapshot.reading_queue.contains_key("q-a"));
+        // Second entries for a queued book are removed for every device
+        assert!(snapshot.reading_queue["q-a2"].deleted_at.is_some());
+        assert!(snapshot.reading_queue["q-b-old"].deleted_at.is_some());
+
+        // A newer removal on the other device drops the entry here
+        snapshot.reading_queue.insert("q-c".into(), RemoteQueueEntryState {
+            updated_at: newer + 1,
+            ..entry("q-c", "book-c", 2, true)
+        });
+        let mut result = SyncResult::empty();
+        engine.merge_reading_queue(&mut conn, &mut snapshot, &mut result).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!((result.changes[0].kind, result.changes[0].direction), (SyncChangeKind::Deleted, SyncDirection::Down));
+        let removed: Option<chrono::NaiveDateTime> = reading_queue::table
+            .filter(reading_queue::uuid.eq("q-c"))
+            .select(reading_queue::deleted_at)
+            .first(&mut conn)
+            .unwrap();
+        assert!(removed.is_some());
+    }
+
+    #[test]
+    fn test_nested_collections_sync() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        let local: Collection = diesel::insert_into(collections::table)
+            .values(&NewCollection {
+                name: "Local shelf".to_string(),
+                description: None,
+                uuid: Some("local-shelf".to_string()),
+            })
+            .returning(Collection::as_returning())
+            .get_result(&mut conn)
+            .unwrap();
+
+        // The child arrives next to a parent that doesn't exist locally yet
+        let now = chrono::Utc::now().timestamp_millis();
+        let collection = |uuid: &str, parent_uuid: Option<&str>| RemoteCollectionState {
+            uuid: uuid.to_string(),
+            name: uuid.to_string(),
+            description: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            parent_uuid: parent_uuid.map(str::to_string),
+        };
+        let mut snapshot = SyncSnapshot::default();
+        snapshot.collections.insert("naruto".into(), collection("naruto", Some("shonen")));
+        snapshot.collections.insert("shonen".into(), collection("shonen", Some("local-shelf")));
+
+        let mut result = SyncResult::empty();
+        engine.merge_collections(&mut conn, &mut snapshot, 0, &mut result).unwrap();
+
+        let parent_of = |conn: &mut diesel::SqliteConnection, uuid: &str| -> Option<i32> {
+            collections::table
+                .filter(collections::uuid.eq(uuid))
+                .select(collections::parent_id)
+                .first(conn)
+                .unwrap()
+        };
+        let shonen_id: i32 = collections::table
+            .filter(collections::uuid.eq("shonen"))
+            .select(collections::id)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(parent_of(&mut conn, "naruto"), Some(shonen_id));
+        assert_eq!(parent_of(&mut conn, "shonen"), Some(local.id));
+
+        // Local nesting is uploaded by UUID
+        let boruto: Collection = diesel::insert_into(collections::table)
+            .values(&NewCollection {
+                name: "Boruto".to_string(),
+                description: None,
+                uuid: Some("boruto".to_string()),
+            })
+            .returning(Collection::as_returning())
+            .get_result(&mut conn)
+            .unwrap();
+        diesel::update(collections::table.find(boruto.id))
+            .set(collections::parent_id.eq(Some(shonen_id)))
+            .execute(&mut conn)
+            .unwrap();
+        engine.merge_collections(&mut conn, &mut snapshot, now, &mut result).unwrap();
+        assert_eq!(snapshot.collections["boruto"].parent_uuid.as_deref(), Some("shonen"));
+        assert_eq!(snapshot.collections["local-shelf"].parent_uuid, None);
+    }
+
+    #[test]
+    fn test_tags_merge_by_uuid_and_name() {
+        let engine = test_engine();
+        let mut conn = test_db();
+
+        diesel::insert_into(books::table)
+            .values(&NewBook {
+                file_path: "/manga/tagged.cbz".to_string(),
+                filename: "tagged.cbz".to_string(),
+                file_size: None,
+                file_hash: None,
+                title: "Tagged".to_string(),
+                current_page: 0,
+                total_pages: 10,
+                uuid: Some("book-a".to_string()),
+                series_name: None,
+                volume_number: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+        diesel::insert_into(tags::table)
+            .values(&NewTag { name: "color".to_string(), uuid: Some("local-color".to_string()) })
+            .execute(&mut conn)
+            .unwrap();
+
+        // Another device created its own "color" tag, plus one that is new here
+        let now = chrono::Utc::now().timestamp_millis();
+        let tag = |uuid: &str, name: &str| RemoteTagState {
+            uuid: uuid.to_string(),
+            name: name.to_string(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+        let link = |uuid: &str, tag_uuid: &str| RemoteBookTagState {
+            uuid: uuid.to_string(),
+            book_uuid: "book-a".to_string(),
+            tag_uuid: tag_uuid.to_string(),
+            added_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+        let mut snapshot = SyncSnapshot::default();
+        snapshot.tags.insert("remote-color".into(), tag("remote-color", "color"));
+        snapshot.tags.insert("inked".into(), tag("inked", "inked"));
+        snapshot.book_tags.insert("bt-color".into(), link("bt-color", "remote-color"));
+        snapshot.book_tags.insert("bt-inked".into(), link("bt-inked", "inked"));
+
+        engine.merge_tags(&mut conn, &mut snapshot, 0, &mut SyncResult::empty()).unwrap();
+        engine.merge_book_tags(&mut conn, &mut snapshot).unwrap();
+
+        let names: Vec<String> = tags::table.order(tags::name.asc()).select(tags::name).load(&mut conn).unwrap();
+        assert_eq!(names, vec!["color", "inked"]);
+
+        // The remote "color" link lands on the local tag of that name
+        let linked: Vec<String> = book_tags::table
+            .inner_join(tags::table)
+            .filter(book_tags::deleted_at.is_null())
+            .order(tags::name.asc())
+            .select(tags::uuid.assume_not_null())
+            .load(&mut conn)
+            .unwrap();
+        assert_eq!(linked, vec!["local-color", "inked"]);
+
+        // The local tag is uploaded alongside the remote one
+        assert!(snapshot.tags.contains_key("local-color"));
+        assert_eq!(snapshot.tags.len(), 3);
+
+        // A newer removal elsewhere is carried over
+        snapshot.book_tags.get_mut("bt-inked").unwrap().deleted_at = Some(now + 1000);
+        snapshot.book_tags.get_mut("bt-inked").unwrap().updated_at = now + 1000;
+        engine.merge_book_tags(&mut conn, &mut snapshot).unwrap();
+        let live_links: i64 = book_tags::table
+            .filter(book_tags::deleted_at.is_null())
+            .count()
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(live_links, 1);
+    }
+
+    #[test]
+    fn test_is_first_sync() {
+        let mut conn = test_db();
+        assert!(is_first_sync(&mut conn).unwrap());
+
+        diesel::update(sync_state::table.find(1))
+            .set(sync_state::last_sync_at.eq(Some(chrono::Utc::now().naive_utc())))
+            .execute(&mut conn)
+            .unwrap();
+        assert!(!is_first_sync(&mut conn).unwrap());
+
+        diesel::delete(sync_state::table).execute(&mut conn).unwrap();
+        assert!(is_first_sync(&mut conn).unwrap());
+    }
+
+    #[test]
+    fn test_first_sync_empty_local_pulls_remote() {
+        let engine = test_engine().pull_dominant(true);
+        let mut conn = test_db();
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut snapshot = SyncSnapshot::default();
+        snapshot.books.insert("live".to_string(), remote_book("live", "livehash", now));
+        let mut deleted = remote_book("gone", "gonehash", now);
+        deleted.deleted_at = Some(now);
+        snapshot.books.insert("gone".to_string(), deleted);
+        snapshot.collections.insert("coll".to_string(), RemoteCollectionState {
+            uuid: "coll".to_string(),
+            name: "Remote shelf".to_string(),
+            description: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            parent_uuid: None,
+        });
+
+        let mut result = SyncResult::empty();
+        engine.merge_books(&mut conn, &mut snapshot, 0, &mut result, true).unwrap();
+        engine.merge_collections(&mut conn, &mut snapshot, 0, &mut result).unwrap();
+
+        // Live remote data is pulled in, tombstones are not
+        let books: Vec<Book> = books::table.load(&mut conn).unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].uuid.as_deref(), Some("live"));
+        let collections: Vec<Collection> = collections::table.load(&mut conn).unwrap();
+        assert_eq!(collections.len(), 1);
+
+        // Nothing on the remote side is removed or replaced
+        assert_eq!(snapshot.books.len(), 2);
+        assert!(snapshot.books["live"].deleted_at.is_none());
+        assert_eq!(snapshot.collections.len(), 1);
+        assert_eq!((result.books_uploaded, result.collections_uploaded), (0, 0));
+    }
+
+    #[test]
+    fn test_first_sync_never_overwrites_remote() {
+        let mut conn = test_db();
+
+        diesel::insert_into(books::table)
+            .values(&NewBook {
+                file_path: "/manga/shared.cbz".to_string(),
+                filename: "shared.cbz".to_string(),
+                file_size: None,
+                file_hash: Some("sharedhash".to_string()),
+                title: "Shared".to_string(),
+                current_page: 0,
+                total_pages: 20,
+                uuid: Some("shared".to_string()),
+                series_name: None,
+                volume_number: None,
+            })
+            .execute(&mut conn)
+            .unwrap();
+        // Deleted locally, after the remote copy was last touched
+        let now = chrono::Utc::now().naive_utc();
+        diesel::update(books::table.filter(books::uuid.eq("shared")))
+            .set((books::deleted_at.eq(Some(now)), books::updated_at.eq(now)))
+            .execute(&mut conn)
+            .unwrap();
+
+        let remote_ts = to_timestamp(&now) - 60_000;
+        let mut snapshot = SyncSnapshot::default();
+        snapshot.books.insert("shared".to_string(), remote_book("shared", "sharedhash", remote_ts));
+
+        // A regular sync propagates the newer local deletion
+        let mut regular = snapshot.clone();
+        test_engine()
+            .merge_books(&mut conn, &mut regular, 0, &mut SyncResult::empty(), true)
+            .unwrap();
+        assert!(regular.books["shared"].deleted_at.is_some());
+
+        // A first sync keeps the remote book and restores it locally
+        let engine = test_engine().pull_dominant(true);
+        let mut result = SyncResult::empty();
+        engine.merge_books(&mut conn, &mut snapshot, 0, &mut result, true).unwrap();
+        assert!(snapshot.books["shared"].deleted_at.is_none());
+        let local: Book = books::table.filter(books::uuid.eq("shared")).first(&mut conn).unwrap();
+        assert!(local.deleted_at.is_none());
+        assert_eq!(local.current_page, 7);
+
+        // Fresh local settings don't replace the ones already on the remote
+        let mut local_settings = create_default_settings();
+        local_settings.updated_at = to_timestamp(&now);
+        snapshot.app_settings_updated_at = remote_ts;
+        snapshot.app_settings.insert("appearance.theme".to_string(), serde_json::json!("dark"));
+        let to_save = engine
+            .merge_app_settings_values(local_settings, &mut snapshot, &mut SyncResult::empty())
+            .expect("remote settings should be applied");
+        assert_eq!(to_save.get("appearance.theme"), Some(&SettingValue::String("dark".to_string())));
+        assert_eq!(snapshot.app_settings_updated_at, remote_ts);
+    }
+}
@@ -2,10 +2,13 @@
 //!
 //! Implements a pull-merge-push strategy for syncing app data across devices.
 
+pub mod backend;
 pub mod drive;
+pub mod encryption;
 pub mod merge;
 pub mod types;
 
+pub use backend::SyncBackend;
 pub use drive::DriveSync;
-pub use merge::MergeEngine;
+pub use merge::{is_first_sync, MergeEngine};
 pub use types::*;
@@ -22,6 +22,26 @@ pub struct RemoteBookState {
     pub added_at: i64,               // Unix timestamp (millis)
     pub updated_at: i64,             // Unix timestamp (millis)
     pub deleted_at: Option<i64>,     // Unix timestamp (millis) - soft delete
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Engagement counters, merged by taking the larger value
+    #[serde(default)]
+    pub open_count: i32,
+    #[serde(default)]
+    pub completed_count: i32,
+    /// Normalized title for ordering; snapshots from older versions lack it
+    #[serde(default)]
+    pub sort_key: Option<String>,
+    /// Scroll position within the current page (vertical mode), synced with progress
+    #[serde(default)]
+    pub page_fraction: Option<f64>,
+    /// Scroll position within the whole strip (vertical mode), synced with progress
+    #[serde(default)]
+    pub scroll_fraction: Option<f64>,
+    #[serde(default)]
+    pub series_name: Option<String>,
+    #[serde(default)]
+    pub volume_number: Option<f64>,
 }
 
 /// Remote bookmark state
@@ -46,6 +66,9 @@ pub struct RemoteCollectionState {
     pub created_at: i64,
     pub updated_at: i64,
     pub deleted_at: Option<i64>,
+    /// Collection this one is nested under (by UUID); None for top-level collections
+    #[serde(default)]
+    pub parent_uuid: Option<String>,
 }
 
 /// Remote book-collection relationship
@@ -59,6 +82,27 @@ pub struct RemoteBookCollectionState {
     pub deleted_at: Option<i64>,
 }
 
+/// Remote tag state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteTagState {
+    pub uuid: String,
+    pub name: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub deleted_at: Option<i64>,
+}
+
+/// Remote book-tag relationship
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteBookTagState {
+    pub uuid: String,
+    pub book_uuid: String,
+    pub tag_uuid: String,
+    pub added_at: i64,
+    pub updated_at: i64,
+    pub deleted_at: Option<i64>,
+}
+
 /// Remote book settings state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteBookSettingsState {
@@ -68,6 +112,23 @@ pub struct RemoteBookSettingsState {
     pub page_display_mode: Option<String>,
     pub image_fit_mode: Option<String>,
     pub sync_progress: Option<bool>,
+    /// Snapshots from older versions lack it
+    #[serde(default)]
+    pub reader_background: Option<String>,
+    pub updated_at: i64,
+    pub deleted_at: Option<i64>,
+}
+
+/// Remote reading queue entry
+///
+/// Each entry is merged last-write-wins on `updated_at`, covering both its
+/// `position` and whether it was removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteQueueEntryState {
+    pub uuid: String,
+    pub book_uuid: String,
+    pub position: i32,
+    pub added_at: i64,
     pub updated_at: i64,
     pub deleted_at: Option<i64>,
 }
@@ -92,6 +153,15 @@ pub struct SyncSnapshot {
     pub book_collections: HashMap<String, RemoteBookCollectionState>,
     /// Book settings indexed by UUID
     pub book_settings: HashMap<String, RemoteBookSettingsState>,
+    /// Reading queue entries indexed by UUID
+    #[serde(default)]
+    pub reading_queue: HashMap<String, RemoteQueueEntryState>,
+    /// Tags indexed by UUID
+    #[serde(default)]
+    pub tags: HashMap<String, RemoteTagState>,
+    /// Book-tag relationships indexed by UUID
+    #[serde(default)]
+    pub book_tags: HashMap<String, RemoteBookTagState>,
     /// App settings (key-value pairs)
     #[serde(default)]
     pub app_settings: HashMap<String, serde_json::Value>,
@@ -113,6 +183,9 @@ impl SyncSnapshot {
             collections: HashMap::new(),
             book_collections: HashMap::new(),
             book_settings: HashMap::new(),
+            reading_queue: HashMap::new(),
+            tags: HashMap::new(),
+            book_tags: HashMap::new(),
             app_settings: HashMap::new(),
             app_settings_updated_at: 0,
         }
@@ -134,6 +207,9 @@ pub struct SyncOptions {
     pub sync_settings: bool,
     /// Sync reading progress (current_page, last_read_at, bookmarks)
     pub sync_progress: bool,
+    /// Only compute what a sync would change: local data and sync state are left untouched
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 impl Default for SyncOptions {
@@ -143,10 +219,21 @@ impl Default for SyncOptions {
             sync_books_files: false,
             sync_settings: false,
             sync_progress: true,  // Progress is on by default
+            dry_run: false,
         }
     }
 }
 
+/// Which snapshot `export_snapshot` writes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotSource {
+    /// The snapshot currently stored on Drive
+    Remote,
+    /// The snapshot built from local data alone, as a sync would upload it to an empty Drive
+    Local,
+}
+
 /// Current sync status for display in UI
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -163,6 +250,38 @@ pub enum SyncStatus {
     Disabled,
 }
 
+/// Step of a running sync, reported by the `sync-progress` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncPhase {
+    /// Fetching the remote snapshot
+    Downloading,
+    /// Merging the remote snapshot with local data
+    Merging,
+    /// Uploading the merged snapshot
+    Uploading,
+    /// Uploading book files that aren't on Drive yet
+    SyncingFiles,
+}
+
+/// Payload of the `sync-progress` event emitted during `sync_now`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncProgress {
+    pub phase: SyncPhase,
+    /// For `SyncingFiles`: position of the file being uploaded (starting at 1) and the
+    /// number of files to upload; None for the other phases
+    pub index: Option<usize>,
+    pub total: Option<usize>,
+    /// Title of the book being uploaded
+    pub title: Option<String>,
+}
+
+impl SyncProgress {
+    pub fn phase(phase: SyncPhase) -> Self {
+        Self { phase, index: None, total: None, title: None }
+    }
+}
+
 /// Result of a sync operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncResult {
@@ -176,6 +295,9 @@ pub struct SyncResult {
     pub conflicts_resolved: usize,
     pub errors: Vec<String>,
     pub completed_at: i64,
+    /// What the merge changed on each side, one entry per entity
+    #[serde(default)]
+    pub changes: Vec<SyncChange>,
 }
 
 impl SyncResult {
@@ -191,8 +313,146 @@ impl SyncResult {
             conflicts_resolved: 0,
             errors: Vec::new(),
             completed_at: chrono::Utc::now().timestamp_millis(),
+            changes: Vec::new(),
         }
     }
+
+    /// Add an entry to the change feed
+    pub fn record(
+        &mut self,
+        entity: SyncEntity,
+        title: &str,
+        kind: SyncChangeKind,
+        direction: SyncDirection,
+        fields: &[&str],
+    ) {
+        self.changes.push(SyncChange {
+            entity,
+            title: title.to_string(),
+            kind,
+            direction,
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            device: None,
+        });
+    }
+}
+
+/// Kind of entry a `SyncChange` is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEntity {
+    Book,
+    Collection,
+    Bookmark,
+    Tag,
+    QueueEntry,
+    BookSettings,
+    AppSettings,
+}
+
+/// What a sync did to an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncChangeKind {
+    /// Created on the receiving side
+    Added,
+    /// Details (title, favorite, ...) replaced by the other side's version
+    Updated,
+    /// Only reading progress (page, status, last read) changed
+    Progress,
+    /// Deleted on the receiving side
+    Deleted,
+}
+
+/// Which way a change went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    /// From this device to Drive
+    Up,
+    /// From Drive to this device
+    Down,
+}
+
+/// One entry of the recent sync changes feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncChange {
+    pub entity: SyncEntity,
+    /// Book title, collection, bookmark or tag name; the book's title for queue
+    /// entries and book settings
+    pub title: String,
+    pub kind: SyncChangeKind,
+    pub direction: SyncDirection,
+    /// Fields that differed between the sides (e.g. `current_page`), empty for
+    /// additions and deletions
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// For downloads, the device that last uploaded the snapshot
+    pub device: Option<String>,
+}
+
+/// Size of the book files a sync would upload to Drive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncUploadEstimate {
+    /// Number of local book files not yet on Drive
+    pub file_count: usize,
+    /// Total size of those files in bytes
+    pub total_bytes: u64,
+}
+
+/// Local and remote versions of one book, for inspecting a conflict before syncing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookConflict {
+    pub local: crate::database::models::Book,
+    /// Snapshot entry with the same UUID, None if the book isn't on Drive yet
+    pub remote: Option<RemoteBookState>,
+    /// Local `updated_at` (Unix timestamp millis)
+    pub local_updated_at: i64,
+    pub remote_updated_at: Option<i64>,
+    /// Last successful sync on this device (Unix timestamp millis)
+    pub last_sync_at: Option<i64>,
+    /// Whether each side changed since the last sync (both true = real conflict)
+    pub local_changed: bool,
+    pub remote_changed: bool,
+}
+
+/// Payload of the `book-file-progress` event emitted while `download_book` runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookFileProgress {
+    pub book_id: i32,
+    pub downloaded_bytes: u64,
+    /// Size of the file, if Drive reported it
+    pub total_bytes: Option<u64>,
+}
+
+/// Payload of the `download-progress` event emitted by `download_all_books`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookDownloadProgress {
+    pub book_id: i32,
+    pub title: String,
+    /// Books finished so far, including this one
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Outcome of downloading one book in `download_all_books`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookDownloadOutcome {
+    pub book_id: i32,
+    pub title: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub bytes: u64,
+}
+
+/// Result of downloading every cloud-only book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadAllResult {
+    pub books: Vec<BookDownloadOutcome>,
+    /// Bytes written for the successful downloads
+    pub total_bytes: u64,
+    /// Whether the run was stopped by `cancel_download_all`
+    pub cancelled: bool,
 }
 
 /// Conflict resolution strategy
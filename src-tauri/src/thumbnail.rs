@@ -0,0 +1,155 @@
+//! Downscaled page thumbnails for the comic:// protocol
+//!
+//! URL format: comic://localhost/book/{book_id}/thumb/{page_number}?w={width}
+//!
+//! Pages are decoded with the `image` crate, shrunk to at most the requested width
+//! (aspect ratio preserved, never upscaled) and re-encoded as JPEG. Rendered thumbnails
+//! are cached on disk as `{book_id}_{page}_{width}.jpg`, so library grids only decode
+//! each cover once. Pages that can't be decoded are served as-is by the caller.
+
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use image::codecs::jpeg::JpegEncoder;
+
+/// Width used when the URL has no `w` parameter
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 300;
+
+/// Widths are clamped to this range so a URL can't request a huge render
+const MIN_THUMBNAIL_WIDTH: u32 = 16;
+const MAX_THUMBNAIL_WIDTH: u32 = 2048;
+
+/// JPEG quality of rendered thumbnails
+const THUMBNAIL_QUALITY: u8 = 85;
+
+/// MIME type of rendered thumbnails
+pub const THUMBNAIL_MIME_TYPE: &str = "image/jpeg";
+
+/// Directory rendered thumbnails are cached in, set at startup
+static THUMBNAIL_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the directory thumbnails are cached in, creating it if needed
+pub fn set_thumbnail_dir(dir: PathBuf) {
+    if let Err(e) = fs::create_dir_all(&dir) {
+        log::warn!("Failed to create thumbnail directory {:?}: {}", dir, e);
+        return;
+    }
+    log::debug!("Thumbnail cache directory: {:?}", dir);
+    *THUMBNAIL_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir);
+}
+
+/// Read the requested width from a URL query string (`w=300`)
+pub fn parse_width(query: &str) -> u32 {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "w")
+        .and_then(|(_, value)| value.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_THUMBNAIL_WIDTH)
+        .clamp(MIN_THUMBNAIL_WIDTH, MAX_THUMBNAIL_WIDTH)
+}
+
+/// Cache file for a thumbnail, if a cache directory is configured
+fn cache_path(book_id: i32, page: usize, width: u32) -> Option<PathBuf> {
+    THUMBNAIL_DIR
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|dir| dir.join(format!("{}_{}_{}.jpg", book_id, page, width)))
+}
+
+/// Get a previously rendered thumbnail from the disk cache
+pub fn get_cached(book_id: i32, page: usize, width: u32) -> Option<Vec<u8>> {
+    fs::read(cache_path(book_id, page, width)?).ok()
+}
+
+/// Render a thumbnail from the page's original bytes and cache it
+///
+/// Returns `None` if the image can't be decoded or encoded.
+pub fn render(book_id: i32, page: usize, width: u32, data: &[u8]) -> Option<Vec<u8>> {
+    let image = match image::load_from_memory(data) {
+        Ok(image) => image,
+        Err(e) => {
+            log::debug!("Can't decode page {} of book {} for a thumbnail: {}", page, book_id, e);
+            return None;
+        }
+    };
+
+    let image = if image.width() > width {
+        // `thumbnail` fits within the bounds, so the height limit never binds
+        image.thumbnail(width, u32::MAX)
+    } else {
+        image
+    };
+
+    // JPEG has no alpha channel
+    let rgb = image.to_rgb8();
+    let mut buffer = Cursor::new(Vec::new());
+    if let Err(e) = JpegEncoder::new_with_quality(&mut buffer, THUMBNAIL_QUALITY).encode_image(&rgb) {
+        log::warn!("Failed to encode thumbnail for book {}: {}", book_id, e);
+        return None;
+    }
+    let thumbnail = buffer.into_inner();
+
+    if let Some(path) = cache_path(book_id, page, width) {
+        if let Err(e) = fs::write(&path, &thumbnail) {
+            log::warn!("Failed to cache thumbnail {:?}: {}", path, e);
+        }
+    }
+
+    Some(thumbnail)
+}
+
+/// Delete cached thumbnails of one book, or of every book when `book_id` is `None`
+pub fn invalidate(book_id: Option<i32>) {
+    let Some(dir) = THUMBNAIL_DIR.read().unwrap_or_else(|e| e.into_inner()).clone() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+
+    let prefix = book_id.map(|id| format!("{}_", id));
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let matches = match &prefix {
+            Some(prefix) => name.starts_with(prefix.as_str()),
+            None => name.ends_with(".jpg"),
+        };
+        if matches {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_width() {
+        assert_eq!(parse_width(""), DEFAULT_THUMBNAIL_WIDTH);
+        assert_eq!(parse_width("w=120"), 120);
+        assert_eq!(parse_width("format=jpg&w=640"), 640);
+        assert_eq!(parse_width("w=abc"), DEFAULT_THUMBNAIL_WIDTH);
+        assert_eq!(parse_width("w=1"), MIN_THUMBNAIL_WIDTH);
+        assert_eq!(parse_width("w=100000"), MAX_THUMBNAIL_WIDTH);
+    }
+
+    #[test]
+    fn test_render_downscales_and_keeps_aspect_ratio() {
+        let source = image::RgbImage::from_pixel(800, 400, image::Rgb([200, 40, 40]));
+        let mut png = Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(source)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+
+        let thumbnail = render(1, 0, 200, png.get_ref()).expect("thumbnail");
+        let decoded = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (200, 100));
+
+        assert!(render(1, 0, 200, b"not an image").is_none());
+    }
+}
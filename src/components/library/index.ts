@@ -1,2 +1,3 @@
 export { default as BookItem } from "./BookItem.svelte";
 export { default as CollectionItem } from "./CollectionItem.svelte";
+export { default as CloudDownloadModal } from "./CloudDownloadModal.svelte";
@@ -5,7 +5,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
-import type { AuthStatus } from "$lib/types/auth";
+import type { AccountSummary, AuthStatus, TokenValidation } from "$lib/types/auth";
 
 // Google OAuth configuration from environment
 const GOOGLE_CLIENT_ID = import.meta.env.VITE_GOOGLE_CLIENT_ID;
@@ -56,6 +56,13 @@ export async function refreshToken(): Promise<AuthStatus> {
 	});
 }
 
+/**
+ * Check with Google whether the stored token is still valid (refreshes once if rejected)
+ */
+export async function validateToken(): Promise<TokenValidation> {
+	return invoke<TokenValidation>("validate_token");
+}
+
 /**
  * Logout from Google (clear stored tokens)
  */
@@ -63,6 +70,27 @@ export async function googleLogout(): Promise<AuthStatus> {
 	return invoke<AuthStatus>("google_logout");
 }
 
+/**
+ * List the signed-in Google accounts
+ */
+export async function listAccounts(): Promise<AccountSummary[]> {
+	return invoke<AccountSummary[]>("list_accounts");
+}
+
+/**
+ * Switch the account used for sync to another signed-in account
+ */
+export async function switchAccount(email: string): Promise<AuthStatus> {
+	return invoke<AuthStatus>("switch_account", { email });
+}
+
+/**
+ * Re-encrypt stored credentials with a new vault key
+ */
+export async function rotateCredentialKey(oldKey: string, newKey: string): Promise<void> {
+	return invoke("rotate_credential_key", { oldKey, newKey });
+}
+
 /**
  * Set auth token manually (for development/testing)
  */
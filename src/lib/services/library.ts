@@ -4,14 +4,43 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
+	ArchiveVerification,
+	ArchiveVerifyProgress,
 	Book,
 	BookWithDetails,
+	BookPage,
 	BookSettings,
 	Bookmark,
+	BookmarkImportResult,
 	Collection,
+	CollectionDiff,
+	CollectionNode,
 	CollectionWithCount,
+	DailyReadingStats,
+	PagesRead,
+	DayActivity,
+	ConversionProgress,
+	DirectoryImportResult,
+	DriveFileInfo,
+	HashBackfillResult,
+	ImportBatchProgress,
+	ImportProgress,
+	ImportResult,
+	LibraryConsistencyReport,
+	LibraryVerification,
+	PageAudit,
+	PageNavigation,
+	QueueEntry,
+	QueuedBook,
+	ReaderBackground,
+	ReaderConfig,
+	ReadingSession,
 	ReadingStatus,
+	ResumeBook,
+	SeriesSummary,
+	Tag,
 } from "$lib/types/library";
 
 /**
@@ -36,7 +65,7 @@ function extractFilename(path: string): string | undefined {
 			// Look for anything after the last slash that looks like a filename
 			const parts = decoded.split("/");
 			const lastPart = parts[parts.length - 1];
-			if (lastPart && /\.(cbz|cbr|zip|rar)$/i.test(lastPart)) {
+			if (lastPart && /\.(cbz|cbr|cb7|zip|rar|7z)$/i.test(lastPart)) {
 				return lastPart;
 			}
 		} catch (e) {
@@ -55,12 +84,17 @@ function extractFilename(path: string): string | undefined {
 // ============================================================================
 
 /**
- * Create a new collection
+ * Create a new collection, optionally nested under another one
  */
-export async function createCollection(name: string, description?: string): Promise<Collection> {
+export async function createCollection(
+	name: string,
+	description?: string,
+	parentId?: number
+): Promise<Collection> {
 	return invoke<Collection>("create_collection", {
 		name,
 		description: description ?? null,
+		parentId: parentId ?? null,
 	});
 }
 
@@ -71,6 +105,14 @@ export async function getCollections(): Promise<CollectionWithCount[]> {
 	return invoke<CollectionWithCount[]>("get_collections");
 }
 
+/**
+ * Get the number of unread books per collection ID, for badges
+ * Collections without unread books are missing from the map
+ */
+export async function getUnreadCounts(): Promise<Record<number, number>> {
+	return invoke<Record<number, number>>("get_unread_counts");
+}
+
 /**
  * Get a single collection by ID
  */
@@ -102,25 +144,93 @@ export async function deleteCollection(collectionId: number): Promise<void> {
 	return invoke<void>("delete_collection", { collectionId });
 }
 
+/**
+ * Compare two collections: books only in A, only in B, and in both
+ */
+export async function diffCollections(
+	collectionA: number,
+	collectionB: number
+): Promise<CollectionDiff> {
+	return invoke<CollectionDiff>("diff_collections", { collectionA, collectionB });
+}
+
+/**
+ * Move a collection under another collection, or to the top level with null
+ * Fails if the collection would end up nested inside itself
+ */
+export async function moveCollection(
+	collectionId: number,
+	parentId: number | null
+): Promise<Collection> {
+	return invoke<Collection>("move_collection", { collectionId, parentId });
+}
+
+/**
+ * Get the collections nested directly under a collection (top-level ones for null)
+ */
+export async function getChildCollections(parentId: number | null): Promise<Collection[]> {
+	return invoke<Collection[]>("get_child_collections", { parentId });
+}
+
+/**
+ * Get all collections as a tree, with book counts
+ */
+export async function getCollectionTree(): Promise<CollectionNode[]> {
+	return invoke<CollectionNode[]>("get_collection_tree");
+}
+
 // ============================================================================
 // BOOK COMMANDS
 // ============================================================================
 
 /**
  * Get all books with optional filtering
+ * @param options.sort - "sort_key" for the same title order on every device;
+ *   defaults to most recently read first
  */
 export async function getBooks(options?: {
 	collectionId?: number;
 	status?: ReadingStatus;
 	favoritesOnly?: boolean;
+	sort?: "recent" | "sort_key";
 }): Promise<BookWithDetails[]> {
-	return invoke<BookWithDetails[]>("get_books", {
+	const page = await getBooksPage(options);
+	return page.books;
+}
+
+/**
+ * Get one page of books with optional filtering
+ * @param options.limit - Page size; omit to get every book after the offset
+ * @param options.offset - Number of matching books to skip
+ * @returns The page of books and the total number of matching books
+ */
+export async function getBooksPage(options?: {
+	collectionId?: number;
+	status?: ReadingStatus;
+	favoritesOnly?: boolean;
+	sort?: "recent" | "sort_key";
+	limit?: number;
+	offset?: number;
+}): Promise<BookPage> {
+	return invoke<BookPage>("get_books", {
 		collectionId: options?.collectionId ?? null,
 		status: options?.status ?? null,
 		favoritesOnly: options?.favoritesOnly ?? false,
+		sort: options?.sort ?? null,
+		limit: options?.limit ?? null,
+		offset: options?.offset ?? null,
 	});
 }
 
+/**
+ * Search books by title and filename (case-insensitive)
+ * Exact title matches come first, then title prefixes, then other matches.
+ * @param limit - Maximum number of results (backend default 50)
+ */
+export async function searchBooks(query: string, limit?: number): Promise<BookWithDetails[]> {
+	return invoke<BookWithDetails[]>("search_books", { query, limit: limit ?? null });
+}
+
 /**
  * Get a single book by ID
  */
@@ -128,27 +238,116 @@ export async function getBook(bookId: number): Promise<Book> {
 	return invoke<Book>("get_book", { bookId });
 }
 
+/**
+ * Get the most recently read in-progress book and the page to resume at
+ */
+export async function getLastReadBook(): Promise<ResumeBook | null> {
+	return invoke<ResumeBook | null>("get_last_read_book");
+}
+
+/**
+ * Get in-progress books that haven't been read for a while, oldest first
+ * @param days - Minimum days since the book was last read
+ * @param limit - Maximum number of books to return
+ */
+export async function getStaleInProgress(days: number, limit = 20): Promise<BookWithDetails[]> {
+	return invoke<BookWithDetails[]>("get_stale_in_progress", { days, limit });
+}
+
+/**
+ * Get favorite books that aren't in any collection, to help organize the library
+ */
+export async function getUnfiledFavorites(): Promise<BookWithDetails[]> {
+	return invoke<BookWithDetails[]>("get_unfiled_favorites");
+}
+
+/**
+ * Get next/previous page indices for the reader, resolved from book and global settings
+ */
+export async function getPageNavigation(
+	bookId: number,
+	currentPage: number
+): Promise<PageNavigation> {
+	return invoke<PageNavigation>("get_page_navigation", { bookId, currentPage });
+}
+
+/**
+ * Get the reading options and resume position for a book, resolved from book and global settings
+ */
+export async function getReaderConfig(bookId: number): Promise<ReaderConfig> {
+	return invoke<ReaderConfig>("get_reader_config", { bookId });
+}
+
+/**
+ * Get the ordered image entry names of a book (index = page number)
+ */
+export async function getPageNames(bookId: number): Promise<string[]> {
+	return invoke<string[]>("get_page_names", { bookId });
+}
+
+/**
+ * Count a book's pages and hash its content again after its file was replaced on disk
+ */
+export async function rescanBook(bookId: number): Promise<Book> {
+	return invoke<Book>("rescan_book", { bookId });
+}
+
+/**
+ * Find books whose file hash starts with the given prefix (at least 6 hex characters)
+ */
+export async function findByHashPrefix(prefix: string): Promise<Book[]> {
+	return invoke<Book[]>("find_by_hash_prefix", { prefix });
+}
+
+/**
+ * Find the local book a Drive file belongs to
+ * @param hash - File hash or Drive file name (book_{hash}.cbz)
+ */
+export async function resolveDriveFile(hash: string): Promise<DriveFileInfo> {
+	return invoke<DriveFileInfo>("resolve_drive_file", { hash });
+}
+
 /**
  * Update a book
+ * @param updates.pageFraction - How far into the current page (0-1) the reader has
+ *   scrolled in vertical mode; a new page without it starts at the top
+ * @param updates.scrollFraction - How far into the whole strip (0-1) the reader has
+ *   scrolled in vertical mode
  */
 export async function updateBook(
 	bookId: number,
 	updates: {
 		title?: string;
 		currentPage?: number;
+		pageFraction?: number;
+		scrollFraction?: number;
 		isFavorite?: boolean;
 		readingStatus?: ReadingStatus;
+		/** Empty string removes the book from its series */
+		seriesName?: string;
+		volumeNumber?: number;
 	}
 ): Promise<Book> {
 	return invoke<Book>("update_book", {
 		bookId,
 		title: updates.title,
 		currentPage: updates.currentPage,
+		pageFraction: updates.pageFraction,
+		scrollFraction: updates.scrollFraction,
 		isFavorite: updates.isFavorite,
 		readingStatus: updates.readingStatus,
+		seriesName: updates.seriesName,
+		volumeNumber: updates.volumeNumber,
 	});
 }
 
+/**
+ * Set or clear the freeform note for a book (empty clears it)
+ */
+export async function setBookNote(bookId: number, note: string | null): Promise<Book> {
+	return invoke<Book>("set_book_note", { bookId, note });
+}
+
 /**
  * Set the collections for a book (replaces existing)
  */
@@ -173,6 +372,62 @@ export async function removeBookFromCollection(
 	return invoke<void>("remove_book_from_collection", { bookId, collectionId });
 }
 
+/**
+ * Create a new tag
+ */
+export async function createTag(name: string): Promise<Tag> {
+	return invoke<Tag>("create_tag", { name });
+}
+
+/**
+ * Get all tags ordered by name
+ */
+export async function getTags(): Promise<Tag[]> {
+	return invoke<Tag[]>("get_tags");
+}
+
+/**
+ * Delete a tag
+ */
+export async function deleteTag(tagId: number): Promise<void> {
+	return invoke<void>("delete_tag", { tagId });
+}
+
+/**
+ * Add a tag to a book
+ */
+export async function addTagToBook(bookId: number, tagId: number): Promise<void> {
+	return invoke<void>("add_tag_to_book", { bookId, tagId });
+}
+
+/**
+ * Remove a tag from a book
+ */
+export async function removeTagFromBook(bookId: number, tagId: number): Promise<void> {
+	return invoke<void>("remove_tag_from_book", { bookId, tagId });
+}
+
+/**
+ * Get the books with a tag
+ */
+export async function getBooksByTag(tagId: number): Promise<Book[]> {
+	return invoke<Book[]>("get_books_by_tag", { tagId });
+}
+
+/**
+ * Get the volumes of a series ordered by volume number (unnumbered volumes last)
+ */
+export async function getSeries(name: string): Promise<Book[]> {
+	return invoke<Book[]>("get_series", { name });
+}
+
+/**
+ * Get every series with its volume count and reading progress
+ */
+export async function getAllSeries(): Promise<SeriesSummary[]> {
+	return invoke<SeriesSummary[]>("get_all_series");
+}
+
 /**
  * Delete a book
  */
@@ -181,15 +436,17 @@ export async function deleteBook(bookId: number): Promise<void> {
 }
 
 /**
- * Import a single book from a zip/cbz/rar/cbr archive file
+ * Import a single book from a zip/cbz/rar/cbr/7z/cb7 archive file
  * !! RAR/CBR support is desktop-only (native unrar crate doesn't compile for Android) !!
  * @param filePath - Path to the archive file
  * @param collectionId - Optional collection to add the imported book to
+ * @param password - Password of an encrypted ZIP/CBZ archive
  * @returns The imported Book
  */
 export async function importBookFromArchive(
 	filePath: string,
-	collectionId?: number
+	collectionId?: number,
+	password?: string
 ): Promise<Book> {
 	const originalFilename = extractFilename(filePath);
 
@@ -197,6 +454,75 @@ export async function importBookFromArchive(
 		filePath,
 		collectionId: collectionId ?? null,
 		originalFilename: originalFilename ?? null,
+		password: password ?? null,
+	});
+}
+
+/**
+ * Enter the password of a book's encrypted archive for this session
+ * Rejects with an ArchiveEncrypted error if the password is wrong; null forgets it
+ */
+export async function setArchivePassword(bookId: number, password: string | null): Promise<void> {
+	return invoke<void>("set_archive_password", { bookId, password });
+}
+
+/**
+ * Whether a book's archive needs a password that wasn't entered this session
+ */
+export async function archivePasswordRequired(bookId: number): Promise<boolean> {
+	return invoke<boolean>("archive_password_required", { bookId });
+}
+
+/**
+ * Import several archives in one call
+ * A failed or duplicate file is reported in its result and doesn't stop the batch
+ * @param filePaths - Paths to the archive files
+ * @param collectionId - Optional collection to add the imported books to
+ * @returns One result per file, in order
+ */
+export async function importBooksFromArchives(
+	filePaths: string[],
+	collectionId?: number
+): Promise<ImportResult[]> {
+	return invoke<ImportResult[]>("import_books_from_archives", {
+		filePaths,
+		collectionId: collectionId ?? null,
+	});
+}
+
+/**
+ * Import every archive in a directory (desktop only)
+ * Non-archive files are ignored and books already in the library are skipped
+ * @param dirPath - Directory to scan
+ * @param collectionId - Optional collection to add the imported books to
+ * @param recursive - Whether to include subdirectories
+ */
+export async function importDirectory(
+	dirPath: string,
+	collectionId?: number,
+	recursive = true
+): Promise<DirectoryImportResult> {
+	return invoke<DirectoryImportResult>("import_directory", {
+		dirPath,
+		collectionId: collectionId ?? null,
+		recursive,
+	});
+}
+
+/**
+ * Import a book stored as a folder of images with a pages.json manifest
+ * Pages are read in manifest order and the folder is referenced in place
+ * @param manifestPath - Path to the pages.json file
+ * @param collectionId - Optional collection to add the imported book to
+ * @returns The imported Book
+ */
+export async function importBookFromManifest(
+	manifestPath: string,
+	collectionId?: number
+): Promise<Book> {
+	return invoke<Book>("import_book_from_manifest", {
+		manifestPath,
+		collectionId: collectionId ?? null,
 	});
 }
 
@@ -213,9 +539,25 @@ export async function toggleFavorite(book: Book): Promise<Book> {
 
 /**
  * Update reading progress (also updates last_read_at automatically)
+ * Writes are buffered and saved once page turns settle; see flushReadingPosition
+ */
+export async function updateReadingProgress(bookId: number, currentPage: number): Promise<void> {
+	return invoke<void>("save_reading_position", { bookId, currentPage });
+}
+
+/**
+ * Count an opening of the book in the reader (shown as "opened N times")
  */
-export async function updateReadingProgress(bookId: number, currentPage: number): Promise<Book> {
-	return updateBook(bookId, { currentPage });
+export async function markBookOpened(bookId: number): Promise<Book> {
+	return invoke<Book>("mark_book_opened", { bookId });
+}
+
+/**
+ * Save buffered reading progress now (e.g. when leaving the reader)
+ * @param bookId - Only flush this book; flushes all books when omitted
+ */
+export async function flushReadingPosition(bookId?: number): Promise<number> {
+	return invoke<number>("flush_reading_position", { bookId });
 }
 
 /**
@@ -246,6 +588,17 @@ export async function getBookSettings(bookId: number): Promise<BookSettings | nu
 	return invoke<BookSettings | null>("get_book_settings", { bookId });
 }
 
+/**
+ * Set the reading direction of every book in a collection (creates settings where absent)
+ * @returns Number of books updated
+ */
+export async function setCollectionReadingDirection(
+	collectionId: number,
+	direction: "ltr" | "rtl" | "vertical"
+): Promise<number> {
+	return invoke<number>("set_collection_reading_direction", { collectionId, direction });
+}
+
 /**
  * Update book-specific settings (creates if not exists)
  */
@@ -256,6 +609,7 @@ export async function updateBookSettings(
 		pageDisplayMode?: string | null;
 		imageFitMode?: string | null;
 		syncProgress?: boolean | null;
+		readerBackground?: ReaderBackground | null;
 	}
 ): Promise<BookSettings> {
 	return invoke<BookSettings>("update_book_settings", {
@@ -264,6 +618,8 @@ export async function updateBookSettings(
 		pageDisplayMode: settings.pageDisplayMode !== undefined ? settings.pageDisplayMode : null,
 		imageFitMode: settings.imageFitMode !== undefined ? settings.imageFitMode : null,
 		syncProgress: settings.syncProgress !== undefined ? settings.syncProgress : null,
+		readerBackground:
+			settings.readerBackground !== undefined ? settings.readerBackground : null,
 	});
 }
 
@@ -316,3 +672,219 @@ export async function updateBookmark(
 export async function deleteBookmark(bookmarkId: number): Promise<void> {
 	return invoke<void>("delete_bookmark", { bookmarkId });
 }
+
+/**
+ * Export a book's bookmarks to a JSON file matched by the book's content hash
+ * @returns Number of bookmarks written
+ */
+export async function exportBookmarks(bookId: number, path: string): Promise<number> {
+	return invoke<number>("export_bookmarks", { bookId, path });
+}
+
+/**
+ * Import bookmarks from a shared JSON file into the local book with the same content
+ */
+export async function importBookmarks(path: string): Promise<BookmarkImportResult> {
+	return invoke<BookmarkImportResult>("import_bookmarks", { path });
+}
+
+/**
+ * Append a book to the end of the reading queue (no-op if already queued)
+ */
+export async function addToQueue(bookId: number): Promise<QueueEntry> {
+	return invoke<QueueEntry>("add_to_queue", { bookId });
+}
+
+/**
+ * Get the reading queue in order
+ */
+export async function getQueue(): Promise<QueuedBook[]> {
+	return invoke<QueuedBook[]>("get_queue");
+}
+
+/**
+ * Remove a book from the reading queue
+ */
+export async function removeFromQueue(bookId: number): Promise<void> {
+	return invoke<void>("remove_from_queue", { bookId });
+}
+
+/**
+ * Reorder the reading queue; queued books not listed keep their order after the listed ones
+ * @returns The queue in its new order
+ */
+export async function reorderQueue(bookIds: number[]): Promise<QueuedBook[]> {
+	return invoke<QueuedBook[]>("reorder_queue", { bookIds });
+}
+
+/**
+ * Get reading activity per day for a heatmap, oldest day first
+ * Days follow this device's time zone
+ * @param days - Number of days ending today
+ */
+export async function getReadingActivity(days = 365): Promise<DayActivity[]> {
+	// getTimezoneOffset() is minutes behind UTC; the backend wants minutes ahead
+	const utcOffsetMinutes = -new Date().getTimezoneOffset();
+	return invoke<DayActivity[]>("get_reading_activity", { days, utcOffsetMinutes });
+}
+
+/**
+ * Record a reader session; call when the reader closes
+ * @param startedAt - When the book was opened (ms since epoch)
+ * @param endedAt - When the reader was closed (ms since epoch)
+ */
+export async function recordReadingSession(
+	bookId: number,
+	pageFrom: number,
+	pageTo: number,
+	startedAt: number,
+	endedAt: number = Date.now()
+): Promise<ReadingSession> {
+	return invoke<ReadingSession>("record_reading_session", {
+		bookId,
+		pageFrom,
+		pageTo,
+		startedAt,
+		endedAt,
+	});
+}
+
+/**
+ * Get a book's reader sessions, most recent first
+ */
+export async function getReadingHistory(bookId: number): Promise<ReadingSession[]> {
+	return invoke<ReadingSession[]>("get_reading_history", { bookId });
+}
+
+/**
+ * Get pages and minutes read per day over the last 30 days, oldest day first
+ * Days follow this device's time zone
+ */
+export async function getReadingStats(): Promise<DailyReadingStats[]> {
+	const utcOffsetMinutes = -new Date().getTimezoneOffset();
+	return invoke<DailyReadingStats[]>("get_reading_stats", { utcOffsetMinutes });
+}
+
+/**
+ * Get the pages read since a date, in total and per reading status
+ */
+export async function getPagesRead(since: Date): Promise<PagesRead> {
+	return invoke<PagesRead>("get_pages_read", { sinceTs: since.getTime() });
+}
+
+/**
+ * Clamp reading positions that fall outside their book's pages
+ * @returns Number of books fixed
+ */
+export async function fixInvalidProgress(): Promise<number> {
+	return invoke<number>("fix_invalid_progress");
+}
+
+/**
+ * Check for orphaned collection links, bookmarks and book settings
+ * @param cleanup - Remove the orphaned rows in a single transaction
+ */
+export async function verifyLibraryConsistency(cleanup = false): Promise<LibraryConsistencyReport> {
+	return invoke<LibraryConsistencyReport>("verify_library_consistency", { cleanup });
+}
+
+/**
+ * Hash the page contents of fast-imported books now instead of waiting for the background pass
+ * @returns Number of books hashed, and books found to duplicate one already in the library
+ */
+export async function backfillContentHashes(): Promise<HashBackfillResult> {
+	return invoke<HashBackfillResult>("backfill_content_hashes");
+}
+
+/**
+ * Decompress a book's file and report corrupt entries, a missing file or a page count mismatch
+ * @param bookId - Book ID
+ */
+export async function verifyArchive(bookId: number): Promise<ArchiveVerification> {
+	return invoke<ArchiveVerification>("verify_archive", { bookId });
+}
+
+/**
+ * Verify every book stored on this device; cloud-only books are skipped
+ * @returns Books with problems, with the number of books checked
+ */
+export async function verifyAllArchives(): Promise<LibraryVerification> {
+	return invoke<LibraryVerification>("verify_all_archives");
+}
+
+/**
+ * Recount the pages of every book in a collection and report wrong stored counts
+ */
+export async function auditCollectionPages(collectionId: number): Promise<PageAudit> {
+	return invoke<PageAudit>("audit_collection_pages", { collectionId });
+}
+
+/**
+ * Stop a running verifyAllArchives after the current book
+ */
+export async function cancelVerifyArchives(): Promise<void> {
+	return invoke<void>("cancel_verify_archives");
+}
+
+/**
+ * Subscribe to per-book progress of verifyAllArchives
+ */
+export async function onVerifyProgress(
+	callback: (progress: ArchiveVerifyProgress) => void
+): Promise<UnlistenFn> {
+	return listen<ArchiveVerifyProgress>("verify-progress", (event) => callback(event.payload));
+}
+
+/**
+ * Move managed book files to a new library directory; returns how many files were moved
+ * @param newPath - Absolute path of the new library directory
+ */
+export async function setLibraryDirectory(newPath: string): Promise<number> {
+	return invoke<number>("set_library_directory", { newPath });
+}
+
+/**
+ * Link cloud-only books to matching archives found locally
+ * @param dir - Directory to scan (defaults to the library directory)
+ */
+export async function reconcileLocalFiles(dir?: string): Promise<Book[]> {
+	return invoke<Book[]>("reconcile_local_files", { dir });
+}
+
+/**
+ * Convert a CBR/RAR book to CBZ so it can be read on Android (desktop only)
+ * @param destPath - Where to write the CBZ
+ * @param relink - Point the book at the new CBZ instead of the CBR
+ */
+export async function convertToCbz(bookId: number, destPath: string, relink = true): Promise<Book> {
+	return invoke<Book>("convert_to_cbz", { bookId, destPath, relink });
+}
+
+/**
+ * Subscribe to page progress of running CBZ conversions
+ */
+export async function onConvertProgress(
+	callback: (progress: ConversionProgress) => void
+): Promise<UnlistenFn> {
+	return listen<ConversionProgress>("convert-progress", (event) => callback(event.payload));
+}
+
+/**
+ * Subscribe to phase and copy progress of running archive imports
+ */
+export async function onImportProgress(
+	callback: (progress: ImportProgress) => void
+): Promise<UnlistenFn> {
+	return listen<ImportProgress>("import-progress", (event) => callback(event.payload));
+}
+
+/**
+ * Subscribe to per-file progress of running batch imports
+ */
+export async function onImportBatchProgress(
+	callback: (progress: ImportBatchProgress) => void
+): Promise<UnlistenFn> {
+	return listen<ImportBatchProgress>("import-batch-progress", (event) =>
+		callback(event.payload)
+	);
+}
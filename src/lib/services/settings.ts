@@ -4,9 +4,12 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
 	AppSettings,
+	ModifiedSetting,
 	SettingCategory,
+	SettingChangedEvent,
 	SettingValue,
 	SettingUpdates,
 } from "$lib/types/settings";
@@ -39,6 +42,13 @@ export async function getSetting(key: string): Promise<SettingValue | null> {
 	return invoke<SettingValue | null>("get_setting", { key });
 }
 
+/**
+ * Whether book progress counts story pages only ('reading.progress_story_pages_only')
+ */
+export async function getProgressStoryPagesOnly(): Promise<boolean> {
+	return (await getSetting("reading.progress_story_pages_only")) === true;
+}
+
 /**
  * Update multiple settings at once
  */
@@ -73,3 +83,57 @@ export async function resetAllSettings(): Promise<AppSettings> {
 export async function resetSetting(key: string): Promise<AppSettings> {
 	return invoke<AppSettings>("reset_setting", { key });
 }
+
+/**
+ * List settings changed from their defaults, with current and default values
+ */
+export async function getModifiedSettings(): Promise<ModifiedSetting[]> {
+	return invoke<ModifiedSetting[]>("get_modified_settings");
+}
+
+/**
+ * Write the settings schema (categories, widgets, options, defaults) to a JSON file
+ * @param path - Destination file
+ * @returns Number of settings written
+ */
+export async function exportSettingsSchema(path: string): Promise<number> {
+	return invoke<number>("export_settings_schema", { path });
+}
+
+/**
+ * Export the current settings as a portable JSON string
+ */
+export async function exportSettings(): Promise<string> {
+	return invoke<string>("export_settings");
+}
+
+/**
+ * Import settings exported by exportSettings; nothing changes if any value is invalid
+ * @throws If the file is from a newer app version or fails validation
+ */
+export async function importSettings(json: string): Promise<AppSettings> {
+	return invoke<AppSettings>("import_settings", { json });
+}
+
+/**
+ * Names of the built-in settings presets (e.g. "manga", "webtoon")
+ */
+export async function listSettingsPresets(): Promise<string[]> {
+	return invoke<string[]>("list_settings_presets");
+}
+
+/**
+ * Apply a built-in preset's reading settings
+ */
+export async function applySettingsPreset(name: string): Promise<AppSettings> {
+	return invoke<AppSettings>("apply_settings_preset", { name });
+}
+
+/**
+ * Subscribe to live setting changes; emitted once per changed key
+ */
+export async function onSettingChanged(
+	callback: (change: SettingChangedEvent) => void
+): Promise<UnlistenFn> {
+	return listen<SettingChangedEvent>("setting-changed", (event) => callback(event.payload));
+}
@@ -4,6 +4,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 
 export interface SyncResult {
 	success: boolean;
@@ -16,6 +17,28 @@ export interface SyncResult {
 	conflicts_resolved: number;
 	errors: string[];
 	completed_at: number;
+	changes: SyncChange[];
+}
+
+/** One entry of the recent sync changes feed */
+export interface SyncChange {
+	entity:
+		| "book"
+		| "collection"
+		| "bookmark"
+		| "tag"
+		| "queue_entry"
+		| "book_settings"
+		| "app_settings";
+	/** Book title, collection, bookmark or tag name; the book's title for queue entries and book settings */
+	title: string;
+	kind: "added" | "updated" | "progress" | "deleted";
+	/** Fields that differed between the sides, e.g. "current_page"; empty for additions and deletions */
+	fields: string[];
+	/** "up" = this device to Drive, "down" = Drive to this device */
+	direction: "up" | "down";
+	/** For downloads, the device that last uploaded the snapshot */
+	device: string | null;
 }
 
 export type SyncStatus =
@@ -36,14 +59,222 @@ export async function syncNow(): Promise<SyncResult> {
 	return invoke<SyncResult>("sync_now");
 }
 
+/**
+ * Run the first sync of this device (onboarding)
+ *
+ * Remote data takes precedence and nothing on Drive is deleted or overwritten.
+ */
+export async function initialSync(): Promise<SyncResult> {
+	return invoke<SyncResult>("initial_sync");
+}
+
+/** How conflicting changes to the same item are resolved */
+export type ConflictStrategy = "remote_wins" | "local_wins" | "last_write_wins";
+
+/**
+ * Run one sync with the given conflict strategy, e.g. "local_wins" to push this
+ * device's version back after unwanted overwrites. Later syncs are unaffected.
+ */
+export async function syncWithStrategy(strategy: ConflictStrategy): Promise<SyncResult> {
+	return invoke<SyncResult>("sync_with_strategy", { strategy });
+}
+
+/**
+ * Show what a sync would change without uploading or changing anything
+ *
+ * The planned changes are listed in `changes`; the counts are what a sync would report.
+ */
+export async function previewSync(): Promise<SyncResult> {
+	return invoke<SyncResult>("preview_sync");
+}
+
+/**
+ * Set the passphrase sync data is encrypted with (`sync.encrypt`)
+ *
+ * Only kept in memory until the app closes; pass null to forget it. Syncs fail with
+ * the `sync_passphrase_required` error code while it is missing or wrong.
+ */
+export async function setSyncPassphrase(passphrase: string | null): Promise<void> {
+	return invoke("set_sync_passphrase", { passphrase });
+}
+
+/**
+ * Whether a sync passphrase was entered this session
+ */
+export async function hasSyncPassphrase(): Promise<boolean> {
+	return invoke<boolean>("has_sync_passphrase");
+}
+
+/**
+ * Which snapshot `exportSnapshot` writes: the one stored on Drive, or the one
+ * this device would upload on its next sync
+ */
+export type SnapshotSource = "remote" | "local";
+
+/**
+ * Write a sync snapshot as pretty-printed JSON to a local file (for debugging)
+ *
+ * Building the local snapshot doesn't upload it or change the database.
+ */
+export async function exportSnapshot(path: string, source: SnapshotSource = "remote"): Promise<void> {
+	return invoke("export_snapshot", { path, source });
+}
+
+/**
+ * Get the errors recorded by the last sync attempt (empty if it succeeded)
+ */
+export async function getLastSyncErrors(): Promise<string[]> {
+	return invoke<string[]>("get_last_sync_errors");
+}
+
+/**
+ * Get what the last successful sync changed (empty before the first sync)
+ */
+export async function getRecentSyncChanges(): Promise<SyncChange[]> {
+	return invoke<SyncChange[]>("get_recent_sync_changes");
+}
+
+export interface SyncUploadEstimate {
+	file_count: number;
+	total_bytes: number;
+}
+
+/**
+ * Estimate how much a book-file sync would upload to Google Drive
+ */
+export async function estimateSyncUploadSize(): Promise<SyncUploadEstimate> {
+	return invoke<SyncUploadEstimate>("estimate_sync_upload_size");
+}
+
 /**
  * Download a cloud-only book from Google Drive
  * Called when user tries to read a book with cloud:// file path
  */
-export async function downloadCloudBook(
-	bookId: number
-): Promise<import("$lib/types/library").Book> {
-	return invoke<import("$lib/types/library").Book>("download_cloud_book", { bookId });
+export async function downloadBook(bookId: number): Promise<import("$lib/types/library").Book> {
+	return invoke<import("$lib/types/library").Book>("download_book", { bookId });
+}
+
+export interface BookFileProgress {
+	book_id: number;
+	downloaded_bytes: number;
+	/** Null when Drive didn't report the file size */
+	total_bytes: number | null;
+}
+
+/**
+ * Subscribe to byte progress of downloadBook
+ */
+export async function onBookFileProgress(
+	callback: (progress: BookFileProgress) => void
+): Promise<UnlistenFn> {
+	return listen<BookFileProgress>("book-file-progress", (event) => callback(event.payload));
+}
+
+/** Book as stored in the Drive sync snapshot (timestamps in Unix millis) */
+export interface RemoteBookState {
+	uuid: string;
+	file_hash: string | null;
+	title: string;
+	filename: string;
+	current_page: number;
+	total_pages: number;
+	is_favorite: boolean;
+	reading_status: string;
+	last_read_at: number | null;
+	added_at: number;
+	updated_at: number;
+	deleted_at: number | null;
+	notes: string | null;
+	open_count: number;
+	completed_count: number;
+}
+
+export interface BookConflict {
+	local: import("$lib/types/library").Book;
+	remote: RemoteBookState | null;
+	local_updated_at: number;
+	remote_updated_at: number | null;
+	last_sync_at: number | null;
+	local_changed: boolean;
+	remote_changed: boolean;
+}
+
+/**
+ * Fetch the local and Drive versions of a book for a side-by-side comparison
+ * Read-only: nothing is merged or uploaded
+ */
+export async function getBookConflict(bookId: number): Promise<BookConflict> {
+	return invoke<BookConflict>("get_book_conflict", { bookId });
+}
+
+export interface BookDownloadOutcome {
+	book_id: number;
+	title: string;
+	success: boolean;
+	error: string | null;
+	bytes: number;
+}
+
+export interface DownloadAllResult {
+	books: BookDownloadOutcome[];
+	total_bytes: number;
+	cancelled: boolean;
+}
+
+export interface BookDownloadProgress {
+	book_id: number;
+	title: string;
+	completed: number;
+	total: number;
+}
+
+/**
+ * Download every cloud-only book for offline reading
+ * @param collectionId - Only download books in this collection
+ */
+export async function downloadAllBooks(collectionId?: number): Promise<DownloadAllResult> {
+	return invoke<DownloadAllResult>("download_all_books", { collectionId: collectionId ?? null });
+}
+
+/**
+ * Stop a running downloadAllBooks after the current book
+ */
+export async function cancelDownloadAll(): Promise<void> {
+	return invoke<void>("cancel_download_all");
+}
+
+/**
+ * Subscribe to per-book progress of downloadAllBooks
+ */
+export async function onDownloadProgress(
+	callback: (progress: BookDownloadProgress) => void
+): Promise<UnlistenFn> {
+	return listen<BookDownloadProgress>("download-progress", (event) => callback(event.payload));
+}
+
+/** Step of a running sync */
+export type SyncPhase = "downloading" | "merging" | "uploading" | "syncing-files";
+
+export interface SyncProgress {
+	phase: SyncPhase;
+	/** Position (1-based) and count of book files during "syncing-files" */
+	index: number | null;
+	total: number | null;
+	title: string | null;
+}
+
+/**
+ * Subscribe to status changes: "syncing" when a sync starts, then its outcome
+ */
+export async function onSyncStatus(callback: (status: SyncStatus) => void): Promise<UnlistenFn> {
+	return listen<SyncStatus>("sync-status", (event) => callback(event.payload));
+}
+
+/**
+ * Subscribe to the phases of a running sync and its per-file upload progress
+ */
+export async function onSyncProgress(callback: (progress: SyncProgress) => void): Promise<UnlistenFn> {
+	return listen<SyncProgress>("sync-progress", (event) => callback(event.payload));
 }
 
 /**
@@ -10,4 +10,24 @@ export interface AuthStatus {
 	needsRefresh: boolean;
 	email?: string;
 	displayName?: string;
+	/** Whether stored credentials were unreadable and cleared; the user needs to sign in again */
+	credentialsReset: boolean;
+}
+
+/** A signed-in Google account, returned by list_accounts */
+export interface AccountSummary {
+	/** Account email, or "default" for a token stored without one */
+	email: string;
+	displayName?: string;
+	/** Whether this account is used for sync */
+	isActive: boolean;
+}
+
+/** Result of validate_token: checks the token against Google, not just the local expiry */
+export interface TokenValidation {
+	/** Whether Google accepted the access token */
+	valid: boolean;
+	/** Whether the token was refreshed during validation */
+	refreshed: boolean;
+	status: AuthStatus;
 }
@@ -26,8 +26,35 @@ export interface Book {
 	updated_at: string;
 	is_favorite: boolean;
 	reading_status: ReadingStatus;
+	notes: string | null;
+	archive_type: string | null;
+	/** Times the book was opened in the reader */
+	open_count: number;
+	/** Times the book was marked completed */
+	completed_count: number;
+	/** Normalized title giving the same library order on every device */
+	sort_key: string | null;
+	/** Fraction (0-1) of the current page scrolled past in vertical mode */
+	page_fraction: number | null;
+	/** Pages left after ComicInfo.xml ads, deleted pages and the front cover */
+	story_page_count: number | null;
+	/** How file_hash was computed: page contents, or the raw file until a fast import is backfilled */
+	hash_scheme: HashScheme;
+	/** Series the book is a volume of */
+	series_name: string | null;
+	/** Position within the series; fractional for side stories (e.g. 1.5) */
+	volume_number: number | null;
+	/** Fraction (0-1) of the whole strip scrolled past in vertical mode */
+	scroll_fraction: number | null;
+	/** Book this fast import duplicates, found by the content hash backfill */
+	content_duplicate_of: number | null;
 }
 
+/**
+ * How a book's file_hash was computed
+ */
+export type HashScheme = "content" | "file";
+
 /**
  * Book-specific settings overrides
  */
@@ -39,8 +66,15 @@ export interface BookSettings {
 	image_fit_mode: string | null;
 	sync_progress: boolean | null;
 	updated_at: string;
+	/** Reader background override; null follows the reading.background setting */
+	reader_background: ReaderBackground | null;
 }
 
+/**
+ * Background color shown around pages in the reader
+ */
+export type ReaderBackground = "white" | "black" | "gray" | "sepia";
+
 /**
  * Bookmark model for saving specific pages
  */
@@ -53,6 +87,88 @@ export interface Bookmark {
 	created_at: string;
 }
 
+/**
+ * Entry in the global reading queue
+ */
+export interface QueueEntry {
+	id: number;
+	book_id: number;
+	position: number;
+	added_at: string;
+	updated_at: string;
+}
+
+/**
+ * Queued book with its place in the reading queue
+ */
+export interface QueuedBook {
+	position: number;
+	added_at: string;
+	book: Book;
+}
+
+/**
+ * Result of importing a shared bookmark file
+ */
+export interface BookmarkImportResult {
+	book_id: number;
+	imported: Bookmark[];
+	skipped: number;
+}
+
+/**
+ * Payload of the 'convert-progress' event emitted by convert_to_cbz
+ */
+export interface ConversionProgress {
+	book_id: number;
+	current: number;
+	total: number;
+}
+
+/**
+ * Step of an archive import
+ */
+export type ImportPhase = "counting" | "hashing" | "copying" | "inserting";
+
+/**
+ * Payload of the 'import-progress' event emitted by import_book_from_archive
+ * Byte counts are only filled in during the copying phase.
+ */
+export interface ImportProgress {
+	filename: string;
+	phase: ImportPhase;
+	bytes_copied: number;
+	total_bytes: number;
+}
+
+/**
+ * Outcome of one archive in import_books_from_archives
+ */
+export type ImportResult =
+	| { imported: { file_path: string; book: Book } }
+	| { duplicate: { file_path: string; existing_title: string } }
+	| { failed: { file_path: string; error: string } };
+
+/**
+ * Counts returned by import_directory
+ */
+export interface DirectoryImportResult {
+	imported: number;
+	/** Archives already in the library */
+	skipped: number;
+	failed: number;
+}
+
+/**
+ * Payload of the 'import-batch-progress' event, emitted before each file of a batch
+ */
+export interface ImportBatchProgress {
+	filename: string;
+	/** 1-based position of the file in the batch */
+	current: number;
+	total: number;
+}
+
 /**
  * Interface mirroring the Rust 'BookWithDetails' struct.
  * Note: Uses #[serde(flatten)] so book fields are at the top level
@@ -64,6 +180,23 @@ export interface BookWithDetails extends Book {
 	bookmark_count: number;
 }
 
+/**
+ * One page of the library listing
+ */
+export interface BookPage {
+	books: BookWithDetails[];
+	/** Number of books matching the filters across all pages */
+	total_count: number;
+}
+
+/**
+ * Most recently read in-progress book with the page to resume at
+ */
+export interface ResumeBook {
+	book: BookWithDetails;
+	resume_page: number;
+}
+
 /**
  * Collection model
  */
@@ -73,6 +206,71 @@ export interface Collection {
 	description: string | null;
 	created_at: string;
 	updated_at: string;
+	/** Collection this one is nested under; null for top-level collections */
+	parent_id: number | null;
+}
+
+/**
+ * Tag for ad-hoc labels on books
+ */
+export interface Tag {
+	id: number;
+	name: string;
+	created_at: string;
+	updated_at: string;
+}
+
+/**
+ * Reading activity on one calendar day (get_reading_activity)
+ */
+export interface DayActivity {
+	/** Local date as YYYY-MM-DD */
+	date: string;
+	count: number;
+}
+
+/**
+ * One reader session of a book
+ */
+export interface ReadingSession {
+	id: number;
+	book_id: number;
+	/** Page the reader opened on (0-indexed) */
+	page_from: number;
+	/** Page the reader was closed on (0-indexed) */
+	page_to: number;
+	started_at: string;
+	ended_at: string;
+}
+
+/**
+ * Pages and minutes read on one calendar day (get_reading_stats)
+ */
+export interface DailyReadingStats {
+	/** Local date as YYYY-MM-DD */
+	date: string;
+	pages_read: number;
+	minutes_read: number;
+}
+
+/** Pages read since a point in time */
+export interface PagesRead {
+	total: number;
+	/** Pages per current reading status of the books they belong to */
+	by_status: Partial<Record<ReadingStatus, number>>;
+}
+
+/**
+ * A series with its volume count and reading progress across all volumes
+ */
+export interface SeriesSummary {
+	name: string;
+	volume_count: number;
+	completed_volumes: number;
+	pages_read: number;
+	total_pages: number;
+	/** Pages read across all volumes as a percentage (0-100) */
+	progress: number;
 }
 
 /**
@@ -82,6 +280,157 @@ export interface CollectionWithCount extends Collection {
 	book_count: number;
 }
 
+/**
+ * Collection in the collection tree, with its nested collections
+ */
+export interface CollectionNode extends CollectionWithCount {
+	children: CollectionNode[];
+}
+
+/**
+ * Books of two collections split by membership (diff_collections)
+ */
+export interface CollectionDiff {
+	only_in_a: Book[];
+	only_in_b: Book[];
+	in_both: Book[];
+}
+
+/**
+ * Resolved reader navigation for a page.
+ * visible_pages is in on-screen order (left to right), reversed for RTL spreads.
+ */
+export interface PageNavigation {
+	current_page: number;
+	total_pages: number;
+	visible_pages: number[];
+	next_page: number | null;
+	previous_page: number | null;
+	reading_direction: string;
+	page_display_mode: string;
+}
+
+/**
+ * Reading options and resume position for opening a book in the reader
+ * Scroll positions are only set when the book is read in vertical mode
+ */
+export interface ReaderConfig {
+	book_id: number;
+	current_page: number;
+	total_pages: number;
+	reading_direction: string;
+	page_display_mode: string;
+	image_fit_mode: string;
+	reader_background: ReaderBackground;
+	page_fraction: number | null;
+	scroll_fraction: number | null;
+}
+
+/**
+ * How a Drive book file relates to the local library
+ */
+export type DriveFileStatus = "local" | "cloud_only" | "deleted" | "orphaned";
+
+/**
+ * The local book a Drive file (book_{hash}.cbz) belongs to, if any
+ */
+export interface DriveFileInfo {
+	file_hash: string;
+	status: DriveFileStatus;
+	book_id: number | null;
+	title: string | null;
+	/** Whether the book's file exists at its recorded path */
+	file_exists: boolean;
+}
+
+/**
+ * Result of a library consistency check (IDs of orphaned rows)
+ */
+export interface LibraryConsistencyReport {
+	orphaned_book_collections: number[];
+	orphaned_bookmarks: number[];
+	orphaned_book_settings: number[];
+	cleaned_up: boolean;
+}
+
+/**
+ * A fast-imported book whose page contents are already in the library
+ */
+export interface HashDuplicate {
+	book_id: number;
+	title: string;
+	existing_book_id: number;
+	existing_title: string;
+}
+
+/**
+ * Result of replacing fast-import file hashes with content hashes
+ */
+export interface HashBackfillResult {
+	hashed: number;
+	duplicates: HashDuplicate[];
+	failed: number;
+}
+
+/**
+ * Problem found while verifying a book's file
+ */
+export type ArchiveProblem =
+	| "missing_file"
+	| { unreadable: { error: string } }
+	| { corrupt_entry: { entry: string; error: string } }
+	| { missing_page: { page: string } }
+	| { page_count_mismatch: { stored: number; actual: number } };
+
+/**
+ * Result of verifying one book's file
+ */
+export interface ArchiveVerification {
+	book_id: number;
+	title: string;
+	file_path: string;
+	problems: ArchiveProblem[];
+}
+
+/**
+ * Progress of verifyAllArchives, emitted after each book
+ */
+export interface ArchiveVerifyProgress {
+	book_id: number;
+	title: string;
+	completed: number;
+	total: number;
+}
+
+/**
+ * Result of verifying every local book; only books with problems are listed
+ */
+export interface LibraryVerification {
+	problems: ArchiveVerification[];
+	checked: number;
+	cancelled: boolean;
+}
+
+/**
+ * A book whose file holds a different number of pages than stored
+ */
+export interface PageCountMismatch {
+	book_id: number;
+	title: string;
+	stored_pages: number;
+	actual_pages: number;
+}
+
+/**
+ * Result of audit_collection_pages
+ */
+export interface PageAudit {
+	/** Books recounted; cloud-only and unreadable books are not included */
+	checked: number;
+	mismatches: PageCountMismatch[];
+	unreadable: { book_id: number; title: string; error: string }[];
+}
+
 /**
  * Information about a skipped book during import
  */
@@ -142,6 +491,18 @@ export function getCoverPath(bookId: number): string {
 	return `${getComicProtocolPrefix()}/book/${bookId}/page/0`;
 }
 
+/**
+ * Get a downscaled JPEG of a page, rendered and cached by the backend.
+ * Falls back to the original image when the page can't be decoded.
+ * @param bookId - The ID of the book.
+ * @param pageNumber - The page number (0-indexed).
+ * @param width - Maximum width in pixels.
+ * @returns The URL for the thumbnail via custom protocol.
+ */
+export function getThumbnailPath(bookId: number, pageNumber = 0, width = 300): string {
+	return `${getComicProtocolPrefix()}/book/${bookId}/thumb/${pageNumber}?w=${width}`;
+}
+
 /**
  * Get the image path for a specific page of a book.
  * Uses the comic:// custom protocol to serve images from archives.
@@ -156,8 +517,12 @@ export function getPagePath(bookId: number, pageNumber: number): string {
 /**
  * Calculate reading progress percentage
  * Note: current_page is 0-indexed, so 1 is added for display
+ * @param storyPagesOnly - Measure against story_page_count when the book has one
+ *   (setting 'reading.progress_story_pages_only')
  */
-export function calculateProgress(book: Book): number {
-	if (book.total_pages === 0) return 0;
-	return Math.round(((book.current_page + 1) / book.total_pages) * 100);
+export function calculateProgress(book: Book, storyPagesOnly = false): number {
+	const total =
+		storyPagesOnly && book.story_page_count ? book.story_page_count : book.total_pages;
+	if (total === 0) return 0;
+	return Math.min(100, Math.round(((book.current_page + 1) / total) * 100));
 }
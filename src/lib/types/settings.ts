@@ -51,3 +51,16 @@ export interface AppSettings {
 
 // Helper type for setting updates
 export type SettingUpdates = Record<string, SettingValue>;
+
+// Setting whose value differs from its default
+export interface ModifiedSetting {
+	key: string;
+	value: SettingValue;
+	defaultValue: SettingValue;
+}
+
+// Payload of the "setting-changed" event
+export interface SettingChangedEvent {
+	key: string;
+	value: SettingValue;
+}